@@ -0,0 +1,85 @@
+//! Populated access lists for type-1 transactions, from a JSON file or `eth_createAccessList`.
+
+use std::fmt;
+use std::path::Path;
+
+use eyre::{eyre, Result};
+use ethers::types::transaction::eip2930::AccessList;
+use ethers::types::U256;
+use serde::Serialize;
+
+/// Where the type-1 access list comes from.
+#[derive(Clone, Debug)]
+pub enum AccessListSource {
+    /// A fixed list, e.g. loaded with [`load`].
+    Fixed(AccessList),
+    /// Ask the node with `eth_createAccessList` for each transaction.
+    Auto,
+}
+
+impl AccessListSource {
+    /// `auto`, or the path of a JSON file holding `[{"address": …, "storageKeys": […]}, …]`.
+    pub fn parse(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case("auto") {
+            Ok(AccessListSource::Auto)
+        } else {
+            Ok(AccessListSource::Fixed(load(s)?))
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccessListSource::Fixed(_) => "file",
+            AccessListSource::Auto => "auto",
+        }
+    }
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<AccessList> {
+    let path = path.as_ref();
+    let json = std::fs::read_to_string(path).map_err(|e| eyre!("reading {}: {}", path.display(), e))?;
+    serde_json::from_str(&json).map_err(|e| eyre!("parsing access list {}: {}", path.display(), e))
+}
+
+/// The list a type-1 transaction was sent with, and its effect on the gas estimate.
+#[derive(Clone, Debug, Serialize)]
+pub struct AccessListReport {
+    /// `file` or `auto`.
+    pub source: String,
+    pub addresses: usize,
+    pub storage_keys: usize,
+    /// `eth_estimateGas` with an empty access list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_without: Option<U256>,
+    /// `eth_estimateGas` with the populated list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_with: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AccessListReport {
+    pub fn new(source: &AccessListSource, list: &AccessList) -> Self {
+        Self {
+            source: source.name().into(),
+            addresses: list.0.len(),
+            storage_keys: list.0.iter().map(|item| item.storage_keys.len()).sum(),
+            gas_without: None,
+            gas_with: None,
+            error: None,
+        }
+    }
+}
+
+impl fmt::Display for AccessListReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} address(es), {} key(s) ({})", self.addresses, self.storage_keys, self.source)?;
+        if let (Some(with), Some(without)) = (self.gas_with, self.gas_without) {
+            write!(f, ", est. gas {} vs {} without", with, without)?;
+        }
+        if let Some(e) = &self.error {
+            write!(f, ", {}", e)?;
+        }
+        Ok(())
+    }
+}
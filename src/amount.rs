@@ -0,0 +1,221 @@
+//! Transfer amounts as written in `AMOUNT`: `1000wei`, `2.5gwei`, `0.001eth` (a bare number is
+//! in the native currency's whole units), or a share of the sender's balance such as `10%`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use eyre::{eyre, Result};
+use ethers::types::U256;
+use ethers::utils::format_units;
+
+/// Decimals of ether, and of native currencies whose preset does not say otherwise.
+pub const DEFAULT_NATIVE_DECIMALS: u8 = 18;
+
+/// A chain's native currency, which amounts are parsed in and balances and fees shown in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Native {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl Default for Native {
+    fn default() -> Self {
+        Self { symbol: "ETH".into(), decimals: DEFAULT_NATIVE_DECIMALS }
+    }
+}
+
+impl Native {
+    /// Whole native units, e.g. `MAX_SPEND_ETH=0.1`, in wei.
+    pub fn parse(&self, s: &str) -> Result<U256> {
+        scale(s.trim(), self.decimals).map_err(|e| eyre!("{} {}: {}", s, self.symbol, e))
+    }
+
+    /// `wei` in whole native units with the symbol, e.g. `0.5 ETH`.
+    pub fn format(&self, wei: U256) -> String {
+        let units = format_units(wei, self.decimals as u32).unwrap_or_else(|_| wei.to_string());
+        format!("{} {}", units, self.symbol)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Amount {
+    /// A decimal number of `unit`s, kept as written until the native decimals are known.
+    Fixed { amount: String, unit: Unit },
+    /// Hundredths of a percent of the sender's balance.
+    Percent(u64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Wei,
+    Gwei,
+    /// Whole native units (`eth`), `native_decimals` wei each.
+    Native,
+}
+
+impl Amount {
+    pub fn zero() -> Self {
+        Amount::Fixed { amount: "0".into(), unit: Unit::Wei }
+    }
+
+    /// Whether the amount depends on the sender's balance.
+    pub fn is_relative(&self) -> bool {
+        matches!(self, Amount::Percent(_))
+    }
+
+    /// The amount in wei, for a native currency with `native_decimals` decimals and a sender
+    /// holding `balance` (needed for percentages only). Fails when the amount has more
+    /// decimals than its unit can represent.
+    pub fn to_wei(&self, native_decimals: u8, balance: Option<U256>) -> Result<U256> {
+        match self {
+            Amount::Percent(basis_points) => {
+                let balance = balance.ok_or_else(|| eyre!("{} needs the sender's balance", self))?;
+                Ok(balance * *basis_points / 10_000)
+            }
+            Amount::Fixed { amount, unit } => {
+                let decimals = match unit {
+                    Unit::Wei => 0,
+                    Unit::Gwei => 9,
+                    Unit::Native => native_decimals,
+                };
+                scale(amount, decimals).map_err(|e| match unit {
+                    Unit::Native => eyre!("amount {}: {} (native currency has {} decimals)", self, e, native_decimals),
+                    _ => eyre!("amount {}: {}", self, e),
+                })
+            }
+        }
+    }
+}
+
+/// `amount` (digits with an optional fraction) times 10^`decimals`.
+fn scale(amount: &str, decimals: u8) -> Result<U256> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if !is_decimal(amount) {
+        return Err(eyre!("not a decimal number"));
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(eyre!("{} decimal places, at most {} allowed", fraction.len(), decimals));
+    }
+    let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    U256::from_dec_str(&digits).map_err(|e| eyre!("{:?}", e))
+}
+
+/// Digits with an optional fraction, e.g. `12` or `0.5`.
+fn is_decimal(n: &str) -> bool {
+    let (whole, fraction) = n.split_once('.').unwrap_or((n, ""));
+    !whole.is_empty() && whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit())
+}
+
+impl FromStr for Amount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        let invalid = || format!("invalid amount '{}': expected e.g. 1000wei, 2.5gwei, 0.001eth or 10%", s);
+        if let Some(percent) = s.strip_suffix('%') {
+            let percent = percent.trim();
+            if !is_decimal(percent) {
+                return Err(invalid());
+            }
+            let basis_points = scale(percent, 2).map_err(|_| invalid())?;
+            if basis_points > U256::from(10_000) {
+                return Err(invalid());
+            }
+            return Ok(Amount::Percent(basis_points.as_u64()));
+        }
+        let (amount, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(i) => s.split_at(i),
+            None => (s.as_str(), "eth"),
+        };
+        let unit = match unit {
+            "wei" => Unit::Wei,
+            "gwei" => Unit::Gwei,
+            "eth" | "ether" => Unit::Native,
+            _ => return Err(invalid()),
+        };
+        let amount = amount.trim();
+        if !is_decimal(amount) {
+            return Err(invalid());
+        }
+        Ok(Amount::Fixed { amount: amount.to_string(), unit })
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Amount::Percent(bp) if bp % 100 == 0 => write!(f, "{}%", bp / 100),
+            Amount::Percent(bp) => write!(f, "{}.{:02}%", bp / 100, bp % 100),
+            Amount::Fixed { amount, unit: Unit::Wei } => write!(f, "{}wei", amount),
+            Amount::Fixed { amount, unit: Unit::Gwei } => write!(f, "{}gwei", amount),
+            Amount::Fixed { amount, unit: Unit::Native } => write!(f, "{}eth", amount),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wei(s: &str, native_decimals: u8, balance: Option<u64>) -> Result<U256> {
+        let amount: Amount = s.parse().map_err(|e: String| eyre!(e))?;
+        amount.to_wei(native_decimals, balance.map(U256::from))
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(wei("1000wei", 18, None).unwrap(), U256::from(1000));
+        assert_eq!(wei("2.5gwei", 18, None).unwrap(), U256::from(2_500_000_000u64));
+        assert_eq!(wei("0.001eth", 18, None).unwrap(), U256::exp10(15));
+        assert_eq!(wei("0.001", 18, None).unwrap(), U256::exp10(15));
+    }
+
+    #[test]
+    fn too_many_decimals() {
+        assert!(wei("1.5wei", 18, None).is_err());
+        assert_eq!(wei("1.000wei", 18, None).unwrap(), U256::one());
+    }
+
+    #[test]
+    fn percent_of_balance() {
+        assert_eq!("10%".parse::<Amount>().unwrap(), Amount::Percent(1000));
+        assert_eq!("12.34%".parse::<Amount>().unwrap(), Amount::Percent(1234));
+        assert_eq!(wei("10%", 18, Some(1000)).unwrap(), U256::from(100));
+        assert!(wei("10%", 18, None).is_err());
+        assert!("100.01%".parse::<Amount>().is_err());
+        assert!("1.234%".parse::<Amount>().is_err());
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        for s in ["", "abc", ".5", "1.2.3", "-1", "1 btc", "%", "1e18"] {
+            assert!(s.parse::<Amount>().is_err(), "{:?} parsed", s);
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in ["1000wei", "2.5gwei", "0.001eth", "10%", "12.34%"] {
+            assert_eq!(s.parse::<Amount>().unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn other_native_decimals() {
+        assert_eq!(wei(" 1.5 ETHER ", 6, None).unwrap(), U256::from(1_500_000));
+        assert_eq!(wei("1gwei", 6, None).unwrap(), U256::exp10(9));
+        assert!(wei("0.0000001eth", 6, None).is_err());
+    }
+
+    #[test]
+    fn native() {
+        let native = Native::default();
+        assert_eq!(native.parse("0.5").unwrap(), U256::exp10(17) * 5);
+        assert_eq!(native.parse(" 2 ").unwrap(), U256::exp10(18) * 2);
+        for s in ["", " ", "abc", ".5", "1eth"] {
+            assert!(native.parse(s).is_err(), "{:?} parsed", s);
+        }
+        assert_eq!(native.format(U256::exp10(17) * 5), "0.500000000000000000 ETH");
+    }
+}
@@ -0,0 +1,65 @@
+//! Full JSON of every sent transaction, its receipt and the header of its block, as the node
+//! returned them, written to disk during the run so anomalies spotted in the summary can be
+//! investigated later without querying the node again (by then it may have pruned them).
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use eyre::{eyre, Result};
+use ethers::providers::Middleware;
+use ethers::types::H256;
+use serde_json::{json, Value};
+
+use crate::report::TxResult;
+
+/// Directory of dumps, one file per transaction: `<seq>-<series>-type-<n>.json`, numbered in
+/// the order the transactions settled.
+#[derive(Debug)]
+pub struct Archive {
+    dir: PathBuf,
+    written: AtomicUsize,
+}
+
+impl Archive {
+    /// Archive into `dir`, created if missing.
+    pub fn create(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).map_err(|e| eyre!("creating {}: {}", dir.display(), e))?;
+        Ok(Self { dir: dir.to_path_buf(), written: AtomicUsize::new(0) })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Fetch and write the transaction of `result`, with its receipt and block header once
+    /// it is mined, next to the result itself. Nothing is written for a result without a
+    /// hash, and any part the node no longer returns is `null`.
+    pub async fn record<M: Middleware>(&self, client: &M, series: &str, result: &TxResult) -> Result<Option<PathBuf>> {
+        let Some(hash) = result.tx_hash else { return Ok(None) };
+        let transaction = fetch(client, "eth_getTransactionByHash", json!([hash])).await?;
+        let receipt = fetch(client, "eth_getTransactionReceipt", json!([hash])).await?;
+        // The block the receipt points at, which is the one it was mined in even after a reorg.
+        let block = match receipt.get("blockHash").and_then(|h| serde_json::from_value::<H256>(h.clone()).ok()) {
+            Some(block_hash) => fetch(client, "eth_getBlockByHash", json!([block_hash, false])).await?,
+            None => Value::Null,
+        };
+        let dump = json!({
+            "series": series,
+            "result": result,
+            "transaction": transaction,
+            "receipt": receipt,
+            "block": block,
+        });
+
+        let n = self.written.fetch_add(1, Ordering::Relaxed) + 1;
+        let label: String = series.chars().map(|c| if c.is_ascii_alphanumeric() || c == '=' { c } else { '_' }).collect();
+        let path = self.dir.join(format!("{:03}-{}-type-{}.json", n, label, result.tx_type));
+        std::fs::write(&path, serde_json::to_string_pretty(&dump)?).map_err(|e| eyre!("writing {}: {}", path.display(), e))?;
+        Ok(Some(path))
+    }
+}
+
+async fn fetch<M: Middleware>(client: &M, method: &str, params: Value) -> Result<Value> {
+    client.provider().request(method, params).await.map_err(|e| eyre!("{}: {}", method, e))
+}
@@ -0,0 +1,162 @@
+//! Base fee over the course of a run: every block's `baseFeePerGas` is recorded in the
+//! background while transactions are in flight, so the inclusion (or not) of zero-fee
+//! transactions can be read against the base fee the chain was at.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ethers::providers::Middleware;
+use ethers::types::{BlockNumber, U256};
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::report::SeriesReport;
+
+/// Most blocks fetched to fill a gap between two polls, so a fast chain does not turn the
+/// tracker into a block scraper.
+const MAX_BACKFILL: u64 = 32;
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct BaseFeeSample {
+    pub block_number: u64,
+    pub timestamp: u64,
+    /// `None` before London or on chains without a base fee.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
+}
+
+/// A zero-fee transaction and the base fee of the block it landed in.
+#[derive(Clone, Debug, Serialize)]
+pub struct ZeroFeeInclusion {
+    pub series: String,
+    pub tx_type: u8,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BaseFeeReport {
+    pub samples: Vec<BaseFeeSample>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<U256>,
+    /// Every transaction of the `0` fee point.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub zero_fee: Vec<ZeroFeeInclusion>,
+}
+
+/// Background poller of new blocks; stops when finished or dropped.
+#[derive(Debug)]
+pub struct BaseFeeTracker {
+    samples: Arc<Mutex<BTreeMap<u64, BaseFeeSample>>>,
+    task: JoinHandle<()>,
+}
+
+impl BaseFeeTracker {
+    /// Start polling `client` for new blocks every `interval`.
+    pub fn start<M: Middleware + 'static>(client: Arc<M>, interval: Duration) -> Self {
+        let samples: Arc<Mutex<BTreeMap<u64, BaseFeeSample>>> = Arc::default();
+        let recorded = samples.clone();
+        let task = tokio::spawn(async move {
+            let mut last: Option<u64> = None;
+            loop {
+                if let Ok(head) = client.get_block_number().await.map(|n| n.as_u64()) {
+                    let from = match last {
+                        Some(last) => (last + 1).max(head.saturating_sub(MAX_BACKFILL - 1)),
+                        None => head,
+                    };
+                    for number in from..=head {
+                        if let Some(sample) = sample(client.as_ref(), number).await {
+                            recorded.lock().unwrap().insert(number, sample);
+                            last = Some(number);
+                        }
+                    }
+                }
+                sleep(interval).await;
+            }
+        });
+        Self { samples, task }
+    }
+
+    /// Stop polling and correlate the samples with the run's zero-fee transactions, fetching
+    /// the blocks they landed in if polling missed them.
+    pub async fn finish<M: Middleware>(self, client: &M, series: &[SeriesReport]) -> BaseFeeReport {
+        self.task.abort();
+        let mut samples = std::mem::take(&mut *self.samples.lock().unwrap());
+        let mut zero_fee = Vec::new();
+        // The fee point as given in `FEE_SERIES`.
+        for s in series.iter().filter(|s| s.fees == "0") {
+            for r in &s.results {
+                let base_fee_per_gas = match r.block_number {
+                    Some(number) => {
+                        if !samples.contains_key(&number)
+                            && let Some(sample) = sample(client, number).await
+                        {
+                            samples.insert(number, sample);
+                        }
+                        samples.get(&number).and_then(|s| s.base_fee_per_gas)
+                    }
+                    None => None,
+                };
+                zero_fee.push(ZeroFeeInclusion {
+                    series: s.label.clone(),
+                    tx_type: r.tx_type,
+                    status: r.status.clone(),
+                    block_number: r.block_number,
+                    base_fee_per_gas,
+                });
+            }
+        }
+        let samples: Vec<BaseFeeSample> = samples.into_values().collect();
+        let fees = || samples.iter().filter_map(|s| s.base_fee_per_gas);
+        BaseFeeReport { min: fees().min(), max: fees().max(), samples, zero_fee }
+    }
+}
+
+impl Drop for BaseFeeTracker {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn sample<M: Middleware>(client: &M, number: u64) -> Option<BaseFeeSample> {
+    let block = client.get_block(BlockNumber::Number(number.into())).await.ok()??;
+    Some(BaseFeeSample { block_number: number, timestamp: block.timestamp.as_u64(), base_fee_per_gas: block.base_fee_per_gas })
+}
+
+impl fmt::Display for BaseFeeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (Some(first), Some(last)) = (self.samples.first(), self.samples.last()) else {
+            return f.write_str("no blocks seen");
+        };
+        write!(f, "blocks {}-{} ({} sampled)", first.block_number, last.block_number, self.samples.len())?;
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => write!(f, ", base fee {}", min)?,
+            (Some(min), Some(max)) => write!(f, ", base fee {}-{}", min, max)?,
+            _ => f.write_str(", no base fee")?,
+        }
+        if self.zero_fee.is_empty() {
+            return Ok(());
+        }
+        let included: Vec<&ZeroFeeInclusion> = self.zero_fee.iter().filter(|z| z.block_number.is_some()).collect();
+        let at_zero = included.iter().filter(|z| z.base_fee_per_gas.is_none_or(|b| b.is_zero())).count();
+        write!(
+            f,
+            "; zero-fee txs: {} of {} included, {} at a zero base fee",
+            included.len(),
+            self.zero_fee.len(),
+            at_zero
+        )?;
+        if let Some(max) = included.iter().filter_map(|z| z.base_fee_per_gas).max().filter(|m| !m.is_zero()) {
+            write!(f, ", up to base fee {}", max)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+//! EIP-4844 (type 3) blob transactions, built and encoded by hand since ethers'
+//! `TypedTransaction` has no variant for them.
+
+use c_kzg::{ethereum_kzg_settings, Blob, Bytes48, BYTES_PER_BLOB, BYTES_PER_FIELD_ELEMENT};
+use eyre::{eyre, Result};
+use ethers::types::transaction::eip2930::AccessList;
+use ethers::types::{Address, Bytes, Signature, H256, U256};
+use ethers::utils::keccak256;
+use ethers::utils::rlp::RlpStream;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+pub const BLOB_TX_TYPE: u8 = 3;
+
+/// Gas limit for a plain blob-carrying transfer (blob gas is accounted separately).
+pub const BLOB_TX_GAS: u64 = 21_000;
+
+/// Blob gas consumed per blob.
+pub const GAS_PER_BLOB: u64 = 1 << 17;
+
+/// Maximum number of blobs per transaction (Cancun).
+pub const MAX_BLOBS_PER_TX: usize = 6;
+
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+// Only the low 31 bytes of each field element carry data so every element stays below the
+// BLS modulus.
+const USABLE_BYTES_PER_FIELD_ELEMENT: usize = BYTES_PER_FIELD_ELEMENT - 1;
+const USABLE_BYTES_PER_BLOB: usize =
+    BYTES_PER_BLOB / BYTES_PER_FIELD_ELEMENT * USABLE_BYTES_PER_FIELD_ELEMENT;
+
+/// Where blob contents come from.
+#[derive(Clone, Debug)]
+pub enum BlobSource {
+    /// The given number of blobs filled with random field elements.
+    Random(usize),
+    /// User-supplied bytes, packed 31 bytes per field element across as many blobs as needed.
+    Data(Vec<u8>),
+}
+
+impl Default for BlobSource {
+    fn default() -> Self {
+        BlobSource::Random(1)
+    }
+}
+
+impl BlobSource {
+    /// Number of blobs a sidecar built from this source carries.
+    pub fn blob_count(&self) -> usize {
+        match self {
+            BlobSource::Random(n) => *n,
+            BlobSource::Data(data) => data.len().div_ceil(USABLE_BYTES_PER_BLOB),
+        }
+    }
+}
+
+/// Blobs plus their KZG commitments and proofs, as gossiped alongside the transaction.
+pub struct Sidecar {
+    pub blobs: Vec<Blob>,
+    pub commitments: Vec<Bytes48>,
+    pub proofs: Vec<Bytes48>,
+}
+
+impl Sidecar {
+    pub fn build(source: &BlobSource) -> Result<Self> {
+        let blobs = match source {
+            BlobSource::Random(n) => (0..*n).map(|_| random_blob()).collect::<Vec<_>>(),
+            BlobSource::Data(data) => pack_blobs(data),
+        };
+        if blobs.is_empty() || blobs.len() > MAX_BLOBS_PER_TX {
+            return Err(eyre!("blob count must be 1..={}, got {}", MAX_BLOBS_PER_TX, blobs.len()));
+        }
+
+        let settings = ethereum_kzg_settings(0);
+        let mut commitments = Vec::with_capacity(blobs.len());
+        let mut proofs = Vec::with_capacity(blobs.len());
+        for blob in &blobs {
+            let commitment = settings
+                .blob_to_kzg_commitment(blob)
+                .map_err(|e| eyre!("kzg commitment: {:?}", e))?
+                .to_bytes();
+            let proof = settings
+                .compute_blob_kzg_proof(blob, &commitment)
+                .map_err(|e| eyre!("kzg proof: {:?}", e))?
+                .to_bytes();
+            commitments.push(commitment);
+            proofs.push(proof);
+        }
+        Ok(Self { blobs, commitments, proofs })
+    }
+
+    pub fn versioned_hashes(&self) -> Vec<H256> {
+        self.commitments.iter().map(|c| versioned_hash(c.as_ref())).collect()
+    }
+}
+
+/// Versioned hash a transaction names a blob by: the SHA-256 of its KZG commitment, with the
+/// first byte replaced by the version.
+pub fn versioned_hash(commitment: &[u8]) -> H256 {
+    let mut hash: [u8; 32] = Sha256::digest(commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    H256(hash)
+}
+
+fn random_blob() -> Blob {
+    let mut bytes = [0u8; BYTES_PER_BLOB];
+    let mut rng = rand::thread_rng();
+    for fe in bytes.chunks_mut(BYTES_PER_FIELD_ELEMENT) {
+        rng.fill_bytes(&mut fe[1..]);
+    }
+    Blob::new(bytes)
+}
+
+fn pack_blobs(data: &[u8]) -> Vec<Blob> {
+    data.chunks(USABLE_BYTES_PER_BLOB)
+        .map(|chunk| {
+            let mut bytes = [0u8; BYTES_PER_BLOB];
+            for (fe, src) in bytes
+                .chunks_mut(BYTES_PER_FIELD_ELEMENT)
+                .zip(chunk.chunks(USABLE_BYTES_PER_FIELD_ELEMENT))
+            {
+                fe[1..=src.len()].copy_from_slice(src);
+            }
+            Blob::new(bytes)
+        })
+        .collect()
+}
+
+/// Unsigned EIP-4844 transaction body.
+#[derive(Clone, Debug, Default)]
+pub struct BlobTransaction {
+    pub chain_id: u64,
+    pub nonce: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas: U256,
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    pub access_list: AccessList,
+    pub max_fee_per_blob_gas: U256,
+    pub blob_versioned_hashes: Vec<H256>,
+}
+
+impl BlobTransaction {
+    /// Hash to sign: `keccak256(0x03 || rlp(fields))`.
+    pub fn sighash(&self) -> H256 {
+        let mut s = RlpStream::new_list(11);
+        self.append_fields(&mut s);
+        keccak256(envelope(&s.out())).into()
+    }
+
+    /// Transaction hash: `keccak256(0x03 || rlp(fields ++ [y_parity, r, s]))`.
+    pub fn hash(&self, sig: &Signature) -> H256 {
+        let mut s = RlpStream::new();
+        self.append_signed(&mut s, sig);
+        keccak256(envelope(&s.out())).into()
+    }
+
+    /// Network form accepted by `eth_sendRawTransaction`, including the sidecar.
+    pub fn rlp_network(&self, sig: &Signature, sidecar: &Sidecar) -> Bytes {
+        let mut s = RlpStream::new_list(4);
+        self.append_signed(&mut s, sig);
+        s.begin_list(sidecar.blobs.len());
+        for blob in &sidecar.blobs {
+            s.append(&blob.as_ref());
+        }
+        s.begin_list(sidecar.commitments.len());
+        for c in &sidecar.commitments {
+            s.append(&&c[..]);
+        }
+        s.begin_list(sidecar.proofs.len());
+        for p in &sidecar.proofs {
+            s.append(&&p[..]);
+        }
+        envelope(&s.out()).into()
+    }
+
+    fn append_signed(&self, s: &mut RlpStream, sig: &Signature) {
+        s.begin_list(14);
+        self.append_fields(s);
+        s.append(&y_parity(sig));
+        s.append(&sig.r);
+        s.append(&sig.s);
+    }
+
+    fn append_fields(&self, s: &mut RlpStream) {
+        s.append(&self.chain_id);
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas);
+        s.append(&self.to);
+        s.append(&self.value);
+        s.append(&self.data.as_ref());
+        s.append(&self.access_list);
+        s.append(&self.max_fee_per_blob_gas);
+        s.append_list(&self.blob_versioned_hashes);
+    }
+}
+
+// Signatures from `sign_hash` carry a legacy `v` of 27/28.
+pub(crate) fn y_parity(sig: &Signature) -> u64 {
+    if sig.v >= 27 { sig.v - 27 } else { sig.v }
+}
+
+fn envelope(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 1);
+    out.push(BLOB_TX_TYPE);
+    out.extend_from_slice(payload);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_31_bytes_per_field_element() {
+        let data: Vec<u8> = (1..=100).collect();
+        let blobs = pack_blobs(&data);
+        assert_eq!(blobs.len(), 1);
+        let bytes: &[u8] = blobs[0].as_ref();
+        let elements: Vec<&[u8]> = bytes.chunks(BYTES_PER_FIELD_ELEMENT).collect();
+        assert_eq!(elements[0], &[&[0u8][..], &data[..31]].concat()[..]);
+        assert_eq!(elements[1][1..], data[31..62]);
+        assert_eq!(elements[3][1..=7], data[93..]);
+        assert!(elements[3][8..].iter().all(|b| *b == 0));
+        assert!(elements[4..].iter().all(|fe| fe.iter().all(|b| *b == 0)));
+    }
+
+    #[test]
+    fn top_byte_stays_zero() {
+        let blobs = pack_blobs(&vec![0xff; USABLE_BYTES_PER_BLOB]);
+        let bytes: &[u8] = blobs[0].as_ref();
+        assert!(bytes.chunks(BYTES_PER_FIELD_ELEMENT).all(|fe| fe[0] == 0 && fe[1..].iter().all(|b| *b == 0xff)));
+    }
+
+    #[test]
+    fn spills_into_more_blobs() {
+        let data = vec![7; USABLE_BYTES_PER_BLOB + 1];
+        let blobs = pack_blobs(&data);
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(BlobSource::Data(data).blob_count(), 2);
+        let second: &[u8] = blobs[1].as_ref();
+        assert_eq!(&second[..2], &[0, 7]);
+        assert!(second[2..].iter().all(|b| *b == 0));
+        assert_eq!(BlobSource::Data(vec![1; USABLE_BYTES_PER_BLOB]).blob_count(), 1);
+    }
+
+    #[test]
+    fn y_parity_from_legacy_v() {
+        for (v, parity) in [(27, 0), (28, 1), (0, 0), (1, 1)] {
+            assert_eq!(y_parity(&Signature { r: U256::one(), s: U256::one(), v }), parity);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Worst-case cost of matrix transactions, for the pre-flight balance and spend checks.
+
+use ethers::types::U256;
+
+use crate::blob::GAS_PER_BLOB;
+use crate::escalator::EscalationPolicy;
+use crate::zksync::ZKSYNC_EIP712_TX_TYPE;
+use crate::{Fees, Workload};
+
+/// Gas assumed for a plain transfer.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Upper bound on the gas per calldata byte: the EIP-7623 floor price of a non-zero byte.
+const GAS_PER_CALLDATA_BYTE: u64 = 40;
+
+/// Gas assumed for a token transfer or the counter deployment; both need well under this.
+const CALL_GAS: u64 = 200_000;
+
+/// Most a single tx of `tx_type` can cost the sender: value plus gas limit times the highest
+/// fee it may be (re)signed with, plus blob gas for type 3. `calldata` is the length of the
+/// `TX_DATA` a native transfer carries. Types the matrix cannot build cost nothing.
+pub fn worst_case_cost(
+    tx_type: u8,
+    workload: Workload,
+    value: U256,
+    calldata: usize,
+    fees: &Fees,
+    blobs: usize,
+    escalation: Option<&EscalationPolicy>,
+) -> U256 {
+    let escalate = |fee: U256| match escalation {
+        Some(policy) => (0..policy.max_bumps).fold(fee, |v, _| (v * (100 + policy.bump_percent) / 100).max(v + 1)),
+        None => fee,
+    };
+    let (value, gas) = match workload {
+        Workload::Native => (value, TRANSFER_GAS + GAS_PER_CALLDATA_BYTE * calldata as u64),
+        Workload::Erc20 { .. } | Workload::Deploy => (U256::zero(), CALL_GAS),
+    };
+    let fee_per_gas = escalate(fees.gas_price.unwrap_or_default().max(fees.max_fee_per_gas));
+    match tx_type {
+        0..=2 => value + fee_per_gas * gas,
+        3 if !matches!(workload, Workload::Deploy) => {
+            let blob_gas = U256::from(GAS_PER_BLOB) * blobs;
+            value + fee_per_gas * gas + escalate(fees.max_fee_per_blob_gas) * blob_gas
+        }
+        // zkSync charges pubdata on top of execution, so even a transfer runs into six figures
+        // (the paymaster may cover it, but the sender cannot count on that).
+        ZKSYNC_EIP712_TX_TYPE if !matches!(workload, Workload::Deploy) => value + fee_per_gas * gas.max(CALL_GAS),
+        _ => U256::zero(),
+    }
+}
@@ -0,0 +1,178 @@
+//! Per-type transaction builders. Each EIP-2718 type the matrix can send is a
+//! [`TxTypeBuilder`] registered in [`TxBuilders`] under its type byte, so chain-specific types
+//! (see [`extra`](crate::extra)) live in their own modules, or outside the crate, and are
+//! plugged in with [`TxMatrix::with_builder`](crate::TxMatrix::with_builder).
+//!
+//! Types 3 and 0x71 need a sidecar or an EIP-712 signature and stay with the matrix itself.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, LazyLock};
+
+use eyre::{eyre, Result};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip2930::AccessList;
+use ethers::types::{
+    Address, Bytes, Eip1559TransactionRequest, Eip2930TransactionRequest, NameOrAddress, Signature, TransactionRequest,
+    H256, U256,
+};
+use serde_json::Value;
+
+use crate::zksync::ZKSYNC_EIP712_TX_TYPE;
+use crate::Fees;
+
+/// Builds the unsigned native transfer of one tx type, and signs and encodes it when the type
+/// has an envelope of its own.
+pub trait TxTypeBuilder: Send + Sync {
+    /// The EIP-2718 type byte (0 for legacy).
+    fn tx_type(&self) -> u8;
+
+    fn build(&self, from: Address, to: Address, value: U256, fees: Fees) -> Result<TypedTransaction>;
+
+    /// Whether the type is encoded by [`sighash`](Self::sighash) and
+    /// [`rlp_signed`](Self::rlp_signed) rather than as the `TypedTransaction` it builds. Such
+    /// types are signed as a bare hash and skip access lists, simulation and fee escalation.
+    fn custom_envelope(&self) -> bool {
+        false
+    }
+
+    /// Hash the sender signs.
+    fn sighash(&self, tx: &TypedTransaction) -> Result<H256> {
+        Ok(tx.sighash())
+    }
+
+    /// Signed envelope accepted by `eth_sendRawTransaction`.
+    fn rlp_signed(&self, tx: &TypedTransaction, sig: &Signature) -> Result<Bytes> {
+        Ok(tx.rlp_signed(sig))
+    }
+
+    /// JSON form of `tx` for `eth_estimateGas`.
+    fn request(&self, tx: &TypedTransaction) -> Result<Value> {
+        Ok(serde_json::to_value(tx)?)
+    }
+}
+
+/// Builders by type byte.
+#[derive(Clone)]
+pub struct TxBuilders {
+    by_type: BTreeMap<u8, Arc<dyn TxTypeBuilder>>,
+}
+
+impl fmt::Debug for TxBuilders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.by_type.keys()).finish()
+    }
+}
+
+impl Default for TxBuilders {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl TxBuilders {
+    /// Types 0, 1 and 2.
+    pub fn standard() -> Self {
+        let mut builders = Self { by_type: BTreeMap::new() };
+        builders.register(Arc::new(Legacy));
+        builders.register(Arc::new(AccessListTx));
+        builders.register(Arc::new(DynamicFee));
+        builders
+    }
+
+    /// Add `builder`, replacing any registered for the same type.
+    pub fn register(&mut self, builder: Arc<dyn TxTypeBuilder>) {
+        self.by_type.insert(builder.tx_type(), builder);
+    }
+
+    pub fn get(&self, tx_type: u8) -> Option<&Arc<dyn TxTypeBuilder>> {
+        self.by_type.get(&tx_type)
+    }
+
+    /// Build with the builder of `tx_type`, or explain why there is none.
+    pub fn build(&self, tx_type: u8, from: Address, to: Address, value: U256, fees: Fees) -> Result<TypedTransaction> {
+        if let Some(builder) = self.get(tx_type) {
+            return builder.build(from, to, value, fees);
+        }
+        match tx_type {
+            // 3: EIP-4844 (blob), not representable as a TypedTransaction
+            3 => Err(eyre!("type 3 requires a blob sidecar; see blob::BlobTransaction")),
+            // 0x71: zkSync EIP-712, signed over typed data
+            ZKSYNC_EIP712_TX_TYPE => Err(eyre!("type 0x71 is signed over EIP-712; see zksync::Eip712Transaction")),
+            // 4..=5: Not supported by current ethers typed transaction API
+            4..=5 => Err(eyre!(
+                "unsupported by current ethers TypedTransaction (no variant for type {})",
+                tx_type
+            )),
+            // Any other value: error
+            _ => Err(eyre!("unknown tx type {}", tx_type)),
+        }
+    }
+}
+
+static STANDARD: LazyLock<TxBuilders> = LazyLock::new(TxBuilders::standard);
+
+/// Build an unsigned native transfer of the given EIP-2718 type.
+///
+/// Type 3 needs a blob sidecar and is built by [`blob::BlobTransaction`](crate::blob::BlobTransaction) instead.
+pub fn build_tx(tx_type: u8, from: Address, to: Address, value: U256, fees: Fees) -> Result<TypedTransaction> {
+    STANDARD.build(tx_type, from, to, value, fees)
+}
+
+/// 0: Legacy
+pub struct Legacy;
+
+impl TxTypeBuilder for Legacy {
+    fn tx_type(&self) -> u8 {
+        0
+    }
+
+    fn build(&self, from: Address, to: Address, value: U256, fees: Fees) -> Result<TypedTransaction> {
+        Ok(legacy_request(from, to, value, fees.gas_price).into())
+    }
+}
+
+/// 1: EIP-2930 (access list)
+pub struct AccessListTx;
+
+impl TxTypeBuilder for AccessListTx {
+    fn tx_type(&self) -> u8 {
+        1
+    }
+
+    fn build(&self, from: Address, to: Address, value: U256, fees: Fees) -> Result<TypedTransaction> {
+        let legacy = legacy_request(from, to, value, fees.gas_price);
+        Ok(Eip2930TransactionRequest::new(legacy, AccessList::default()).into())
+    }
+}
+
+/// 2: EIP-1559
+pub struct DynamicFee;
+
+impl TxTypeBuilder for DynamicFee {
+    fn tx_type(&self) -> u8 {
+        2
+    }
+
+    fn build(&self, from: Address, to: Address, value: U256, fees: Fees) -> Result<TypedTransaction> {
+        let tx = Eip1559TransactionRequest {
+            from: Some(from),
+            to: Some(NameOrAddress::Address(to)),
+            value: Some(value),
+            max_priority_fee_per_gas: Some(fees.max_priority_fee_per_gas),
+            max_fee_per_gas: Some(fees.max_fee_per_gas),
+            ..Default::default()
+        };
+        Ok(tx.into())
+    }
+}
+
+fn legacy_request(from: Address, to: Address, value: U256, gas_price: Option<U256>) -> TransactionRequest {
+    TransactionRequest {
+        from: Some(from),
+        to: Some(NameOrAddress::Address(to)),
+        value: Some(value),
+        gas_price,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,157 @@
+//! Known chains, so `--chain base-sepolia` can stand in for a chain id, the native currency,
+//! an explorer and fee defaults that suit the chain. Chains missing here can be looked up on
+//! chainlist.
+
+use eyre::{eyre, Result};
+use ethers::types::H256;
+use serde::{Deserialize, Serialize};
+
+/// Where chainlist publishes its registry.
+const CHAINLIST_URL: &str = "https://chainid.network/chains.json";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Chain {
+    pub name: String,
+    pub chain_id: u64,
+    pub native_symbol: String,
+    pub native_decimals: u8,
+    /// Explorer base URL, without a trailing slash.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explorer: Option<String>,
+    /// Suggested `PRIORITY_GWEI`.
+    pub priority_gwei: String,
+    /// Suggested `FEE_MULTIPLIER`.
+    pub fee_multiplier: u64,
+}
+
+impl Chain {
+    /// Explorer page of the transaction `hash`, if the chain has an explorer.
+    pub fn tx_url(&self, hash: H256) -> Option<String> {
+        self.explorer.as_deref().map(|explorer| tx_url(explorer, hash))
+    }
+}
+
+/// `<explorer>/tx/<hash>`, the layout Etherscan, Blockscout and their clones share.
+pub fn tx_url(explorer: &str, hash: H256) -> String {
+    format!("{}/tx/0x{:x}", explorer, hash)
+}
+
+/// (name, chain id, native symbol, native decimals, explorer, priority gwei, fee multiplier).
+type Preset = (&'static str, u64, &'static str, u8, Option<&'static str>, &'static str, u64);
+
+// L2s charge a tiny fraction of L1 priority fees; Polygon's validators ignore tips below 25 gwei.
+const PRESETS: [Preset; 22] = [
+    ("mainnet", 1, "ETH", 18, Some("https://etherscan.io"), "2", 2),
+    ("sepolia", 11155111, "ETH", 18, Some("https://sepolia.etherscan.io"), "2", 2),
+    ("holesky", 17000, "ETH", 18, Some("https://holesky.etherscan.io"), "2", 2),
+    ("hoodi", 560048, "ETH", 18, Some("https://hoodi.etherscan.io"), "2", 2),
+    ("optimism", 10, "ETH", 18, Some("https://optimistic.etherscan.io"), "0.001", 2),
+    ("optimism-sepolia", 11155420, "ETH", 18, Some("https://sepolia-optimism.etherscan.io"), "0.001", 2),
+    ("base", 8453, "ETH", 18, Some("https://basescan.org"), "0.001", 2),
+    ("base-sepolia", 84532, "ETH", 18, Some("https://sepolia.basescan.org"), "0.001", 2),
+    ("arbitrum", 42161, "ETH", 18, Some("https://arbiscan.io"), "0", 2),
+    ("arbitrum-sepolia", 421614, "ETH", 18, Some("https://sepolia.arbiscan.io"), "0", 2),
+    ("polygon", 137, "POL", 18, Some("https://polygonscan.com"), "30", 2),
+    ("polygon-amoy", 80002, "POL", 18, Some("https://amoy.polygonscan.com"), "30", 2),
+    ("bsc", 56, "BNB", 18, Some("https://bscscan.com"), "1", 2),
+    ("bsc-testnet", 97, "tBNB", 18, Some("https://testnet.bscscan.com"), "1", 2),
+    ("gnosis", 100, "xDAI", 18, Some("https://gnosisscan.io"), "1", 2),
+    ("gnosis-chiado", 10200, "xDAI", 18, Some("https://gnosis-chiado.blockscout.com"), "1", 2),
+    ("linea-sepolia", 59141, "ETH", 18, Some("https://sepolia.lineascan.build"), "0.1", 2),
+    ("scroll-sepolia", 534351, "ETH", 18, Some("https://sepolia.scrollscan.com"), "0.001", 2),
+    ("zksync-sepolia", 300, "ETH", 18, Some("https://sepolia.explorer.zksync.io"), "0", 2),
+    ("avalanche-fuji", 43113, "AVAX", 18, Some("https://testnet.snowtrace.io"), "1", 2),
+    ("anvil", 31337, "ETH", 18, None, "1", 2),
+    ("hardhat", 31337, "ETH", 18, None, "1", 2),
+];
+
+/// Arbitrum One, Nova and Sepolia, whose `gasUsed` includes the gas bought to post the
+/// transaction to L1, an amount that moves with the L1 price.
+const L1_GAS_IN_GAS_USED: [u64; 3] = [42161, 42170, 421614];
+
+/// Whether the chain folds L1 gas into `gasUsed`, so a plain transfer uses more than its
+/// intrinsic gas.
+pub fn counts_l1_gas(chain_id: u64) -> bool {
+    L1_GAS_IN_GAS_USED.contains(&chain_id)
+}
+
+fn preset(entry: &Preset) -> Chain {
+    let (name, chain_id, symbol, decimals, explorer, priority_gwei, fee_multiplier) = *entry;
+    Chain {
+        name: name.into(),
+        chain_id,
+        native_symbol: symbol.into(),
+        native_decimals: decimals,
+        explorer: explorer.map(Into::into),
+        priority_gwei: priority_gwei.into(),
+        fee_multiplier,
+    }
+}
+
+/// The preset called `name`, or with `name` as its chain id.
+pub fn by_name(name: &str) -> Option<Chain> {
+    let name = name.trim().to_ascii_lowercase();
+    PRESETS.iter().find(|p| p.0 == name || p.1.to_string() == name).map(preset)
+}
+
+/// The first preset for `chain_id`.
+pub fn by_id(chain_id: u64) -> Option<Chain> {
+    PRESETS.iter().find(|p| p.1 == chain_id).map(preset)
+}
+
+/// Names of every preset, for error messages.
+pub fn names() -> Vec<&'static str> {
+    PRESETS.iter().map(|p| p.0).collect()
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChainlistEntry {
+    name: String,
+    short_name: String,
+    chain_id: u64,
+    native_currency: NativeCurrency,
+    #[serde(default)]
+    explorers: Vec<Explorer>,
+}
+
+#[derive(Deserialize)]
+struct NativeCurrency {
+    symbol: String,
+    decimals: u8,
+}
+
+#[derive(Deserialize)]
+struct Explorer {
+    url: String,
+}
+
+/// Look `name` (a chain id, chainlist short name, or full name) up on chainlist. Fee
+/// suggestions are the L1 defaults since chainlist has none.
+pub async fn fetch(name: &str) -> Result<Chain> {
+    let entries: Vec<ChainlistEntry> = reqwest::get(CHAINLIST_URL)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| eyre!("fetching {}: {}", CHAINLIST_URL, e))?
+        .json()
+        .await
+        .map_err(|e| eyre!("parsing {}: {}", CHAINLIST_URL, e))?;
+    let wanted = name.trim().to_ascii_lowercase();
+    let entry = entries
+        .into_iter()
+        .find(|e| {
+            e.chain_id.to_string() == wanted
+                || e.short_name.to_ascii_lowercase() == wanted
+                || e.name.to_ascii_lowercase().replace(' ', "-") == wanted
+        })
+        .ok_or_else(|| eyre!("chain '{}' is neither a preset nor on chainlist", name))?;
+    Ok(Chain {
+        name: entry.name,
+        chain_id: entry.chain_id,
+        native_symbol: entry.native_currency.symbol,
+        native_decimals: entry.native_currency.decimals,
+        explorer: entry.explorers.into_iter().next().map(|e| e.url.trim_end_matches('/').to_string()),
+        priority_gwei: "2".into(),
+        fee_multiplier: 2,
+    })
+}
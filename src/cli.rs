@@ -0,0 +1,1217 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use test_transaction_type::amount::Amount;
+use test_transaction_type::scenarios::calldata_sweep::DataSize;
+use test_transaction_type::scenarios::envelope_fuzz::{Payload, FIRST_FUZZ_TYPE, LAST_FUZZ_TYPE};
+use test_transaction_type::private::DEFAULT_MAX_BLOCKS;
+use test_transaction_type::zksync::DEFAULT_GAS_PER_PUBDATA;
+use test_transaction_type::FeeSeries;
+
+/// Probe which EIP-2718 transaction types and fee levels a chain accepts.
+///
+/// Every flag falls back to the environment variable named in its help text,
+/// which in turn may come from a `.env` file.
+#[derive(Debug, Parser)]
+#[command(name = "test-transaction", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Format of the progress log on stderr; verbosity follows `RUST_LOG` (default `info`).
+    #[arg(long, env = "LOG_FORMAT", global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Send one native transfer per tx type and fee level (default).
+    Send(Box<SendArgs>),
+    /// Sign the matrix without sending and write each raw transaction to a file.
+    ExportRaw(Box<ExportRawArgs>),
+    /// Broadcast raw signed transactions, e.g. written by `export-raw` on another machine.
+    SendRaw(SendRawArgs),
+    /// Decode raw signed transactions: print every field, recover the sender and check the
+    /// signature.
+    Decode(DecodeArgs),
+    /// Run a targeted multi-step scenario.
+    #[command(subcommand)]
+    Scenario(ScenarioCommand),
+    /// Send transactions of one type at a fixed rate and report throughput and latency.
+    Load(LoadArgs),
+    /// Query the node, without sending anything, and predict which tx types and fee levels
+    /// it should accept.
+    Probe(ProbeArgs),
+    /// Compare two runs and list tx types whose status changed per fee series.
+    Diff(DiffArgs),
+    /// Replace the transactions stuck between the sender's latest and pending nonce with
+    /// priced 0-value self-transfers, so later runs are not blocked behind them.
+    Unstick(UnstickArgs),
+    /// Send each sender's whole balance, less the transfer fee, to a treasury or faucet address.
+    Sweep(SweepArgs),
+    /// Send the transfer from an ERC-4337 smart account as a UserOperation through a bundler.
+    Userop(UserOpArgs),
+    /// Sign an EIP-712 payload and check the signature through the chain's `ecrecover`
+    /// precompile and, optionally, an EIP-1271 contract.
+    SignTyped(SignTypedArgs),
+    /// Serve an HTTP API that queues `send` runs (`POST /runs`) and returns their reports
+    /// (`GET /runs/{id}`).
+    Serve(ServeArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ScenarioCommand {
+    /// Submit an underpriced tx, then cancel it with a same-nonce 0-value self-transfer at
+    /// higher fees, and report which of the two gets mined.
+    CancelReplace(CancelReplaceArgs),
+    /// Send 0-value transfers with growing random calldata at one fee level and report the
+    /// largest size the chain accepts.
+    CalldataSweep(CalldataSweepArgs),
+    /// Send the same transfer with the gas limit left to the client, at the node's estimate,
+    /// and a margin below the estimate, and report which run out of gas.
+    GasLimit(GasLimitArgs),
+    /// Send type-2 transactions with invalid or borderline EIP-1559 fee combinations and
+    /// report whether each is rejected at submission or at inclusion.
+    FeeEdges(FeeEdgesArgs),
+    /// Send type-2 transfers at halving priority fees, then binary-search, and report the
+    /// lowest tip still included within a number of blocks.
+    PrioritySweep(PrioritySweepArgs),
+    /// Send legacy transfers at halving gas prices, then binary-search, and report the
+    /// minimum viable gas price.
+    GasPriceFloor(GasPriceFloorArgs),
+    /// Submit raw envelopes with unassigned type bytes and malformed payloads and catalog the
+    /// node's error responses.
+    EnvelopeFuzz(EnvelopeFuzzArgs),
+    /// Deposit through an OP Stack chain's L1 `OptimismPortal` and follow the resulting
+    /// deposited (type 0x7E) transaction onto L2.
+    OpDeposit(OpDepositArgs),
+    /// Submit an Arbitrum retryable ticket through the L1 `Inbox` and track its creation and
+    /// auto-redeem on L2.
+    Retryable(RetryableArgs),
+    /// Run the transactions described in a TOML plan file and check each against its
+    /// expected outcome.
+    Run(PlanArgs),
+}
+
+/// Where to send from.
+#[derive(Clone, Debug, Args)]
+pub struct ConnectionArgs {
+    /// RPC endpoint: http(s)://, ws(s):// or the path of a node's IPC socket (WebSocket and IPC
+    /// wait for receipts on pushed block heads). A comma-separated list falls over to the
+    /// next endpoint when one errors or times out.
+    #[arg(long, env = "RPC_URL", required_unless_present_any = ["offline", "local"])]
+    pub rpc_url: Option<String>,
+
+    #[command(flatten)]
+    pub client: RpcClientArgs,
+
+    /// Most requests per second to send to `RPC_URL`, e.g. a free tier's limit; requests
+    /// beyond it wait their turn.
+    #[arg(long, env = "MAX_RPS")]
+    pub max_rps: Option<f64>,
+
+    /// Most requests to send to `RPC_URL` over the whole run; later ones fail.
+    #[arg(long, env = "MAX_REQUESTS")]
+    pub max_requests: Option<u64>,
+
+    /// Spawn a throwaway Anvil node and run against it instead of `RPC_URL`; its first dev
+    /// account sends unless a key is configured. Needs `anvil` on PATH and `--features anvil`.
+    #[arg(long, env = "LOCAL", conflicts_with = "offline")]
+    pub local: bool,
+
+    /// With `--local`, fork this RPC's latest state instead of starting from an empty chain.
+    #[arg(long, env = "FORK_URL", requires = "local")]
+    pub fork_url: Option<String>,
+
+    /// Never contact the RPC: sign with `CHAIN_ID`, `--nonce` and `--gas-limit` and only print
+    /// or export the raw transactions (`send` and `export-raw`; implies `--dry-run`).
+    #[arg(long, env = "OFFLINE")]
+    pub offline: bool,
+
+    /// Signer backend: `local` (private key, mnemonic or keystore) or `ledger`.
+    #[arg(long, env = "SIGNER", value_enum, default_value_t = SignerKind::Local)]
+    pub signer: SignerKind,
+
+    /// Sender's private key (0x-prefixed); a comma-separated list runs from several senders.
+    #[arg(long, env = "PRIVATE_KEY", hide_env_values = true, value_delimiter = ',')]
+    pub private_key: Vec<String>,
+
+    /// BIP-39 mnemonic to derive the sender from instead of a private key.
+    #[arg(long, env = "MNEMONIC", hide_env_values = true)]
+    pub mnemonic: Option<String>,
+
+    /// Encrypted JSON keystore (web3 secret storage) holding the sender's key.
+    #[arg(long, env = "KEYSTORE_PATH")]
+    pub keystore: Option<PathBuf>,
+
+    /// Keystore password; prompted for (hidden) when unset.
+    #[arg(long, env = "KEYSTORE_PASSWORD", hide_env_values = true)]
+    pub keystore_password: Option<String>,
+
+    /// Never ask on the terminal (for a keystore password or to go over `MAX_SPEND_ETH`),
+    /// failing instead; set for runs queued through `serve`, which a question would stall.
+    #[arg(skip)]
+    pub no_prompt: bool,
+
+    /// BIP-44 path the account index is appended to (mnemonic and Ledger).
+    #[arg(long, env = "DERIVATION_PATH", default_value = "m/44'/60'/0'/0")]
+    pub derivation_path: String,
+
+    /// Account index under the derivation path.
+    #[arg(long, env = "ACCOUNT_INDEX", default_value_t = 0)]
+    pub account_index: u32,
+
+    /// Number of mnemonic accounts to send from, starting at the account index.
+    #[arg(long, env = "SENDER_COUNT", default_value_t = 1)]
+    pub sender_count: u32,
+
+    /// Chain id used for EIP-155 signing (default: the `--chain` preset's, else Sepolia).
+    #[arg(long, env = "CHAIN_ID")]
+    pub chain_id: Option<u64>,
+
+    /// Chain preset (`sepolia`, `base-sepolia`, ...) supplying the chain id, native currency,
+    /// explorer and fee defaults.
+    #[arg(long, env = "CHAIN")]
+    pub chain: Option<String>,
+
+    /// Decimals of the chain's native currency, for chains whose preset does not know them
+    /// (default: the preset's, else 18).
+    #[arg(long, env = "NATIVE_DECIMALS")]
+    pub native_decimals: Option<u8>,
+
+    /// Look `--chain` up on chainlist when it is not a built-in preset.
+    #[arg(long, env = "CHAINLIST")]
+    pub chainlist: bool,
+
+    /// Sign with the RPC's `eth_chainId` when it differs from `CHAIN_ID` instead of failing.
+    #[arg(long, env = "ADOPT_CHAIN_ID")]
+    pub adopt_chain_id: bool,
+
+    /// Chain ids refused unless `--i-know-what-im-doing` is passed (default: Ethereum, OP,
+    /// BNB, Gnosis, Polygon, zkSync Era, Base, Arbitrum One/Nova, Avalanche, Linea, Scroll).
+    #[arg(
+        long,
+        env = "PRODUCTION_CHAIN_IDS",
+        value_delimiter = ',',
+        default_value = "1,10,56,100,137,324,8453,42161,42170,43114,59144,534352"
+    )]
+    pub production_chain_ids: Vec<u64>,
+
+    /// Run against a production chain anyway. Deliberately has no environment variable.
+    #[arg(long = "i-know-what-im-doing")]
+    pub i_know_what_im_doing: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SignerKind {
+    Local,
+    /// Ledger device; needs the `ledger` build feature.
+    Ledger,
+}
+
+/// How `RPC_URL` is reached beyond the URL itself.
+#[derive(Clone, Debug, Args)]
+pub struct RpcClientArgs {
+    /// Extra HTTP headers for `RPC_URL`, comma-separated `Name: value` pairs such as
+    /// `Authorization: Bearer ...` (over WebSocket, `Authorization` only).
+    #[arg(long, env = "RPC_HEADERS", hide_env_values = true, value_delimiter = ',')]
+    pub rpc_headers: Vec<String>,
+
+    /// Proxy for RPC requests (`http://`, `https://`); without it `HTTPS_PROXY`, `HTTP_PROXY`
+    /// and `NO_PROXY` apply as usual.
+    #[arg(long, env = "RPC_PROXY")]
+    pub rpc_proxy: Option<String>,
+
+    /// PEM file of CA certificates to trust for `RPC_URL` besides the usual roots, e.g. an
+    /// internal devnet's.
+    #[arg(long, env = "RPC_CA_CERT")]
+    pub rpc_ca_cert: Option<PathBuf>,
+
+    /// Accept any TLS certificate from `RPC_URL`, self-signed or not: only for devnets you
+    /// control, since anyone on the path could then read and alter the traffic.
+    #[arg(long, env = "RPC_INSECURE_TLS")]
+    pub rpc_insecure_tls: bool,
+}
+
+/// Optional Prometheus endpoint for long-running runs.
+#[derive(Clone, Debug, Args)]
+pub struct MetricsArgs {
+    /// Serve Prometheus metrics on `http://<addr>/metrics` while the run lasts.
+    #[arg(long, env = "METRICS_ADDR")]
+    pub metrics_addr: Option<SocketAddr>,
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Address the run API listens on.
+    #[arg(long, env = "SERVE_ADDR", default_value = "127.0.0.1:8080")]
+    pub addr: SocketAddr,
+
+    /// Bearer token every request must carry (`Authorization: Bearer <token>`). Required
+    /// unless `addr` is a loopback address; unset there, any local request is served.
+    #[arg(long, env = "SERVE_TOKEN")]
+    pub token: Option<String>,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct SendArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+
+    /// Recipient address, a comma-separated list, or a file with one address per line; `self`
+    /// sends every transaction back to its sender.
+    #[arg(long, env = "TO_ADDRESS")]
+    pub to: String,
+
+    /// With several recipients: send every tx type to `each` of them, or `round-robin` one
+    /// recipient per transaction.
+    #[arg(long, env = "RECIPIENT_MODE", value_enum, default_value_t = RecipientMode::Each)]
+    pub recipient_mode: RecipientMode,
+
+    /// Amount of native token to send: `1000wei`, `2.5gwei`, `0.001eth` (a bare
+    /// number is whole native units) or `10%` of the sender's balance.
+    #[arg(long, env = "AMOUNT", default_value = "0.001eth")]
+    pub amount: Amount,
+
+    /// Send every matrix transaction with value 0, overriding `AMOUNT`, so only fee
+    /// acceptance is tested and the sender needs no balance beyond gas (none at all on
+    /// gasless chains).
+    #[arg(long, env = "ZERO_VALUE")]
+    pub zero_value: bool,
+
+    /// Priority fee in gwei for `market` fee points when the node reports no recent rewards
+    /// (default: the chain preset's, else 2).
+    #[arg(long, env = "PRIORITY_GWEI")]
+    pub priority_gwei: Option<String>,
+
+    /// Multiplier applied to the next block's base fee when computing the `market` max fee
+    /// (default: the chain preset's, else 2).
+    #[arg(long, env = "FEE_MULTIPLIER")]
+    pub fee_multiplier: Option<u64>,
+
+    /// ERC-20 token to send instead of the native token (each tx calls `transfer`).
+    #[arg(long, env = "TOKEN_ADDRESS")]
+    pub token: Option<String>,
+
+    /// Token amount per transfer, in whole tokens (scaled by the token's `decimals()`).
+    #[arg(long, env = "TOKEN_AMOUNT", default_value = "1")]
+    pub token_amount: String,
+
+    /// Blocks to wait for after a tx is mined (counting its own) before re-checking that its
+    /// receipt is still canonical; 1 only waits for inclusion.
+    #[arg(long, env = "CONFIRMATIONS", default_value_t = 1)]
+    pub confirmations: u64,
+
+    /// Blocks after a tx's own to keep re-checking its receipt for, marking it `reorged`
+    /// (with the depth) if it disappears or moves.
+    #[arg(long, env = "REORG_WATCH_BLOCKS", default_value_t = 0)]
+    pub reorg_watch_blocks: u64,
+
+    /// Sign type-0 transactions without EIP-155 replay protection (v = 27/28), to see whether
+    /// the chain still accepts them.
+    #[arg(long, env = "PRE_EIP155")]
+    pub pre_eip155: bool,
+
+    /// Calldata for every native transfer: 0x-prefixed hex, or `@path` to a binary file.
+    #[arg(long, env = "TX_DATA", conflicts_with_all = ["token", "deploy"])]
+    pub data: Option<String>,
+
+    /// Deploy a bundled counter contract with each tx type instead of transferring.
+    #[arg(long, env = "DEPLOY", conflicts_with = "token")]
+    pub deploy: bool,
+
+    /// Safe (v1.3 or later; address or ENS name) to send from: every transaction calls its
+    /// `execTransaction`, signed by the sender as its single owner, so the value or tokens
+    /// come from the Safe. Takes a single sender.
+    #[arg(long, env = "SAFE_ADDRESS", conflicts_with_all = ["deploy", "parallel"])]
+    pub safe: Option<String>,
+
+    /// Access list for type-1 txs: a JSON file path, or `auto` for `eth_createAccessList`.
+    #[arg(long, env = "ACCESS_LIST")]
+    pub access_list: Option<String>,
+
+    /// Second RPC endpoint to re-read every mined tx and receipt from; disagreements with the
+    /// submitting endpoint are reported as anomalies.
+    #[arg(long, env = "VERIFY_RPC_URL")]
+    pub verify_rpc_url: Option<String>,
+
+    /// Submit through this private relay (or Protect-style RPC) instead of the public mempool;
+    /// receipts are still read from `RPC_URL`.
+    #[arg(long, env = "PRIVATE_TX_URL", conflicts_with = "escalate_after_blocks")]
+    pub private_tx_url: Option<String>,
+
+    /// API of `PRIVATE_TX_URL`.
+    #[arg(long, env = "PRIVATE_TX_API", value_enum, default_value_t = PrivateApi::Relay)]
+    pub private_tx_api: PrivateApi,
+
+    /// Key relay requests are signed with (`X-Flashbots-Signature`); a throwaway key if unset.
+    #[arg(long, env = "FLASHBOTS_AUTH_KEY", hide_env_values = true)]
+    pub flashbots_auth_key: Option<String>,
+
+    /// Blocks a private transaction stays eligible for before the relay drops it.
+    #[arg(long, env = "PRIVATE_MAX_BLOCKS", default_value_t = DEFAULT_MAX_BLOCKS)]
+    pub private_max_blocks: u64,
+
+    /// Skip the post-receipt cross-check of tx fields, effective gas price and balance change.
+    #[arg(long, env = "NO_VERIFY")]
+    pub no_verify: bool,
+
+    /// Read balances and Safe state one call at a time instead of batching them through
+    /// Multicall3 when the chain has it deployed.
+    #[arg(long, env = "NO_MULTICALL")]
+    pub no_multicall: bool,
+
+    /// Offline: nonce of each sender's first transaction, incremented per transaction.
+    #[arg(long, env = "NONCE", required_if_eq("offline", "true"))]
+    pub nonce: Option<u64>,
+
+    /// Offline: gas limit of every transaction.
+    #[arg(long, env = "GAS_LIMIT", required_if_eq("offline", "true"))]
+    pub gas_limit: Option<u64>,
+
+    /// Refuse to run (or ask, on a terminal) when the worst-case spend across all senders
+    /// exceeds this many ether.
+    #[arg(long, env = "MAX_SPEND_ETH")]
+    pub max_spend_eth: Option<String>,
+
+    /// TOML file of faucets per chain; a sender holding less than `FAUCET_MIN_BALANCE_ETH`
+    /// requests funds from the current chain's faucet and waits for them before the run.
+    #[arg(long, env = "FAUCET_CONFIG")]
+    pub faucet_config: Option<PathBuf>,
+
+    /// Balance, in ether, below which a sender asks the faucet for funds.
+    #[arg(long, env = "FAUCET_MIN_BALANCE_ETH", default_value = "0.01", requires = "faucet_config")]
+    pub faucet_min_balance_eth: String,
+
+    /// Seconds to wait for requested faucet funds to arrive.
+    #[arg(long, env = "FAUCET_WAIT_SECS", default_value_t = 300)]
+    pub faucet_wait_secs: u64,
+
+    /// Comma-separated tx types to attempt.
+    #[arg(long, env = "TX_TYPES", value_delimiter = ',', default_value = "0,1,2,3,4,5")]
+    pub types: Vec<u8>,
+
+    /// Comma-separated fee points; each runs the matrix once. A point is a wei amount with an
+    /// optional unit (`1`, `1gwei`, `0.1eth`) applied to every fee field, or `market` for the
+    /// node's current suggestion.
+    #[arg(long, env = "FEE_SERIES", value_delimiter = ',', default_value = "0,1,market")]
+    pub fees: Vec<FeeSeries>,
+
+    /// File whose contents are packed into the type-3 blob sidecar (random blobs if unset).
+    #[arg(long, env = "BLOB_FILE")]
+    pub blob_file: Option<PathBuf>,
+
+    /// Number of random blobs to attach to type-3 transactions.
+    #[arg(long, env = "BLOB_COUNT", default_value_t = 1)]
+    pub blob_count: usize,
+
+    /// Paymaster that pays the fee of type-113 (zkSync 0x71) transactions.
+    #[arg(long, env = "ZKSYNC_PAYMASTER")]
+    pub paymaster: Option<String>,
+
+    /// Input passed to the paymaster, as hex or `@file`; the general flow (`general(bytes)`
+    /// with no data) if unset.
+    #[arg(long, env = "ZKSYNC_PAYMASTER_INPUT", requires = "paymaster")]
+    pub paymaster_input: Option<String>,
+
+    /// Gas per pubdata byte that type-113 transactions are willing to pay.
+    #[arg(long, env = "ZKSYNC_GAS_PER_PUBDATA", default_value_t = DEFAULT_GAS_PER_PUBDATA)]
+    pub gas_per_pubdata: u64,
+
+    /// TOML file describing a chain-specific tx type that extends type 1 or 2 with extra
+    /// fields (e.g. a Celo fee currency); it is sent when its type is in `TX_TYPES`.
+    #[arg(long, env = "EXTRA_FIELDS")]
+    pub extra_fields: Option<PathBuf>,
+
+    /// TOML list of compiled-in tx-type plugins to load, each with its settings; a plugin's
+    /// type is sent when it is in `TX_TYPES`.
+    #[arg(long, env = "TX_PLUGINS")]
+    pub plugins: Option<PathBuf>,
+
+    /// Build and sign every transaction, print the raw envelope and hash, but never broadcast.
+    #[arg(long, env = "DRY_RUN")]
+    pub dry_run: bool,
+
+    /// Run eth_call and eth_estimateGas before each send and report the verdict next to the result.
+    #[arg(long, env = "SIMULATE")]
+    pub simulate: bool,
+
+    /// Simulate as if the sender held this much ETH (an eth_call state override) and take the
+    /// gas limit from it, so `--dry-run --simulate` works from an unfunded address.
+    #[arg(long, env = "SIMULATE_BALANCE_ETH", requires = "simulate")]
+    pub simulate_balance_eth: Option<String>,
+
+    /// Fetch debug_traceTransaction (callTracer) for every mined tx and report its gas
+    /// breakdown and internal calls; the node must expose the debug namespace.
+    #[arg(long, env = "TRACE")]
+    pub trace: bool,
+
+    /// Decode every signed transaction back and compare it field by field with what was built,
+    /// then with the node's eth_getTransactionByHash once mined, reporting lossy fields as
+    /// anomalies.
+    #[arg(long, env = "ROUND_TRIP")]
+    pub round_trip: bool,
+
+    /// Record every block's base fee while the run is in flight and report it, with the base
+    /// fee each zero-fee (`0` fee point) tx was included at.
+    #[arg(long, env = "TRACK_BASE_FEE")]
+    pub track_base_fee: bool,
+
+    /// Write the full JSON of every sent transaction, its receipt and its block header to this
+    /// directory, one file per transaction.
+    #[arg(long, env = "ARCHIVE_DIR")]
+    pub archive_dir: Option<PathBuf>,
+
+    /// Command run by `sh -c` before each transaction is submitted, with its JSON (hash, raw
+    /// bytes and decoded fields) on stdin; the submission waits for it. Not run by `serve`.
+    #[arg(long, env = "PRE_SEND_CMD")]
+    pub pre_send_cmd: Option<String>,
+
+    /// Command run by `sh -c` once each transaction has a receipt, with its result and
+    /// receipt as JSON on stdin. Not run by `serve`.
+    #[arg(long, env = "POST_RECEIPT_CMD")]
+    pub post_receipt_cmd: Option<String>,
+
+    /// Show a live table of the matrix entries (series, type, status, confirmations, latency)
+    /// instead of progress logs; needs a build with `--features tui`.
+    #[arg(long, env = "TUI")]
+    pub tui: bool,
+
+    /// Submit every tx type of a series concurrently with pre-assigned nonces.
+    #[arg(long, env = "PARALLEL")]
+    pub parallel: bool,
+
+    /// Seconds to wait for each receipt before marking the entry `timeout` (no limit if unset).
+    #[arg(long, env = "TX_TIMEOUT_SECS")]
+    pub tx_timeout_secs: Option<u64>,
+
+    /// Seconds the whole run may take; pending waits are cut short and unstarted entries skipped.
+    #[arg(long, env = "RUN_DEADLINE_SECS")]
+    pub run_deadline_secs: Option<u64>,
+
+    /// Run the matrix again every interval (`90s`, `15m`, `1h`; a bare number is seconds)
+    /// until interrupted, alerting the webhook only when a tx type / fee point combination
+    /// changes status.
+    #[arg(long, env = "INTERVAL", value_parser = interval, conflicts_with_all = ["resume", "tui"])]
+    pub interval: Option<Duration>,
+
+    /// Seconds in-flight txs get to be mined after Ctrl-C before the partial summary is printed.
+    #[arg(long, env = "INTERRUPT_GRACE_SECS", default_value_t = 10)]
+    pub interrupt_grace_secs: u64,
+
+    /// Directory of run files, which track in-flight txs so an interrupted run can be resumed.
+    #[arg(long, env = "RUN_DIR", default_value = ".runs")]
+    pub run_dir: PathBuf,
+
+    /// Resume run `<run-id>`: wait for its in-flight txs and report its settled ones instead
+    /// of sending them again; the remaining entries run as usual.
+    #[arg(long, value_name = "RUN_ID", conflicts_with = "dry_run")]
+    pub resume: Option<String>,
+
+    /// Rebroadcast a tx with bumped fees once this many blocks pass without a receipt.
+    #[arg(long, env = "ESCALATE_AFTER_BLOCKS")]
+    pub escalate_after_blocks: Option<u64>,
+
+    /// Percentage added to every fee field on each bump.
+    #[arg(long, env = "ESCALATE_PERCENT", default_value_t = 10)]
+    pub escalate_percent: u64,
+
+    /// Maximum number of fee bumps per transaction.
+    #[arg(long, env = "ESCALATE_MAX_BUMPS", default_value_t = 3)]
+    pub escalate_max_bumps: u32,
+
+    /// Retries of a submission that failed transiently (rate limit, timeout, dropped
+    /// connection); 0 makes every failure final.
+    #[arg(long, env = "RETRY_MAX", default_value_t = 3)]
+    pub retry_max: u32,
+
+    /// Delay before the first retry in milliseconds; doubled (with jitter) on each further one.
+    #[arg(long, env = "RETRY_BASE_MS", default_value_t = 500)]
+    pub retry_base_ms: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    /// Append the run to this SQLite database (created if missing).
+    #[arg(long, env = "RESULTS_DB")]
+    pub db: Option<PathBuf>,
+
+    /// TOML file of expected statuses per tx type and fee point; exit with status 2 if any
+    /// result deviates.
+    #[arg(long, env = "EXPECT_FILE")]
+    pub expect: Option<PathBuf>,
+
+    /// POST a summary of the run here when it ends or fails, flagging results that deviate
+    /// from `--expect`.
+    #[arg(long, env = "WEBHOOK_URL")]
+    pub webhook_url: Option<String>,
+
+    /// Body of the webhook request.
+    #[arg(long, env = "WEBHOOK_FORMAT", value_enum, default_value_t = WebhookFormat::Json)]
+    pub webhook_format: WebhookFormat,
+
+    /// Only call the webhook when results deviate from `--expect` or the run fails.
+    #[arg(long, env = "WEBHOOK_ONLY_REGRESSIONS")]
+    pub webhook_only_regressions: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per event, with the series/tx span fields attached.
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum WebhookFormat {
+    /// The summary as JSON: event, totals per status, statuses per series and deviations.
+    Json,
+    /// A Slack incoming-webhook message.
+    Slack,
+    /// A Discord webhook message.
+    Discord,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum RecipientMode {
+    Each,
+    RoundRobin,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PrivateApi {
+    /// Flashbots-style relay: `eth_callBundle` simulation, then `eth_sendPrivateTransaction`.
+    Relay,
+    /// Private RPC taking `eth_sendRawTransaction`, e.g. Flashbots Protect.
+    Protect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PaymasterApi {
+    /// `pm_sponsorUserOperation` (Pimlico, Stackup).
+    Sponsor,
+    /// `pm_getPaymasterStubData` and `pm_getPaymasterData`.
+    Erc7677,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Per-series summary.
+    Text,
+    /// A single machine-readable report on stdout.
+    Json,
+    /// JUnit XML on stdout, one test case per tx type and fee series (`send` only).
+    Junit,
+}
+
+impl Cli {
+    /// The requested command, defaulting to `send` configured purely from env.
+    pub fn command(self) -> Command {
+        self.command
+            .unwrap_or_else(|| Cli::parse_from(["test-transaction", "send"]).command.expect("send subcommand"))
+    }
+}
+
+/// `--interval`: a number with an `s`, `m`, `h` or `d` suffix, or bare seconds.
+fn interval(s: &str) -> Result<Duration, String> {
+    let (number, unit) = match s.trim().find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.trim().split_at(i),
+        None => (s.trim(), "s"),
+    };
+    let n: u64 = number.parse().map_err(|_| format!("not a duration: {}", s))?;
+    let per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(format!("unknown unit '{}' (use s, m, h or d)", unit)),
+    };
+    let secs = n.checked_mul(per_unit).ok_or_else(|| format!("interval too long: {}", s))?;
+    if secs == 0 {
+        return Err("the interval must be positive".into());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+#[derive(Debug, Args)]
+pub struct CancelReplaceArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// Recipient of the original (underpriced) transfer.
+    #[arg(long, env = "TO_ADDRESS")]
+    pub to: String,
+
+    /// Amount of the original transfer: `1000wei`, `2.5gwei`, `0.001eth` (a bare
+    /// number is whole native units) or `10%` of the sender's balance.
+    #[arg(long, env = "AMOUNT", default_value = "0.001eth")]
+    pub amount: Amount,
+
+    /// Comma-separated tx types to run the scenario with (0, 1 or 2).
+    #[arg(long, value_delimiter = ',', default_value = "0,2")]
+    pub types: Vec<u8>,
+
+    /// Fee point for the deliberately underpriced original.
+    #[arg(long, default_value = "0")]
+    pub underpriced_fee: FeeSeries,
+
+    /// Fee point for the cancelling replacement.
+    #[arg(long, default_value = "market")]
+    pub replacement_fee: FeeSeries,
+
+    /// Seconds to wait for either transaction to be mined.
+    #[arg(long, default_value_t = 120)]
+    pub wait_secs: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct CalldataSweepArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// Recipient of the transfers.
+    #[arg(long, env = "TO_ADDRESS")]
+    pub to: String,
+
+    /// Comma-separated tx types to sweep (0, 1 or 2).
+    #[arg(long, value_delimiter = ',', default_value = "2")]
+    pub types: Vec<u8>,
+
+    /// Comma-separated calldata sizes, in bytes or with a `kb` / `mb` suffix.
+    #[arg(long, value_delimiter = ',', default_value = "1kb,10kb,64kb,128kb,256kb,1mb")]
+    pub sizes: Vec<DataSize>,
+
+    /// Fee point every transfer is sent at.
+    #[arg(long, default_value = "market")]
+    pub fee: FeeSeries,
+
+    /// Keep sweeping a type after a size was not accepted.
+    #[arg(long)]
+    pub keep_going: bool,
+
+    /// Seconds to wait for each transaction to be mined.
+    #[arg(long, default_value_t = 120)]
+    pub wait_secs: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct GasLimitArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// Recipient of the transfers; point it at a contract to test a call.
+    #[arg(long, env = "TO_ADDRESS")]
+    pub to: String,
+
+    /// Amount per transfer: `1000wei`, `2.5gwei`, `0.001eth` (a bare
+    /// number is whole native units) or `10%` of the sender's balance.
+    #[arg(long, env = "AMOUNT", default_value = "0.001eth")]
+    pub amount: Amount,
+
+    /// Calldata: 0x-prefixed hex, or `@path` to a binary file.
+    #[arg(long, env = "TX_DATA")]
+    pub data: Option<String>,
+
+    /// Comma-separated tx types to run the scenario with (0, 1 or 2).
+    #[arg(long, value_delimiter = ',', default_value = "0,1,2")]
+    pub types: Vec<u8>,
+
+    /// Fee point every transaction is sent at.
+    #[arg(long, default_value = "market")]
+    pub fee: FeeSeries,
+
+    /// How far below the estimate the last transaction's gas limit is, in percent.
+    #[arg(long, default_value_t = 10)]
+    pub margin_pct: u64,
+
+    /// Seconds to wait for each transaction to be mined.
+    #[arg(long, default_value_t = 120)]
+    pub wait_secs: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct FeeEdgesArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// Recipient of the 0-value transfers (default: the sender itself).
+    #[arg(long)]
+    pub to: Option<String>,
+
+    /// Seconds to wait for each transaction to be mined before counting it as pending.
+    #[arg(long, default_value_t = 60)]
+    pub wait_secs: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct PrioritySweepArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// Recipient of the 0-value transfers (default: the sender itself).
+    #[arg(long)]
+    pub to: Option<String>,
+
+    /// Tip of the first transfer, in gwei; each next one halves it.
+    #[arg(long, default_value = "2")]
+    pub max_tip_gwei: String,
+
+    /// Smallest tip difference the search resolves, in gwei; halving below it jumps to 0.
+    #[arg(long, default_value = "0.01")]
+    pub resolution_gwei: String,
+
+    /// Blocks a transfer has to be included within to count.
+    #[arg(long, default_value_t = 3)]
+    pub blocks: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct GasPriceFloorArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// Recipient of the 0-value transfers (default: the sender itself).
+    #[arg(long)]
+    pub to: Option<String>,
+
+    /// Comma-separated legacy tx types to sweep (0 or 1).
+    #[arg(long, value_delimiter = ',', default_value = "0,1", value_parser = clap::value_parser!(u8).range(0..=1))]
+    pub types: Vec<u8>,
+
+    /// Gas price of the first transfer, in gwei; each next one halves it. Defaults to twice
+    /// the node's eth_gasPrice, and at least 1 gwei.
+    #[arg(long)]
+    pub max_gwei: Option<String>,
+
+    /// Smallest price difference the search resolves, in gwei; halving below it jumps to 0.
+    #[arg(long, default_value = "0.01")]
+    pub resolution_gwei: String,
+
+    /// Blocks a transfer has to be included within to count.
+    #[arg(long, default_value_t = 3)]
+    pub blocks: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct EnvelopeFuzzArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// First type byte to fuzz (3-127).
+    #[arg(long, default_value_t = FIRST_FUZZ_TYPE, value_parser = clap::value_parser!(u8).range(3..=127))]
+    pub first_type: u8,
+
+    /// Last type byte to fuzz (3-127).
+    #[arg(long, default_value_t = LAST_FUZZ_TYPE, value_parser = clap::value_parser!(u8).range(3..=127))]
+    pub last_type: u8,
+
+    /// Comma-separated payloads to put behind each type byte: `empty`, `retagged` (a signed
+    /// type-2 body), `truncated`, `garbage`, `oversized-length`.
+    #[arg(long, value_delimiter = ',', default_value = "empty,retagged,truncated,garbage,oversized-length")]
+    pub payloads: Vec<Payload>,
+
+    /// Fee point the re-tagged type-2 body is signed at.
+    #[arg(long, default_value = "market")]
+    pub fee: FeeSeries,
+
+    /// Output format for the catalog.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct OpDepositArgs {
+    /// The L1 side: RPC, chain and signer the deposit is sent with.
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// RPC of the OP Stack L2 the deposit lands on.
+    #[arg(long, env = "L2_RPC_URL")]
+    pub l2_rpc_url: String,
+
+    /// Address of the L2's `OptimismPortal` proxy on L1.
+    #[arg(long, env = "OPTIMISM_PORTAL")]
+    pub portal: String,
+
+    /// Recipient on L2.
+    #[arg(long, env = "TO_ADDRESS")]
+    pub to: String,
+
+    /// Amount deposited and sent to the recipient: `1000wei`, `2.5gwei`, `0.001eth` (a bare
+    /// number is whole native units) or `10%` of the sender's balance.
+    #[arg(long, env = "AMOUNT", default_value = "0.001eth")]
+    pub amount: Amount,
+
+    /// Gas limit of the deposited transaction on L2 (bought on L1).
+    #[arg(long, default_value_t = 100_000)]
+    pub l2_gas_limit: u64,
+
+    /// Fee point of the L1 transaction.
+    #[arg(long, default_value = "market")]
+    pub fee: FeeSeries,
+
+    /// Seconds to wait for each side to be mined; L2 inclusion usually follows L1 by a few
+    /// minutes.
+    #[arg(long, default_value_t = 600)]
+    pub wait_secs: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct RetryableArgs {
+    /// The L1 side: RPC, chain and signer the ticket is submitted with.
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// RPC of the Arbitrum chain the ticket is redeemed on.
+    #[arg(long, env = "L2_RPC_URL")]
+    pub l2_rpc_url: String,
+
+    /// Address of the L2's delayed `Inbox` on L1.
+    #[arg(long, env = "ARBITRUM_INBOX")]
+    pub inbox: String,
+
+    /// Recipient of the call value on L2.
+    #[arg(long, env = "TO_ADDRESS")]
+    pub to: String,
+
+    /// L2 call value: `1000wei`, `2.5gwei`, `0.001eth` (a bare number is whole native units)
+    /// or `10%` of the sender's balance.
+    #[arg(long, env = "AMOUNT", default_value = "0.001eth")]
+    pub amount: Amount,
+
+    /// L2 gas limit of the auto-redeem.
+    #[arg(long, default_value_t = 100_000)]
+    pub l2_gas_limit: u64,
+
+    /// Fee point of the L1 transaction.
+    #[arg(long, default_value = "market")]
+    pub fee: FeeSeries,
+
+    /// Seconds to wait for each step to be mined; the ticket usually reaches L2 within
+    /// about ten minutes of L1 inclusion.
+    #[arg(long, default_value_t = 900)]
+    pub wait_secs: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct PlanArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// Plan file: `[defaults]` and one `[[step]]` per transaction (see the README).
+    pub file: PathBuf,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct LoadArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+
+    /// Recipient of the generated transfers.
+    #[arg(long, env = "TO_ADDRESS")]
+    pub to: String,
+
+    /// Amount per transfer: `1000wei`, `2.5gwei`, `0.001eth` (a bare
+    /// number is whole native units) or `10%` of the sender's balance.
+    #[arg(long, env = "AMOUNT", default_value = "0.001eth")]
+    pub amount: Amount,
+
+    /// Tx type to generate (0, 1 or 2).
+    #[arg(long = "type", default_value_t = 2)]
+    pub tx_type: u8,
+
+    /// Transactions per second.
+    #[arg(long, default_value_t = 1.0)]
+    pub rate: f64,
+
+    /// Seconds to keep submitting.
+    #[arg(long, default_value_t = 60)]
+    pub duration_secs: u64,
+
+    /// Fee point for every transaction.
+    #[arg(long, default_value = "market")]
+    pub fee: FeeSeries,
+
+    /// Seconds to keep waiting for receipts after the last submission.
+    #[arg(long, default_value_t = 120)]
+    pub wait_secs: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// Baseline: a `--output json` report file, or a run id / `latest` / `previous` in `--db`.
+    pub old: String,
+
+    /// Run to compare against the baseline, in the same forms.
+    pub new: String,
+
+    /// SQLite history to look run ids up in.
+    #[arg(long, env = "RESULTS_DB")]
+    pub db: Option<PathBuf>,
+
+    /// Output format for the changes.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct UnstickArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// Tx type of the self-transfers (0, 1 or 2).
+    #[arg(long = "type", default_value_t = 2)]
+    pub tx_type: u8,
+
+    /// Fee point of the self-transfers; raised above a stuck transaction's fees when the
+    /// node's txpool shows them.
+    #[arg(long, default_value = "market")]
+    pub fee: FeeSeries,
+
+    /// Seconds to wait for the self-transfers to be mined.
+    #[arg(long, default_value_t = 120)]
+    pub wait_secs: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct SweepArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// Address (or ENS name) that receives the balances.
+    #[arg(long, env = "SWEEP_TO")]
+    pub to: String,
+
+    /// Tx type of the transfers (0, 1 or 2); legacy leaves exactly zero behind.
+    #[arg(long = "type", default_value_t = 0)]
+    pub tx_type: u8,
+
+    /// Fee point the fee kept back is computed at.
+    #[arg(long, default_value = "market")]
+    pub fee: FeeSeries,
+
+    /// Seconds to wait for each transfer to be mined.
+    #[arg(long, default_value_t = 120)]
+    pub wait_secs: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct UserOpArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// Bundler endpoint serving `eth_sendUserOperation`.
+    #[arg(long, env = "BUNDLER_URL")]
+    pub bundler_url: String,
+
+    /// Smart account the operation runs from; the configured key must be its owner.
+    #[arg(long, env = "SMART_ACCOUNT")]
+    pub account: String,
+
+    /// EntryPoint the bundler serves (v0.6).
+    #[arg(long, env = "ENTRY_POINT", default_value = "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789")]
+    pub entry_point: String,
+
+    /// Factory address and calldata (hex or `@file`) that deploy the account, for a first
+    /// operation from an undeployed account.
+    #[arg(long, env = "ACCOUNT_INIT_CODE")]
+    pub init_code: Option<String>,
+
+    /// Recipient of the transfer.
+    #[arg(long, env = "TO_ADDRESS")]
+    pub to: String,
+
+    /// Amount sent from the account; a percentage is of the account's balance.
+    #[arg(long, env = "AMOUNT", default_value = "0.001eth")]
+    pub amount: Amount,
+
+    /// Fee point the operation is priced at; `0` tests a fully sponsored zero-fee operation.
+    #[arg(long, default_value = "market")]
+    pub fee: FeeSeries,
+
+    /// Paymaster service API to request sponsorship from.
+    #[arg(long, env = "PAYMASTER_API", value_enum, conflicts_with = "paymaster_and_data")]
+    pub paymaster_api: Option<PaymasterApi>,
+
+    /// Paymaster service endpoint (the bundler's if unset).
+    #[arg(long, env = "PAYMASTER_URL", requires = "paymaster_api")]
+    pub paymaster_url: Option<String>,
+
+    /// JSON context passed to the paymaster service, e.g. `{"sponsorshipPolicyId":"sp_…"}`.
+    #[arg(long, env = "PAYMASTER_CONTEXT", requires = "paymaster_api")]
+    pub paymaster_context: Option<String>,
+
+    /// Fixed `paymasterAndData` (hex or `@file`) instead of asking a service.
+    #[arg(long, env = "PAYMASTER_AND_DATA")]
+    pub paymaster_and_data: Option<String>,
+
+    /// Seconds to wait for the operation to be bundled.
+    #[arg(long, default_value_t = 120)]
+    pub wait_secs: u64,
+
+    /// Output format for the result.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct SignTypedArgs {
+    #[command(flatten)]
+    pub conn: ConnectionArgs,
+
+    /// Typed data to sign, as JSON in the `eth_signTypedData_v4` format; EIP-712's `Mail`
+    /// example on the current chain if unset.
+    #[arg(long, env = "TYPED_DATA_FILE")]
+    pub payload: Option<PathBuf>,
+
+    /// EIP-1271 contract (or ENS name) whose `isValidSignature` must accept the signature,
+    /// e.g. a smart account owned by the signer. It is also the sample's verifying contract.
+    #[arg(long, env = "EIP1271_VERIFIER")]
+    pub verifier: Option<String>,
+
+    /// Output format for the result.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct ProbeArgs {
+    /// RPC endpoint: http(s)://, ws(s):// or an IPC socket path.
+    #[arg(long, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    #[command(flatten)]
+    pub client: RpcClientArgs,
+
+    /// Output format for the capability table.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportRawArgs {
+    #[command(flatten)]
+    pub send: SendArgs,
+
+    /// Directory the `.hex` files are written to (created if missing).
+    #[arg(long, env = "EXPORT_DIR", default_value = "raw-txs")]
+    pub out_dir: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct DecodeArgs {
+    /// 0x-prefixed raw transactions, files with one per line, or directories of `.hex` files.
+    #[arg(required = true)]
+    pub raw: Vec<String>,
+
+    /// Output format for the decoded transactions.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct SendRawArgs {
+    /// 0x-prefixed raw transactions, files with one per line, or directories of `.hex` files;
+    /// broadcast in the order given (directories in file name order).
+    #[arg(required = true)]
+    pub raw: Vec<String>,
+
+    /// RPC endpoint: http(s)://, ws(s):// or an IPC socket path.
+    #[arg(long, env = "RPC_URL")]
+    pub rpc_url: String,
+
+    #[command(flatten)]
+    pub client: RpcClientArgs,
+
+    /// Seconds to wait for each receipt; 0 only submits.
+    #[arg(long, default_value_t = 120)]
+    pub wait_secs: u64,
+
+    /// Output format for the results.
+    #[arg(long, env = "OUTPUT_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_units() {
+        assert_eq!(interval("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(interval("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(interval(" 15m ").unwrap(), Duration::from_secs(900));
+        assert_eq!(interval("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(interval("1d").unwrap(), Duration::from_secs(86_400));
+    }
+
+    #[test]
+    fn interval_rejects() {
+        for s in ["", "0", "0m", "m", "5w", "1.5h", "-1", "1 h"] {
+            assert!(interval(s).is_err(), "{:?} parsed", s);
+        }
+    }
+
+    #[test]
+    fn interval_overflow() {
+        assert!(interval(&format!("{}d", u64::MAX / 86_400 + 1)).is_err());
+        assert!(interval(&format!("{}", u64::MAX)).is_ok());
+    }
+}
@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use eyre::{eyre, Result};
+use test_transaction_type::diff::{self, Outcome};
+use test_transaction_type::history::History;
+
+use crate::cli::{DiffArgs, OutputFormat};
+
+pub fn run(args: DiffArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let history = args.db.as_ref().map(History::open).transpose()?;
+    let old = outcomes(&args.old, history.as_ref())?;
+    let new = outcomes(&args.new, history.as_ref())?;
+    let changes = diff::compare(&old, &new);
+
+    match args.output {
+        OutputFormat::Text if changes.is_empty() => println!("No status changes ({} entries compared)", new.len()),
+        OutputFormat::Text => {
+            for change in &changes {
+                println!("{}", change);
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&changes)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    Ok(())
+}
+
+/// A report file if `spec` names one, otherwise a run in the history database.
+fn outcomes(spec: &str, history: Option<&History>) -> Result<Vec<Outcome>> {
+    if Path::new(spec).is_file() {
+        return diff::from_report_json(&std::fs::read_to_string(spec)?);
+    }
+    let history = history.ok_or_else(|| eyre!("{} is not a file; pass --db to look up run ids", spec))?;
+    history.outcomes(history.run_id(spec)?)
+}
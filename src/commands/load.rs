@@ -0,0 +1,55 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use test_transaction_type::format_address;
+use test_transaction_type::load::{self, LoadConfig};
+use test_transaction_type::nonce::NonceManager;
+
+use tracing::info;
+
+use crate::cli::{LoadArgs, OutputFormat};
+use crate::commands::{amount, connect, metrics, recipient, Connection};
+
+pub async fn run(args: LoadArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    if !args.rate.is_finite() || args.rate <= 0.0 {
+        return Err(eyre!("--rate must be positive and finite"));
+    }
+    let Connection { client, native, node: _node, .. } = connect(&args.conn).await?;
+    let from = client.address();
+    let to = recipient(&client, &args.to).await?;
+    let value = amount(&client, &args.amount, &native).await?;
+    let fees = args.fee.resolve(client.as_ref()).await?;
+    let nonces = Arc::new(NonceManager::init(client.as_ref(), from).await?);
+
+    info!(
+        "From={} To={} type-{} at {} tx/s for {}s (fees={})",
+        format_address(from),
+        format_address(to),
+        args.tx_type,
+        args.rate,
+        args.duration_secs,
+        args.fee
+    );
+    let config = LoadConfig {
+        tx_type: args.tx_type,
+        to,
+        value,
+        fees,
+        rate: args.rate,
+        duration: Duration::from_secs(args.duration_secs),
+        wait: Duration::from_secs(args.wait_secs),
+    };
+    let metrics = metrics(&args.metrics).await?;
+    let report = load::run(client, nonces, config, metrics).await?;
+
+    match args.output {
+        OutputFormat::Text => println!("\n{}", report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    Ok(())
+}
@@ -0,0 +1,302 @@
+//! One module per CLI subcommand; shared setup lives here.
+
+pub mod diff;
+pub mod load;
+pub mod probe;
+pub mod raw;
+pub mod scenario;
+pub mod send;
+pub mod serve;
+pub mod sign_typed;
+pub mod sweep;
+pub mod unstick;
+pub mod userop;
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use eyre::{eyre, Result};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Address, Bytes, U256};
+use ethers::signers::coins_bip39::English;
+#[cfg(feature = "ledger")]
+use ethers::signers::{HDPath, Ledger};
+use ethers::signers::{LocalWallet, MnemonicBuilder, Signer};
+#[cfg(feature = "anvil")]
+use ethers::utils::Anvil;
+use ethers::utils::AnvilInstance;
+use test_transaction_type::amount::{Amount, Native};
+use test_transaction_type::chains::{self, Chain};
+use test_transaction_type::ens;
+use test_transaction_type::metrics::{self, Metrics};
+use test_transaction_type::signer::AnySigner;
+use test_transaction_type::transport::{self, ClientOptions, Transport};
+#[cfg(feature = "anvil")]
+use test_transaction_type::transport::redact;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::cli::{ConnectionArgs, MetricsArgs, RpcClientArgs, SignerKind};
+
+pub type Client = SignerMiddleware<Provider<Transport>, AnySigner>;
+
+pub struct Connection {
+    /// The first sender.
+    pub client: Arc<Client>,
+    /// Every configured sender, `client` first; more than one with several private keys or
+    /// `SENDER_COUNT` > 1.
+    pub senders: Vec<Arc<Client>>,
+    /// Pushed block heads, when the transport supports subscriptions.
+    pub heads: Option<watch::Receiver<u64>>,
+    /// Chain id the senders sign with, as verified against the node.
+    pub chain_id: u64,
+    /// The `--chain` preset, or the preset matching `chain_id` if there is one.
+    pub chain: Option<Chain>,
+    /// Native currency amounts are parsed and shown in: the preset's, with `NATIVE_DECIMALS`
+    /// taking precedence.
+    pub native: Native,
+    /// The `--local` Anvil node, killed when this is dropped: keep it bound for the run.
+    pub node: Option<AnvilInstance>,
+}
+
+pub async fn connect(args: &ConnectionArgs) -> Result<Connection> {
+    let node = local_node(args)?;
+    let rpc_url = node.as_ref().map(AnvilInstance::endpoint).or(args.rpc_url.clone());
+    if args.max_rps.is_some_and(|rps| rps.is_nan() || rps <= 0.0) {
+        return Err(eyre!("MAX_RPS must be a positive number"));
+    }
+    let transport = match &rpc_url {
+        Some(url) if !args.offline => {
+            Transport::connect_with(url, &client_options(&args.client)?)
+                .await?
+                .limited(args.max_rps, args.max_requests)
+        }
+        _ => Transport::Offline,
+    };
+    let heads = transport.head_notifications();
+    let provider = Provider::new(transport);
+    let preset = preset(args).await?;
+    let configured = args.chain_id.or(preset.as_ref().map(|c| c.chain_id)).unwrap_or(SEPOLIA);
+    let chain_id = match (&node, args.offline) {
+        // A local node (or fork) signs for whatever chain it runs.
+        (Some(_), _) => provider.get_chainid().await.map_err(|e| eyre!("eth_chainId: {}", e))?.as_u64(),
+        (None, true) => configured,
+        (None, false) => chain_id(&provider, args, configured).await?,
+    };
+    let chain = preset.filter(|c| c.chain_id == chain_id).or_else(|| chains::by_id(chain_id));
+    let mut native = Native::default();
+    if let Some(c) = &chain {
+        native = Native { symbol: c.native_symbol.clone(), decimals: c.native_decimals };
+    }
+    native.decimals = args.native_decimals.unwrap_or(native.decimals);
+    if let Some(c) = &chain {
+        let explorer = c.explorer.as_deref().unwrap_or("no explorer");
+        info!("Chain: {} ({}), native {} with {} decimals, {}", c.name, c.chain_id, native.symbol, native.decimals, explorer);
+    }
+    if args.production_chain_ids.contains(&chain_id) && node.is_none() && !args.i_know_what_im_doing {
+        return Err(eyre!(
+            "chain {} is in PRODUCTION_CHAIN_IDS; this tool sends deliberately odd fee combinations \
+             and real funds would be spent. Pass --i-know-what-im-doing to run anyway",
+            chain_id
+        ));
+    }
+    let signers = match &node {
+        Some(node) if args.private_key.is_empty() && args.mnemonic.is_none() && args.keystore.is_none() => node
+            .keys()
+            .iter()
+            .take(args.sender_count.max(1) as usize)
+            .map(|key| AnySigner::Local(LocalWallet::from(key.clone())))
+            .collect(),
+        _ => signers(args, chain_id).await?,
+    };
+    let senders: Vec<Arc<Client>> = signers
+        .into_iter()
+        .map(|signer| Arc::new(SignerMiddleware::new(provider.clone(), signer.with_chain_id(chain_id))))
+        .collect();
+    let client = senders[0].clone();
+    Ok(Connection { client, senders, heads, chain_id, chain, native, node })
+}
+
+/// Spawn the `--local` Anvil node, forking `FORK_URL` if set.
+#[cfg(feature = "anvil")]
+fn local_node(args: &ConnectionArgs) -> Result<Option<AnvilInstance>> {
+    if !args.local {
+        return Ok(None);
+    }
+    // `Anvil::spawn` panics when the binary is missing; fail with a readable error instead.
+    std::process::Command::new("anvil")
+        .arg("--version")
+        .output()
+        .map_err(|e| eyre!("--local needs Foundry's `anvil` on PATH: {}", e))?;
+    let node = match &args.fork_url {
+        // Fetching the fork's state can take a while.
+        Some(url) => Anvil::new().fork(url.clone()).timeout(FORK_STARTUP_MS).spawn(),
+        None => Anvil::new().spawn(),
+    };
+    match &args.fork_url {
+        Some(url) => info!("Local Anvil node at {}, forked from {}", node.endpoint(), redact(url)),
+        None => info!("Local Anvil node at {}", node.endpoint()),
+    }
+    Ok(Some(node))
+}
+
+#[cfg(not(feature = "anvil"))]
+fn local_node(args: &ConnectionArgs) -> Result<Option<AnvilInstance>> {
+    if args.local {
+        return Err(eyre!("--local needs a build with `--features anvil`"));
+    }
+    Ok(None)
+}
+
+/// How long a forking Anvil may take to start listening.
+#[cfg(feature = "anvil")]
+const FORK_STARTUP_MS: u64 = 60_000;
+
+/// Chain id signed for when neither `CHAIN_ID` nor `CHAIN` is set.
+const SEPOLIA: u64 = 11155111;
+
+/// The `CHAIN` preset, from the built-in table or, with `CHAINLIST`, from chainlist. An
+/// explicit `CHAIN_ID` must agree with it.
+async fn preset(args: &ConnectionArgs) -> Result<Option<Chain>> {
+    let Some(name) = &args.chain else { return Ok(None) };
+    let chain = match chains::by_name(name) {
+        Some(chain) => chain,
+        None if args.chainlist => chains::fetch(name).await?,
+        None => {
+            return Err(eyre!(
+                "unknown chain '{}'; known presets: {} (or pass --chainlist)",
+                name,
+                chains::names().join(", ")
+            ));
+        }
+    };
+    match args.chain_id {
+        Some(id) if id != chain.chain_id => {
+            Err(eyre!("CHAIN={} is chain id {} but CHAIN_ID={}", name, chain.chain_id, id))
+        }
+        _ => Ok(Some(chain)),
+    }
+}
+
+/// `configured` (`CHAIN_ID` or the preset's), checked against `eth_chainId`: a mismatch would
+/// make every signature invalid for the node, so fail fast unless `ADOPT_CHAIN_ID` asks to
+/// use the node's value.
+async fn chain_id(provider: &Provider<Transport>, args: &ConnectionArgs, configured: u64) -> Result<u64> {
+    let remote = provider.get_chainid().await.map_err(|e| eyre!("eth_chainId: {}", e))?.as_u64();
+    if remote == configured {
+        return Ok(remote);
+    }
+    if args.adopt_chain_id {
+        warn!("CHAIN_ID={} but the RPC reports {}; signing for {}", configured, remote, remote);
+        return Ok(remote);
+    }
+    Err(eyre!(
+        "CHAIN_ID={} but the RPC reports chain id {}; set CHAIN_ID={} or pass --adopt-chain-id",
+        configured,
+        remote,
+        remote
+    ))
+}
+
+/// Headers, proxy and TLS settings for `RPC_URL`.
+pub fn client_options(args: &RpcClientArgs) -> Result<ClientOptions> {
+    if args.rpc_insecure_tls {
+        warn!("TLS certificates from the RPC are not verified (RPC_INSECURE_TLS)");
+    }
+    Ok(ClientOptions {
+        headers: transport::parse_headers(&args.rpc_headers)?,
+        proxy: args.rpc_proxy.clone(),
+        ca_certs: match &args.rpc_ca_cert {
+            Some(path) => transport::load_certificates(path)?,
+            None => Vec::new(),
+        },
+        insecure_tls: args.rpc_insecure_tls,
+    })
+}
+
+/// Start the metrics endpoint when `METRICS_ADDR` is set.
+pub async fn metrics(args: &MetricsArgs) -> Result<Option<Arc<Metrics>>> {
+    let Some(addr) = args.metrics_addr else { return Ok(None) };
+    if let Some(metrics) = SERVED_METRICS.lock().unwrap().get(&addr) {
+        return Ok(Some(metrics.clone()));
+    }
+    let metrics = Arc::new(Metrics::default());
+    metrics::serve(addr, metrics.clone()).await?;
+    SERVED_METRICS.lock().unwrap().insert(addr, metrics.clone());
+    Ok(Some(metrics))
+}
+
+/// Metrics listeners run for the rest of the process, so later runs in it (`--interval`,
+/// `serve`) count into the one already on their address.
+static SERVED_METRICS: Mutex<BTreeMap<SocketAddr, Arc<Metrics>>> = Mutex::new(BTreeMap::new());
+
+#[cfg_attr(not(feature = "ledger"), allow(unused_variables))]
+async fn signers(args: &ConnectionArgs, chain_id: u64) -> Result<Vec<AnySigner>> {
+    match args.signer {
+        SignerKind::Local => Ok(wallets(args)?.into_iter().map(AnySigner::Local).collect()),
+        #[cfg(feature = "ledger")]
+        SignerKind::Ledger => {
+            let path = format!("{}/{}", args.derivation_path.trim_end_matches('/'), args.account_index);
+            Ok(vec![AnySigner::Ledger(Ledger::new(HDPath::Other(path), chain_id).await?)])
+        }
+        #[cfg(not(feature = "ledger"))]
+        SignerKind::Ledger => Err(eyre!("SIGNER=ledger needs a build with `--features ledger`")),
+    }
+}
+
+/// The senders' keys, from the first of `KEYSTORE_PATH`, `MNEMONIC` and `PRIVATE_KEY` that is set.
+fn wallets(args: &ConnectionArgs) -> Result<Vec<LocalWallet>> {
+    if let Some(path) = &args.keystore {
+        let password = match &args.keystore_password {
+            Some(p) => p.clone(),
+            None if args.no_prompt => return Err(eyre!("KEYSTORE_PASSWORD is not set for {}", path.display())),
+            None => rpassword::prompt_password(format!("Password for {}: ", path.display()))?,
+        };
+        let wallet = LocalWallet::decrypt_keystore(path, password)
+            .map_err(|e| eyre!("decrypting keystore {}: {}", path.display(), e))?;
+        return Ok(vec![wallet]);
+    }
+    if let Some(phrase) = &args.mnemonic {
+        let base = args.derivation_path.trim_end_matches('/');
+        return (args.account_index..args.account_index + args.sender_count.max(1))
+            .map(|index| {
+                let path = format!("{}/{}", base, index);
+                Ok(MnemonicBuilder::<English>::default().phrase(phrase.as_str()).derivation_path(&path)?.build()?)
+            })
+            .collect();
+    }
+    if args.private_key.is_empty() {
+        return Err(eyre!("set PRIVATE_KEY, MNEMONIC or KEYSTORE_PATH"));
+    }
+    args.private_key.iter().map(|key| Ok(key.trim().parse()?)).collect()
+}
+
+/// `TX_DATA`: `@path` reads a binary file, anything else is parsed as hex.
+pub fn calldata(arg: &str) -> Result<Bytes> {
+    match arg.strip_prefix('@') {
+        Some(path) => Ok(std::fs::read(path).map_err(|e| eyre!("reading TX_DATA file {}: {}", path, e))?.into()),
+        None => arg.parse().map_err(|e| eyre!("invalid TX_DATA: {}", e)),
+    }
+}
+
+/// `TO_ADDRESS` (or one entry of it): an address, or an ENS name resolved now and logged.
+pub async fn recipient(client: &Client, s: &str) -> Result<Address> {
+    let address = ens::resolve(client, s).await?;
+    if ens::is_name(s) {
+        info!("{} resolves to {:?}", s, address);
+    }
+    Ok(address)
+}
+
+/// `AMOUNT` in wei for `sender`, in the native currency's decimals; a percentage is of the
+/// sender's current balance.
+pub async fn amount(sender: &Client, amount: &Amount, native: &Native) -> Result<U256> {
+    let balance = if amount.is_relative() {
+        Some(sender.get_balance(sender.address(), None).await.map_err(|e| eyre!("eth_getBalance: {}", e))?)
+    } else {
+        None
+    };
+    amount.to_wei(native.decimals, balance)
+}
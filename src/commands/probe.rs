@@ -0,0 +1,22 @@
+use ethers::providers::Provider;
+use eyre::{eyre, Result};
+use test_transaction_type::probe;
+use test_transaction_type::transport::Transport;
+
+use crate::cli::{OutputFormat, ProbeArgs};
+use crate::commands::client_options;
+
+pub async fn run(args: ProbeArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let provider = Provider::new(Transport::connect_with(&args.rpc_url, &client_options(&args.client)?).await?);
+    let probe = probe::probe(&provider).await?;
+
+    match args.output {
+        OutputFormat::Text => print!("{}", probe),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&probe)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ethers::providers::{Middleware, Provider};
+use ethers::types::Bytes;
+use eyre::{eyre, Result};
+use test_transaction_type::chains;
+use test_transaction_type::decode;
+use test_transaction_type::transport::Transport;
+use test_transaction_type::{print_summary, Report, TxResult};
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+use crate::cli::{DecodeArgs, ExportRawArgs, OutputFormat, SendRawArgs};
+use crate::commands::{client_options, send};
+
+/// `send` as a dry run, writing every signed envelope to `out_dir`.
+pub async fn export(mut args: ExportRawArgs) -> Result<()> {
+    args.send.dry_run = true;
+    send::run(args.send, Some(&args.out_dir)).await
+}
+
+/// Write each signed transaction of `report` to `<dir>/<seq>-<series>-type-<n>.hex`, numbered
+/// in signing order so `send-raw <dir>` broadcasts them with increasing nonces.
+pub fn write(dir: &Path, report: &Report) -> Result<usize> {
+    std::fs::create_dir_all(dir)?;
+    let mut written = 0;
+    for series in &report.series {
+        let label: String =
+            series.label.chars().map(|c| if c.is_ascii_alphanumeric() || c == '=' { c } else { '_' }).collect();
+        for r in &series.results {
+            let Some(raw) = &r.raw else { continue };
+            written += 1;
+            let path = dir.join(format!("{:03}-{}-type-{}.hex", written, label, r.tx_type));
+            std::fs::write(&path, format!("{}\n", raw))?;
+        }
+    }
+    Ok(written)
+}
+
+pub async fn send(args: SendRawArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let mut raws = Vec::new();
+    for arg in &args.raw {
+        raws.extend(load(arg)?);
+    }
+    let provider = Provider::new(Transport::connect_with(&args.rpc_url, &client_options(&args.client)?).await?);
+    let chain = provider.get_chainid().await.ok().and_then(|id| chains::by_id(id.as_u64()));
+    let wait = (args.wait_secs > 0).then(|| Duration::from_secs(args.wait_secs));
+
+    let mut results = Vec::with_capacity(raws.len());
+    for raw in raws {
+        // Typed envelopes start with their type byte, legacy ones with an RLP list header.
+        let tx_type = raw.first().copied().filter(|&b| b < 0x7f).unwrap_or(0);
+        let pending = match provider.send_raw_transaction(raw).await {
+            Ok(pending) => pending,
+            Err(e) => {
+                warn!("type-{}: submission failed: {}", tx_type, e);
+                results.push(TxResult::new(tx_type, "submit error").with_error(e));
+                continue;
+            }
+        };
+        let hash = pending.tx_hash();
+        let url = chain.as_ref().and_then(|c| c.tx_url(hash));
+        info!("type-{}: submitted 0x{:x} {}", tx_type, hash, url.as_deref().unwrap_or_default());
+        let mut result = match wait {
+            None => TxResult::new(tx_type, "pending"),
+            Some(limit) => match timeout(limit, pending).await {
+                Ok(Ok(Some(receipt))) => TxResult::mined(tx_type, &receipt),
+                Ok(Ok(None)) => TxResult::new(tx_type, "pending").with_error("transaction dropped"),
+                Ok(Err(e)) => TxResult::new(tx_type, "await error").with_error(e),
+                Err(_) => TxResult::new(tx_type, "timeout").with_error(format!("no receipt after {:?}", limit)),
+            },
+        };
+        result.tx_hash = Some(hash);
+        result.explorer_url = url;
+        results.push(result);
+    }
+
+    match args.output {
+        OutputFormat::Text => print_summary("send-raw", &results),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    Ok(())
+}
+
+/// Print every field of each raw transaction, with its recovered sender; fails when any
+/// would be rejected for its signature.
+pub fn decode(args: DecodeArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let mut raws = Vec::new();
+    for arg in &args.raw {
+        raws.extend(load(arg)?);
+    }
+    let decoded = raws
+        .iter()
+        .enumerate()
+        .map(|(i, raw)| decode::decode(raw).map_err(|e| eyre!("transaction {}: {}", i + 1, e)))
+        .collect::<Result<Vec<_>>>()?;
+
+    match args.output {
+        OutputFormat::Text => {
+            for (i, tx) in decoded.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!("{}", tx);
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&decoded)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    let invalid = decoded.iter().filter(|tx| !tx.valid()).count();
+    if invalid > 0 {
+        return Err(eyre!("{} of {} transaction(s) would be rejected", invalid, decoded.len()));
+    }
+    Ok(())
+}
+
+/// Raw transactions from a hex literal, a file with one per line, or a directory of `.hex`
+/// files in name order.
+fn load(arg: &str) -> Result<Vec<Bytes>> {
+    let path = Path::new(arg);
+    let text = if path.is_dir() {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<_>>()?;
+        files.retain(|f| f.extension().is_some_and(|ext| ext == "hex"));
+        files.sort();
+        files.iter().map(std::fs::read_to_string).collect::<std::io::Result<Vec<_>>>()?.join("\n")
+    } else if path.is_file() {
+        std::fs::read_to_string(path)?
+    } else {
+        arg.to_string()
+    };
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.parse::<Bytes>().map_err(|e| eyre!("invalid raw transaction {}: {}", line, e)))
+        .collect()
+}
@@ -0,0 +1,469 @@
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Address, H256, U256};
+use ethers::utils::{format_units, parse_units};
+use test_transaction_type::{build_tx, format_address};
+use test_transaction_type::scenarios::calldata_sweep;
+use test_transaction_type::scenarios::envelope_fuzz;
+use test_transaction_type::scenarios::fee_edges::{self, FeeEdge};
+use test_transaction_type::scenarios::fee_floor::{self, ProbeOutcome, SweepReport, SweptFee};
+use test_transaction_type::scenarios::plan::{self, Plan};
+use test_transaction_type::scenarios::{cancel_replace, gas_limit, op_deposit, retryable};
+use test_transaction_type::transport::Transport;
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::cli::{
+    CalldataSweepArgs, CancelReplaceArgs, EnvelopeFuzzArgs, FeeEdgesArgs, GasLimitArgs, GasPriceFloorArgs, OpDepositArgs,
+    OutputFormat, PlanArgs, PrioritySweepArgs, RetryableArgs,
+};
+use crate::commands::send::EXIT_EXPECTATIONS;
+use crate::commands::{amount, calldata, connect, recipient, Connection};
+
+pub async fn cancel_replace(args: CancelReplaceArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let Connection { client, native, node: _node, .. } = connect(&args.conn).await?;
+    let to = recipient(&client, &args.to).await?;
+    let value = amount(&client, &args.amount, &native).await?;
+    let underpriced = args.underpriced_fee.resolve(client.as_ref()).await?;
+    let replacement = args.replacement_fee.resolve(client.as_ref()).await?;
+    let text = args.output == OutputFormat::Text;
+
+    let mut outcomes = Vec::with_capacity(args.types.len());
+    for &tx_type in &args.types {
+        info!(
+            "type-{}: original {} → {} at fees={}, cancel at fees={}",
+            tx_type,
+            args.amount,
+            format_address(to),
+            args.underpriced_fee,
+            args.replacement_fee
+        );
+        let outcome = cancel_replace::run(
+            client.as_ref(),
+            tx_type,
+            to,
+            value,
+            underpriced,
+            replacement,
+            Duration::from_secs(args.wait_secs),
+        )
+        .await?;
+        if text {
+            println!("\ntype-{}: nonce {}", tx_type, outcome.nonce);
+            match (&outcome.original, &outcome.original_error) {
+                (Some(h), _) => println!("  original:    0x{:x}", h),
+                (None, Some(e)) => println!("  original rejected: {}", e),
+                (None, None) => {}
+            }
+            match (&outcome.replacement, &outcome.replacement_error) {
+                (Some(h), _) => println!("  replacement: 0x{:x}", h),
+                (None, Some(e)) => println!("  replacement rejected: {}", e),
+                (None, None) => {}
+            }
+            let verdict = if outcome.honored() { "replacement honored" } else { "replacement not honored" };
+            println!("  mined: {} ({})", outcome.mined, verdict);
+        }
+        outcomes.push(outcome);
+    }
+
+    if args.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&outcomes)?);
+    }
+    Ok(())
+}
+
+pub async fn calldata_sweep(args: CalldataSweepArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let Connection { client, node: _node, .. } = connect(&args.conn).await?;
+    let to = recipient(&client, &args.to).await?;
+    let fees = args.fee.resolve(client.as_ref()).await?;
+    let text = args.output == OutputFormat::Text;
+    let mut sizes = args.sizes.clone();
+    sizes.sort_by_key(|s| s.0);
+
+    let mut outcomes = Vec::new();
+    for &tx_type in &args.types {
+        info!("type-{}: calldata sweep → {} at fees={}", tx_type, format_address(to), args.fee);
+        if text {
+            println!("\ntype-{}:", tx_type);
+        }
+        let mut largest = None;
+        for &size in &sizes {
+            let outcome =
+                calldata_sweep::run(client.as_ref(), tx_type, to, fees, size.0, Duration::from_secs(args.wait_secs))
+                    .await?;
+            let accepted = outcome.accepted();
+            if text {
+                let error = outcome.error.as_deref().map(|e| format!(": {}", e)).unwrap_or_default();
+                let estimated = if outcome.estimate_error.is_some() { " (not estimable)" } else { "" };
+                println!("  {:>7}  gas {}{}  {}{}", size.to_string(), outcome.gas, estimated, outcome.status, error);
+            }
+            outcomes.push(outcome);
+            if accepted {
+                largest = Some(size);
+            } else if !args.keep_going {
+                break;
+            }
+        }
+        if text {
+            match largest {
+                Some(size) => println!("  largest accepted: {}", size),
+                None => println!("  largest accepted: none"),
+            }
+        }
+    }
+
+    if args.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&outcomes)?);
+    }
+    Ok(())
+}
+
+pub async fn gas_limit(args: GasLimitArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let Connection { client, native, node: _node, .. } = connect(&args.conn).await?;
+    let to = recipient(&client, &args.to).await?;
+    let value = amount(&client, &args.amount, &native).await?;
+    let data = args.data.as_deref().map(calldata).transpose()?.unwrap_or_default();
+    let fees = args.fee.resolve(client.as_ref()).await?;
+    let text = args.output == OutputFormat::Text;
+
+    let mut outcomes = Vec::new();
+    for &tx_type in &args.types {
+        info!("type-{}: {} → {} at fees={}, gas limit auto/estimate/-{}%", tx_type, args.amount, format_address(to), args.fee, args.margin_pct);
+        let mut tx = build_tx(tx_type, client.address(), to, value, fees)?;
+        tx.set_data(data.clone());
+        let type_outcomes =
+            gas_limit::run(client.as_ref(), tx, args.margin_pct, Duration::from_secs(args.wait_secs)).await?;
+        if text {
+            println!("\ntype-{}:", tx_type);
+            for o in &type_outcomes {
+                let gas = o.gas.map(|g| g.to_string()).unwrap_or_else(|| "-".into());
+                let used = o.gas_used.map(|g| format!(", used {}", g)).unwrap_or_default();
+                let error = o.error.as_deref().map(|e| format!(": {}", e)).unwrap_or_default();
+                println!("  {:<13} gas {}{}  {}{}", o.limit, gas, used, o.status, error);
+            }
+        }
+        outcomes.extend(type_outcomes);
+    }
+
+    if args.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&outcomes)?);
+    }
+    Ok(())
+}
+
+pub async fn fee_edges(args: FeeEdgesArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let Connection { client, node: _node, .. } = connect(&args.conn).await?;
+    let to: Address = match &args.to {
+        Some(to) => recipient(&client, to).await?,
+        None => client.address(),
+    };
+    info!("type-2 fee edge cases → {}", format_address(to));
+    let outcomes = fee_edges::run(client.as_ref(), to, &FeeEdge::ALL, Duration::from_secs(args.wait_secs)).await?;
+
+    match args.output {
+        OutputFormat::Text => {
+            println!("\nbase fee {}", outcomes.first().map(|o| o.base_fee).unwrap_or_default());
+            for o in &outcomes {
+                let wei = |v: U256| if v == U256::MAX { "U256::MAX".to_string() } else { v.to_string() };
+                let fees = match (o.max_fee_per_gas, o.max_priority_fee_per_gas) {
+                    (Some(max), Some(priority)) => format!("max={} priority={}", wei(max), wei(priority)),
+                    _ => "-".into(),
+                };
+                let error = o.error.as_deref().map(|e| format!(": {}", e)).unwrap_or_default();
+                println!("  {:<19} {}  {}{}", o.case, fees, o.status, error);
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&outcomes)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    Ok(())
+}
+
+pub async fn priority_sweep(args: PrioritySweepArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let max_tip = gwei(&args.max_tip_gwei, "--max-tip-gwei")?;
+    let resolution = gwei(&args.resolution_gwei, "--resolution-gwei")?.max(U256::one());
+    let Connection { client, node: _node, .. } = connect(&args.conn).await?;
+    let to: Address = match &args.to {
+        Some(to) => recipient(&client, to).await?,
+        None => client.address(),
+    };
+    info!("Priority fee sweep from {} gwei → {}, {} block(s) per tip", args.max_tip_gwei, format_address(to), args.blocks);
+    let report = fee_floor::run(client.as_ref(), to, SweptFee::PriorityFee, max_tip, resolution, args.blocks).await?;
+
+    match args.output {
+        OutputFormat::Text => {
+            print_sweep(&report, "tip");
+            match report.lowest_included {
+                Some(tip) => println!("lowest included tip: {} gwei", format_gwei(tip)),
+                None => println!("lowest included tip: none"),
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    Ok(())
+}
+
+/// Per-type floors and the one gas price every swept type was included at.
+#[derive(Serialize)]
+struct GasPriceFloor {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum_viable_gas_price: Option<U256>,
+    sweeps: Vec<SweepReport>,
+}
+
+pub async fn gas_price_floor(args: GasPriceFloorArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let resolution = gwei(&args.resolution_gwei, "--resolution-gwei")?.max(U256::one());
+    let Connection { client, node: _node, .. } = connect(&args.conn).await?;
+    let to: Address = match &args.to {
+        Some(to) => recipient(&client, to).await?,
+        None => client.address(),
+    };
+    let max = match &args.max_gwei {
+        Some(max) => gwei(max, "--max-gwei")?,
+        // Twice what the node suggests, and no less than 1 gwei.
+        None => {
+            let suggested = client.get_gas_price().await.map_err(|e| eyre!("eth_gasPrice: {}", e))?;
+            (suggested * U256::from(2)).max(U256::exp10(9))
+        }
+    };
+    let text = args.output == OutputFormat::Text;
+    let mut floor = GasPriceFloor { minimum_viable_gas_price: None, sweeps: Vec::new() };
+    for &tx_type in &args.types {
+        info!(
+            "type-{}: gas price sweep from {} gwei → {}, {} block(s) per price",
+            tx_type,
+            format_gwei(max),
+            format_address(to),
+            args.blocks
+        );
+        let swept = SweptFee::GasPrice { tx_type };
+        let report = fee_floor::run(client.as_ref(), to, swept, max, resolution, args.blocks).await?;
+        if text {
+            println!("\ntype-{}:", tx_type);
+            print_sweep(&report, "gas price");
+        }
+        floor.sweeps.push(report);
+    }
+    // Only a price every type was included at is viable for all of them.
+    let floors: Option<Vec<U256>> = floor.sweeps.iter().map(|s| s.lowest_included).collect();
+    floor.minimum_viable_gas_price = floors.and_then(|f| f.into_iter().max());
+
+    match args.output {
+        OutputFormat::Text => match floor.minimum_viable_gas_price {
+            Some(price) => println!("\nminimum viable gas price: {} gwei", format_gwei(price)),
+            None => println!("\nminimum viable gas price: none found"),
+        },
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&floor)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    Ok(())
+}
+
+fn gwei(s: &str, name: &str) -> Result<U256> {
+    Ok(parse_units(s, "gwei").map_err(|e| eyre!("invalid {}: {}", name, e))?.into())
+}
+
+fn format_gwei(wei: U256) -> String {
+    format_units(wei, "gwei").unwrap_or_else(|_| wei.to_string())
+}
+
+/// Every probe of a fee sweep, the swept fee named `fee`.
+fn print_sweep(report: &SweepReport, fee: &str) {
+    let line = |o: &ProbeOutcome| {
+        let max = o.max_fee_per_gas.map(|m| format!("  max {:>14} gwei", format_gwei(m))).unwrap_or_default();
+        let error = o.error.as_deref().map(|e| format!(": {}", e)).unwrap_or_default();
+        let waited = o.blocks_waited.map(|b| format!(" after {} block(s)", b)).unwrap_or_default();
+        let replaced = if o.replacement { " (replacement)" } else { "" };
+        println!("  {} {:>14} gwei{}  {}{}{}{}", fee, format_gwei(o.fee), max, o.status, waited, replaced, error);
+    };
+    if let Some(base_fee) = report.probes.first().and_then(|o| o.base_fee) {
+        println!("  base fee {} gwei", format_gwei(base_fee));
+    }
+    report.probes.iter().for_each(line);
+    if let Some(cleanup) = &report.cleanup {
+        println!("  cleanup:");
+        line(cleanup);
+    }
+}
+
+pub async fn envelope_fuzz(args: EnvelopeFuzzArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    if args.first_type > args.last_type {
+        return Err(eyre!("--first-type {} is above --last-type {}", args.first_type, args.last_type));
+    }
+    let Connection { client, node: _node, .. } = connect(&args.conn).await?;
+    let fees = args.fee.resolve(client.as_ref()).await?;
+    let type_bytes = args.first_type..=args.last_type;
+    info!(
+        "Fuzzing envelopes 0x{:02x}-0x{:02x} with {} payload(s) each",
+        args.first_type,
+        args.last_type,
+        args.payloads.len()
+    );
+    let outcomes = envelope_fuzz::run(client.as_ref(), type_bytes, &args.payloads, fees).await?;
+    let catalog = envelope_fuzz::catalog(&outcomes);
+    let accepted: Vec<_> = outcomes.iter().filter(|o| o.status == "accepted").collect();
+
+    match args.output {
+        OutputFormat::Text => {
+            println!("\n{} envelope(s), {} distinct error(s):", outcomes.len(), catalog.len());
+            for class in &catalog {
+                let payloads: Vec<String> = class.payloads.iter().map(ToString::to_string).collect();
+                println!("  {:>4}  [{}] {}", class.count, class.error_kind, class.error);
+                println!("        types {}; payloads {}", envelope_fuzz::format_type_bytes(&class.type_bytes), payloads.join(", "));
+            }
+            for o in &accepted {
+                let hash = o.tx_hash.map(|h| format!(" as 0x{:x}", h)).unwrap_or_default();
+                println!("  ACCEPTED  type 0x{:02x} {} ({} bytes){}", o.type_byte, o.payload, o.size, hash);
+            }
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "outcomes": outcomes, "catalog": catalog }))?)
+        }
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    if !accepted.is_empty() {
+        return Err(eyre!("{} malformed envelope(s) accepted", accepted.len()));
+    }
+    Ok(())
+}
+
+pub async fn op_deposit(args: OpDepositArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let Connection { client, native, node: _node, .. } = connect(&args.conn).await?;
+    let l2 = Provider::new(Transport::connect(&args.l2_rpc_url).await?);
+    let portal = recipient(&client, &args.portal).await?;
+    let to = recipient(&client, &args.to).await?;
+    let mint = amount(&client, &args.amount, &native).await?;
+    let fees = args.fee.resolve(client.as_ref()).await?;
+    info!("Depositing {} → {} on L2 through portal {}", native.format(mint), format_address(to), format_address(portal));
+    let o = op_deposit::run(
+        client.as_ref(),
+        &l2,
+        portal,
+        to,
+        mint,
+        args.l2_gas_limit,
+        fees,
+        Duration::from_secs(args.wait_secs),
+    )
+    .await?;
+
+    match args.output {
+        OutputFormat::Text => {
+            let hash = |h: Option<H256>| h.map(|h| format!("0x{:x}", h)).unwrap_or_else(|| "-".into());
+            let block = |b: Option<u64>| b.map(|b| format!(" in block {}", b)).unwrap_or_default();
+            println!("\nL1 deposit: {}{}", hash(o.l1_tx_hash), block(o.l1_block_number));
+            println!("L2 deposit: {}{}", hash(o.l2_tx_hash), block(o.l2_block_number));
+            let error = o.error.as_deref().map(|e| format!(": {}", e)).unwrap_or_default();
+            println!("status: {}{}", o.status, error);
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&o)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    if o.status != "success" {
+        return Err(eyre!("deposit ended {}", o.status));
+    }
+    Ok(())
+}
+
+pub async fn retryable(args: RetryableArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let Connection { client, native, node: _node, .. } = connect(&args.conn).await?;
+    let l2 = Provider::new(Transport::connect(&args.l2_rpc_url).await?);
+    let inbox = recipient(&client, &args.inbox).await?;
+    let to = recipient(&client, &args.to).await?;
+    let value = amount(&client, &args.amount, &native).await?;
+    let fees = args.fee.resolve(client.as_ref()).await?;
+    info!("Retryable ticket: {} → {} on L2 through inbox {}", native.format(value), format_address(to), format_address(inbox));
+    let o = retryable::run(
+        client.as_ref(),
+        &l2,
+        inbox,
+        to,
+        value,
+        args.l2_gas_limit,
+        fees,
+        Duration::from_secs(args.wait_secs),
+    )
+    .await?;
+
+    match args.output {
+        OutputFormat::Text => {
+            let hash = |h: Option<H256>| h.map(|h| format!("0x{:x}", h)).unwrap_or_else(|| "-".into());
+            println!("\nL1 submission: {} (deposit {})", hash(o.l1_tx_hash), native.format(o.deposit));
+            println!("L2 ticket:     {}", hash(o.ticket_id));
+            println!("L2 redeem:     {}", hash(o.redeem_tx_hash));
+            let error = o.error.as_deref().map(|e| format!(": {}", e)).unwrap_or_default();
+            println!("status: {}{}", o.status, error);
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&o)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    if o.status != "redeemed" {
+        return Err(eyre!("retryable ticket ended {}", o.status));
+    }
+    Ok(())
+}
+
+pub async fn plan(args: PlanArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let plan = Plan::load(&args.file)?;
+    let Connection { client, native, node, .. } = connect(&args.conn).await?;
+    info!("Running plan {} from {}", args.file.display(), format_address(client.address()));
+    let outcomes = plan::run(client.as_ref(), &plan, &native).await?;
+    // The exit below would skip stopping a `--local` node.
+    drop(node);
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+
+    match args.output {
+        OutputFormat::Text => {
+            println!();
+            for o in &outcomes {
+                let error = o.error.as_deref().map(|e| format!(": {}", e)).unwrap_or_default();
+                let gas = o.gas_used.map(|g| format!(" (gas {})", g)).unwrap_or_default();
+                println!("  {:<24} type-{}  {}{}{}", o.name, o.tx_type, o.status, gas, error);
+                for v in &o.violations {
+                    println!("    FAILED {}", v);
+                }
+            }
+            println!("\n{} of {} steps as expected", outcomes.len() - failed, outcomes.len());
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&outcomes)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    if failed > 0 {
+        std::process::exit(EXIT_EXPECTATIONS);
+    }
+    Ok(())
+}
@@ -0,0 +1,786 @@
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use futures::future::join_all;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Address, Bytes, U256};
+use ethers::signers::LocalWallet;
+use ethers::utils::parse_units;
+use test_transaction_type::access_list::AccessListSource;
+use test_transaction_type::amount::{Amount, Native};
+use test_transaction_type::archive::Archive;
+use test_transaction_type::basefee::BaseFeeTracker;
+use test_transaction_type::blob::BlobSource;
+use test_transaction_type::builder::TxTypeBuilder;
+use test_transaction_type::chains::Chain;
+#[cfg(feature = "tui")]
+use test_transaction_type::dashboard::Tui;
+use test_transaction_type::dashboard::Dashboard;
+use test_transaction_type::budget::worst_case_cost;
+use test_transaction_type::diff;
+use test_transaction_type::ens;
+use test_transaction_type::erc20;
+use test_transaction_type::escalator::EscalationPolicy;
+use test_transaction_type::expect::{Deviation, Expectations};
+use test_transaction_type::extra::Extension;
+use test_transaction_type::faucet::Faucets;
+use test_transaction_type::history::History;
+use test_transaction_type::hooks::Hooks;
+use test_transaction_type::multicall::Reader;
+use test_transaction_type::nonce::NonceManager;
+use test_transaction_type::plugins;
+use test_transaction_type::private::{PrivateRelay, RelayApi};
+use test_transaction_type::probe::fingerprint;
+use test_transaction_type::receipts::ReceiptBatcher;
+use test_transaction_type::retry::RetryPolicy;
+use test_transaction_type::runlog::RunLog;
+use test_transaction_type::safe;
+use test_transaction_type::shutdown::{Shutdown, EXIT_INTERRUPTED};
+use test_transaction_type::transport::{redact, Transport};
+use test_transaction_type::webhook::{self, Notification, Webhook};
+use test_transaction_type::zksync::{Eip712Meta, Paymaster};
+use test_transaction_type::{
+    format_address, print_recipient_totals, FeeSeries, MarketFees, print_summary, Report, SeriesReport, TxMatrix, TxResult, Workload,
+};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::cli::{OutputFormat, PrivateApi, RecipientMode, SendArgs, WebhookFormat};
+use crate::commands::{calldata, connect, metrics, raw, recipient, Client, Connection};
+
+/// Run the matrix; with `export`, also write every signed envelope of a dry run there.
+pub async fn run(args: SendArgs, export: Option<&Path>) -> Result<()> {
+    if let Some(interval) = args.interval {
+        return monitor(args, interval).await;
+    }
+    let output = args.output;
+    let finished = execute(args, export).await?;
+    match output {
+        OutputFormat::Text => {}
+        OutputFormat::Json => println!("{}", finished.report.to_json()?),
+        OutputFormat::Junit => println!("{}", finished.report.to_junit()),
+    }
+    if finished.interrupted {
+        std::process::exit(EXIT_INTERRUPTED);
+    }
+
+    if let Some(deviations) = finished.deviations {
+        if output == OutputFormat::Text {
+            println!("\nExpectations: {}", if deviations.is_empty() { "all met" } else { "NOT met" });
+            for d in &deviations {
+                println!("  {}", d);
+            }
+        } else {
+            for d in &deviations {
+                warn!("expectation not met: {}", d);
+            }
+        }
+        if !deviations.is_empty() {
+            std::process::exit(EXIT_EXPECTATIONS);
+        }
+    }
+
+    Ok(())
+}
+
+/// A matrix run that went to the end, or was cut short by Ctrl-C.
+pub(crate) struct Finished {
+    pub report: Report,
+    pub interrupted: bool,
+    /// Results that deviate from `--expect`, when given.
+    pub deviations: Option<Vec<Deviation>>,
+}
+
+/// Run the matrix every `interval` until Ctrl-C, comparing every round's statuses with the
+/// previous round's (with `--db`, the latest stored run to begin with), and call the webhook
+/// only when they change or rounds start failing.
+async fn monitor(args: SendArgs, interval: Duration) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit cannot be used with --interval"));
+    }
+    let webhook = webhook(&args);
+    let mut previous = args.db.as_ref().and_then(|path| {
+        let history = History::open(path).ok()?;
+        history.outcomes(history.run_id("latest").ok()?).ok()
+    });
+    let mut failing = false;
+    // One listener for every round, so a Ctrl-C at any point is seen once.
+    let shutdown = Shutdown::on_ctrl_c(Duration::from_secs(args.interrupt_grace_secs));
+    for round in 1u64.. {
+        let started = Instant::now();
+        let mut round_args = args.clone();
+        // Alerts come from comparing rounds, not from each run.
+        round_args.webhook_url = None;
+        let notification = match run_matrix(round_args, None, Some(shutdown.clone())).await {
+            Ok(finished) => {
+                if args.output == OutputFormat::Json {
+                    println!("{}", serde_json::to_string(&finished.report)?);
+                }
+                if finished.interrupted {
+                    std::process::exit(EXIT_INTERRUPTED);
+                }
+                for d in finished.deviations.iter().flatten() {
+                    warn!("expectation not met: {}", d);
+                }
+                let recovered = std::mem::replace(&mut failing, false);
+                let outcomes = diff::from_report(&finished.report);
+                let changes = previous.as_ref().map(|p| diff::compare(p, &outcomes)).unwrap_or_default();
+                previous = Some(outcomes);
+                for c in &changes {
+                    warn!("status changed: {}", c);
+                }
+                info!("Round {}: {} status change(s); next in {:?}", round, changes.len(), interval);
+                if recovered {
+                    Some(Notification::recovered(&finished.report, changes))
+                } else {
+                    (!changes.is_empty()).then(|| Notification::changed(&finished.report, changes))
+                }
+            }
+            Err(e) => {
+                warn!("round {} failed: {:#}", round, e);
+                (!std::mem::replace(&mut failing, true)).then(|| Notification::failed(format!("{:#}", e)))
+            }
+        };
+        if let (Some(webhook), Some(notification)) = (&webhook, notification) {
+            notify(webhook, &notification).await;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep_until(started + interval) => {}
+            _ = shutdown.interrupted() => return Ok(()),
+        }
+    }
+    Ok(())
+}
+
+/// Run the matrix and build its report, then tell the webhook, if any, how it ended.
+pub(crate) async fn execute(args: SendArgs, export: Option<&Path>) -> Result<Finished> {
+    let webhook = webhook(&args);
+    let only_regressions = args.webhook_only_regressions;
+    let finished = run_matrix(args, export, None).await;
+    if let Some(webhook) = webhook {
+        let notification = match &finished {
+            Ok(f) => Notification::finished(&f.report, f.interrupted, f.deviations.as_deref().unwrap_or_default()),
+            Err(e) => Notification::failed(format!("{:#}", e)),
+        };
+        if !only_regressions || notification.regressed() || finished.is_err() {
+            notify(&webhook, &notification).await;
+        }
+    }
+    finished
+}
+
+fn webhook(args: &SendArgs) -> Option<Webhook> {
+    let url = args.webhook_url.as_ref()?;
+    let format = match args.webhook_format {
+        WebhookFormat::Json => webhook::Format::Json,
+        WebhookFormat::Slack => webhook::Format::Slack,
+        WebhookFormat::Discord => webhook::Format::Discord,
+    };
+    Some(Webhook::new(url, format))
+}
+
+async fn notify(webhook: &Webhook, notification: &Notification) {
+    match webhook.send(notification).await {
+        Ok(()) => info!("Notified the webhook: run {}", notification.event),
+        Err(e) => warn!("could not notify the webhook: {}", e),
+    }
+}
+
+/// Run the matrix and build its report; only the text summaries are printed. Without a
+/// `shutdown` to share, the run listens for Ctrl-C itself once it is about to send.
+async fn run_matrix(mut args: SendArgs, export: Option<&Path>, shutdown: Option<Shutdown>) -> Result<Finished> {
+    if args.conn.offline {
+        check_offline(&args)?;
+        args.dry_run = true;
+        if args.fees.contains(&FeeSeries::Market) {
+            warn!("skipping the market fee point: it needs the RPC");
+            args.fees.retain(|f| *f != FeeSeries::Market);
+        }
+        if args.fees.is_empty() {
+            return Err(eyre!("--offline needs at least one fixed FEE_SERIES point"));
+        }
+    }
+    if args.zero_value {
+        args.amount = Amount::zero();
+    }
+    if args.tui && !cfg!(feature = "tui") {
+        return Err(eyre!("--tui needs a build with `--features tui`"));
+    }
+    if args.tui && !std::io::stderr().is_terminal() {
+        return Err(eyre!("--tui needs a terminal on stderr"));
+    }
+    let deadline = args.run_deadline_secs.map(|s| Instant::now() + Duration::from_secs(s));
+
+    // Provider and wallet
+    let Connection { client, senders, heads, chain_id, chain, native, node } = connect(&args.conn).await?;
+    let market = market_fees(&args, chain.as_ref())?;
+    let reader = if args.no_multicall { Reader::disabled() } else { Reader::default() };
+    let from = match senders.as_slice() {
+        [one] => format_address(one.address()),
+        many => format!("{} senders", many.len()),
+    };
+
+    // Parse inputs
+    // `self`: every sender pays itself, so only gas is spent.
+    let self_transfer = args.to.trim().eq_ignore_ascii_case("self");
+    let recipients =
+        if self_transfer { vec![client.address()] } else { recipients(&args.to, client.as_ref()).await? };
+    let to_label = match recipients.as_slice() {
+        _ if self_transfer => "self".to_string(),
+        [to] => format_address(*to),
+        many => format!("{} recipients", many.len()),
+    };
+    // A percentage of the balance comes out different for every sender.
+    let balances = if args.amount.is_relative() {
+        let addresses: Vec<_> = senders.iter().map(|s| s.address()).collect();
+        reader.balances(client.as_ref(), &addresses).await?.into_iter().map(Some).collect()
+    } else {
+        vec![None; senders.len()]
+    };
+    let mut values = HashMap::new();
+    for (sender, balance) in senders.iter().zip(balances) {
+        values.insert(sender.address(), args.amount.to_wei(native.decimals, balance)?);
+    }
+    let value = values[&client.address()];
+    let simulate_balance = match &args.simulate_balance_eth {
+        Some(eth) => Some(native.parse(eth).map_err(|e| eyre!("invalid SIMULATE_BALANCE_ETH: {e}"))?),
+        None => None,
+    };
+
+    let text = args.output == OutputFormat::Text;
+    let workload = match &args.token {
+        Some(token) => {
+            let token: Address = token.parse()?;
+            let decimals = erc20::decimals(client.as_ref(), token).await?;
+            let amount = parse_units(&args.token_amount, decimals).map_err(|e| eyre!("invalid token amount: {e}"))?;
+            info!("From={} To={} Token={} Amount={}", from, to_label, format_address(token), args.token_amount);
+            Workload::Erc20 { token, amount: amount.into() }
+        }
+        None if args.deploy => {
+            info!("From={} Deploying bundled counter contract", from);
+            Workload::Deploy
+        }
+        None => {
+            info!("From={} To={} Amount={}", from, to_label, args.amount);
+            Workload::Native
+        }
+    };
+    let safe = match &args.safe {
+        Some(s) => {
+            if args.amount.is_relative() {
+                return Err(eyre!("a percentage AMOUNT is of the sender's balance, not the Safe's; give a fixed amount"));
+            }
+            // Senders run concurrently and would all sign for the Safe's same next nonce.
+            if senders.len() > 1 {
+                return Err(eyre!("--safe takes a single sender; {} are configured", senders.len()));
+            }
+            let safe = recipient(&client, s).await?;
+            let owners: Vec<_> = senders.iter().map(|s| s.address()).collect();
+            safe::check_owners(&reader, client.as_ref(), safe, &owners).await?;
+            info!("Sending through the Safe {}", format_address(safe));
+            Some(safe)
+        }
+        None => None,
+    };
+    let data = match &args.data {
+        Some(arg) => calldata(arg)?,
+        None => Bytes::new(),
+    };
+    if !data.is_empty() {
+        let zeros = data.iter().filter(|&&b| b == 0).count();
+        info!("Calldata: {} bytes ({} zero, {} non-zero)", data.len(), zeros, data.len() - zeros);
+    }
+
+    let blob_source = match &args.blob_file {
+        Some(path) => BlobSource::Data(std::fs::read(path)?),
+        None => BlobSource::Random(args.blob_count),
+    };
+    let paymaster = match &args.paymaster {
+        Some(address) => {
+            let address = recipient(&client, address).await?;
+            Some(match &args.paymaster_input {
+                Some(input) => Paymaster { address, input: calldata(input)? },
+                None => Paymaster::general(address),
+            })
+        }
+        None => None,
+    };
+    let eip712_meta = Eip712Meta { gas_per_pubdata: args.gas_per_pubdata.into(), paymaster };
+    let mut builders: Vec<Arc<dyn TxTypeBuilder>> = Vec::new();
+    if let Some(path) = &args.extra_fields {
+        let ext = Extension::load(path)?;
+        info!("Type-{} ({:#x}) extends type {} with {}", ext.tx_type, ext.tx_type, ext.base, ext.field_names().join(", "));
+        builders.push(Arc::new(ext));
+    }
+    if let Some(path) = &args.plugins {
+        for plugin in plugins::load(path)? {
+            let tx_type = plugin.builder.tx_type();
+            info!("Plugin {} builds type-{} ({:#x})", plugin.name, tx_type, tx_type);
+            builders.push(plugin.builder);
+        }
+    }
+    for (i, builder) in builders.iter().enumerate() {
+        let tx_type = builder.tx_type();
+        if tx_type <= 2 || builders[..i].iter().any(|b| b.tx_type() == tx_type) {
+            return Err(eyre!("type {} is built twice; give each plugin its own type", tx_type));
+        }
+        if !args.types.contains(&tx_type) {
+            warn!("Type {} is not in TX_TYPES, so its builder is unused", tx_type);
+        }
+    }
+
+    // Fetch each sender's starting nonce once and hand out sequential nonces for the whole run.
+    let mut pool = Vec::new();
+    for sender in &senders {
+        let nonces = match args.nonce.filter(|_| args.conn.offline) {
+            Some(start) => NonceManager::starting_at(sender.address(), start.into()),
+            None => NonceManager::init(sender.as_ref(), sender.address()).await?,
+        };
+        pool.push((sender.clone(), Arc::new(nonces)));
+    }
+
+    let retry = RetryPolicy {
+        max_retries: args.retry_max,
+        base_delay: Duration::from_millis(args.retry_base_ms),
+        ..RetryPolicy::default()
+    };
+    let escalation = args.escalate_after_blocks.map(|after_blocks| EscalationPolicy {
+        after_blocks,
+        bump_percent: args.escalate_percent,
+        max_bumps: args.escalate_max_bumps,
+    });
+    if !args.dry_run && let Some(path) = &args.faucet_config {
+        fund(&args, path, chain_id, &native, &senders).await?;
+    }
+    if !args.dry_run {
+        let value = values.values().copied().max().unwrap_or_default();
+        let spend = Spend { workload, value, calldata: data.len(), blob_source: &blob_source, escalation, safe };
+        preflight(&args, &pool, &recipients, spend, market, &native, client.as_ref(), &reader).await?;
+    }
+
+    let second_rpc = match &args.verify_rpc_url {
+        Some(url) => Some(Arc::new(Provider::new(Transport::connect(url).await?))),
+        None => None,
+    };
+    let private = match &args.private_tx_url {
+        Some(url) => {
+            let auth = args.flashbots_auth_key.as_deref().map(|k| k.trim().parse::<LocalWallet>()).transpose()?;
+            let api = match args.private_tx_api {
+                PrivateApi::Relay => RelayApi::Relay,
+                PrivateApi::Protect => RelayApi::Protect,
+            };
+            info!("Submitting privately via {}", redact(url));
+            Some(Arc::new(PrivateRelay::new(url, api, auth).with_max_blocks(args.private_max_blocks)))
+        }
+        None => None,
+    };
+    let expectations = args.expect.as_ref().map(Expectations::load).transpose()?;
+    let access_list = args.access_list.as_deref().map(AccessListSource::parse).transpose()?;
+    let metrics = metrics(&args.metrics).await?;
+    // From here on the first Ctrl-C ends the run early but still reports it.
+    let shutdown = shutdown.unwrap_or_else(|| Shutdown::on_ctrl_c(Duration::from_secs(args.interrupt_grace_secs)));
+    let run_log = match (&args.resume, args.dry_run) {
+        (_, true) => None,
+        (Some(id), false) => {
+            let log = RunLog::open(&args.run_dir, id)?;
+            if log.chain_id() != chain_id {
+                return Err(eyre!("run {} was on chain {}, not {}", id, log.chain_id(), chain_id));
+            }
+            info!("Resuming run {} ({} tx(s) in flight)", id, log.in_flight());
+            Some(Arc::new(log))
+        }
+        (None, false) => {
+            let log = RunLog::create(&args.run_dir, chain_id)?;
+            info!("Run {} (pick it up with --resume {} if interrupted)", log.id(), log.id());
+            Some(Arc::new(log))
+        }
+    };
+
+    let dashboard = args.tui.then(|| Arc::new(Dashboard::default()));
+    let archive = match (&args.archive_dir, args.dry_run) {
+        (Some(dir), false) => Some(Arc::new(Archive::create(dir)?)),
+        _ => None,
+    };
+
+    // Receipts are awaited concurrently with --parallel or several senders: poll them together.
+    // With MAX_REQUESTS too, since ethers' own polling retries a used-up budget forever.
+    let concurrent = args.parallel || pool.len() > 1;
+    let receipts = (!args.dry_run && (concurrent || args.conn.max_requests.is_some())).then(|| {
+        Arc::new(ReceiptBatcher::new(client.provider().as_ref().clone(), client.provider().get_interval()))
+    });
+    let hooks = Hooks { pre_send: args.pre_send_cmd.clone(), post_receipt: args.post_receipt_cmd.clone() };
+
+    let matrix = |sender: &Arc<Client>, nonces: &Arc<NonceManager>, to: Address, types: &[u8]| {
+        let to = if self_transfer { sender.address() } else { to };
+        let matrix = TxMatrix::new(sender.clone(), to, values[&sender.address()])
+            .with_tx_types(types.iter().copied())
+            .with_workload(workload)
+            .with_data(data.clone())
+            .with_safe(safe)
+            .with_access_list(access_list.clone())
+            .with_blob_source(blob_source.clone())
+            .with_eip712_meta(eip712_meta.clone())
+            .with_dry_run(args.dry_run)
+            .with_simulation(args.simulate)
+            .with_simulation_balance(simulate_balance)
+            .with_trace(args.trace)
+            .with_round_trip(args.round_trip)
+            .with_verification(!args.no_verify)
+            .with_metrics(metrics.clone())
+            .with_dashboard(dashboard.clone())
+            .with_archive(archive.clone())
+            .with_hooks(hooks.clone())
+            .with_progress(dashboard.is_none())
+            .with_head_notifications(heads.clone())
+            .with_receipt_batcher(receipts.clone())
+            .with_nonce_manager(nonces.clone())
+            .with_parallel(args.parallel)
+            .with_tx_timeout(args.tx_timeout_secs.map(Duration::from_secs))
+            .with_deadline(deadline)
+            .with_shutdown(Some(shutdown.clone()))
+            .with_run_log(run_log.clone())
+            .with_escalation(escalation)
+            .with_retry(retry)
+            .with_verification_rpc(second_rpc.clone())
+            .with_private_relay(private.clone())
+            .with_offline_gas(args.gas_limit.filter(|_| args.conn.offline).map(U256::from))
+            .with_pre_eip155(args.pre_eip155)
+            .with_confirmations(args.confirmations)
+            .with_reorg_watch(args.reorg_watch_blocks)
+            .with_explorer(chain.as_ref().and_then(|c| c.explorer.clone()));
+        builders.iter().fold(matrix, |matrix, builder| matrix.with_builder(builder.clone()))
+    };
+
+    let node_fingerprint = if args.conn.offline { None } else { Some(fingerprint(client.as_ref()).await) };
+    if let Some(fp) = &node_fingerprint {
+        info!("Node: {}", fp);
+    }
+    let mut report = Report {
+        chain_id,
+        node: node_fingerprint,
+        from: senders.iter().map(|s| s.address()).collect(),
+        to: if self_transfer { senders.iter().map(|s| s.address()).collect() } else { recipients.clone() },
+        value,
+        series: Vec::new(),
+        nonces: Vec::new(),
+        base_fee: None,
+        rpc_requests: None,
+    };
+    let base_fee_tracker = args
+        .track_base_fee
+        .then(|| BaseFeeTracker::start(client.clone(), client.provider().get_interval()));
+    let fan_out = recipients.len() > 1;
+    let multi_sender = pool.len() > 1;
+    #[cfg(feature = "tui")]
+    let tui = dashboard.clone().map(|d| Tui::start(d, Some(shutdown.clone()))).transpose()?;
+
+    // One series per fee point (0 = gasless chain), per recipient when fanning out, and per
+    // sender when running from a pool; senders run concurrently.
+    for (i, series) in args.fees.iter().enumerate() {
+        let fees = series.resolve_with(client.as_ref(), market).await;
+        if let (FeeSeries::Market, Ok(fees)) = (series, &fees) {
+            info!(
+                "Market fees: max={} priority={} gasPrice={}",
+                fees.max_fee_per_gas,
+                fees.max_priority_fee_per_gas,
+                fees.gas_price.unwrap_or_default()
+            );
+        }
+        for (to, types) in assign(&recipients, &args.types, args.recipient_mode, i) {
+            let runs: Vec<_> = pool
+                .iter()
+                .map(|(sender, nonces)| {
+                    let mut label = format!("fees={}", series);
+                    if multi_sender {
+                        label.push_str(&format!(" from={}", format_address(sender.address())));
+                    }
+                    if fan_out {
+                        label.push_str(&format!(" to={}", format_address(to)));
+                    }
+                    (sender.address(), label, matrix(sender, nonces, to, &types))
+                })
+                .collect();
+            let (fees, types) = (&fees, &types);
+            let results = join_all(runs.iter().map(|(_, label, matrix)| async move {
+                match fees {
+                    Ok(fees) => matrix.run(label, *fees).await,
+                    Err(e) => {
+                        warn!(series = %label, "could not resolve fees: {}", e);
+                        types.iter().map(|&t| TxResult::new(t, "fee error").with_error(e)).collect()
+                    }
+                }
+            }))
+            .await;
+            for ((sender, label, _), mut results) in runs.into_iter().zip(results) {
+                let transport: &Transport = client.provider().as_ref();
+                for r in &mut results {
+                    r.rpc = r.tx_hash.and_then(|hash| transport.served_by(hash));
+                    if !args.dry_run {
+                        r.explorer_url = r.tx_hash.zip(chain.as_ref()).and_then(|(hash, c)| c.tx_url(hash));
+                    }
+                }
+                // Printed once the dashboard is gone.
+                if text && dashboard.is_none() {
+                    print_summary(&label, &results);
+                }
+                report.series.push(SeriesReport {
+                    label,
+                    fees: series.to_string(),
+                    from: multi_sender.then_some(sender),
+                    to: fan_out.then_some(to),
+                    results,
+                });
+            }
+        }
+    }
+    #[cfg(feature = "tui")]
+    drop(tui);
+    if text && dashboard.is_some() {
+        for s in &report.series {
+            print_summary(&s.label, &s.results);
+        }
+    }
+    if text && fan_out {
+        print_recipient_totals(&report.series);
+    }
+    if let Some(tracker) = base_fee_tracker {
+        let base_fee = tracker.finish(client.as_ref(), &report.series).await;
+        if text {
+            println!("\nBase fee: {}", base_fee);
+        }
+        report.base_fee = Some(base_fee);
+    }
+
+    for (sender, nonces) in pool.iter().filter(|_| !args.conn.offline) {
+        let prefix = if multi_sender { format!("Nonces {}", format_address(sender.address())) } else { "Nonces".into() };
+        match nonces.check(sender.as_ref()).await {
+            Ok(nonce_report) => {
+                if text {
+                    println!("\n{}: {}", prefix, nonce_report);
+                }
+                report.nonces.push(nonce_report);
+            }
+            Err(e) => {
+                if text {
+                    println!("\n{}: could not query account nonce: {}", prefix, e);
+                }
+            }
+        }
+    }
+
+    report.rpc_requests = client.provider().as_ref().requests();
+    if text && let Some(requests) = report.rpc_requests {
+        match args.conn.max_requests {
+            Some(max) => println!("\nRPC requests: {} of MAX_REQUESTS={}", requests, max),
+            None => println!("\nRPC requests: {}", requests),
+        }
+    }
+
+    // Done with the node; the exits below would skip stopping a `--local` one.
+    drop(node);
+
+    if let Some(dir) = export {
+        let written = raw::write(dir, &report)?;
+        info!("Wrote {} signed transactions to {}", written, dir.display());
+    }
+
+    if let Some(log) = &run_log {
+        if log.in_flight() == 0 && !shutdown.requested() {
+            log.remove()?;
+        } else {
+            info!("Run {} is unfinished; pick it up with --resume {}", log.id(), log.id());
+        }
+    }
+
+    if let Some(path) = &args.db {
+        let run_id = History::open(path)?.record(&report, args.conn.rpc_url.as_deref().unwrap_or("offline"))?;
+        info!("Stored run {} in {}", run_id, path.display());
+    }
+
+    let deviations = expectations.map(|e| e.check(&report.series, &args.fees));
+    Ok(Finished { report, interrupted: shutdown.requested(), deviations })
+}
+
+/// Exit status when results deviate from `--expect`; runtime errors exit with 1.
+pub(crate) const EXIT_EXPECTATIONS: i32 = 2;
+
+/// Options that need the node cannot be combined with `--offline`.
+fn check_offline(args: &SendArgs) -> Result<()> {
+    let needs_rpc = [
+        (args.token.is_some(), "TOKEN_ADDRESS (decimals are read from the token)"),
+        (args.access_list.as_deref() == Some("auto"), "ACCESS_LIST=auto"),
+        (args.simulate, "SIMULATE"),
+        (args.track_base_fee, "TRACK_BASE_FEE"),
+        (args.archive_dir.is_some(), "ARCHIVE_DIR"),
+        (args.safe.is_some(), "SAFE_ADDRESS"),
+        (args.verify_rpc_url.is_some(), "VERIFY_RPC_URL"),
+        (args.amount.is_relative(), "a percentage AMOUNT (it is of the sender's balance)"),
+    ];
+    match needs_rpc.iter().find(|(set, _)| *set) {
+        Some((_, option)) => Err(eyre!("{} needs the RPC and cannot be used with --offline", option)),
+        None => Ok(()),
+    }
+}
+
+/// `FEE_MULTIPLIER` and `PRIORITY_GWEI`, for `market` fee points; unset ones come from the
+/// chain preset.
+fn market_fees(args: &SendArgs, chain: Option<&Chain>) -> Result<MarketFees> {
+    let defaults = MarketFees::default();
+    let priority = args.priority_gwei.as_deref().or(chain.map(|c| c.priority_gwei.as_str()));
+    let fallback_priority = match priority {
+        Some(gwei) => parse_units(gwei, "gwei").map_err(|e| eyre!("invalid PRIORITY_GWEI: {e}"))?.into(),
+        None => defaults.fallback_priority,
+    };
+    let base_fee_multiplier =
+        args.fee_multiplier.or(chain.map(|c| c.fee_multiplier)).unwrap_or(defaults.base_fee_multiplier);
+    Ok(MarketFees { base_fee_multiplier, fallback_priority })
+}
+
+/// Top up every sender below `FAUCET_MIN_BALANCE_ETH` from the chain's faucet in `path`.
+async fn fund(args: &SendArgs, path: &Path, chain_id: u64, native: &Native, senders: &[Arc<Client>]) -> Result<()> {
+    let faucets = Faucets::load(path)?;
+    let Some(faucet) = faucets.for_chain(chain_id) else {
+        warn!("{} has no faucet for chain {}", path.display(), chain_id);
+        return Ok(());
+    };
+    let threshold =
+        native.parse(&args.faucet_min_balance_eth).map_err(|e| eyre!("invalid FAUCET_MIN_BALANCE_ETH: {e}"))?;
+    let wait = Duration::from_secs(args.faucet_wait_secs);
+    for sender in senders {
+        if let Some(balance) = faucet.top_up(sender.as_ref(), sender.address(), threshold, wait).await? {
+            info!("{} funded by faucet: {}", format_address(sender.address()), native.format(balance));
+        }
+    }
+    Ok(())
+}
+
+/// What each matrix transaction spends, as configured for this run.
+struct Spend<'a> {
+    workload: Workload,
+    value: U256,
+    calldata: usize,
+    blob_source: &'a BlobSource,
+    escalation: Option<EscalationPolicy>,
+    /// Pays the value instead of the senders.
+    safe: Option<Address>,
+}
+
+/// Compare the worst-case spend of the whole matrix with each sender's balance (warning when
+/// short) and with `MAX_SPEND_ETH` (refusing, or asking on a terminal, when over).
+#[allow(clippy::too_many_arguments)]
+async fn preflight(
+    args: &SendArgs,
+    pool: &[(Arc<Client>, Arc<NonceManager>)],
+    recipients: &[Address],
+    spend: Spend<'_>,
+    market: MarketFees,
+    native: &Native,
+    client: &Client,
+    reader: &Reader,
+) -> Result<()> {
+    let Spend { workload, value, calldata, blob_source, escalation, safe } = spend;
+    let sender_value = if safe.is_some() { U256::zero() } else { value };
+    let mut per_sender = U256::zero();
+    let mut transactions = 0;
+    for (i, series) in args.fees.iter().enumerate() {
+        // Unresolvable market fees fail the series anyway; nothing is spent on it.
+        let Ok(fees) = series.resolve_with(client, market).await else { continue };
+        for (_, types) in assign(recipients, &args.types, args.recipient_mode, i) {
+            for t in types {
+                let blobs = blob_source.blob_count();
+                per_sender += worst_case_cost(t, workload, sender_value, calldata, &fees, blobs, escalation.as_ref());
+                transactions += 1;
+            }
+        }
+    }
+
+    // The Safe's balance, when it pays, comes first in the same batch as the senders'.
+    let safe = safe.filter(|_| matches!(workload, Workload::Native));
+    let addresses: Vec<_> = safe.into_iter().chain(pool.iter().map(|(sender, _)| sender.address())).collect();
+    let mut balances = reader.balances(client, &addresses).await?.into_iter();
+    if let Some(safe) = safe {
+        let needed = value * transactions * pool.len();
+        let balance = balances.next().unwrap_or_default();
+        if balance < needed {
+            warn!(
+                "the Safe {} holds {} but the run sends up to {} from it; later transactions may fail",
+                format_address(safe),
+                native.format(balance),
+                native.format(needed)
+            );
+        }
+    }
+
+    for ((sender, _), balance) in pool.iter().zip(balances) {
+        if balance < per_sender {
+            warn!(
+                "{} holds {} but the run may spend up to {}; later transactions may fail",
+                format_address(sender.address()),
+                native.format(balance),
+                native.format(per_sender)
+            );
+        }
+    }
+
+    let Some(max) = &args.max_spend_eth else { return Ok(()) };
+    let max = native.parse(max).map_err(|e| eyre!("invalid MAX_SPEND_ETH: {e}"))?;
+    let total = per_sender * pool.len();
+    if total <= max {
+        return Ok(());
+    }
+    let message = format!("worst-case spend {} exceeds MAX_SPEND_ETH={}", native.format(total), native.format(max));
+    if args.conn.no_prompt || !std::io::stdin().is_terminal() {
+        return Err(eyre!("{}; refusing to run", message));
+    }
+    eprint!("{}. Continue? [y/N] ", message);
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        return Err(eyre!("aborted: {}", message));
+    }
+    Ok(())
+}
+
+/// `TO_ADDRESS`: a file of addresses or ENS names (one per line, `#` comments allowed) if
+/// such a file exists, otherwise a comma-separated list.
+async fn recipients(arg: &str, client: &Client) -> Result<Vec<Address>> {
+    let list = if Path::new(arg).is_file() || ens::is_path(arg) {
+        std::fs::read_to_string(arg).map_err(|e| eyre!("reading TO_ADDRESS file {}: {}", arg, e))?
+    } else {
+        arg.to_string()
+    };
+    let mut recipients = Vec::new();
+    for s in list.lines().map(|line| line.split('#').next().unwrap_or_default()).flat_map(|line| line.split(',')) {
+        let s = s.trim();
+        if !s.is_empty() {
+            recipients.push(recipient(client, s).await?);
+        }
+    }
+    if recipients.is_empty() {
+        return Err(eyre!("no recipients in TO_ADDRESS"));
+    }
+    Ok(recipients)
+}
+
+/// Which tx types go to which recipient in series number `series`. Round-robin keeps
+/// rotating across series so every recipient gets a turn even with few tx types.
+fn assign(recipients: &[Address], types: &[u8], mode: RecipientMode, series: usize) -> Vec<(Address, Vec<u8>)> {
+    match mode {
+        RecipientMode::Each => recipients.iter().map(|&to| (to, types.to_vec())).collect(),
+        RecipientMode::RoundRobin => {
+            let mut assigned: Vec<(Address, Vec<u8>)> = Vec::new();
+            for (j, &t) in types.iter().enumerate() {
+                let to = recipients[(series * types.len() + j) % recipients.len()];
+                match assigned.iter_mut().find(|(r, _)| *r == to) {
+                    Some((_, types)) => types.push(t),
+                    None => assigned.push((to, vec![t])),
+                }
+            }
+            assigned
+        }
+    }
+}
@@ -0,0 +1,436 @@
+//! `serve`: a small HTTP API for orchestration systems to start matrix runs and fetch their
+//! reports, instead of invoking the binary once per run.
+//!
+//! `POST /runs` takes `{"args": ["--rpc-url", "...", "--types", "0,2"]}`, the `send` flags of
+//! one run (anything not given falls back to the server's environment, as on the command
+//! line), and answers `202` with the run's id. `GET /runs/{id}` answers with its status and,
+//! once it is over, its report as `--output json` would print it. Runs go one at a time, in
+//! order, so they never compete for a sender's nonces.
+//!
+//! Requests only set parameters of the run itself (see [`RUN_FLAGS`]): options that read or
+//! write files, run commands, bind addresses or start processes keep the server's settings.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use clap::{CommandFactory, Parser};
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use test_transaction_type::{ens, Report};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::cli::{Cli, Command, OutputFormat, SendArgs, ServeArgs};
+use crate::commands::send;
+
+/// Largest request accepted, headers included.
+const MAX_REQUEST: usize = 64 * 1024;
+
+/// How long a client has to send its whole request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `send` flags a request may give.
+const RUN_FLAGS: &[&str] = &[
+    // Endpoint and chain.
+    "rpc-url",
+    "rpc-headers",
+    "rpc-proxy",
+    "max-rps",
+    "max-requests",
+    "offline",
+    "chain-id",
+    "chain",
+    "native-decimals",
+    "chainlist",
+    "adopt-chain-id",
+    "verify-rpc-url",
+    // Senders derived from the server's key.
+    "sender-count",
+    "account-index",
+    // The transactions.
+    "to",
+    "recipient-mode",
+    "amount",
+    "zero-value",
+    "priority-gwei",
+    "fee-multiplier",
+    "token",
+    "token-amount",
+    "confirmations",
+    "reorg-watch-blocks",
+    "pre-eip155",
+    "data",
+    "deploy",
+    "safe",
+    "access-list",
+    "private-tx-url",
+    "private-tx-api",
+    "flashbots-auth-key",
+    "private-max-blocks",
+    "no-verify",
+    "no-multicall",
+    "nonce",
+    "gas-limit",
+    "max-spend-eth",
+    "types",
+    "fees",
+    "blob-count",
+    "paymaster",
+    "paymaster-input",
+    "gas-per-pubdata",
+    // How the run goes.
+    "dry-run",
+    "simulate",
+    "simulate-balance-eth",
+    "trace",
+    "round-trip",
+    "track-base-fee",
+    "parallel",
+    "tx-timeout-secs",
+    "run-deadline-secs",
+    "interrupt-grace-secs",
+    "escalate-after-blocks",
+    "escalate-percent",
+    "escalate-max-bumps",
+    "retry-max",
+    "retry-base-ms",
+    "webhook-url",
+    "webhook-format",
+    "webhook-only-regressions",
+];
+
+#[derive(Deserialize)]
+struct RunRequest {
+    args: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Run {
+    id: u64,
+    /// `queued`, `running`, `finished`, `interrupted` or `failed`.
+    status: &'static str,
+    args: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Results that deviate from `--expect`, when given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deviations: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<Report>,
+}
+
+#[derive(Default)]
+struct Runs {
+    next_id: u64,
+    by_id: BTreeMap<u64, Run>,
+}
+
+type Shared = Arc<Mutex<Runs>>;
+
+pub async fn run(args: ServeArgs) -> Result<()> {
+    if args.token.is_none() && !args.addr.ip().is_loopback() {
+        return Err(eyre!("SERVE_TOKEN is required to listen on {}, which is not a loopback address", args.addr));
+    }
+    let listener = TcpListener::bind(args.addr).await.map_err(|e| eyre!("binding {}: {}", args.addr, e))?;
+    info!("Serving the run API on http://{} (POST /runs, GET /runs/{{id}})", args.addr);
+    if args.token.is_none() {
+        warn!("SERVE_TOKEN is not set: anyone on this machine can spend the configured keys");
+    }
+
+    let runs: Shared = Arc::default();
+    let (queue, mut queued) = mpsc::unbounded_channel::<(u64, SendArgs)>();
+    let worker = runs.clone();
+    tokio::spawn(async move {
+        while let Some((id, send_args)) = queued.recv().await {
+            set(&worker, id, |run| run.status = "running");
+            info!("Run {} started", id);
+            let finished = send::execute(send_args, None).await;
+            set(&worker, id, |run| match finished {
+                Ok(f) => {
+                    run.status = if f.interrupted { "interrupted" } else { "finished" };
+                    run.deviations = f.deviations.map(|d| d.iter().map(ToString::to_string).collect());
+                    run.report = Some(f.report);
+                }
+                Err(e) => {
+                    run.status = "failed";
+                    run.error = Some(format!("{:#}", e));
+                }
+            });
+            info!("Run {} is over", id);
+        }
+    });
+
+    let token = Arc::new(args.token);
+    loop {
+        let (stream, _) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        };
+        let (runs, queue, token) = (runs.clone(), queue.clone(), token.clone());
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, &runs, &queue, token.as_deref()).await {
+                warn!("run API request failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle(
+    mut stream: TcpStream,
+    runs: &Shared,
+    queue: &mpsc::UnboundedSender<(u64, SendArgs)>,
+    token: Option<&str>,
+) -> Result<()> {
+    let request = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await {
+        Ok(request) => request?,
+        Err(_) => return respond(&mut stream, "408 Request Timeout", json!({ "error": "request not received in time" })).await,
+    };
+    let Some(request) = request else {
+        return respond(&mut stream, "413 Payload Too Large", json!({ "error": "request too large" })).await;
+    };
+    if let Some(token) = token {
+        let expected = format!("Bearer {}", token);
+        if !request.header("authorization").is_some_and(|h| constant_time_eq(h.as_bytes(), expected.as_bytes())) {
+            return respond(&mut stream, "401 Unauthorized", json!({ "error": "missing or wrong bearer token" })).await;
+        }
+    }
+
+    let path = request.path.trim_end_matches('/');
+    let (status, body) = match (request.method.as_str(), path.strip_prefix("/runs")) {
+        ("POST", Some("")) => match start(runs, queue, &request.body) {
+            Ok(id) => ("202 Accepted", json!({ "id": id, "status": "queued" })),
+            Err(e) => ("400 Bad Request", json!({ "error": e.to_string() })),
+        },
+        ("GET", Some(id)) if id.starts_with('/') => {
+            let run = id[1..].parse().ok().and_then(|id: u64| runs.lock().unwrap().by_id.get(&id).cloned());
+            match run {
+                Some(run) => ("200 OK", serde_json::to_value(run)?),
+                None => ("404 Not Found", json!({ "error": "no such run" })),
+            }
+        }
+        (_, Some("")) => ("405 Method Not Allowed", json!({ "error": "use POST /runs" })),
+        (_, Some(id)) if id.starts_with('/') => ("405 Method Not Allowed", json!({ "error": "use GET /runs/{id}" })),
+        _ => ("404 Not Found", json!({ "error": "not found" })),
+    };
+    respond(&mut stream, status, body).await
+}
+
+/// Parse the `send` flags of a run request and queue the run.
+fn start(runs: &Shared, queue: &mpsc::UnboundedSender<(u64, SendArgs)>, body: &[u8]) -> Result<u64> {
+    let request: RunRequest = serde_json::from_slice(body).map_err(|e| eyre!("expected {{\"args\": [...]}}: {}", e))?;
+    check_flags(&request.args)?;
+    let argv = ["test-transaction", "send"].into_iter().map(String::from).chain(request.args.iter().cloned());
+    let Some(Command::Send(mut send_args)) = Cli::try_parse_from(argv)?.command else {
+        unreachable!("parsed as send")
+    };
+    // Reports are returned by the API, and nobody is at the terminal to answer.
+    send_args.output = OutputFormat::Json;
+    send_args.conn.no_prompt = true;
+    // Hooks are shell commands: served runs get none, not even the server's own.
+    send_args.pre_send_cmd = None;
+    send_args.post_receipt_cmd = None;
+
+    let mut runs = runs.lock().unwrap();
+    runs.next_id += 1;
+    let id = runs.next_id;
+    let run = Run { id, status: "queued", args: request.args, error: None, deviations: None, report: None };
+    runs.by_id.insert(id, run);
+    queue.send((id, *send_args)).map_err(|_| eyre!("the run worker stopped"))?;
+    info!("Run {} queued", id);
+    Ok(id)
+}
+
+/// Refuse anything but [`RUN_FLAGS`], and values of them that name a file.
+fn check_flags(args: &[String]) -> Result<()> {
+    let command = <Cli as CommandFactory>::command();
+    let send = command.find_subcommand("send").expect("send is a subcommand");
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            return Err(eyre!("unexpected argument {:?}: give each flag as --name [value]", arg));
+        };
+        let (name, inline) = match flag.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (flag, None),
+        };
+        if !RUN_FLAGS.contains(&name) {
+            return Err(eyre!("--{} is not available for served runs", name));
+        }
+        let takes_value = send.get_arguments().any(|a| a.get_long() == Some(name) && a.get_action().takes_values());
+        let value = match inline {
+            Some(value) => Some(value),
+            // A value that looks like a flag would be parsed as one, unchecked.
+            None if takes_value => match args.next() {
+                Some(value) if !value.starts_with('-') => Some(value.as_str()),
+                _ => return Err(eyre!("--{} needs a value", name)),
+            },
+            None => None,
+        };
+        let names_file = match (name, value) {
+            ("to", Some(v)) => ens::is_path(v) || Path::new(v).exists(),
+            ("data", Some(v)) => v.starts_with('@'),
+            ("access-list", Some(v)) => v != "auto",
+            _ => false,
+        };
+        if names_file {
+            return Err(eyre!("--{} cannot name a file in a served run", name));
+        }
+    }
+    Ok(())
+}
+
+/// Equality that takes as long wherever the first difference is, so response times do not
+/// reveal how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn set(runs: &Shared, id: u64, f: impl FnOnce(&mut Run)) {
+    if let Some(run) = runs.lock().unwrap().by_id.get_mut(&id) {
+        f(run);
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// Value of header `name`, given in lowercase.
+    fn header(&self, name: &str) -> Option<&String> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+/// Read one request, its body as long as its `Content-Length`; `None` when it is larger than
+/// `MAX_REQUEST`.
+async fn read_request(stream: &mut TcpStream) -> Result<Option<Request>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i;
+        }
+        if buf.len() > MAX_REQUEST {
+            return Ok(None);
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(eyre!("connection closed mid-request"));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(n, v)| (n.trim().to_ascii_lowercase(), v.trim().to_string()))
+        .collect();
+
+    let length: usize = headers.iter().find(|(n, _)| n == "content-length").and_then(|(_, v)| v.parse().ok()).unwrap_or(0);
+    // Compared without adding, which a huge Content-Length would overflow.
+    if head_end + 4 > MAX_REQUEST || length > MAX_REQUEST - (head_end + 4) {
+        return Ok(None);
+    }
+    let mut body = buf.split_off(head_end + 4);
+    while body.len() < length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(eyre!("connection closed mid-body"));
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(length);
+    Ok(Some(Request { method, path, headers, body }))
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: Value) -> Result<()> {
+    let body = serde_json::to_string_pretty(&body)?;
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(args: &[&str]) -> Result<()> {
+        check_flags(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn takes_run_parameters() {
+        check(&["--rpc-url", "https://rpc.example", "--types", "0,2", "--fees=market", "--parallel"]).unwrap();
+        check(&["--to", "0x70997970C51812dc3A010C7d01b50e0d17dc79C8", "--data", "0xdead", "--access-list", "auto"]).unwrap();
+    }
+
+    #[test]
+    fn refuses_commands_and_paths() {
+        for args in [
+            &["--pre-send-cmd", "id"][..],
+            &["--post-receipt-cmd=id"],
+            &["--db", "/tmp/runs.db"],
+            &["--keystore", "/etc/passwd"],
+            &["--blob-file", "/etc/passwd"],
+            &["--tui"],
+            &["--interval", "1m"],
+            &["-h"],
+            &["--", "--pre-send-cmd", "id"],
+        ] {
+            assert!(check(args).is_err(), "{:?} accepted", args);
+        }
+    }
+
+    #[test]
+    fn refuses_values_naming_files() {
+        for args in [
+            &["--data", "@/etc/passwd"][..],
+            &["--data=@/etc/passwd"],
+            &["--to", "/etc/hosts"],
+            &["--to", "addresses.txt"],
+            &["--access-list", "list.json"],
+        ] {
+            assert!(check(args).is_err(), "{:?} accepted", args);
+        }
+    }
+
+    #[test]
+    fn a_flag_is_not_taken_as_a_value() {
+        assert!(check(&["--amount", "--pre-send-cmd", "id"]).is_err());
+        assert!(check(&["--amount"]).is_err());
+    }
+
+    #[test]
+    fn compares_tokens() {
+        assert!(constant_time_eq(b"Bearer s3cret", b"Bearer s3cret"));
+        assert!(!constant_time_eq(b"Bearer s3cret", b"Bearer s3creT"));
+        assert!(!constant_time_eq(b"Bearer s3cret", b"Bearer s3cre"));
+        assert!(!constant_time_eq(b"", b"Bearer s3cret"));
+    }
+
+    #[test]
+    fn run_flags_are_send_flags() {
+        let command = <Cli as CommandFactory>::command();
+        let send = command.find_subcommand("send").unwrap();
+        for flag in RUN_FLAGS {
+            assert!(send.get_arguments().any(|a| a.get_long() == Some(flag)), "--{} is not a send flag", flag);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use eyre::{eyre, Result};
+use ethers::types::transaction::eip712::TypedData;
+use test_transaction_type::format_address;
+use test_transaction_type::typed_data;
+use tracing::info;
+
+use crate::cli::{OutputFormat, SignTypedArgs};
+use crate::commands::{connect, recipient, Connection};
+
+pub async fn run(args: SignTypedArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    if args.conn.offline {
+        return Err(eyre!("sign-typed verifies through eth_call and cannot be used with --offline"));
+    }
+    let Connection { client, chain_id, node: _node, .. } = connect(&args.conn).await?;
+    let verifier = match &args.verifier {
+        Some(s) => Some(recipient(&client, s).await?),
+        None => None,
+    };
+    let payload: TypedData = match &args.payload {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).map_err(|e| eyre!("reading {}: {}", path.display(), e))?;
+            serde_json::from_str(&text).map_err(|e| eyre!("parsing {}: {}", path.display(), e))?
+        }
+        None => typed_data::sample(chain_id, verifier)?,
+    };
+    info!("Signing a {} as {}", payload.primary_type, format_address(client.address()));
+
+    let verification = typed_data::sign_and_verify(client.as_ref(), &payload, verifier).await?;
+    match args.output {
+        OutputFormat::Text => {
+            println!("signer:    {:?}", verification.signer);
+            println!("digest:    {:?}", verification.digest);
+            println!("signature: {}", verification.signature);
+            for check in &verification.checks {
+                println!("{}", check);
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&verification)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    if !verification.valid() {
+        let failed = verification.checks.iter().filter(|c| !c.ok).count();
+        return Err(eyre!("{} of {} signature check(s) failed", failed, verification.checks.len()));
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use test_transaction_type::{format_address, sweep};
+use tracing::info;
+
+use crate::cli::{OutputFormat, SweepArgs};
+use crate::commands::{connect, recipient, Connection};
+
+pub async fn run(args: SweepArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let Connection { client, senders, native, node: _node, .. } = connect(&args.conn).await?;
+    let to = recipient(&client, &args.to).await?;
+    let fees = args.fee.resolve(client.as_ref()).await?;
+    let wait = Duration::from_secs(args.wait_secs);
+
+    let mut sweeps = Vec::with_capacity(senders.len());
+    for sender in &senders {
+        if sender.address() == to {
+            info!("{}: is the sweep target, skipping", format_address(to));
+            continue;
+        }
+        let s = sweep::run(sender.as_ref(), to, args.tx_type, fees, wait).await?;
+        if args.output == OutputFormat::Text {
+            let hash = s.tx_hash.map(|h| format!(" 0x{:x}", h)).unwrap_or_default();
+            let error = s.error.as_deref().map(|e| format!(": {}", e)).unwrap_or_default();
+            println!(
+                "{} → {}: {} of {} (fee {}) {}{}{}",
+                format_address(s.from),
+                format_address(s.to),
+                native.format(s.value),
+                native.format(s.balance),
+                native.format(s.fee),
+                s.status,
+                hash,
+                error
+            );
+        }
+        sweeps.push(s);
+    }
+
+    match args.output {
+        OutputFormat::Text => {}
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&sweeps)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    let failed = sweeps.iter().filter(|s| s.status == "rejected" || s.status == "pending").count();
+    if failed > 0 {
+        return Err(eyre!("{} sweep(s) not mined", failed));
+    }
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use test_transaction_type::format_address;
+use test_transaction_type::unstick;
+use tracing::info;
+
+use crate::cli::{OutputFormat, UnstickArgs};
+use crate::commands::{connect, Connection};
+
+pub async fn run(args: UnstickArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let Connection { senders, node: _node, .. } = connect(&args.conn).await?;
+    let fees = args.fee.resolve(senders[0].as_ref()).await?;
+    let wait = Duration::from_secs(args.wait_secs);
+
+    let mut stuck = 0;
+    let mut all = Vec::with_capacity(senders.len());
+    for sender in &senders {
+        let from = format_address(sender.address());
+        let fills = unstick::run(sender.as_ref(), args.tx_type, fees, wait).await?;
+        if fills.is_empty() {
+            info!("{}: no stuck nonces", from);
+        }
+        stuck += fills.iter().filter(|f| f.status != "mined" && f.status != "filled").count();
+        if args.output == OutputFormat::Text && !fills.is_empty() {
+            println!("\n{}:", from);
+            for f in &fills {
+                let hash = f.tx_hash.map(|h| format!(" 0x{:x}", h)).unwrap_or_default();
+                let block = f.block_number.map(|b| format!(" in block {}", b)).unwrap_or_default();
+                let error = f.error.as_deref().map(|e| format!(": {}", e)).unwrap_or_default();
+                println!("  nonce {}: {}{}{}{}", f.nonce, f.status, hash, block, error);
+            }
+        }
+        all.push(serde_json::json!({ "from": sender.address(), "fills": fills }));
+    }
+
+    match args.output {
+        OutputFormat::Text => {}
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&all)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    if stuck > 0 {
+        return Err(eyre!("{} nonce(s) still stuck; retry with a higher --fee", stuck));
+    }
+    Ok(())
+}
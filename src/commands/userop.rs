@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Bytes, H256};
+use test_transaction_type::transport::Transport;
+use test_transaction_type::userop::{self, Paymaster};
+use test_transaction_type::format_address;
+use tracing::info;
+
+use crate::cli::{OutputFormat, PaymasterApi, UserOpArgs};
+use crate::commands::{calldata, connect, recipient, Connection};
+
+pub async fn run(args: UserOpArgs) -> Result<()> {
+    if args.output == OutputFormat::Junit {
+        return Err(eyre!("--output junit is only supported by send"));
+    }
+    let Connection { client, native, node: _node, .. } = connect(&args.conn).await?;
+    let bundler = Provider::new(Transport::connect(&args.bundler_url).await?);
+    let account = recipient(&client, &args.account).await?;
+    let entry_point = recipient(&client, &args.entry_point).await?;
+    let to = recipient(&client, &args.to).await?;
+    let init_code = match &args.init_code {
+        Some(arg) => calldata(arg)?,
+        None => Bytes::new(),
+    };
+    let balance = if args.amount.is_relative() {
+        Some(client.get_balance(account, None).await.map_err(|e| eyre!("eth_getBalance: {}", e))?)
+    } else {
+        None
+    };
+    let value = args.amount.to_wei(native.decimals, balance)?;
+    let fees = args.fee.resolve(client.as_ref()).await?;
+    let paymaster = match (&args.paymaster_and_data, args.paymaster_api) {
+        (Some(data), _) => Some(Paymaster::Static(calldata(data)?)),
+        (None, Some(api)) => {
+            let rpc = Provider::new(Transport::connect(args.paymaster_url.as_ref().unwrap_or(&args.bundler_url)).await?);
+            let context = match &args.paymaster_context {
+                Some(json) => Some(serde_json::from_str(json).map_err(|e| eyre!("invalid PAYMASTER_CONTEXT: {}", e))?),
+                None => None,
+            };
+            Some(match api {
+                PaymasterApi::Sponsor => Paymaster::Sponsor { rpc, context },
+                PaymasterApi::Erc7677 => Paymaster::Erc7677 { rpc, context },
+            })
+        }
+        (None, None) => None,
+    };
+    info!(
+        "UserOperation: {} → {} from account {} (owner {}) via EntryPoint {}",
+        native.format(value),
+        format_address(to),
+        format_address(account),
+        format_address(client.address()),
+        format_address(entry_point)
+    );
+    let o = userop::run(
+        client.as_ref(),
+        &bundler,
+        entry_point,
+        account,
+        init_code,
+        to,
+        value,
+        fees,
+        paymaster.as_ref(),
+        Duration::from_secs(args.wait_secs),
+    )
+    .await?;
+
+    match args.output {
+        OutputFormat::Text => {
+            let hash = |h: Option<H256>| h.map(|h| format!("0x{:x}", h)).unwrap_or_else(|| "-".into());
+            let block = o.block_number.map(|b| format!(" in block {}", b)).unwrap_or_default();
+            println!("\nUserOperation: {} (nonce {})", hash(o.user_op_hash), o.nonce);
+            println!("bundle tx: {}{}", hash(o.tx_hash), block);
+            if let (Some(cost), Some(gas)) = (o.actual_gas_cost, o.actual_gas_used) {
+                let payer = o.paymaster.map(|p| format!("paymaster {}", format_address(p))).unwrap_or_else(|| "account".into());
+                println!("paid: {} for {} gas by {}", native.format(cost), gas, payer);
+            }
+            let error = o.error.as_deref().map(|e| format!(": {}", e)).unwrap_or_default();
+            println!("status: {}{}", o.status, error);
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&o)?),
+        OutputFormat::Junit => unreachable!("rejected above"),
+    }
+    if o.status != "success" {
+        return Err(eyre!("UserOperation ended {}", o.status));
+    }
+    Ok(())
+}
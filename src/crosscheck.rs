@@ -0,0 +1,68 @@
+//! Compare what a second RPC endpoint reports for a mined transaction with what the endpoint
+//! it was submitted through reports, e.g. a chain's public gateway against its sequencer.
+
+use std::fmt::Debug;
+use std::time::Duration;
+
+use ethers::providers::Middleware;
+use ethers::types::H256;
+use eyre::{eyre, Result};
+use tokio::time::{sleep, Instant};
+
+/// How long the second endpoint may lag behind before a missing receipt counts as a discrepancy.
+const SYNC_WAIT: Duration = Duration::from_secs(30);
+
+/// Fields of the transaction and its receipt on which `primary` and `secondary` disagree;
+/// empty when they agree.
+pub async fn compare<A, B>(primary: &A, secondary: &B, hash: H256) -> Result<Vec<String>>
+where
+    A: Middleware,
+    B: Middleware,
+    A::Error: 'static,
+    B::Error: 'static,
+{
+    let receipt = primary.get_transaction_receipt(hash).await?.ok_or_else(|| eyre!("no receipt on primary"))?;
+    let tx = primary.get_transaction(hash).await?.ok_or_else(|| eyre!("no transaction on primary"))?;
+
+    let give_up_at = Instant::now() + SYNC_WAIT;
+    let other_receipt = loop {
+        match secondary.get_transaction_receipt(hash).await? {
+            Some(r) => break r,
+            None if Instant::now() >= give_up_at => {
+                return Ok(vec![format!("no receipt after {:?}", SYNC_WAIT)]);
+            }
+            None => sleep(secondary.provider().get_interval()).await,
+        }
+    };
+    let mut discrepancies = Vec::new();
+    let mut field = |name: &str, ours: &dyn Debug, theirs: &dyn Debug| {
+        let (ours, theirs) = (format!("{:?}", ours), format!("{:?}", theirs));
+        if ours != theirs {
+            discrepancies.push(format!("{} is {} there, {} here", name, theirs, ours));
+        }
+    };
+    field("block number", &receipt.block_number, &other_receipt.block_number);
+    field("block hash", &receipt.block_hash, &other_receipt.block_hash);
+    field("status", &receipt.status, &other_receipt.status);
+    field("gas used", &receipt.gas_used, &other_receipt.gas_used);
+    field("effective gas price", &receipt.effective_gas_price, &other_receipt.effective_gas_price);
+    field("contract address", &receipt.contract_address, &other_receipt.contract_address);
+    field("log count", &receipt.logs.len(), &other_receipt.logs.len());
+
+    match secondary.get_transaction(hash).await? {
+        Some(other) => {
+            field("type", &tx.transaction_type, &other.transaction_type);
+            field("from", &tx.from, &other.from);
+            field("to", &tx.to, &other.to);
+            field("nonce", &tx.nonce, &other.nonce);
+            field("value", &tx.value, &other.value);
+            field("gas", &tx.gas, &other.gas);
+            field("gas price", &tx.gas_price, &other.gas_price);
+            field("max fee", &tx.max_fee_per_gas, &other.max_fee_per_gas);
+            field("priority fee", &tx.max_priority_fee_per_gas, &other.max_priority_fee_per_gas);
+            field("input", &tx.input, &other.input);
+        }
+        None => discrepancies.push("eth_getTransactionByHash returned nothing".into()),
+    }
+    Ok(discrepancies)
+}
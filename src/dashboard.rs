@@ -0,0 +1,258 @@
+//! Live view of a matrix run: every entry's state as it moves from attempted to submitted,
+//! mined and confirmed, kept for a terminal dashboard (`send --tui`, built with
+//! `--features tui`) that redraws it in place instead of scrolling progress logs.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use ethers::types::H256;
+use tokio::time::Instant;
+
+use crate::error_kind::ErrorKind;
+use crate::report::TxResult;
+
+/// Set while the dashboard owns the terminal.
+static DRAWING: AtomicBool = AtomicBool::new(false);
+
+/// Whether the dashboard is on screen, so log output would only garble it.
+pub fn drawing() -> bool {
+    DRAWING.load(Ordering::Relaxed)
+}
+
+/// One matrix entry as last seen.
+#[derive(Clone, Debug)]
+pub struct TxRow {
+    pub series: String,
+    pub tx_type: u8,
+    /// `sending`, `submitted`, `mined`, then the final status of the result.
+    pub status: String,
+    pub tx_hash: Option<H256>,
+    pub block_number: Option<u64>,
+    /// Blocks on top of the mined one (counting it) and how many are waited for.
+    pub confirmations: Option<(u64, u64)>,
+    pub error: Option<String>,
+    pub error_kind: Option<ErrorKind>,
+    pub started: Instant,
+    pub finished: Option<Instant>,
+}
+
+impl TxRow {
+    /// Time since the entry was attempted, frozen once it is settled.
+    pub fn elapsed(&self) -> Duration {
+        self.finished.unwrap_or_else(Instant::now).saturating_duration_since(self.started)
+    }
+
+    pub fn in_flight(&self) -> bool {
+        self.finished.is_none()
+    }
+}
+
+/// Entries of a run in the order they were attempted, updated by every matrix sharing it.
+#[derive(Debug)]
+pub struct Dashboard {
+    rows: Mutex<Vec<TxRow>>,
+    started: Instant,
+}
+
+impl Default for Dashboard {
+    fn default() -> Self {
+        Self { rows: Mutex::default(), started: Instant::now() }
+    }
+}
+
+impl Dashboard {
+    /// A transaction of `tx_type` in `series` is being built and sent.
+    pub fn started(&self, series: &str, tx_type: u8) {
+        self.update(series, tx_type, |row| row.status = "sending".into());
+    }
+
+    pub fn submitted(&self, series: &str, tx_type: u8, hash: H256) {
+        self.update(series, tx_type, |row| {
+            row.status = "submitted".into();
+            row.tx_hash = Some(hash);
+        });
+    }
+
+    /// Mined in `block`, with `confirmations` of `target` blocks seen so far.
+    pub fn confirmed(&self, series: &str, tx_type: u8, block: u64, confirmations: u64, target: u64) {
+        self.update(series, tx_type, |row| {
+            row.status = "mined".into();
+            row.block_number = Some(block);
+            row.confirmations = Some((confirmations, target));
+        });
+    }
+
+    pub fn finished(&self, series: &str, result: &TxResult) {
+        self.update(series, result.tx_type, |row| {
+            row.status = result.status.clone();
+            row.tx_hash = result.tx_hash.or(row.tx_hash);
+            row.block_number = result.block_number;
+            row.error = result.error.clone();
+            row.error_kind = result.error_kind;
+            row.finished = Some(Instant::now());
+        });
+    }
+
+    /// Every entry so far.
+    pub fn rows(&self) -> Vec<TxRow> {
+        self.rows.lock().unwrap().clone()
+    }
+
+    /// Time since the dashboard was created.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    fn update(&self, series: &str, tx_type: u8, f: impl FnOnce(&mut TxRow)) {
+        let mut rows = self.rows.lock().unwrap();
+        let i = match rows.iter().position(|r| r.tx_type == tx_type && r.series == series) {
+            Some(i) => i,
+            None => {
+                rows.push(TxRow {
+                    series: series.to_string(),
+                    tx_type,
+                    status: "sending".into(),
+                    tx_hash: None,
+                    block_number: None,
+                    confirmations: None,
+                    error: None,
+                    error_kind: None,
+                    started: Instant::now(),
+                    finished: None,
+                });
+                rows.len() - 1
+            }
+        };
+        f(&mut rows[i]);
+    }
+}
+
+#[cfg(feature = "tui")]
+pub use tui::Tui;
+
+#[cfg(feature = "tui")]
+mod tui {
+    use std::io;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use ratatui::backend::CrosstermBackend;
+    use ratatui::crossterm::cursor::{Hide, Show};
+    use ratatui::crossterm::execute;
+    use ratatui::crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
+    use ratatui::layout::{Constraint, Layout};
+    use ratatui::style::{Color, Modifier, Style};
+    use ratatui::text::Line;
+    use ratatui::widgets::{Block, Cell, Row, Table};
+    use ratatui::{Frame, Terminal};
+    use tokio::task::JoinHandle;
+
+    use super::{Dashboard, TxRow, DRAWING};
+    use crate::shutdown::Shutdown;
+
+    const REDRAW: Duration = Duration::from_millis(250);
+
+    /// The dashboard drawn on stderr's alternate screen until dropped, which gives the
+    /// terminal back. It stays in cooked mode, so Ctrl-C still reaches the shutdown handler.
+    #[derive(Debug)]
+    pub struct Tui {
+        task: JoinHandle<()>,
+    }
+
+    impl Tui {
+        pub fn start(dashboard: Arc<Dashboard>, shutdown: Option<Shutdown>) -> io::Result<Self> {
+            let mut terminal = Terminal::new(CrosstermBackend::new(io::stderr()))?;
+            execute!(terminal.backend_mut(), EnterAlternateScreen, Hide)?;
+            terminal.clear()?;
+            DRAWING.store(true, Ordering::Relaxed);
+            let task = tokio::spawn(async move {
+                let interrupted = || shutdown.as_ref().is_some_and(Shutdown::requested);
+                loop {
+                    if terminal.draw(|frame| render(frame, &dashboard, interrupted())).is_err() {
+                        return;
+                    }
+                    tokio::time::sleep(REDRAW).await;
+                }
+            });
+            Ok(Self { task })
+        }
+    }
+
+    impl Drop for Tui {
+        fn drop(&mut self) {
+            self.task.abort();
+            let _ = execute!(io::stderr(), LeaveAlternateScreen, Show);
+            DRAWING.store(false, Ordering::Relaxed);
+        }
+    }
+
+    fn render(frame: &mut Frame, dashboard: &Dashboard, interrupted: bool) {
+        let rows = dashboard.rows();
+        let [header, body] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(frame.area());
+
+        let in_flight = rows.iter().filter(|r| r.in_flight()).count();
+        let succeeded = rows.iter().filter(|r| r.status == "success").count();
+        let mut status = format!(
+            " {:.0?} elapsed | {} entries: {} in flight, {} succeeded, {} other",
+            dashboard.elapsed(),
+            rows.len(),
+            in_flight,
+            succeeded,
+            rows.len() - in_flight - succeeded
+        );
+        if interrupted {
+            status.push_str(" | interrupted, waiting for in-flight receipts");
+        }
+        frame.render_widget(Line::from(status).style(Style::new().add_modifier(Modifier::BOLD)), header);
+
+        // Bordered table with a header line: the latest entries that fit.
+        let visible = body.height.saturating_sub(3) as usize;
+        let table = Table::new(
+            rows.iter().skip(rows.len().saturating_sub(visible)).map(row),
+            [
+                Constraint::Fill(2),
+                Constraint::Length(4),
+                Constraint::Length(11),
+                Constraint::Length(12),
+                Constraint::Length(9),
+                Constraint::Fill(3),
+            ],
+        )
+        .header(
+            Row::new(["Series", "Type", "Status", "Confirmed", "Latency", "Tx / error"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title(" test-transaction "));
+        frame.render_widget(table, body);
+    }
+
+    fn row(r: &TxRow) -> Row<'static> {
+        let color = match r.status.as_str() {
+            "success" => Color::Green,
+            "sending" | "submitted" | "mined" | "pending" => Color::Yellow,
+            "signed" | "skipped" | "unsupported" => Color::Gray,
+            _ => Color::Red,
+        };
+        let confirmed = match (r.confirmations, r.block_number) {
+            (Some((seen, target)), _) => format!("{}/{}", seen, target),
+            (None, Some(block)) => format!("#{}", block),
+            (None, None) => String::new(),
+        };
+        let detail = match (&r.error, r.error_kind, r.tx_hash) {
+            (Some(e), Some(kind), _) => format!("[{}] {}", kind, e),
+            (Some(e), None, _) => e.clone(),
+            (None, _, Some(hash)) => format!("0x{:x}", hash),
+            (None, _, None) => String::new(),
+        };
+        Row::new([
+            Cell::from(r.series.clone()),
+            Cell::from(r.tx_type.to_string()),
+            Cell::from(r.status.clone()).style(Style::new().fg(color)),
+            Cell::from(confirmed),
+            Cell::from(format!("{:.1?}", r.elapsed())),
+            Cell::from(detail),
+        ])
+    }
+}
@@ -0,0 +1,569 @@
+//! Raw signed transactions of every type this tool sends (legacy, 1, 2, 3 with or without its
+//! sidecar, and zkSync's 0x71) taken apart again: every field, the hashes, the sender
+//! recovered from the signature, and what a node would reject about the signature. Meant for
+//! checking what another tool actually broadcast.
+
+use std::fmt;
+
+use eyre::{eyre, Result};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip2930::{AccessList, Eip2930TransactionRequest};
+use ethers::types::{Address, Bytes, Eip1559TransactionRequest, Signature, TransactionRequest, H256, U256};
+use ethers::utils::keccak256;
+use ethers::utils::rlp::{Decodable, Rlp};
+use serde::Serialize;
+
+use crate::blob::{self, BlobTransaction, BLOB_TX_TYPE};
+use crate::zksync::{Eip712Meta, Eip712Transaction, Paymaster, ZKSYNC_EIP712_TX_TYPE};
+
+/// Order of the secp256k1 curve: `r` and `s` must be below it, and `s` at most half of it.
+const SECP256K1N: &str = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DecodedTx {
+    pub tx_type: u8,
+    /// Hash the node will know the transaction by.
+    pub hash: H256,
+    /// Digest the sender signed.
+    pub signing_hash: H256,
+    /// `None` for a legacy transaction signed without EIP-155 replay protection.
+    pub chain_id: Option<u64>,
+    pub nonce: U256,
+    pub gas_limit: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_blob_gas: Option<U256>,
+    /// `None` for a contract creation.
+    pub to: Option<Address>,
+    pub value: U256,
+    pub input: Bytes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_list: Option<AccessList>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub blob_versioned_hashes: Vec<H256>,
+    /// Blobs carried along in the network form of a type-3 transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sidecar_blobs: Option<usize>,
+    /// Sender a type-0x71 transaction names itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declared_from: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_per_pubdata: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster_input: Option<Bytes>,
+    /// As encoded: 27/28 or EIP-155 for legacy transactions, 27/28 for 0x71, the y parity
+    /// otherwise.
+    pub v: u64,
+    pub r: U256,
+    pub s: U256,
+    /// Sender recovered from the signature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<Address>,
+    /// Why a node would reject the transaction as signed; empty when it would not.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<String>,
+}
+
+impl DecodedTx {
+    pub fn valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Decode `raw`, as passed to `eth_sendRawTransaction`, and check its signature.
+pub fn decode(raw: &[u8]) -> Result<DecodedTx> {
+    let first = *raw.first().ok_or_else(|| eyre!("empty transaction"))?;
+    // Legacy transactions are a bare RLP list, typed ones start with their type byte.
+    let mut tx = match first {
+        0xc0.. => legacy(raw)?,
+        1 | 2 => eip2718(first, &raw[1..])?,
+        BLOB_TX_TYPE => blob(&raw[1..])?,
+        ZKSYNC_EIP712_TX_TYPE => zksync(&raw[1..])?,
+        _ => return Err(eyre!("type {} is not supported (0, 1, 2, 3 and 0x71 are)", first)),
+    };
+    check_signature(&mut tx);
+    Ok(tx)
+}
+
+fn legacy(raw: &[u8]) -> Result<DecodedTx> {
+    let rlp = list(raw)?;
+    expect_items(&rlp, 9)?;
+    let v: u64 = field(&rlp, 6, "v")?;
+    let mut tx = DecodedTx {
+        tx_type: 0,
+        hash: keccak256(raw).into(),
+        chain_id: (v >= 35).then(|| (v - 35) / 2),
+        nonce: field(&rlp, 0, "nonce")?,
+        gas_price: Some(field(&rlp, 1, "gasPrice")?),
+        gas_limit: field(&rlp, 2, "gasLimit")?,
+        to: to(&rlp, 3)?,
+        value: field(&rlp, 4, "value")?,
+        input: field::<Vec<u8>>(&rlp, 5, "data")?.into(),
+        v,
+        r: field(&rlp, 7, "r")?,
+        s: field(&rlp, 8, "s")?,
+        ..Default::default()
+    };
+    if !matches!(v, 27 | 28) && v < 35 {
+        tx.problems.push(format!("v is {}, neither 27/28 nor EIP-155 (chain id * 2 + 35/36)", v));
+    }
+    tx.signing_hash = TypedTransaction::Legacy(legacy_request(&tx)).sighash();
+    Ok(tx)
+}
+
+/// Type 1 or 2 from its `payload`, the envelope without the type byte.
+fn eip2718(tx_type: u8, payload: &[u8]) -> Result<DecodedTx> {
+    let rlp = list(payload)?;
+    expect_items(&rlp, if tx_type == 1 { 11 } else { 12 })?;
+    // Type 2 has two fee fields where type 1 has the gas price.
+    let fees = if tx_type == 1 { 1 } else { 2 };
+    let mut tx = DecodedTx {
+        tx_type,
+        hash: keccak256([&[tx_type], payload].concat()).into(),
+        chain_id: Some(field(&rlp, 0, "chainId")?),
+        nonce: field(&rlp, 1, "nonce")?,
+        gas_limit: field(&rlp, 2 + fees, "gasLimit")?,
+        to: to(&rlp, 3 + fees)?,
+        value: field(&rlp, 4 + fees, "value")?,
+        input: field::<Vec<u8>>(&rlp, 5 + fees, "data")?.into(),
+        access_list: Some(field(&rlp, 6 + fees, "accessList")?),
+        v: field(&rlp, 7 + fees, "yParity")?,
+        r: field(&rlp, 8 + fees, "r")?,
+        s: field(&rlp, 9 + fees, "s")?,
+        ..Default::default()
+    };
+    let request: TypedTransaction = if tx_type == 1 {
+        tx.gas_price = Some(field(&rlp, 2, "gasPrice")?);
+        Eip2930TransactionRequest::new(legacy_request(&tx), tx.access_list.clone().unwrap_or_default()).into()
+    } else {
+        tx.max_priority_fee_per_gas = Some(field(&rlp, 2, "maxPriorityFeePerGas")?);
+        tx.max_fee_per_gas = Some(field(&rlp, 3, "maxFeePerGas")?);
+        Eip1559TransactionRequest {
+            to: tx.to.map(Into::into),
+            gas: Some(tx.gas_limit),
+            value: Some(tx.value),
+            data: Some(tx.input.clone()),
+            nonce: Some(tx.nonce),
+            access_list: tx.access_list.clone().unwrap_or_default(),
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+            max_fee_per_gas: tx.max_fee_per_gas,
+            chain_id: tx.chain_id.map(Into::into),
+            ..Default::default()
+        }
+        .into()
+    };
+    check_y_parity(&mut tx);
+    tx.signing_hash = request.sighash();
+    Ok(tx)
+}
+
+/// Type 3 from its `payload`, either the canonical form or the network form that carries the
+/// sidecar after the transaction.
+fn blob(payload: &[u8]) -> Result<DecodedTx> {
+    let outer = list(payload)?;
+    let network = outer.item_count()? == 4 && outer.at(0)?.is_list();
+    let rlp = if network { outer.at(0)? } else { outer.clone() };
+    expect_items(&rlp, 14)?;
+
+    let blob_tx = BlobTransaction {
+        chain_id: field(&rlp, 0, "chainId")?,
+        nonce: field(&rlp, 1, "nonce")?,
+        max_priority_fee_per_gas: field(&rlp, 2, "maxPriorityFeePerGas")?,
+        max_fee_per_gas: field(&rlp, 3, "maxFeePerGas")?,
+        gas: field(&rlp, 4, "gasLimit")?,
+        to: to(&rlp, 5)?.ok_or_else(|| eyre!("a blob transaction cannot create a contract, but `to` is empty"))?,
+        value: field(&rlp, 6, "value")?,
+        data: field::<Vec<u8>>(&rlp, 7, "data")?.into(),
+        access_list: field(&rlp, 8, "accessList")?,
+        max_fee_per_blob_gas: field(&rlp, 9, "maxFeePerBlobGas")?,
+        blob_versioned_hashes: rlp.list_at(10).map_err(|e| eyre!("blobVersionedHashes: {}", e))?,
+    };
+    let mut tx = DecodedTx {
+        tx_type: BLOB_TX_TYPE,
+        hash: keccak256([&[BLOB_TX_TYPE], rlp.as_raw()].concat()).into(),
+        signing_hash: blob_tx.sighash(),
+        chain_id: Some(blob_tx.chain_id),
+        nonce: blob_tx.nonce,
+        gas_limit: blob_tx.gas,
+        max_priority_fee_per_gas: Some(blob_tx.max_priority_fee_per_gas),
+        max_fee_per_gas: Some(blob_tx.max_fee_per_gas),
+        max_fee_per_blob_gas: Some(blob_tx.max_fee_per_blob_gas),
+        to: Some(blob_tx.to),
+        value: blob_tx.value,
+        input: blob_tx.data,
+        access_list: Some(blob_tx.access_list),
+        v: field(&rlp, 11, "yParity")?,
+        r: field(&rlp, 12, "r")?,
+        s: field(&rlp, 13, "s")?,
+        ..Default::default()
+    };
+    tx.blob_versioned_hashes = blob_tx.blob_versioned_hashes;
+    check_y_parity(&mut tx);
+    if tx.blob_versioned_hashes.is_empty() {
+        tx.problems.push("no blob versioned hashes".into());
+    }
+
+    if network {
+        let blobs = outer.at(1)?.item_count()?;
+        let commitments: Vec<Vec<u8>> = outer.list_at(2).map_err(|e| eyre!("commitments: {}", e))?;
+        let proofs = outer.at(3)?.item_count()?;
+        tx.sidecar_blobs = Some(blobs);
+        if blobs != commitments.len() || blobs != proofs || blobs != tx.blob_versioned_hashes.len() {
+            tx.problems.push(format!(
+                "sidecar has {} blobs, {} commitments and {} proofs for {} versioned hashes",
+                blobs,
+                commitments.len(),
+                proofs,
+                tx.blob_versioned_hashes.len()
+            ));
+        }
+        for (i, (commitment, hash)) in commitments.iter().zip(&tx.blob_versioned_hashes).enumerate() {
+            if blob::versioned_hash(commitment) != *hash {
+                tx.problems.push(format!("sidecar commitment {} does not match versioned hash {:?}", i, hash));
+            }
+        }
+    }
+    Ok(tx)
+}
+
+/// Type 0x71 from its `payload`. Deployments (with factory deps) are not covered.
+fn zksync(payload: &[u8]) -> Result<DecodedTx> {
+    let rlp = list(payload)?;
+    expect_items(&rlp, 16)?;
+    if !rlp.at(13)?.is_empty() {
+        return Err(eyre!("type 0x71 with factory deps (a deployment) is not supported"));
+    }
+    let signature: Vec<u8> = field(&rlp, 14, "customSignature")?;
+    let sig = Signature::try_from(&signature[..])
+        .map_err(|_| eyre!("customSignature is {} bytes, not a 65-byte ECDSA signature", signature.len()))?;
+    let paymaster = rlp.at(15)?;
+    let paymaster = if paymaster.is_empty() {
+        None
+    } else {
+        Some(Paymaster {
+            address: field(&paymaster, 0, "paymaster")?,
+            input: field::<Vec<u8>>(&paymaster, 1, "paymasterInput")?.into(),
+        })
+    };
+    let zk_tx = Eip712Transaction {
+        chain_id: field(&rlp, 10, "chainId")?,
+        nonce: field(&rlp, 0, "nonce")?,
+        from: field(&rlp, 11, "from")?,
+        to: to(&rlp, 4)?.ok_or_else(|| eyre!("`to` is empty"))?,
+        gas: field(&rlp, 3, "gasLimit")?,
+        max_priority_fee_per_gas: field(&rlp, 1, "maxPriorityFeePerGas")?,
+        max_fee_per_gas: field(&rlp, 2, "maxFeePerGas")?,
+        value: field(&rlp, 5, "value")?,
+        data: field::<Vec<u8>>(&rlp, 6, "data")?.into(),
+        meta: Eip712Meta { gas_per_pubdata: field(&rlp, 12, "gasPerPubdata")?, paymaster: paymaster.clone() },
+    };
+    Ok(DecodedTx {
+        tx_type: ZKSYNC_EIP712_TX_TYPE,
+        hash: zk_tx.hash(&sig),
+        signing_hash: zk_tx.sighash(),
+        chain_id: Some(zk_tx.chain_id),
+        nonce: zk_tx.nonce,
+        gas_limit: zk_tx.gas,
+        max_priority_fee_per_gas: Some(zk_tx.max_priority_fee_per_gas),
+        max_fee_per_gas: Some(zk_tx.max_fee_per_gas),
+        to: Some(zk_tx.to),
+        value: zk_tx.value,
+        input: zk_tx.data,
+        declared_from: Some(zk_tx.from),
+        gas_per_pubdata: Some(zk_tx.meta.gas_per_pubdata),
+        paymaster: paymaster.as_ref().map(|p| p.address),
+        paymaster_input: paymaster.map(|p| p.input),
+        v: sig.v,
+        r: sig.r,
+        s: sig.s,
+        ..Default::default()
+    })
+}
+
+/// The checks a node makes before it accepts the signature, and the sender it recovers.
+fn check_signature(tx: &mut DecodedTx) {
+    let n = U256::from_str_radix(SECP256K1N, 16).expect("valid constant");
+    if tx.r.is_zero() || tx.r >= n {
+        tx.problems.push("r is 0 or not below the curve order".into());
+    }
+    if tx.s.is_zero() || tx.s >= n {
+        tx.problems.push("s is 0 or not below the curve order".into());
+    } else if tx.s > n / 2 {
+        tx.problems.push("s is in the upper half of the curve order, rejected since EIP-2".into());
+    }
+    match (Signature { r: tx.r, s: tx.s, v: tx.v }).recover(tx.signing_hash) {
+        Ok(from) => tx.from = Some(from),
+        Err(e) => tx.problems.push(format!("the sender cannot be recovered: {}", e)),
+    }
+    if let (Some(declared), Some(from)) = (tx.declared_from, tx.from)
+        && declared != from
+    {
+        tx.problems.push(format!("signed by {:?}, not by the declared sender {:?}", from, declared));
+    }
+}
+
+fn check_y_parity(tx: &mut DecodedTx) {
+    if tx.v > 1 {
+        tx.problems.push(format!("y parity is {}, not 0 or 1", tx.v));
+    }
+}
+
+/// The fields a legacy or type-1 transaction signs over.
+fn legacy_request(tx: &DecodedTx) -> TransactionRequest {
+    TransactionRequest {
+        from: None,
+        to: tx.to.map(Into::into),
+        gas: Some(tx.gas_limit),
+        gas_price: tx.gas_price,
+        value: Some(tx.value),
+        data: Some(tx.input.clone()),
+        nonce: Some(tx.nonce),
+        chain_id: tx.chain_id.map(Into::into),
+    }
+}
+
+/// `payload` as one RLP list, with nothing after it.
+fn list(payload: &[u8]) -> Result<Rlp<'_>> {
+    let rlp = Rlp::new(payload);
+    if !rlp.is_list() {
+        return Err(eyre!("expected an RLP list"));
+    }
+    let info = rlp.payload_info()?;
+    if info.header_len + info.value_len != payload.len() {
+        return Err(eyre!("{} trailing bytes after the RLP list", payload.len() - info.header_len - info.value_len));
+    }
+    Ok(rlp)
+}
+
+fn expect_items(rlp: &Rlp, items: usize) -> Result<()> {
+    let count = rlp.item_count()?;
+    if count != items {
+        return Err(eyre!("expected {} fields, got {}", items, count));
+    }
+    Ok(())
+}
+
+fn field<T: Decodable>(rlp: &Rlp, i: usize, name: &str) -> Result<T> {
+    rlp.val_at(i).map_err(|e| eyre!("{}: {}", name, e))
+}
+
+/// The recipient at `i`, empty for a contract creation.
+fn to(rlp: &Rlp, i: usize) -> Result<Option<Address>> {
+    let item = rlp.at(i)?;
+    if item.is_empty() {
+        return Ok(None);
+    }
+    item.as_val().map(Some).map_err(|e| eyre!("to: {}", e))
+}
+
+pub fn type_name(tx_type: u8) -> &'static str {
+    match tx_type {
+        0 => "legacy",
+        1 => "EIP-2930",
+        2 => "EIP-1559",
+        BLOB_TX_TYPE => "EIP-4844",
+        ZKSYNC_EIP712_TX_TYPE => "zkSync EIP-712",
+        _ => "unknown",
+    }
+}
+
+impl fmt::Display for DecodedTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "type                  {} ({})", self.tx_type, type_name(self.tx_type))?;
+        writeln!(f, "hash                  {:?}", self.hash)?;
+        writeln!(f, "signing hash          {:?}", self.signing_hash)?;
+        match self.chain_id {
+            Some(chain_id) => writeln!(f, "chain id              {}", chain_id)?,
+            None => writeln!(f, "chain id              none (pre-EIP-155, replayable on any chain)")?,
+        }
+        writeln!(f, "nonce                 {}", self.nonce)?;
+        writeln!(f, "gas limit             {}", self.gas_limit)?;
+        let fees = [
+            ("gas price", self.gas_price),
+            ("max priority fee", self.max_priority_fee_per_gas),
+            ("max fee", self.max_fee_per_gas),
+            ("max fee per blob gas", self.max_fee_per_blob_gas),
+        ];
+        for (name, fee) in fees {
+            if let Some(fee) = fee {
+                writeln!(f, "{:<21} {} wei", name, fee)?;
+            }
+        }
+        match self.to {
+            Some(to) => writeln!(f, "to                    {:?}", to)?,
+            None => writeln!(f, "to                    none (contract creation)")?,
+        }
+        writeln!(f, "value                 {} wei", self.value)?;
+        writeln!(f, "input                 {} ({} bytes)", self.input, self.input.len())?;
+        if let Some(list) = &self.access_list {
+            writeln!(f, "access list           {} entries", list.0.len())?;
+            for item in &list.0 {
+                writeln!(f, "  {:?} ({} storage keys)", item.address, item.storage_keys.len())?;
+            }
+        }
+        for hash in &self.blob_versioned_hashes {
+            writeln!(f, "blob versioned hash   {:?}", hash)?;
+        }
+        if let Some(blobs) = self.sidecar_blobs {
+            writeln!(f, "sidecar               {} blobs", blobs)?;
+        }
+        if let Some(from) = self.declared_from {
+            writeln!(f, "declared from         {:?}", from)?;
+        }
+        if let Some(gas_per_pubdata) = self.gas_per_pubdata {
+            writeln!(f, "gas per pubdata       {}", gas_per_pubdata)?;
+        }
+        if let Some(paymaster) = self.paymaster {
+            writeln!(f, "paymaster             {:?}", paymaster)?;
+        }
+        if let Some(input) = &self.paymaster_input {
+            writeln!(f, "paymaster input       {}", input)?;
+        }
+        writeln!(f, "v                     {}", self.v)?;
+        writeln!(f, "r                     {:#x}", self.r)?;
+        writeln!(f, "s                     {:#x}", self.s)?;
+        match self.from {
+            Some(from) => writeln!(f, "sender                {:?} (recovered)", from)?,
+            None => writeln!(f, "sender                unrecoverable")?,
+        }
+        if self.valid() {
+            return write!(f, "signature             valid");
+        }
+        write!(f, "signature             INVALID")?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::transaction::eip2930::AccessListItem;
+
+    use super::*;
+    use crate::blob::{BlobSource, Sidecar};
+
+    const KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn wallet() -> LocalWallet {
+        KEY.parse::<LocalWallet>().unwrap().with_chain_id(10u64)
+    }
+
+    fn to() -> Address {
+        "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".parse().unwrap()
+    }
+
+    /// Sign `tx`, decode it and check what every type carries.
+    fn round_trip(tx: TypedTransaction) -> DecodedTx {
+        let wallet = wallet();
+        let sig = wallet.sign_transaction_sync(&tx).unwrap();
+        let raw = tx.rlp_signed(&sig);
+        let decoded = decode(&raw).unwrap();
+        assert!(decoded.valid(), "{:?}", decoded.problems);
+        assert_eq!(decoded.from, Some(wallet.address()));
+        assert_eq!(decoded.hash, H256::from(keccak256(&raw)));
+        assert_eq!(decoded.signing_hash, tx.sighash());
+        assert_eq!(decoded.nonce, U256::from(7));
+        assert_eq!(decoded.to, Some(to()));
+        assert_eq!(decoded.value, U256::from(1000));
+        assert_eq!(decoded.input, Bytes::from(vec![0xde, 0xad]));
+        decoded
+    }
+
+    #[test]
+    fn legacy() {
+        let tx = TransactionRequest::new().to(to()).value(1000).nonce(7).gas(21_000).gas_price(5).data(vec![0xde, 0xad]);
+        let decoded = round_trip(tx.chain_id(10u64).into());
+        assert_eq!((decoded.tx_type, decoded.chain_id, decoded.gas_price), (0, Some(10), Some(U256::from(5))));
+        // EIP-155: chain_id * 2 + 35 + y parity.
+        assert!(matches!(decoded.v, 55 | 56), "v is {}", decoded.v);
+    }
+
+    #[test]
+    fn legacy_without_chain_id() {
+        let tx: TypedTransaction =
+            TransactionRequest::new().to(to()).value(1000).nonce(7).gas(21_000).gas_price(5).into();
+        let sig = wallet().sign_hash(tx.sighash()).unwrap();
+        let decoded = decode(&tx.rlp_signed(&sig)).unwrap();
+        assert!(decoded.valid(), "{:?}", decoded.problems);
+        assert_eq!(decoded.chain_id, None);
+        assert_eq!(decoded.from, Some(wallet().address()));
+    }
+
+    #[test]
+    fn access_list() {
+        let list = AccessList(vec![AccessListItem { address: to(), storage_keys: vec![H256::repeat_byte(1)] }]);
+        let tx = TransactionRequest::new().to(to()).value(1000).nonce(7).gas(30_000).gas_price(5).data(vec![0xde, 0xad]);
+        let decoded = round_trip(TypedTransaction::Eip2930(tx.chain_id(10u64).with_access_list(list.clone())));
+        assert_eq!((decoded.tx_type, decoded.access_list), (1, Some(list)));
+    }
+
+    #[test]
+    fn dynamic_fee() {
+        let tx = Eip1559TransactionRequest::new()
+            .to(to())
+            .value(1000)
+            .nonce(7)
+            .gas(21_000)
+            .max_priority_fee_per_gas(2)
+            .max_fee_per_gas(9)
+            .data(vec![0xde, 0xad])
+            .chain_id(10u64);
+        let decoded = round_trip(tx.into());
+        assert_eq!(decoded.tx_type, 2);
+        assert_eq!((decoded.max_priority_fee_per_gas, decoded.max_fee_per_gas), (Some(2.into()), Some(9.into())));
+    }
+
+    #[test]
+    fn blob_with_sidecar() {
+        let sidecar = Sidecar::build(&BlobSource::Data(b"hello".to_vec())).unwrap();
+        let tx = BlobTransaction {
+            chain_id: 10,
+            nonce: 7.into(),
+            max_priority_fee_per_gas: 2.into(),
+            max_fee_per_gas: 9.into(),
+            gas: 21_000.into(),
+            to: to(),
+            value: 1000.into(),
+            max_fee_per_blob_gas: 3.into(),
+            blob_versioned_hashes: sidecar.versioned_hashes(),
+            ..Default::default()
+        };
+        let sig = wallet().sign_hash(tx.sighash()).unwrap();
+        let decoded = decode(&tx.rlp_network(&sig, &sidecar)).unwrap();
+        assert!(decoded.valid(), "{:?}", decoded.problems);
+        assert_eq!(decoded.tx_type, BLOB_TX_TYPE);
+        assert_eq!(decoded.hash, tx.hash(&sig));
+        assert_eq!(decoded.from, Some(wallet().address()));
+        assert_eq!(decoded.sidecar_blobs, Some(1));
+        assert_eq!(decoded.blob_versioned_hashes, sidecar.versioned_hashes());
+        assert_eq!(decoded.max_fee_per_blob_gas, Some(3.into()));
+    }
+
+    #[test]
+    fn high_s_is_a_problem() {
+        let tx: TypedTransaction = Eip1559TransactionRequest::new().to(to()).nonce(7).gas(21_000).chain_id(10u64).into();
+        let mut sig = wallet().sign_transaction_sync(&tx).unwrap();
+        let n = U256::from_str_radix(SECP256K1N, 16).unwrap();
+        sig.s = n - sig.s;
+        let decoded = decode(&tx.rlp_signed(&sig)).unwrap();
+        assert!(!decoded.valid());
+        assert!(decoded.problems.iter().any(|p| p.contains("upper half")), "{:?}", decoded.problems);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(decode(&[]).is_err());
+        assert!(decode(&[0x05, 0xc0]).is_err());
+        assert!(decode(&[0x02, 0x01]).is_err());
+    }
+}
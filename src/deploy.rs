@@ -0,0 +1,34 @@
+//! A tiny bundled contract for the deployment series, and what to check once it is mined.
+
+use eyre::Result;
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes};
+
+/// Init code of a counter: every call increments storage slot 0 and returns the new value.
+///
+/// Runtime (18 bytes): `PUSH1 0 SLOAD PUSH1 1 ADD DUP1 PUSH1 0 SSTORE PUSH1 0 MSTORE
+/// PUSH1 32 PUSH1 0 RETURN`, prefixed by a 12-byte constructor that copies it out.
+pub const COUNTER_INIT_CODE: &str = "0x6012600c60003960126000f36000546001018060005560005260206000f3";
+
+/// The counter's init code as calldata.
+pub fn counter_init_code() -> Bytes {
+    COUNTER_INIT_CODE.parse().expect("valid hex")
+}
+
+/// Turn `tx` into a contract creation by dropping its recipient.
+pub fn into_create(tx: &mut TypedTransaction) {
+    match tx {
+        TypedTransaction::Legacy(t) => t.to = None,
+        TypedTransaction::Eip2930(t) => t.tx.to = None,
+        TypedTransaction::Eip1559(t) => t.to = None,
+    }
+}
+
+/// Size of the code now stored at `address` (0 means the deployment left nothing behind).
+pub async fn code_size<M: Middleware>(client: &M, address: Address) -> Result<usize>
+where
+    M::Error: 'static,
+{
+    Ok(client.get_code(address, None).await?.len())
+}
@@ -0,0 +1,104 @@
+//! Compare two result sets per series and tx type, to spot chain upgrades that changed
+//! which transactions go through.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use eyre::{eyre, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::report::Report;
+
+/// One tx type's outcome within a series, as stored or exported.
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub series: String,
+    pub tx_type: u8,
+    pub status: String,
+    pub error: Option<String>,
+}
+
+/// A series/tx type whose status differs between the two sets; `None` means absent.
+#[derive(Clone, Debug, Serialize)]
+pub struct Change {
+    pub series: String,
+    pub tx_type: u8,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    /// Error of the new outcome, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = |s: &Option<String>| s.clone().unwrap_or_else(|| "(absent)".into());
+        write!(f, "type-{} {}: {} → {}", self.tx_type, self.series, status(&self.before), status(&self.after))?;
+        if let Some(e) = &self.error {
+            write!(f, " ({})", e)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcomes of a run's report.
+pub fn from_report(report: &Report) -> Vec<Outcome> {
+    report
+        .series
+        .iter()
+        .flat_map(|s| {
+            s.results.iter().map(|r| Outcome {
+                series: s.label.clone(),
+                tx_type: r.tx_type,
+                status: r.status.clone(),
+                error: r.error.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Outcomes from a `--output json` report.
+pub fn from_report_json(json: &str) -> Result<Vec<Outcome>> {
+    let report: Value = serde_json::from_str(json)?;
+    let series = report["series"].as_array().ok_or_else(|| eyre!("not a report: missing `series`"))?;
+    let mut outcomes = Vec::new();
+    for s in series {
+        let label = s["label"].as_str().unwrap_or_default();
+        for r in s["results"].as_array().into_iter().flatten() {
+            outcomes.push(Outcome {
+                series: label.to_string(),
+                tx_type: r["tx_type"].as_u64().unwrap_or_default() as u8,
+                status: r["status"].as_str().unwrap_or_default().to_string(),
+                error: r["error"].as_str().map(str::to_string),
+            });
+        }
+    }
+    Ok(outcomes)
+}
+
+/// Status changes from `old` to `new`, ordered by series and tx type.
+pub fn compare(old: &[Outcome], new: &[Outcome]) -> Vec<Change> {
+    let index = |set: &[Outcome]| -> BTreeMap<(String, u8), Outcome> {
+        set.iter().map(|o| ((o.series.clone(), o.tx_type), o.clone())).collect()
+    };
+    let (old, new) = (index(old), index(new));
+    let mut keys: Vec<&(String, u8)> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter_map(|key| {
+            let (before, after) = (old.get(key), new.get(key));
+            if before.map(|o| &o.status) == after.map(|o| &o.status) {
+                return None;
+            }
+            Some(Change {
+                series: key.0.clone(),
+                tx_type: key.1,
+                before: before.map(|o| o.status.clone()),
+                after: after.map(|o| o.status.clone()),
+                error: after.and_then(|o| o.error.clone()),
+            })
+        })
+        .collect()
+}
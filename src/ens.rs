@@ -0,0 +1,33 @@
+//! ENS names wherever a recipient address is expected.
+
+use eyre::{eyre, Result};
+use ethers::providers::{Middleware, ENS_ADDRESS};
+use ethers::types::Address;
+
+/// Extensions of recipient list files, which would otherwise pass for ENS names.
+const LIST_EXTENSIONS: [&str; 4] = [".txt", ".csv", ".list", ".lst"];
+
+/// Whether `s` should be resolved through ENS rather than parsed as an address.
+pub fn is_name(s: &str) -> bool {
+    s.contains('.') && !s.starts_with("0x") && !is_path(s)
+}
+
+/// Whether `s` looks like a file path rather than an address or ENS name: it has a path
+/// separator or a list file extension.
+pub fn is_path(s: &str) -> bool {
+    s.contains(['/', '\\']) || LIST_EXTENSIONS.iter().any(|ext| s.to_ascii_lowercase().ends_with(ext))
+}
+
+/// `s` as an address, resolving it through the chain's ENS registry if it is a name. Chains
+/// without the registry get a clear error instead of the resolver's empty-call failure.
+pub async fn resolve<M: Middleware>(client: &M, s: &str) -> Result<Address> {
+    if !is_name(s) {
+        return s.parse().map_err(|e| eyre!("invalid address {}: {}", s, e));
+    }
+    let code = client.get_code(ENS_ADDRESS, None).await.map_err(|e| eyre!("resolving {}: {}", s, e))?;
+    if code.is_empty() {
+        let chain_id = client.get_chainid().await.map_err(|e| eyre!("eth_chainId: {}", e))?;
+        return Err(eyre!("{} is an ENS name, but chain {} has no ENS registry at {:?}", s, chain_id, ENS_ADDRESS));
+    }
+    client.resolve_name(s).await.map_err(|e| eyre!("resolving {}: {}", s, e))
+}
@@ -0,0 +1,45 @@
+//! Minimal ERC-20 support: `transfer` calldata, `decimals`, and `Transfer` event matching.
+
+use eyre::{eyre, Result};
+use ethers::abi::{self, Token};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionReceipt, TransactionRequest, H256, U256};
+use ethers::utils::id;
+
+/// `transfer(to, amount)` calldata.
+pub fn transfer_calldata(to: Address, amount: U256) -> Bytes {
+    let mut data = id("transfer(address,uint256)").to_vec();
+    data.extend(abi::encode(&[Token::Address(to), Token::Uint(amount)]));
+    data.into()
+}
+
+/// Read the token's `decimals()`.
+pub async fn decimals<M: Middleware>(client: &M, token: Address) -> Result<u32>
+where
+    M::Error: 'static,
+{
+    let call: TypedTransaction = TransactionRequest::new().to(token).data(id("decimals()").to_vec()).into();
+    let out = client.call(&call, None).await?;
+    let decoded = U256::from_big_endian(out.get(..32).ok_or_else(|| eyre!("decimals() returned {} bytes", out.len()))?);
+    Ok(decoded.as_u32())
+}
+
+/// Whether `receipt` carries a `Transfer(from, to, amount)` log emitted by `token`.
+pub fn transfer_event_matches(
+    receipt: &TransactionReceipt,
+    token: Address,
+    from: Address,
+    to: Address,
+    amount: U256,
+) -> bool {
+    let topic0 = H256::from(ethers::utils::keccak256("Transfer(address,address,uint256)"));
+    receipt.logs.iter().any(|log| {
+        log.address == token
+            && log.topics.len() == 3
+            && log.topics[0] == topic0
+            && log.topics[1] == H256::from(from)
+            && log.topics[2] == H256::from(to)
+            && U256::from_big_endian(&log.data) == amount
+    })
+}
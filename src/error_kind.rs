@@ -0,0 +1,167 @@
+//! Classification of node and transport errors. Clients word their rejections differently
+//! (geth's `nonce too low`, Nethermind's `OldNonce`, Erigon's `nonce too low: address ...`),
+//! so reports carry a stable category next to the raw message for downstream analysis.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::retry;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    InsufficientFunds,
+    /// Fee below the pool's minimum or the block's base fee.
+    Underpriced,
+    /// Replacement for a pending nonce without the required fee bump.
+    ReplacementUnderpriced,
+    /// Priority fee above the fee cap.
+    TipAboveFeeCap,
+    NonceTooLow,
+    NonceTooHigh,
+    AlreadyKnown,
+    TypeNotSupported,
+    IntrinsicGasTooLow,
+    /// Gas limit above the block's, or above what the sender can pay for.
+    GasLimitExceeded,
+    /// Oversized transaction or calldata.
+    TooLarge,
+    ExecutionReverted,
+    InvalidSignature,
+    /// Wrong chain id, or a pre-EIP-155 signature on a node that requires replay protection.
+    InvalidChainId,
+    /// The node does not know the JSON-RPC method.
+    MethodNotFound,
+    /// The node could not decode what it was sent.
+    InvalidParams,
+    RateLimited,
+    /// Connection trouble or a gateway error: the node may never have seen the request.
+    RpcTransport,
+    Timeout,
+    /// The transaction left the pool without being mined.
+    Dropped,
+    /// Mined, then reorged out.
+    Reorged,
+    /// The run was interrupted before the transaction was sent.
+    Interrupted,
+    Other,
+}
+
+/// Message fragments, lowercased, checked in order: more specific ones first, as
+/// `replacement transaction underpriced` also contains `underpriced`.
+const FRAGMENTS: &[(&str, ErrorKind)] = &[
+    ("insufficient funds", ErrorKind::InsufficientFunds),
+    ("insufficientfunds", ErrorKind::InsufficientFunds),
+    ("insufficient balance", ErrorKind::InsufficientFunds),
+    ("replacement transaction underpriced", ErrorKind::ReplacementUnderpriced),
+    ("replacement fee too low", ErrorKind::ReplacementUnderpriced),
+    ("feetoolowtoreplace", ErrorKind::ReplacementUnderpriced),
+    ("tip higher than fee cap", ErrorKind::TipAboveFeeCap),
+    ("max priority fee per gas higher than max fee per gas", ErrorKind::TipAboveFeeCap),
+    ("maxpriorityfeepergas > maxfeepergas", ErrorKind::TipAboveFeeCap),
+    ("underpriced", ErrorKind::Underpriced),
+    ("less than block base fee", ErrorKind::Underpriced),
+    ("max fee per gas less than", ErrorKind::Underpriced),
+    ("gas price too low", ErrorKind::Underpriced),
+    ("feetoolow", ErrorKind::Underpriced),
+    ("nonce too low", ErrorKind::NonceTooLow),
+    ("oldnonce", ErrorKind::NonceTooLow),
+    ("nonce has already been used", ErrorKind::NonceTooLow),
+    ("nonce too high", ErrorKind::NonceTooHigh),
+    ("nonce gap", ErrorKind::NonceTooHigh),
+    ("futurenonce", ErrorKind::NonceTooHigh),
+    ("already known", ErrorKind::AlreadyKnown),
+    ("known transaction", ErrorKind::AlreadyKnown),
+    ("already imported", ErrorKind::AlreadyKnown),
+    ("alreadyknown", ErrorKind::AlreadyKnown),
+    ("transaction type not supported", ErrorKind::TypeNotSupported),
+    ("tx type not supported", ErrorKind::TypeNotSupported),
+    ("unsupported transaction type", ErrorKind::TypeNotSupported),
+    ("unknown transaction type", ErrorKind::TypeNotSupported),
+    ("intrinsic gas too low", ErrorKind::IntrinsicGasTooLow),
+    ("intrinsic gas", ErrorKind::IntrinsicGasTooLow),
+    ("exceeds block gas limit", ErrorKind::GasLimitExceeded),
+    ("gas limit reached", ErrorKind::GasLimitExceeded),
+    ("gas limit too high", ErrorKind::GasLimitExceeded),
+    ("gas required exceeds allowance", ErrorKind::GasLimitExceeded),
+    ("oversized data", ErrorKind::TooLarge),
+    ("too large", ErrorKind::TooLarge),
+    ("execution reverted", ErrorKind::ExecutionReverted),
+    ("reverted", ErrorKind::ExecutionReverted),
+    ("only replay-protected", ErrorKind::InvalidChainId),
+    ("invalid chain id", ErrorKind::InvalidChainId),
+    ("chain id mismatch", ErrorKind::InvalidChainId),
+    ("invalid sender", ErrorKind::InvalidSignature),
+    ("invalid signature", ErrorKind::InvalidSignature),
+    ("method not found", ErrorKind::MethodNotFound),
+    ("does not exist/is not available", ErrorKind::MethodNotFound),
+    ("typed transaction too short", ErrorKind::InvalidParams),
+    ("rlp", ErrorKind::InvalidParams),
+    ("invalid params", ErrorKind::InvalidParams),
+    ("invalid argument", ErrorKind::InvalidParams),
+    ("deadline", ErrorKind::Timeout),
+    ("no receipt", ErrorKind::Timeout),
+    ("dropped", ErrorKind::Dropped),
+    ("interrupted", ErrorKind::Interrupted),
+    ("not supported", ErrorKind::TypeNotSupported),
+    ("unsupported", ErrorKind::TypeNotSupported),
+];
+
+impl ErrorKind {
+    /// Classify an error as the rest of the crate keeps them, a message that may embed the
+    /// JSON-RPC response (`(code: -32000, message: ..., data: ...)`, as ethers formats it).
+    pub fn classify(message: &str) -> Self {
+        match rpc_code(message) {
+            Some(code) => ErrorKind::from_rpc(code, message),
+            None => ErrorKind::by_message(message),
+        }
+    }
+
+    /// Classify a JSON-RPC error response: the code settles the standard cases, the message
+    /// the rest (most clients answer every rejection with -32000).
+    pub fn from_rpc(code: i64, message: &str) -> Self {
+        match code {
+            3 => ErrorKind::ExecutionReverted,
+            -32601 => ErrorKind::MethodNotFound,
+            -32005 => ErrorKind::RateLimited,
+            -32602 | -32700 => match ErrorKind::by_message(message) {
+                ErrorKind::Other => ErrorKind::InvalidParams,
+                kind => kind,
+            },
+            _ => ErrorKind::by_message(message),
+        }
+    }
+
+    fn by_message(message: &str) -> Self {
+        let lower = message.to_ascii_lowercase();
+        if let Some(&(_, kind)) = FRAGMENTS.iter().find(|(fragment, _)| lower.contains(fragment)) {
+            return kind;
+        }
+        if retry::is_transient(&lower) {
+            return if ["rate limit", "too many requests", "429", "limit exceeded"].iter().any(|t| lower.contains(t)) {
+                ErrorKind::RateLimited
+            } else if lower.contains("timeout") || lower.contains("timed out") {
+                ErrorKind::Timeout
+            } else {
+                ErrorKind::RpcTransport
+            };
+        }
+        ErrorKind::Other
+    }
+}
+
+/// The `code: N` ethers puts in a JSON-RPC error's message.
+fn rpc_code(message: &str) -> Option<i64> {
+    let rest = &message[message.find("code: ")? + "code: ".len()..];
+    let end = rest.find(|c: char| c != '-' && !c.is_ascii_digit()).unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The serde name, so text and JSON output agree.
+        let name = serde_json::to_value(self).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+        f.write_str(&name)
+    }
+}
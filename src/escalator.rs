@@ -0,0 +1,112 @@
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{H256, U256};
+use serde::Serialize;
+
+/// When and how much to bump the fees of a transaction that is not getting mined.
+#[derive(Clone, Copy, Debug)]
+pub struct EscalationPolicy {
+    /// Rebroadcast once this many blocks pass without a receipt.
+    pub after_blocks: u64,
+    /// Percentage added to every fee field on each bump.
+    pub bump_percent: u64,
+    /// Give up bumping (but keep waiting) after this many attempts.
+    pub max_bumps: u32,
+}
+
+/// One rebroadcast of a stuck transaction with the same nonce and higher fees.
+#[derive(Clone, Debug, Serialize)]
+pub struct FeeBump {
+    /// Block number at which the bump was sent.
+    pub at_block: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FeeBump {
+    /// Record the fee fields `tx` now carries.
+    pub fn of(tx: &TypedTransaction, at_block: u64) -> Self {
+        let mut bump = FeeBump {
+            at_block,
+            gas_price: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            tx_hash: None,
+            error: None,
+        };
+        match tx {
+            TypedTransaction::Legacy(t) => bump.gas_price = t.gas_price,
+            TypedTransaction::Eip2930(t) => bump.gas_price = t.tx.gas_price,
+            TypedTransaction::Eip1559(t) => {
+                bump.max_fee_per_gas = t.max_fee_per_gas;
+                bump.max_priority_fee_per_gas = t.max_priority_fee_per_gas;
+            }
+        }
+        bump
+    }
+}
+
+/// Raise every fee field of `tx` by `percent`, by at least 1 wei so zero fees move too.
+pub fn bump_fees(tx: &mut TypedTransaction, percent: u64) {
+    let bump = |v: Option<U256>| {
+        let v = v.unwrap_or_default();
+        let bumped = v * (100 + percent) / 100;
+        Some(if bumped > v { bumped } else { v + 1 })
+    };
+    match tx {
+        TypedTransaction::Legacy(t) => t.gas_price = bump(t.gas_price),
+        TypedTransaction::Eip2930(t) => t.tx.gas_price = bump(t.tx.gas_price),
+        TypedTransaction::Eip1559(t) => {
+            t.max_fee_per_gas = bump(t.max_fee_per_gas);
+            t.max_priority_fee_per_gas = bump(t.max_priority_fee_per_gas);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::transaction::eip2930::{AccessList, Eip2930TransactionRequest};
+    use ethers::types::{Eip1559TransactionRequest, TransactionRequest};
+
+    use super::*;
+
+    #[test]
+    fn bumps_the_gas_price_by_the_percentage() {
+        let mut tx: TypedTransaction = TransactionRequest::new().gas_price(100_000_000_000u64).into();
+        bump_fees(&mut tx, 10);
+        assert_eq!(tx.gas_price(), Some(U256::from(110_000_000_000u64)));
+        bump_fees(&mut tx, 12);
+        assert_eq!(tx.gas_price(), Some(U256::from(123_200_000_000u64)));
+        let FeeBump { gas_price, max_fee_per_gas, .. } = FeeBump::of(&tx, 7);
+        assert_eq!((gas_price, max_fee_per_gas), (Some(U256::from(123_200_000_000u64)), None));
+    }
+
+    #[test]
+    fn bumps_both_dynamic_fees() {
+        let request = Eip1559TransactionRequest::new().max_fee_per_gas(30_000_000_000u64);
+        let mut tx: TypedTransaction = request.max_priority_fee_per_gas(2_000_000_000u64).into();
+        bump_fees(&mut tx, 10);
+        let TypedTransaction::Eip1559(t) = &tx else { unreachable!() };
+        assert_eq!(t.max_fee_per_gas, Some(U256::from(33_000_000_000u64)));
+        assert_eq!(t.max_priority_fee_per_gas, Some(U256::from(2_200_000_000u64)));
+    }
+
+    #[test]
+    fn small_and_missing_fees_move_by_at_least_one_wei() {
+        let request = TransactionRequest::new().gas_price(5);
+        let mut tx = TypedTransaction::Eip2930(Eip2930TransactionRequest::new(request, AccessList::default()));
+        bump_fees(&mut tx, 10);
+        assert_eq!(tx.gas_price(), Some(U256::from(6)));
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new().into();
+        bump_fees(&mut tx, 0);
+        let TypedTransaction::Eip1559(t) = &tx else { unreachable!() };
+        assert_eq!((t.max_fee_per_gas, t.max_priority_fee_per_gas), (Some(U256::one()), Some(U256::one())));
+    }
+}
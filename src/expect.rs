@@ -0,0 +1,121 @@
+//! Expected results for CI conformance checks: which tx type / fee point combinations must
+//! end in which status.
+//!
+//! ```toml
+//! [[expect]]
+//! types = [0, 1, 2]
+//! fees = ["1gwei", "market"]   # every series when omitted
+//! status = "success"
+//!
+//! [[expect]]
+//! types = [3, 4]
+//! status = "unsupported"
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use eyre::{eyre, Result};
+use serde::Deserialize;
+
+use crate::fees::FeeSeries;
+use crate::report::SeriesReport;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Expectations {
+    #[serde(default)]
+    expect: Vec<Expectation>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Expectation {
+    types: Vec<u8>,
+    /// Normalized to `FeeSeries` display form on load.
+    #[serde(default)]
+    fees: Vec<String>,
+    status: String,
+}
+
+/// A result that did not match, or an expected combination that never ran.
+#[derive(Clone, Debug)]
+pub struct Deviation {
+    pub series: String,
+    pub tx_type: u8,
+    pub expected: String,
+    /// `None` when the run had no result for this combination.
+    pub actual: Option<String>,
+    pub error: Option<String>,
+}
+
+impl fmt::Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let actual = self.actual.as_deref().unwrap_or("no result");
+        write!(f, "type-{} {}: expected {}, got {}", self.tx_type, self.series, self.expected, actual)?;
+        if let Some(e) = &self.error {
+            write!(f, " ({})", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl Expectations {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut expectations: Self = toml::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| eyre!("invalid expectations file {}: {}", path.display(), e))?;
+        for e in &mut expectations.expect {
+            for fees in &mut e.fees {
+                *fees = fees.parse::<FeeSeries>().map_err(|err| eyre!("expectations file: {}", err))?.to_string();
+            }
+        }
+        Ok(expectations)
+    }
+
+    /// Every expected combination whose result differs. Combinations not covered by any
+    /// expectation are ignored; when several match, the last one wins.
+    pub fn check(&self, series: &[SeriesReport], fee_points: &[FeeSeries]) -> Vec<Deviation> {
+        let mut deviations = Vec::new();
+        for fees in fee_points.iter().map(|f| f.to_string()) {
+            let runs: Vec<&SeriesReport> = series.iter().filter(|s| s.fees == fees).collect();
+            let mut types: Vec<u8> = self.expect.iter().flat_map(|e| e.types.iter().copied()).collect();
+            types.sort();
+            types.dedup();
+            for tx_type in types {
+                let Some(expected) = self.expected(&fees, tx_type) else { continue };
+                let mut seen = false;
+                for s in &runs {
+                    for r in s.results.iter().filter(|r| r.tx_type == tx_type) {
+                        seen = true;
+                        if r.status != expected {
+                            deviations.push(Deviation {
+                                series: s.label.clone(),
+                                tx_type,
+                                expected: expected.to_string(),
+                                actual: Some(r.status.clone()),
+                                error: r.error.clone(),
+                            });
+                        }
+                    }
+                }
+                if !seen {
+                    deviations.push(Deviation {
+                        series: format!("fees={}", fees),
+                        tx_type,
+                        expected: expected.to_string(),
+                        actual: None,
+                        error: None,
+                    });
+                }
+            }
+        }
+        deviations
+    }
+
+    fn expected(&self, fees: &str, tx_type: u8) -> Option<&str> {
+        self.expect
+            .iter()
+            .rev()
+            .find(|e| e.types.contains(&tx_type) && (e.fees.is_empty() || e.fees.iter().any(|f| f == fees)))
+            .map(|e| e.status.as_str())
+    }
+}
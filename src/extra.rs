@@ -0,0 +1,195 @@
+//! Chain-specific tx types that extend a standard one with extra fields appended to its
+//! payload, such as Celo's CIP-64 (type 0x7b: EIP-1559 plus `feeCurrency`), described in a
+//! TOML file instead of being built into the tool.
+//!
+//! ```toml
+//! tx_type = 0x7b
+//! base = 2
+//!
+//! [[field]]
+//! name = "feeCurrency"
+//! kind = "address"
+//! value = "0x765DE816845861e75A25fCA122bb6898B8B1282a"
+//! ```
+//!
+//! The fields are RLP-encoded after those of `base`, in the order given, and signed along with
+//! them; the JSON form used for `eth_estimateGas` carries them under their `name`. An
+//! [`Extension`] is a [`TxTypeBuilder`] like any other.
+
+use std::path::Path;
+
+use eyre::{eyre, Result};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, Signature, H256, U256};
+use ethers::utils::keccak256;
+use ethers::utils::rlp::{Rlp, RlpStream};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::blob::y_parity;
+use crate::builder::{build_tx, TxTypeBuilder};
+use crate::{tx_type_of, Fees};
+use crate::zksync::ZKSYNC_EIP712_TX_TYPE;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Extension {
+    /// Envelope type byte of the extended transaction.
+    pub tx_type: u8,
+    /// Standard type whose fields come first: 1 (EIP-2930) or 2 (EIP-1559).
+    pub base: u8,
+    #[serde(rename = "field", default)]
+    pub fields: Vec<ExtraField>,
+    /// Values of `fields`, parsed on load.
+    #[serde(skip)]
+    values: Vec<FieldValue>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExtraField {
+    /// Key in the JSON form of the transaction.
+    pub name: String,
+    pub kind: FieldKind,
+    pub value: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    Address,
+    /// Decimal or `0x` hex.
+    Uint,
+    Bytes,
+}
+
+#[derive(Clone, Debug)]
+enum FieldValue {
+    Address(Address),
+    Uint(U256),
+    Bytes(Bytes),
+}
+
+impl FieldValue {
+    fn parse(kind: FieldKind, value: &str) -> Result<Self> {
+        let parsed = match kind {
+            FieldKind::Address => value.parse().map(Self::Address).map_err(|e| eyre!("{}", e)),
+            FieldKind::Uint => match value.strip_prefix("0x") {
+                Some(hex) => U256::from_str_radix(hex, 16).map_err(|e| eyre!("{}", e)),
+                None => U256::from_dec_str(value).map_err(|e| eyre!("{}", e)),
+            }
+            .map(Self::Uint),
+            FieldKind::Bytes => value.parse().map(Self::Bytes).map_err(|e| eyre!("{}", e)),
+        };
+        parsed.map_err(|e| eyre!("{:?} is not a valid {:?}: {}", value, kind, e))
+    }
+
+    fn append(&self, s: &mut RlpStream) {
+        match self {
+            Self::Address(a) => s.append(a),
+            Self::Uint(u) => s.append(u),
+            Self::Bytes(b) => s.append(&b.as_ref()),
+        };
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            Self::Address(a) => json!(a),
+            Self::Uint(u) => json!(u),
+            Self::Bytes(b) => json!(b),
+        }
+    }
+}
+
+impl Extension {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| eyre!("reading {}: {}", path.display(), e))?;
+        let ext: Self = toml::from_str(&text).map_err(|e| eyre!("parsing {}: {}", path.display(), e))?;
+        ext.validated().map_err(|e| eyre!("{}: {}", path.display(), e))
+    }
+
+    /// Check the types and parse the field values, which deserializing leaves undone.
+    pub fn validated(mut self) -> Result<Self> {
+        if !matches!(self.base, 1 | 2) {
+            return Err(eyre!("base must be 1 or 2, got {}", self.base));
+        }
+        // Types the matrix already builds, and bytes that would read as a legacy RLP list.
+        if self.tx_type <= 5 || self.tx_type == ZKSYNC_EIP712_TX_TYPE || self.tx_type >= 0x80 {
+            return Err(eyre!("tx_type {:#x} is taken or not a valid envelope type", self.tx_type));
+        }
+        self.values = self
+            .fields
+            .iter()
+            .map(|f| FieldValue::parse(f.kind, &f.value).map_err(|e| eyre!("field {}: {}", f.name, e)))
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    /// Names of the extra fields, in encoding order.
+    pub fn field_names(&self) -> Vec<&str> {
+        self.fields.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    /// The fields of `tx`, as its base type encodes them, followed by the extra ones and the
+    /// signature if given, behind the extension's type byte.
+    fn encode(&self, tx: &TypedTransaction, sig: Option<&Signature>) -> Result<Vec<u8>> {
+        if tx_type_of(tx) != self.base {
+            return Err(eyre!("type-{:#x} transactions extend type {}", self.tx_type, self.base));
+        }
+        let base = tx.rlp();
+        let items = Rlp::new(&base[1..]);
+        let count = items.item_count().map_err(|e| eyre!("re-reading the type-{} fields: {}", self.base, e))?;
+        let signature = if sig.is_some() { 3 } else { 0 };
+        let mut s = RlpStream::new_list(count + self.values.len() + signature);
+        for item in items.iter() {
+            s.append_raw(item.as_raw(), 1);
+        }
+        for value in &self.values {
+            value.append(&mut s);
+        }
+        if let Some(sig) = sig {
+            s.append(&y_parity(sig));
+            s.append(&sig.r);
+            s.append(&sig.s);
+        }
+        let mut out = vec![self.tx_type];
+        out.extend_from_slice(&s.out());
+        Ok(out)
+    }
+}
+
+impl TxTypeBuilder for Extension {
+    fn tx_type(&self) -> u8 {
+        self.tx_type
+    }
+
+    /// A transfer of the base type, which the extra fields are added to when encoding.
+    fn build(&self, from: Address, to: Address, value: U256, fees: Fees) -> Result<TypedTransaction> {
+        build_tx(self.base, from, to, value, fees)
+    }
+
+    fn custom_envelope(&self) -> bool {
+        true
+    }
+
+    /// `keccak256(tx_type || rlp(base fields ++ extra fields))`.
+    fn sighash(&self, tx: &TypedTransaction) -> Result<H256> {
+        Ok(keccak256(self.encode(tx, None)?).into())
+    }
+
+    fn rlp_signed(&self, tx: &TypedTransaction, sig: &Signature) -> Result<Bytes> {
+        self.encode(tx, Some(sig)).map(Bytes::from)
+    }
+
+    /// With the extra fields and the extension's type.
+    fn request(&self, tx: &TypedTransaction) -> Result<Value> {
+        let mut request = serde_json::to_value(tx)?;
+        let object = request.as_object_mut().ok_or_else(|| eyre!("transaction did not serialize to an object"))?;
+        for (field, value) in self.fields.iter().zip(&self.values) {
+            object.insert(field.name.clone(), value.to_json());
+        }
+        object.insert("type".into(), json!(format!("{:#x}", self.tx_type)));
+        Ok(request)
+    }
+}
@@ -0,0 +1,123 @@
+//! Testnet faucets, as HTTP POST templates per chain, so a run can top up a sender that is
+//! running dry before it starts.
+//!
+//! ```toml
+//! [[faucet]]
+//! chain_id = 11155111
+//! url = "https://faucet.example/api/claim"
+//! body = '{"address": "{address}", "network": "sepolia"}'
+//! headers = { Authorization = "Bearer {env:FAUCET_TOKEN}" }
+//! ```
+//!
+//! `{address}`, `{chain_id}` and `{env:NAME}` are filled in in the URL, body and header values.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::providers::Middleware;
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+use tokio::time::Instant;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Faucets {
+    #[serde(default)]
+    faucet: Vec<Faucet>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Faucet {
+    pub chain_id: u64,
+    pub url: String,
+    /// Request body; sent as JSON when it parses as JSON once filled in.
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Faucets {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| eyre!("reading {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| eyre!("parsing {}: {}", path.display(), e))
+    }
+
+    /// The faucet configured for `chain_id`, if any.
+    pub fn for_chain(&self, chain_id: u64) -> Option<&Faucet> {
+        self.faucet.iter().find(|f| f.chain_id == chain_id)
+    }
+}
+
+impl Faucet {
+    /// Ask the faucet to fund `address`.
+    pub async fn request(&self, address: Address) -> Result<()> {
+        let fill = |s: &str| fill(s, address, self.chain_id);
+        let url = fill(&self.url)?;
+        let mut request = reqwest::Client::new().post(&url);
+        for (name, value) in &self.headers {
+            request = request.header(name, fill(value)?);
+        }
+        if let Some(body) = &self.body {
+            let body = fill(body)?;
+            request = match serde_json::from_str::<serde_json::Value>(&body) {
+                Ok(json) => request.json(&json),
+                Err(_) => request.body(body),
+            };
+        }
+        let response = request.send().await.map_err(|e| eyre!("faucet {}: {}", self.url, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(eyre!("faucet {} answered {}: {}", self.url, status, text.trim()));
+        }
+        Ok(())
+    }
+
+    /// Request funds for `address` if its balance is below `threshold`, then wait up to `wait`
+    /// for the balance to rise. Returns the new balance, or `None` if no funds were needed.
+    pub async fn top_up<M: Middleware>(
+        &self,
+        client: &M,
+        address: Address,
+        threshold: U256,
+        wait: Duration,
+    ) -> Result<Option<U256>> {
+        let balance = balance(client, address).await?;
+        if balance >= threshold {
+            return Ok(None);
+        }
+        self.request(address).await?;
+        let give_up_at = Instant::now() + wait;
+        let interval = client.provider().get_interval();
+        while Instant::now() < give_up_at {
+            tokio::time::sleep(interval).await;
+            let now = self::balance(client, address).await?;
+            if now > balance {
+                return Ok(Some(now));
+            }
+        }
+        Err(eyre!("faucet accepted the request but no funds arrived within {:?}", wait))
+    }
+}
+
+async fn balance<M: Middleware>(client: &M, address: Address) -> Result<U256> {
+    client.get_balance(address, None).await.map_err(|e| eyre!("eth_getBalance: {}", e))
+}
+
+/// Replace the `{address}`, `{chain_id}` and `{env:NAME}` placeholders in `template`.
+fn fill(template: &str, address: Address, chain_id: u64) -> Result<String> {
+    let mut out = template.replace("{address}", &format!("{:?}", address)).replace("{chain_id}", &chain_id.to_string());
+    let mut from = 0;
+    while let Some(start) = out[from..].find("{env:").map(|i| from + i) {
+        let Some(len) = out[start..].find('}') else { break };
+        let name = &out[start + 5..start + len];
+        let value = std::env::var(name).map_err(|_| eyre!("faucet template needs {} to be set", name))?;
+        out.replace_range(start..=start + len, &value);
+        from = start + value.len();
+    }
+    Ok(out)
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+use std::str::FromStr;
+
+use eyre::{eyre, Result};
+use ethers::providers::Middleware;
+use ethers::types::{BlockNumber, U256};
+use ethers::utils::parse_units;
+
+/// Fee fields applied to every transaction of a series.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fees {
+    pub gas_price: Option<U256>,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_fee_per_blob_gas: U256,
+}
+
+impl Fees {
+    /// Every fee field set to the same value (e.g. `0` for gasless chains).
+    pub fn flat(v: U256) -> Self {
+        Self {
+            gas_price: Some(v),
+            max_priority_fee_per_gas: v,
+            max_fee_per_gas: v,
+            max_fee_per_blob_gas: v,
+        }
+    }
+}
+
+/// Blocks of `eth_feeHistory` a `market` point is derived from.
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// How a `market` point turns `eth_feeHistory` into fees (`FEE_MULTIPLIER`, `PRIORITY_GWEI`).
+#[derive(Clone, Copy, Debug)]
+pub struct MarketFees {
+    /// The next block's base fee is multiplied by this for `maxFeePerGas`, leaving room for
+    /// the base fee to rise before inclusion.
+    pub base_fee_multiplier: u64,
+    /// Priority fee used when the node reports no rewards for recent blocks.
+    pub fallback_priority: U256,
+}
+
+impl Default for MarketFees {
+    fn default() -> Self {
+        Self { base_fee_multiplier: 2, fallback_priority: U256::exp10(9) * 2 }
+    }
+}
+
+/// One fee point the matrix is run at, as written in `FEE_SERIES` (`0,1,1gwei,market`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeSeries {
+    /// Every fee field set to this many wei.
+    Fixed(U256),
+    /// Whatever the node currently suggests via `eth_gasPrice` / `eth_feeHistory`.
+    Market,
+}
+
+impl FeeSeries {
+    /// Concrete fees for this point, querying the node for market rates if needed.
+    pub async fn resolve<M: Middleware>(&self, client: &M) -> Result<Fees> {
+        self.resolve_with(client, MarketFees::default()).await
+    }
+
+    /// Like [`resolve`](Self::resolve), deriving `market` fees with `market`: the median
+    /// priority fee paid over the last blocks, and the next block's base fee times the
+    /// multiplier plus that priority fee as the max fee.
+    pub async fn resolve_with<M: Middleware>(&self, client: &M, market: MarketFees) -> Result<Fees> {
+        match self {
+            FeeSeries::Fixed(v) => Ok(Fees::flat(*v)),
+            FeeSeries::Market => {
+                let gas_price = client.get_gas_price().await.map_err(|e| eyre!("eth_gasPrice: {}", e))?;
+                let history = client
+                    .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &[50.0])
+                    .await
+                    .map_err(|e| eyre!("eth_feeHistory: {}", e))?;
+                // The last entry is the base fee of the block after `newest`.
+                let base_fee = history.base_fee_per_gas.last().copied().unwrap_or_default();
+                let mut rewards: Vec<U256> = history.reward.iter().filter_map(|r| r.first().copied()).collect();
+                rewards.sort();
+                let max_priority_fee_per_gas = match rewards.get(rewards.len() / 2) {
+                    Some(median) => *median,
+                    None => market.fallback_priority,
+                };
+                let max_fee_per_gas = base_fee * market.base_fee_multiplier + max_priority_fee_per_gas;
+                // Pre-Cancun nodes do not know eth_blobBaseFee; fall back to the execution max fee.
+                let max_fee_per_blob_gas = client
+                    .provider()
+                    .request::<_, U256>("eth_blobBaseFee", ())
+                    .await
+                    .unwrap_or(max_fee_per_gas);
+                Ok(Fees {
+                    gas_price: Some(gas_price),
+                    max_priority_fee_per_gas,
+                    max_fee_per_gas,
+                    max_fee_per_blob_gas,
+                })
+            }
+        }
+    }
+}
+
+impl FromStr for FeeSeries {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        if s == "market" {
+            return Ok(FeeSeries::Market);
+        }
+        let (amount, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(i) => s.split_at(i),
+            None => (s.as_str(), "wei"),
+        };
+        let unit = match unit {
+            "eth" => "ether",
+            other => other,
+        };
+        // `parse_units` reads an empty amount as zero and takes a sign.
+        let amount = amount.trim();
+        if amount.is_empty() || !amount.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return Err(format!("invalid fee '{}': expected e.g. 0, 1gwei or market", s));
+        }
+        parse_units(amount, unit)
+            .map(|v| FeeSeries::Fixed(v.into()))
+            .map_err(|e| format!("invalid fee '{}': {}", s, e))
+    }
+}
+
+impl fmt::Display for FeeSeries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gwei = U256::exp10(9);
+        let eth = U256::exp10(18);
+        match self {
+            FeeSeries::Market => write!(f, "market"),
+            FeeSeries::Fixed(v) if !v.is_zero() && (*v % eth).is_zero() => write!(f, "{}eth", v / eth),
+            FeeSeries::Fixed(v) if !v.is_zero() && (*v % gwei).is_zero() => write!(f, "{}gwei", v / gwei),
+            FeeSeries::Fixed(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fee_points() {
+        assert_eq!("market".parse::<FeeSeries>().unwrap(), FeeSeries::Market);
+        assert_eq!(" Market ".parse::<FeeSeries>().unwrap(), FeeSeries::Market);
+        assert_eq!("0".parse::<FeeSeries>().unwrap(), FeeSeries::Fixed(U256::zero()));
+        assert_eq!("1000".parse::<FeeSeries>().unwrap(), FeeSeries::Fixed(U256::from(1000)));
+        assert_eq!("1gwei".parse::<FeeSeries>().unwrap(), FeeSeries::Fixed(U256::exp10(9)));
+        assert_eq!("1.5gwei".parse::<FeeSeries>().unwrap(), FeeSeries::Fixed(U256::from(1_500_000_000u64)));
+        assert_eq!("1eth".parse::<FeeSeries>().unwrap(), FeeSeries::Fixed(U256::exp10(18)));
+        assert_eq!("2 gwei".parse::<FeeSeries>().unwrap(), FeeSeries::Fixed(U256::exp10(9) * 2));
+    }
+
+    #[test]
+    fn rejects_malformed_fee_points() {
+        for s in ["", "abc", "1furlong", "gwei", "-1", "1e9"] {
+            assert!(s.parse::<FeeSeries>().is_err(), "{:?} parsed", s);
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in ["market", "0", "1", "2gwei", "3eth", "1500000000"] {
+            assert_eq!(s.parse::<FeeSeries>().unwrap().to_string(), s);
+        }
+    }
+}
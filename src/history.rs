@@ -0,0 +1,122 @@
+//! SQLite history of matrix runs, so regressions can be queried over time
+//! (e.g. "when did type-1 stop working on chain X").
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use eyre::{eyre, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::diff::Outcome;
+use crate::report::Report;
+use crate::transport::redact;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id          INTEGER PRIMARY KEY,
+    started_at  INTEGER NOT NULL,  -- unix seconds
+    chain_id    INTEGER NOT NULL,
+    rpc         TEXT NOT NULL,     -- scheme and host only
+    value       TEXT NOT NULL      -- wei
+);
+CREATE TABLE IF NOT EXISTS results (
+    run_id              INTEGER NOT NULL REFERENCES runs(id),
+    series              TEXT NOT NULL,
+    fees                TEXT NOT NULL,
+    sender              TEXT NOT NULL,
+    recipient           TEXT,
+    tx_type             INTEGER NOT NULL,
+    status              TEXT NOT NULL,
+    error               TEXT,
+    tx_hash             TEXT,
+    block_number        INTEGER,
+    gas_used            TEXT,
+    effective_gas_price TEXT,
+    inclusion_ms        INTEGER,
+    submit_ms           INTEGER
+);
+CREATE INDEX IF NOT EXISTS results_by_type ON results (tx_type, fees);
+";
+
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    /// Open (or create) the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Store a finished run and return its id.
+    pub fn record(&mut self, report: &Report, rpc_url: &str) -> Result<i64> {
+        let started_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO runs (started_at, chain_id, rpc, value) VALUES (?1, ?2, ?3, ?4)",
+            params![started_at as i64, report.chain_id as i64, redact(rpc_url), report.value.to_string()],
+        )?;
+        let run_id = tx.last_insert_rowid();
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO results (run_id, series, fees, sender, recipient, tx_type, status, error, tx_hash,
+                    block_number, gas_used, effective_gas_price, inclusion_ms, submit_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            )?;
+            for series in &report.series {
+                let sender = series.from.or_else(|| report.from.first().copied()).unwrap_or_default();
+                let recipient = series.to.or_else(|| report.to.first().copied());
+                for r in &series.results {
+                    let latency = r.latency.unwrap_or_default();
+                    insert.execute(params![
+                        run_id,
+                        series.label,
+                        series.fees,
+                        format!("{:?}", sender),
+                        recipient.map(|a| format!("{:?}", a)),
+                        r.tx_type,
+                        r.status,
+                        r.error,
+                        r.tx_hash.map(|h| format!("{:?}", h)),
+                        r.block_number.map(|n| n as i64),
+                        r.gas_used.map(|g| g.to_string()),
+                        r.effective_gas_price.map(|p| p.to_string()),
+                        latency.inclusion_ms.map(|ms| ms as i64),
+                        latency.submit_ms.map(|ms| ms as i64),
+                    ])?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(run_id)
+    }
+
+    /// Resolve a run id, `latest`, or `previous` (the run before the latest).
+    pub fn run_id(&self, spec: &str) -> Result<i64> {
+        let offset = match spec {
+            "latest" => 0,
+            "previous" => 1,
+            id => return id.parse().map_err(|_| eyre!("not a run id: {}", id)),
+        };
+        self.conn
+            .query_row("SELECT id FROM runs ORDER BY id DESC LIMIT 1 OFFSET ?1", [offset], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| eyre!("no {} run in history", spec))
+    }
+
+    /// Per-series outcomes of a stored run.
+    pub fn outcomes(&self, run_id: i64) -> Result<Vec<Outcome>> {
+        let mut query =
+            self.conn.prepare("SELECT series, tx_type, status, error FROM results WHERE run_id = ?1 ORDER BY rowid")?;
+        let rows = query.query_map([run_id], |row| {
+            Ok(Outcome { series: row.get(0)?, tx_type: row.get(1)?, status: row.get(2)?, error: row.get(3)? })
+        })?;
+        let outcomes = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        if outcomes.is_empty() {
+            return Err(eyre!("run {} has no results", run_id));
+        }
+        Ok(outcomes)
+    }
+}
@@ -0,0 +1,97 @@
+//! External commands run at points of a transaction's life, with its JSON on stdin, for
+//! integrations such as posting to a tracking system or starting sequencer-side log capture
+//! just before a transaction goes out.
+//!
+//! Commands run through `sh -c` with `HOOK_EVENT`, `TX_HASH` and `TX_TYPE` set, and the run
+//! waits for them: a pre-send hook has finished before the transaction is submitted. A hook
+//! that fails is logged and does not stop the run. The strings are handed to the shell as they
+//! are, so they must come from the operator, never from a request: `serve` clears them.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use eyre::{eyre, Result};
+use ethers::providers::Middleware;
+use ethers::types::{Bytes, H256};
+use serde_json::{json, Value};
+
+use crate::decode;
+use crate::report::TxResult;
+
+#[derive(Clone, Debug, Default)]
+pub struct Hooks {
+    /// Run before each `eth_sendRawTransaction`, resubmissions at a higher fee included.
+    pub pre_send: Option<String>,
+    /// Run once a transaction has a receipt.
+    pub post_receipt: Option<String>,
+}
+
+impl Hooks {
+    /// Run the pre-send hook, if any, with the transaction about to be sent: its raw bytes and
+    /// hash, and its fields when they decode.
+    pub async fn pre_send(&self, raw: &Bytes, hash: H256) -> Result<()> {
+        let Some(cmd) = &self.pre_send else { return Ok(()) };
+        let decoded = decode::decode(raw).ok();
+        let tx_type = match &decoded {
+            Some(tx) => tx.tx_type,
+            None => raw.first().copied().unwrap_or_default(),
+        };
+        let input = json!({
+            "event": "pre_send",
+            "hash": hash,
+            "tx_type": tx_type,
+            "raw": raw,
+            "transaction": decoded,
+        });
+        run(cmd, "pre_send", hash, tx_type, input).await
+    }
+
+    /// Run the post-receipt hook, if any, with the result and the receipt as the node returned
+    /// it. Nothing runs for a result that was not mined.
+    pub async fn post_receipt<M: Middleware>(&self, client: &M, series: &str, result: &TxResult) -> Result<()> {
+        let Some(cmd) = &self.post_receipt else { return Ok(()) };
+        let Some(hash) = result.tx_hash.filter(|_| result.block_number.is_some()) else { return Ok(()) };
+        let receipt: Value = client
+            .provider()
+            .request("eth_getTransactionReceipt", json!([hash]))
+            .await
+            .map_err(|e| eyre!("eth_getTransactionReceipt: {}", e))?;
+        let input = json!({
+            "event": "post_receipt",
+            "series": series,
+            "result": result,
+            "receipt": receipt,
+        });
+        run(cmd, "post_receipt", hash, result.tx_type, input).await
+    }
+}
+
+/// Run `cmd` with `input` on stdin until it exits, off the async runtime.
+async fn run(cmd: &str, event: &'static str, hash: H256, tx_type: u8, input: Value) -> Result<()> {
+    let cmd = cmd.to_string();
+    let input = serde_json::to_vec(&input)?;
+    tokio::task::spawn_blocking(move || {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&cmd)
+            .env("HOOK_EVENT", event)
+            .env("TX_HASH", format!("{:?}", hash))
+            .env("TX_TYPE", tx_type.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| eyre!("starting {:?}: {}", cmd, e))?;
+        // A hook that ignores its input may exit before reading it.
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(&input);
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(eyre!("{:?} exited with {}: {}", cmd, output.status, stderr.trim()));
+        }
+        Ok(())
+    })
+    .await?
+}
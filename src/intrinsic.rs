@@ -0,0 +1,92 @@
+//! Intrinsic gas: what a transaction costs before any code runs, from its type, calldata and
+//! access list. A plain transfer uses exactly this much on an EVM-equivalent chain, so a
+//! difference shows a chain charging differently (L1 data fees folded into `gasUsed`, custom
+//! gas schedules).
+
+use ethers::types::transaction::eip2930::AccessList;
+use ethers::types::Transaction;
+
+/// Base cost of every transaction.
+pub const TX_GAS: u64 = 21_000;
+/// Extra base cost of a contract creation.
+pub const CREATE_GAS: u64 = 32_000;
+pub const ZERO_BYTE_GAS: u64 = 4;
+pub const NONZERO_BYTE_GAS: u64 = 16;
+/// Per 32-byte word of init code (EIP-3860).
+pub const INITCODE_WORD_GAS: u64 = 2;
+pub const ACCESS_LIST_ADDRESS_GAS: u64 = 2_400;
+pub const ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1_900;
+/// Per calldata token in the EIP-7623 floor, a token being a zero byte or a quarter of a
+/// non-zero one.
+pub const FLOOR_TOKEN_GAS: u64 = 10;
+
+/// Gas charged for `data` as calldata.
+pub fn calldata_gas(data: &[u8]) -> u64 {
+    data.iter().map(|&b| if b == 0 { ZERO_BYTE_GAS } else { NONZERO_BYTE_GAS }).sum()
+}
+
+/// Intrinsic gas of a transaction with these fields.
+pub fn intrinsic_gas(create: bool, data: &[u8], access_list: &AccessList) -> u64 {
+    let mut gas = TX_GAS + calldata_gas(data);
+    if create {
+        gas += CREATE_GAS + INITCODE_WORD_GAS * data.len().div_ceil(32) as u64;
+    }
+    for item in &access_list.0 {
+        gas += ACCESS_LIST_ADDRESS_GAS + ACCESS_LIST_STORAGE_KEY_GAS * item.storage_keys.len() as u64;
+    }
+    gas
+}
+
+/// Least gas a transaction with `data` uses on a Prague chain (EIP-7623), however little it
+/// executes.
+pub fn floor_gas(data: &[u8]) -> u64 {
+    let tokens: u64 = data.iter().map(|&b| if b == 0 { 1 } else { 4 }).sum();
+    TX_GAS + FLOOR_TOKEN_GAS * tokens
+}
+
+/// What is wrong with `used` against the intrinsic gas and the EIP-7623 `floor`: nothing uses
+/// less than its intrinsic gas, and with `no_code` to run it must be exactly one of the two.
+pub fn anomaly(used: u64, intrinsic: u64, floor: u64, no_code: bool) -> Option<String> {
+    let delta = used as i64 - intrinsic as i64;
+    if delta < 0 {
+        Some(format!("gasUsed {} is below the intrinsic gas {}", used, intrinsic))
+    } else if no_code && used != intrinsic && used != floor {
+        Some(format!("gasUsed {} is {:+} from the intrinsic gas {} with no code to run", used, delta, intrinsic))
+    } else {
+        None
+    }
+}
+
+/// Intrinsic gas of a mined transaction, as the node returned it.
+pub fn of(tx: &Transaction) -> u64 {
+    let access_list = tx.access_list.clone().unwrap_or_default();
+    intrinsic_gas(tx.to.is_none(), &tx.input, &access_list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_transfer_uses_exactly_its_intrinsic_gas() {
+        assert_eq!(anomaly(TX_GAS, TX_GAS, TX_GAS, true), None);
+        assert!(anomaly(TX_GAS + 1, TX_GAS, TX_GAS, true).unwrap().contains("+1 from the intrinsic gas 21000"));
+        assert!(anomaly(TX_GAS - 1, TX_GAS, TX_GAS, true).unwrap().contains("below the intrinsic gas"));
+    }
+
+    #[test]
+    fn the_calldata_floor_is_not_an_anomaly() {
+        let data = [1u8; 100];
+        let intrinsic = intrinsic_gas(false, &data, &AccessList::default());
+        let floor = floor_gas(&data);
+        assert_eq!((intrinsic, floor), (22_600, 25_000));
+        assert_eq!(anomaly(floor, intrinsic, floor, true), None);
+        assert!(anomaly(floor - 1, intrinsic, floor, true).is_some());
+    }
+
+    #[test]
+    fn code_may_use_more() {
+        assert_eq!(anomaly(50_000, TX_GAS, TX_GAS, false), None);
+        assert!(anomaly(20_000, TX_GAS, TX_GAS, false).is_some());
+    }
+}
@@ -0,0 +1,1572 @@
+pub mod access_list;
+pub mod amount;
+pub mod archive;
+pub mod basefee;
+pub mod blob;
+pub mod builder;
+pub mod budget;
+pub mod chains;
+pub mod crosscheck;
+pub mod dashboard;
+pub mod decode;
+pub mod deploy;
+pub mod diff;
+pub mod ens;
+pub mod erc20;
+pub mod error_kind;
+pub mod escalator;
+pub mod expect;
+pub mod extra;
+pub mod faucet;
+pub mod fees;
+pub mod history;
+pub mod hooks;
+pub mod intrinsic;
+pub mod load;
+pub mod metrics;
+pub mod multicall;
+pub mod nonce;
+pub mod plugins;
+pub mod private;
+pub mod probe;
+pub mod receipts;
+pub mod report;
+pub mod retry;
+pub mod revert;
+pub mod roundtrip;
+pub mod runlog;
+pub mod safe;
+pub mod scenarios;
+pub mod shutdown;
+pub mod signer;
+pub mod sweep;
+pub mod trace;
+pub mod transport;
+pub mod txpool;
+pub mod typed_data;
+pub mod unstick;
+pub mod userop;
+pub mod verify;
+pub mod webhook;
+pub mod zksync;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use futures::future::join_all;
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::{spoof, Middleware, MiddlewareError, PendingTransaction, Provider, RawCall};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip2930::AccessList;
+use ethers::types::{Address, BlockNumber, Bytes, TransactionReceipt, H256, U256};
+use ethers::utils::keccak256;
+use tokio::sync::watch;
+use tracing::{info_span, Instrument};
+use tokio::time::{sleep, timeout, Instant};
+
+// Progress events go through `tracing`, inside the current series/tx span.
+macro_rules! progress {
+    ($m:expr, warn, $($arg:tt)*) => {
+        if $m.progress {
+            tracing::warn!($($arg)*);
+        }
+    };
+    ($m:expr, $($arg:tt)*) => {
+        if $m.progress {
+            tracing::info!($($arg)*);
+        }
+    };
+}
+
+use crate::access_list::{AccessListReport, AccessListSource};
+use crate::archive::Archive;
+use crate::blob::{BlobSource, BlobTransaction, Sidecar, BLOB_TX_GAS, BLOB_TX_TYPE};
+use crate::builder::{TxBuilders, TxTypeBuilder};
+use crate::dashboard::Dashboard;
+use crate::escalator::{bump_fees, EscalationPolicy, FeeBump};
+use crate::hooks::Hooks;
+use crate::metrics::Metrics;
+use crate::nonce::NonceManager;
+use crate::private::{PrivateRelay, PrivateReport};
+use crate::receipts::ReceiptBatcher;
+use crate::retry::RetryPolicy;
+use crate::roundtrip::Signed;
+use crate::runlog::{Entry, RunLog};
+use crate::shutdown::Shutdown;
+use crate::transport::Transport;
+use crate::zksync::{Eip712Meta, Eip712Transaction, ZKSYNC_EIP712_TX_TYPE};
+pub use crate::builder::build_tx;
+pub use crate::fees::{FeeSeries, Fees, MarketFees};
+pub use crate::report::{
+    print_recipient_totals, print_summary, Latency, Report, SeriesReport, Simulation, TxResult,
+};
+pub use crate::signer::HashSigner;
+
+/// What each matrix transaction does.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Workload {
+    /// Native transfer of the matrix value to the recipient.
+    #[default]
+    Native,
+    /// ERC-20 `transfer(recipient, amount)` on `token`, confirmed via its `Transfer` event.
+    Erc20 { token: Address, amount: U256 },
+    /// Deploy the bundled counter contract; the recipient is ignored.
+    Deploy,
+}
+
+/// Sends one native transfer per tx type and records how the chain handled each.
+pub struct TxMatrix<M, S> {
+    client: Arc<SignerMiddleware<M, S>>,
+    from: Address,
+    to: Address,
+    value: U256,
+    tx_types: Vec<u8>,
+    blob_source: BlobSource,
+    eip712_meta: Eip712Meta,
+    builders: TxBuilders,
+    progress: bool,
+    dry_run: bool,
+    simulate: bool,
+    simulate_balance: Option<U256>,
+    heads: Option<watch::Receiver<u64>>,
+    receipts: Option<Arc<ReceiptBatcher>>,
+    nonces: Option<Arc<NonceManager>>,
+    parallel: bool,
+    tx_timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    escalation: Option<EscalationPolicy>,
+    workload: Workload,
+    data: Bytes,
+    safe: Option<Address>,
+    access_list: Option<AccessListSource>,
+    verify: bool,
+    metrics: Option<Arc<Metrics>>,
+    dashboard: Option<Arc<Dashboard>>,
+    archive: Option<Arc<Archive>>,
+    hooks: Hooks,
+    retry: RetryPolicy,
+    second_rpc: Option<Arc<Provider<Transport>>>,
+    private: Option<Arc<PrivateRelay>>,
+    offline_gas: Option<U256>,
+    pre_eip155: bool,
+    explorer: Option<String>,
+    confirmations: u64,
+    reorg_watch_blocks: u64,
+    shutdown: Option<Shutdown>,
+    run_log: Option<Arc<RunLog>>,
+    trace: bool,
+    round_trip: bool,
+    /// Transactions signed with round-trip checks on, by hash, with the fields that did not
+    /// decode back as signed.
+    signed: Mutex<HashMap<H256, (Signed, Vec<String>)>>,
+}
+
+impl<M, S> TxMatrix<M, S>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    /// Matrix covering tx types 0..=5, sent from the client's signer.
+    pub fn new(client: Arc<SignerMiddleware<M, S>>, to: Address, value: U256) -> Self {
+        let from = client.address();
+        Self {
+            client,
+            from,
+            to,
+            value,
+            tx_types: (0u8..=5u8).collect(),
+            blob_source: BlobSource::default(),
+            eip712_meta: Eip712Meta::default(),
+            builders: TxBuilders::standard(),
+            progress: true,
+            dry_run: false,
+            simulate: false,
+            simulate_balance: None,
+            heads: None,
+            receipts: None,
+            nonces: None,
+            parallel: false,
+            tx_timeout: None,
+            deadline: None,
+            escalation: None,
+            workload: Workload::default(),
+            data: Bytes::new(),
+            safe: None,
+            access_list: None,
+            verify: true,
+            metrics: None,
+            dashboard: None,
+            archive: None,
+            hooks: Hooks::default(),
+            retry: RetryPolicy::default(),
+            second_rpc: None,
+            private: None,
+            offline_gas: None,
+            pre_eip155: false,
+            explorer: None,
+            confirmations: 1,
+            reorg_watch_blocks: 0,
+            shutdown: None,
+            run_log: None,
+            trace: false,
+            round_trip: false,
+            signed: Mutex::default(),
+        }
+    }
+
+    /// Restrict the matrix to the given tx types.
+    pub fn with_tx_types(mut self, tx_types: impl IntoIterator<Item = u8>) -> Self {
+        self.tx_types = tx_types.into_iter().collect();
+        self
+    }
+
+    /// Blob contents used for type-3 transactions.
+    pub fn with_blob_source(mut self, blob_source: BlobSource) -> Self {
+        self.blob_source = blob_source;
+        self
+    }
+
+    /// Pubdata price and paymaster used for zkSync type-0x71 transactions.
+    pub fn with_eip712_meta(mut self, eip712_meta: Eip712Meta) -> Self {
+        self.eip712_meta = eip712_meta;
+        self
+    }
+
+    /// Build transactions of `builder`'s type with it, e.g. a chain-specific type; it is sent
+    /// when the type is among the matrix's types.
+    pub fn with_builder(mut self, builder: Arc<dyn TxTypeBuilder>) -> Self {
+        self.builders.register(builder);
+        self
+    }
+
+    /// Emit per-transaction progress events through `tracing` (on by default).
+    pub fn with_progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Build and sign every transaction but never broadcast it; results carry the raw
+    /// envelope and its hash instead of a receipt.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Run `eth_call` and `eth_estimateGas` before each send and record the verdict.
+    pub fn with_simulation(mut self, simulate: bool) -> Self {
+        self.simulate = simulate;
+        self
+    }
+
+    /// Simulate with the sender's balance overridden to `balance` (an `eth_call` state
+    /// override), and take the gas limit from that simulation, so a dry run works from an
+    /// unfunded address while the node still validates every tx type.
+    pub fn with_simulation_balance(mut self, balance: Option<U256>) -> Self {
+        self.simulate_balance = balance;
+        self
+    }
+
+    /// Wait for receipts by re-checking on every pushed block head instead of polling
+    /// (see [`transport::Transport::head_notifications`]).
+    pub fn with_head_notifications(mut self, heads: Option<watch::Receiver<u64>>) -> Self {
+        self.heads = heads;
+        self
+    }
+
+    /// Poll for receipts through `receipts`, which batches the polls of every transaction
+    /// waiting on it, instead of one request per transaction.
+    pub fn with_receipt_batcher(mut self, receipts: Option<Arc<ReceiptBatcher>>) -> Self {
+        self.receipts = receipts;
+        self
+    }
+
+    /// Assign nonces from `nonces` instead of letting the node pick one per send.
+    pub fn with_nonce_manager(mut self, nonces: Arc<NonceManager>) -> Self {
+        self.nonces = Some(nonces);
+        self
+    }
+
+    /// Submit all tx types of a series concurrently and gather receipts as they land.
+    /// Only takes effect with a nonce manager, which assigns the distinct nonces up front.
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Stop waiting for a receipt after `tx_timeout` and report the entry as `timeout`.
+    pub fn with_tx_timeout(mut self, tx_timeout: Option<Duration>) -> Self {
+        self.tx_timeout = tx_timeout;
+        self
+    }
+
+    /// Hard deadline for the whole run: waits are cut short at it and entries not yet
+    /// started are reported as `timeout` without being sent.
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Rebroadcast stuck type 0-2 transactions with bumped fees (see [`EscalationPolicy`]).
+    pub fn with_escalation(mut self, escalation: Option<EscalationPolicy>) -> Self {
+        self.escalation = escalation;
+        self
+    }
+
+    /// How transient submission failures (rate limits, timeouts, dropped connections) are
+    /// retried; 3 retries from 500ms by default.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Independent endpoint to re-read every mined tx and receipt from, flagging disagreements
+    /// with the endpoint it was submitted through as anomalies.
+    pub fn with_verification_rpc(mut self, provider: Option<Arc<Provider<Transport>>>) -> Self {
+        self.second_rpc = provider;
+        self
+    }
+
+    /// Submit through a private relay instead of the public mempool (see [`PrivateRelay`]);
+    /// receipts are still read from the client's node.
+    pub fn with_private_relay(mut self, relay: Option<Arc<PrivateRelay>>) -> Self {
+        self.private = relay;
+        self
+    }
+
+    /// Sign without contacting the node: every tx gets this gas limit and the signer's chain
+    /// id instead of being filled from the RPC. Nonces must come from a nonce manager, fees
+    /// must be fixed, and nothing can be sent, so combine with a dry run.
+    pub fn with_offline_gas(mut self, gas: Option<U256>) -> Self {
+        self.offline_gas = gas;
+        self
+    }
+
+    /// Sign type-0 transactions without EIP-155 replay protection: no chain id in the sighash
+    /// and `v` = 27/28. Needs a signer that can sign raw digests.
+    pub fn with_pre_eip155(mut self, enabled: bool) -> Self {
+        self.pre_eip155 = enabled;
+        self
+    }
+
+    /// Block explorer base URL; submitted transactions are logged with a link to their page.
+    pub fn with_explorer(mut self, explorer: Option<String>) -> Self {
+        self.explorer = explorer;
+        self
+    }
+
+    /// Blocks (counting the one it was mined in) to wait for after a transaction is mined,
+    /// before checking that its receipt is still canonical; 1 (the default) checks nothing.
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations.max(1);
+        self
+    }
+
+    /// Keep re-checking a mined transaction's receipt for this many blocks after its own
+    /// (0, the default, stops once it is confirmed).
+    pub fn with_reorg_watch(mut self, blocks: u64) -> Self {
+        self.reorg_watch_blocks = blocks;
+        self
+    }
+
+    /// Once `shutdown` fires, report entries not yet started as `skipped` instead of sending
+    /// them, and stop waiting on in-flight ones when its grace period is over.
+    pub fn with_shutdown(mut self, shutdown: Option<Shutdown>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Fetch a `callTracer` trace of every mined transaction and attach its gas breakdown and
+    /// internal calls (needs the node's `debug` namespace).
+    pub fn with_trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// Decode every signed transaction back and compare it with what was built, then, once it
+    /// is mined, with the node's copy, reporting every field that differs as an anomaly.
+    pub fn with_round_trip(mut self, round_trip: bool) -> Self {
+        self.round_trip = round_trip;
+        self
+    }
+
+    /// Record nonces, hashes and outcomes in `run_log` as they happen, and take over the
+    /// entries it already has: settled ones are reported as recorded, in-flight ones are
+    /// waited for instead of being sent again.
+    pub fn with_run_log(mut self, run_log: Option<Arc<RunLog>>) -> Self {
+        self.run_log = run_log;
+        self
+    }
+
+    /// What each transaction does (native transfer by default).
+    pub fn with_workload(mut self, workload: Workload) -> Self {
+        self.workload = workload;
+        self
+    }
+
+    /// Calldata carried by every native transfer (none by default); token transfers and
+    /// deployments bring their own.
+    pub fn with_data(mut self, data: Bytes) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Send every transaction through this Safe's `execTransaction`, signed by the client's
+    /// signer as its single owner, so the workload runs from the Safe.
+    pub fn with_safe(mut self, safe: Option<Address>) -> Self {
+        self.safe = safe;
+        self
+    }
+
+    /// Send type-1 transactions with a populated access list instead of an empty one.
+    pub fn with_access_list(mut self, source: Option<AccessListSource>) -> Self {
+        self.access_list = source;
+        self
+    }
+
+    /// Cross-check every mined transaction against the node's records (on by default).
+    pub fn with_verification(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Count every result into `metrics` as soon as it is known.
+    pub fn with_metrics(mut self, metrics: Option<Arc<Metrics>>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Write every transaction with its receipt and block header to `archive` once it is
+    /// settled.
+    pub fn with_archive(mut self, archive: Option<Arc<Archive>>) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Run `hooks` before each submission and after each receipt.
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Keep `dashboard` up to date with every entry as it is sent, mined and confirmed.
+    pub fn with_dashboard(mut self, dashboard: Option<Arc<Dashboard>>) -> Self {
+        self.dashboard = dashboard;
+        self
+    }
+
+    pub fn from(&self) -> Address {
+        self.from
+    }
+
+    pub fn to(&self) -> Address {
+        self.to
+    }
+
+    /// Run every tx type once with `fees`, printing progress under `label`.
+    pub async fn run(&self, label: &str, fees: Fees) -> Vec<TxResult> {
+        let series = async {
+            progress!(self, "Series: {}", label);
+            if self.parallel && self.nonces.is_some() {
+                // Every future reserves its nonce on first poll, before any of them awaits.
+                return join_all(self.tx_types.iter().map(|&tx_type| self.run_one(tx_type, label, fees))).await;
+            }
+            let mut results = Vec::with_capacity(self.tx_types.len());
+            for &tx_type in &self.tx_types {
+                results.push(self.run_one(tx_type, label, fees).await);
+            }
+            results
+        };
+        series.instrument(info_span!("series", %label, from = %format_address(self.from))).await
+    }
+
+    /// Destination, native value and calldata of every transaction, per the workload.
+    /// No destination means a contract creation.
+    fn call(&self) -> (Option<Address>, U256, Bytes) {
+        match self.workload {
+            Workload::Native => (Some(self.to), self.value, self.data.clone()),
+            Workload::Erc20 { token, amount } => {
+                (Some(token), U256::zero(), erc20::transfer_calldata(self.to, amount))
+            }
+            Workload::Deploy => (None, U256::zero(), deploy::counter_init_code()),
+        }
+    }
+
+    /// The call the matrix transaction makes: the workload's own or, through a Safe,
+    /// `execTransaction` carrying it, signed for the Safe's next nonce.
+    async fn sent_call(&self) -> Result<(Option<Address>, U256, Bytes)> {
+        let (to, value, data) = self.call();
+        let Some(safe) = self.safe else { return Ok((to, value, data)) };
+        let to = to.ok_or_else(|| eyre!("a Safe cannot deploy through execTransaction"))?;
+        let nonce = safe::nonce(self.client.as_ref(), safe).await?;
+        let hash = safe::tx_hash(self.client.signer().chain_id(), safe, to, value, &data, nonce);
+        let sig = self.client.signer().sign_hash(hash)?;
+        Ok((Some(safe), U256::zero(), safe::exec_calldata(to, value, &data, &sig)))
+    }
+
+    /// How long we may still wait for a receipt, given the per-tx timeout and run deadline.
+    fn wait_limit(&self) -> Option<Duration> {
+        let left = self.deadline.map(|d| d.saturating_duration_since(Instant::now()));
+        match (self.tx_timeout, left) {
+            (Some(t), Some(l)) => Some(t.min(l)),
+            (t, l) => t.or(l),
+        }
+    }
+
+    async fn run_one(&self, tx_type: u8, label: &str, fees: Fees) -> TxResult {
+        let mut result = self.attempt(tx_type, label, fees).instrument(info_span!("tx", tx_type)).await;
+        self.inspect_pool(&mut result).await;
+        if let Some(archive) = &self.archive {
+            match archive.record(self.client.as_ref(), label, &result).await {
+                Ok(Some(path)) => progress!(self, "archived to {}", path.display()),
+                Ok(None) => {}
+                Err(e) => progress!(self, warn, "could not archive: {}", e),
+            }
+        }
+        if let Err(e) = self.hooks.post_receipt(self.client.as_ref(), label, &result).await {
+            progress!(self, warn, "post-receipt hook failed: {}", e);
+        }
+        if let Some(log) = &self.run_log {
+            log.finished(label, &result);
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.record(&result);
+        }
+        if let Some(dashboard) = &self.dashboard {
+            dashboard.finished(label, &result);
+        }
+        result
+    }
+
+    async fn attempt(&self, tx_type: u8, label: &str, fees: Fees) -> TxResult {
+        if let Some(entry) = self.run_log.as_ref().and_then(|log| log.entry(label, tx_type)) {
+            if entry.in_flight() {
+                return self.resume(tx_type, label, entry).await;
+            }
+            if entry.status.is_some() {
+                progress!(self, "Type-{} already settled in run: {}", tx_type, entry.status.as_deref().unwrap_or_default());
+                return entry.result();
+            }
+        }
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            progress!(self, "Skipping type-{}: run deadline reached", tx_type);
+            return TxResult::new(tx_type, "timeout").with_error("run deadline reached before sending");
+        }
+        if self.shutdown.as_ref().is_some_and(Shutdown::requested) {
+            progress!(self, "Skipping type-{}: interrupted", tx_type);
+            return TxResult::new(tx_type, "skipped").with_error("interrupted before sending");
+        }
+        let custom = self.builders.get(tx_type).is_some_and(|b| b.custom_envelope());
+        if tx_type == BLOB_TX_TYPE || tx_type == ZKSYNC_EIP712_TX_TYPE || custom {
+            if matches!(self.workload, Workload::Deploy) && !custom {
+                let reason = if tx_type == BLOB_TX_TYPE {
+                    "blob transactions cannot create contracts"
+                } else {
+                    "type-0x71 deployments need factory deps, which are not supported"
+                };
+                progress!(self, "Skipping type-{}: {}", tx_type, reason);
+                return TxResult::new(tx_type, "unsupported").with_error(reason);
+            }
+            progress!(self, "Attempting type-{} ({})…", tx_type, label);
+            self.show_started(label, tx_type);
+            let nonce = self.reserve(label, tx_type);
+            let mut result = if self.dry_run {
+                self.dry_run_envelope(tx_type, fees, nonce).await
+            } else {
+                self.send_envelope(tx_type, fees, nonce, label).await
+            };
+            self.confirm(&mut result, label).await;
+            self.explain_revert(&mut result).await;
+            self.verify(&mut result).await;
+            self.cross_check(&mut result).await;
+            self.compare_round_trip(&mut result).await;
+            self.trace(&mut result).await;
+            return self.settle_nonce(nonce, result);
+        }
+        let (to, value, data) = match self.sent_call().await {
+            Ok(call) => call,
+            Err(e) => {
+                progress!(self, warn, "could not build type-{} tx: {}", tx_type, e);
+                return TxResult::new(tx_type, "build error").with_error(e);
+            }
+        };
+        match self.builders.build(tx_type, self.from, to.unwrap_or_default(), value, fees) {
+            Ok(mut tx) => {
+                progress!(self, "Attempting type-{} ({})…", tx_type, label);
+                self.show_started(label, tx_type);
+                if to.is_none() {
+                    deploy::into_create(&mut tx);
+                }
+                if !data.is_empty() {
+                    tx.set_data(data);
+                }
+                let nonce = self.reserve(label, tx_type);
+                if let Some(n) = nonce {
+                    tx.set_nonce(n);
+                }
+                let access_list = match &self.access_list {
+                    Some(source) if tx_type == 1 => Some(self.populate_access_list(&mut tx, source).await),
+                    _ => None,
+                };
+                let simulation = if self.simulate { Some(self.simulate(&tx).await) } else { None };
+                // The node's own estimate would fail for lack of funds.
+                if self.simulate_balance.is_some()
+                    && tx.gas().is_none()
+                    && let Some(gas) = simulation.as_ref().and_then(|s| s.estimated_gas)
+                {
+                    tx.set_gas(gas);
+                }
+                let mut result = match (self.dry_run, self.escalation) {
+                    (true, _) => self.dry_run_typed(tx).await,
+                    (false, Some(policy)) => self.send_escalating(tx, policy, label).await,
+                    (false, None) => self.send(tx, label).await,
+                };
+                result.simulation = simulation;
+                result.access_list = access_list;
+                self.confirm(&mut result, label).await;
+                self.explain_revert(&mut result).await;
+                self.verify(&mut result).await;
+                self.cross_check(&mut result).await;
+                self.compare_round_trip(&mut result).await;
+                self.trace(&mut result).await;
+                if let Some(address) = result.contract_address {
+                    result.code_size = self.code_size(address).await;
+                }
+                self.settle_nonce(nonce, result)
+            }
+            Err(e) => {
+                progress!(self, "Skipping type-{}: {}", tx_type, e);
+                TxResult::new(tx_type, "unsupported").with_error(e)
+            }
+        }
+    }
+
+    /// Next nonce from the nonce manager, noted in the run log.
+    fn reserve(&self, label: &str, tx_type: u8) -> Option<U256> {
+        let nonce = self.nonces.as_ref().map(|n| n.reserve());
+        if let (Some(log), Some(n)) = (&self.run_log, nonce) {
+            log.reserved(label, tx_type, n);
+        }
+        nonce
+    }
+
+    /// Pick up an entry a previous, interrupted run submitted: wait for its receipt rather
+    /// than sending again.
+    async fn resume(&self, tx_type: u8, label: &str, entry: Entry) -> TxResult {
+        let Some(hash) = entry.tx_hash else { return entry.result() };
+        progress!(self, "Resuming type-{} ({}): waiting for 0x{:x}", tx_type, label, hash);
+        self.show_started(label, tx_type);
+        let pending = PendingTransaction::new(hash, self.client.provider());
+        let mut result = self.wait(tx_type, label, pending, Latency::default()).await;
+        result.nonce = entry.nonce;
+        self.confirm(&mut result, label).await;
+        self.explain_revert(&mut result).await;
+        self.verify(&mut result).await;
+        self.cross_check(&mut result).await;
+        self.trace(&mut result).await;
+        result
+    }
+
+    // A transaction that never got a hash never used its nonce.
+    fn settle_nonce(&self, nonce: Option<U256>, mut result: TxResult) -> TxResult {
+        if let (Some(manager), Some(n)) = (&self.nonces, nonce) {
+            if result.tx_hash.is_some() {
+                result.nonce = Some(n);
+            } else {
+                manager.release(n);
+            }
+        }
+        result
+    }
+
+    /// Fill in the access list, estimating gas before and after for the report.
+    async fn populate_access_list(&self, tx: &mut TypedTransaction, source: &AccessListSource) -> AccessListReport {
+        let gas_without = self.client.estimate_gas(tx, None).await;
+        let list = match source {
+            AccessListSource::Fixed(list) => Ok(list.clone()),
+            AccessListSource::Auto => self.client.create_access_list(tx, None).await.map(|l| l.access_list),
+        };
+        let mut report = match list {
+            Ok(list) => {
+                let report = AccessListReport::new(source, &list);
+                tx.set_access_list(list);
+                report
+            }
+            Err(e) => {
+                let mut report = AccessListReport::new(source, &AccessList::default());
+                report.error = Some(format!("eth_createAccessList: {}", e));
+                report
+            }
+        };
+        report.gas_without = gas_without.ok();
+        if report.error.is_none() {
+            report.gas_with = self.client.estimate_gas(tx, None).await.ok();
+        }
+        progress!(self, "access list: {}", report);
+        report
+    }
+
+    /// Replay a reverted transaction with `eth_call` against the state before its block and
+    /// record why it reverted.
+    async fn explain_revert(&self, result: &mut TxResult) {
+        let (Some(hash), Some(block)) = (result.tx_hash, result.block_number) else { return };
+        if result.status != "failed" {
+            return;
+        }
+        let tx = match self.client.get_transaction(hash).await {
+            Ok(Some(tx)) => tx,
+            _ => return,
+        };
+        let call: TypedTransaction = (&tx).into();
+        let at = BlockNumber::Number(block.saturating_sub(1).into()).into();
+        let reason = match self.client.call(&call, Some(at)).await {
+            // The revert depended on earlier transactions in the same block.
+            Ok(_) => "replay did not revert".to_string(),
+            Err(e) => match e.as_error_response().and_then(|r| r.as_revert_data()) {
+                Some(data) => revert::decode(&data),
+                None => e.to_string(),
+            },
+        };
+        progress!(self, "revert reason: {}", reason);
+        result.revert_reason = Some(reason);
+    }
+
+    /// Compare the mined transaction with what we sent, recording any chain anomalies.
+    async fn verify(&self, result: &mut TxResult) {
+        let (true, Some(hash), Some(_)) = (self.verify, result.tx_hash, result.block_number) else { return };
+        let (to, value) = match self.safe {
+            Some(safe) => (Some(safe), U256::zero()),
+            None => {
+                let (to, value, _) = self.call();
+                (to, value)
+            }
+        };
+        let expected = verify::Expected { from: self.from, to, value };
+        match verify::check(self.client.as_ref(), hash, &expected).await {
+            Ok(v) => {
+                for a in &v.anomalies {
+                    progress!(self, warn, "anomaly: {}", a);
+                }
+                result.anomalies = v.anomalies;
+                result.fee_audit = v.fee_audit;
+            }
+            Err(e) => progress!(self, warn, "could not verify: {}", e),
+        }
+        self.analyze_gas(result).await;
+    }
+
+    /// Compare `gas_used` with the intrinsic gas of the transaction as mined. Sent to an
+    /// account without code, a transaction uses exactly that (or the EIP-7623 calldata floor),
+    /// so anything else is an anomaly, as is any transaction using less.
+    async fn analyze_gas(&self, result: &mut TxResult) {
+        let (Some(hash), Some(gas_used)) = (result.tx_hash, result.gas_used) else { return };
+        let tx = match self.client.get_transaction(hash).await {
+            Ok(Some(tx)) => tx,
+            Ok(None) => return,
+            Err(e) => {
+                progress!(self, warn, "could not fetch the transaction for gas analysis: {}", e);
+                return;
+            }
+        };
+        // zkSync prices its own transaction type on a different schedule.
+        if tx.transaction_type.is_some_and(|t| t.as_u64() == ZKSYNC_EIP712_TX_TYPE as u64) {
+            return;
+        }
+        let intrinsic = intrinsic::of(&tx);
+        let used = gas_used.low_u64();
+        let delta = used as i64 - intrinsic as i64;
+        result.intrinsic_gas = Some(intrinsic);
+        result.gas_delta = Some(delta);
+        // Where L1 gas is in gasUsed, a transfer uses more than its intrinsic gas by design.
+        let no_code = match tx.to {
+            Some(_) if chains::counts_l1_gas(self.client.signer().chain_id()) => false,
+            Some(to) => self.client.get_code(to, None).await.is_ok_and(|code| code.is_empty()),
+            None => false,
+        };
+        let floor = intrinsic.max(intrinsic::floor_gas(&tx.input));
+        if let Some(a) = intrinsic::anomaly(used, intrinsic, floor, no_code) {
+            progress!(self, warn, "anomaly: {}", a);
+            result.anomalies.push(a);
+        }
+    }
+
+    /// Follow a mined transaction until it has `confirmations` blocks on top (counting its
+    /// own) and for `reorg_watch_blocks` blocks after its own, fetching its receipt again on
+    /// every new block: gone, or in a block with a different hash, means a reorg replaced the
+    /// block and the result becomes `reorged`.
+    async fn confirm(&self, result: &mut TxResult, label: &str) {
+        let (Some(hash), Some(mined)) = (result.tx_hash, result.block_number) else { return };
+        let last = mined + (self.confirmations - 1).max(self.reorg_watch_blocks);
+        let show = |seen: u64| {
+            if let Some(dashboard) = &self.dashboard {
+                dashboard.confirmed(label, result.tx_type, mined, seen.min(last) - mined + 1, last - mined + 1);
+            }
+        };
+        show(mined);
+        if last == mined {
+            return;
+        }
+        progress!(self, "watching until block {} ({} confirmations)", last, last - mined + 1);
+        let give_up_at = self.wait_limit().map(|l| Instant::now() + l);
+        let mut heads = self.heads.clone();
+        let mut seen = mined;
+        loop {
+            let head = self.current_block().await.unwrap_or(seen);
+            if head > seen {
+                seen = head;
+                show(seen);
+                match self.client.get_transaction_receipt(hash).await {
+                    Ok(Some(r)) if r.block_hash == result.block_hash => {}
+                    Ok(r) => {
+                        let depth = head - mined + 1;
+                        let now = match r.as_ref().and_then(|r| r.block_number) {
+                            Some(block) => format!("moved from block {} to {}", mined, block),
+                            None => format!("receipt from block {} is gone", mined),
+                        };
+                        progress!(self, warn, "reorged at depth {}: {}", depth, now);
+                        result.status = "reorged".into();
+                        result.error = Some(now);
+                        result.error_kind = Some(error_kind::ErrorKind::Reorged);
+                        result.reorg_depth = Some(depth);
+                        if let Some(r) = r {
+                            result.block_number = r.block_number.map(|n| n.as_u64());
+                            result.block_hash = r.block_hash;
+                        }
+                        return;
+                    }
+                    Err(e) => progress!(self, warn, "could not re-check receipt: {}", e),
+                }
+            }
+            if seen >= last {
+                progress!(self, "still in block {} at block {}", mined, seen);
+                return;
+            }
+            if self.grace_over() {
+                progress!(self, warn, "stopped watching at block {}: interrupted", seen);
+                return;
+            }
+            let left = give_up_at.map(|t| t.saturating_duration_since(Instant::now()));
+            if left.is_some_and(|l| l.is_zero()) {
+                let confirmed = seen - mined + 1;
+                progress!(self, warn, "block {} not reached in time ({} confirmations)", last, confirmed);
+                if confirmed < self.confirmations {
+                    result.anomalies.push(format!("only {} of {} confirmations in time", confirmed, self.confirmations));
+                }
+                return;
+            }
+            let next = async {
+                let pushed = match heads.as_mut() {
+                    Some(heads) => heads.changed().await.is_ok(),
+                    None => false,
+                };
+                if !pushed {
+                    sleep(self.client.provider().get_interval()).await;
+                }
+            };
+            match left {
+                Some(left) => {
+                    let _ = timeout(left, next).await;
+                }
+                None => next.await,
+            }
+        }
+    }
+
+    /// Ask the node's pool why a transaction that timed out is still not mined.
+    async fn inspect_pool(&self, result: &mut TxResult) {
+        let ("timeout", Some(hash)) = (result.status.as_str(), result.tx_hash) else { return };
+        match txpool::inspect(self.client.as_ref(), self.from, hash, result.nonce).await {
+            Ok(report) => {
+                progress!(self, warn, "txpool: {}", report);
+                result.txpool = Some(report);
+            }
+            Err(e) => progress!(self, warn, "txpool inspection unavailable: {}", e),
+        }
+    }
+
+    async fn trace(&self, result: &mut TxResult) {
+        let (true, Some(hash), Some(_)) = (self.trace, result.tx_hash, result.block_number) else { return };
+        match trace::trace(self.client.as_ref(), hash).await {
+            Ok(report) => {
+                progress!(self, "trace: {}", report);
+                result.trace = Some(report);
+            }
+            Err(e) => progress!(self, warn, "could not trace: {}", e),
+        }
+    }
+
+    /// Compare the mined tx and receipt with the second RPC endpoint, adding every
+    /// disagreement to the anomalies.
+    async fn cross_check(&self, result: &mut TxResult) {
+        let (Some(second), Some(hash), Some(_)) = (&self.second_rpc, result.tx_hash, result.block_number) else {
+            return;
+        };
+        match crosscheck::compare(self.client.as_ref(), second.as_ref(), hash).await {
+            Ok(discrepancies) => {
+                for d in discrepancies {
+                    progress!(self, warn, "second RPC disagrees: {}", d);
+                    result.anomalies.push(format!("second RPC: {}", d));
+                }
+            }
+            Err(e) => progress!(self, warn, "could not cross-check with the second RPC: {}", e),
+        }
+    }
+
+    /// Keep what was signed for [`compare_round_trip`](Self::compare_round_trip), logging
+    /// every field that `raw` does not decode back to.
+    fn record_signed(&self, signed: impl FnOnce() -> Signed, raw: &[u8]) {
+        if !self.round_trip {
+            return;
+        }
+        let signed = signed();
+        let mismatches = signed.decode_mismatches(raw);
+        for m in &mismatches {
+            progress!(self, warn, "round trip: {}", m);
+        }
+        self.signed.lock().unwrap().insert(signed.hash, (signed, mismatches));
+    }
+
+    /// Add the fields that did not decode back as signed to the anomalies and, for a mined
+    /// transaction, those the node's copy has differently.
+    async fn compare_round_trip(&self, result: &mut TxResult) {
+        let Some(hash) = result.tx_hash else { return };
+        let Some((signed, mismatches)) = self.signed.lock().unwrap().remove(&hash) else { return };
+        result.anomalies.extend(mismatches.into_iter().map(|m| format!("round trip: {}", m)));
+        if result.block_number.is_none() {
+            return;
+        }
+        match signed.node_mismatches(self.client.as_ref()).await {
+            Ok(mismatches) => {
+                for m in mismatches {
+                    progress!(self, warn, "node's copy differs: {}", m);
+                    result.anomalies.push(format!("node: {}", m));
+                }
+            }
+            Err(e) => progress!(self, warn, "could not compare with the node's copy: {}", e),
+        }
+    }
+
+    async fn code_size(&self, address: Address) -> Option<usize> {
+        match deploy::code_size(self.client.as_ref(), address).await {
+            Ok(size) => {
+                progress!(self, "deployed at {} ({} bytes of code)", format_address(address), size);
+                Some(size)
+            }
+            Err(e) => {
+                progress!(self, warn, "eth_getCode failed: {}", e);
+                None
+            }
+        }
+    }
+
+    async fn simulate(&self, tx: &TypedTransaction) -> Simulation {
+        if let Some(balance) = self.simulate_balance {
+            return self.simulate_funded(tx, balance).await;
+        }
+        let mut sim = Simulation { ok: true, estimated_gas: None, error: None };
+        if let Err(e) = self.client.call(tx, None).await {
+            sim.ok = false;
+            sim.error = Some(format!("eth_call: {}", e));
+        }
+        match self.client.estimate_gas(tx, None).await {
+            Ok(gas) => sim.estimated_gas = Some(gas),
+            Err(e) => {
+                sim.ok = false;
+                sim.error.get_or_insert_with(|| format!("eth_estimateGas: {}", e));
+            }
+        }
+        progress!(self, "simulation: {}", sim);
+        sim
+    }
+
+    /// [`simulate`](Self::simulate) as if the sender held `balance`. `eth_call` gives the
+    /// verdict; not every node takes overrides in `eth_estimateGas`, so a failed estimate
+    /// only leaves the gas unknown.
+    async fn simulate_funded(&self, tx: &TypedTransaction, balance: U256) -> Simulation {
+        let state = spoof::balance(self.from, balance);
+        let provider = self.client.provider();
+        let mut sim = Simulation { ok: true, estimated_gas: None, error: None };
+        if let Err(e) = provider.call_raw(tx).state(&state).await {
+            sim.ok = false;
+            sim.error = Some(format!("eth_call with balance override: {}", e));
+        }
+        let latest = BlockNumber::Latest;
+        match provider.request::<_, U256>("eth_estimateGas", (tx, latest, &state)).await {
+            Ok(gas) => sim.estimated_gas = Some(gas),
+            Err(e) => progress!(self, warn, "eth_estimateGas with balance override: {}", e),
+        }
+        progress!(self, "simulation: {}", sim);
+        sim
+    }
+
+    async fn send(&self, mut tx: TypedTransaction, label: &str) -> TxResult {
+        let tx_type = tx_type_of(&tx);
+        let mut latency = Latency::default();
+        let mut retries = 0;
+        let mut private = None;
+        let submitted = match self.fill_and_sign(&mut tx, &mut latency).await {
+            Ok(raw) => {
+                let hash = H256::from(keccak256(&raw));
+                self.submit(raw, hash, &mut latency, &mut retries, &mut private).await
+            }
+            Err(e) => Err(e),
+        };
+        let mut result = match submitted {
+            Ok(pending) => self.wait(tx_type, label, pending, latency).await,
+            Err(e) => {
+                progress!(self, warn, "submission failed: {}", e);
+                let mut result = TxResult::new(tx_type, "submit error").with_error(e);
+                result.latency = Some(latency);
+                result
+            }
+        };
+        result.retries = retries;
+        result.private = private;
+        result
+    }
+
+    /// Fill in nonce, gas and chain id (from the offline settings if set), then sign, timing
+    /// both phases. Pre-EIP-155 legacy transactions drop the chain id again before signing.
+    async fn fill_and_sign(&self, tx: &mut TypedTransaction, latency: &mut Latency) -> Result<Bytes> {
+        let started = Instant::now();
+        match self.offline_gas {
+            Some(gas) => {
+                if tx.nonce().is_none() {
+                    return Err(eyre!("offline signing needs a nonce manager"));
+                }
+                tx.set_from(self.from).set_chain_id(self.client.signer().chain_id()).set_gas(gas);
+            }
+            None => self.client.fill_transaction(tx, None).await?,
+        }
+        latency.build_ms = Some(started.elapsed().as_millis());
+        let started = Instant::now();
+        let sig = match tx {
+            // Without a chain id the legacy sighash is the pre-EIP-155 one; signers would put
+            // theirs back in, so sign the digest directly.
+            TypedTransaction::Legacy(legacy) if self.pre_eip155 => {
+                legacy.chain_id = None;
+                self.client.signer().sign_hash(tx.sighash())?
+            }
+            _ => self.client.signer().sign_transaction(tx).await.map_err(|e| eyre!("{}", e))?,
+        };
+        latency.sign_ms = Some(started.elapsed().as_millis());
+        let raw = tx.rlp_signed(&sig);
+        self.record_signed(|| Signed::typed(tx, self.from, keccak256(&raw).into()), &raw);
+        Ok(raw)
+    }
+
+    /// `eth_sendRawTransaction`, timed, retrying transient failures per the retry policy.
+    /// `hash` is the hash of `raw`, used when a retry finds the tx already in the pool.
+    async fn submit(
+        &self,
+        raw: Bytes,
+        hash: H256,
+        latency: &mut Latency,
+        retries: &mut u32,
+        private: &mut Option<PrivateReport>,
+    ) -> Result<PendingTransaction<'_, M::Provider>> {
+        if let Err(e) = self.hooks.pre_send(&raw, hash).await {
+            progress!(self, warn, "pre-send hook failed: {}", e);
+        }
+        let started = Instant::now();
+        if let Some(relay) = &self.private {
+            let block = self.client.get_block_number().await?.as_u64();
+            let (sent, report) = relay.submit(&raw, block).await;
+            progress!(self, "private: {}", report);
+            *private = Some(report);
+            let hash = sent?;
+            latency.submit_ms = Some(started.elapsed().as_millis());
+            return Ok(PendingTransaction::new(hash, self.client.provider()));
+        }
+        let pending = loop {
+            match self.client.send_raw_transaction(raw.clone()).await {
+                Ok(pending) => break pending,
+                Err(e) if *retries > 0 && retry::is_already_known(&e.to_string()) => {
+                    break PendingTransaction::new(hash, self.client.provider());
+                }
+                Err(e) if *retries < self.retry.max_retries && retry::is_transient(&e.to_string()) => {
+                    let delay = self.retry.delay(*retries);
+                    *retries += 1;
+                    progress!(self, warn, "transient submission error, retry {} in {:?}: {}", retries, delay, e);
+                    sleep(delay).await;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+        latency.submit_ms = Some(started.elapsed().as_millis());
+        Ok(pending)
+    }
+
+    /// Send a type ethers cannot build (blob, zkSync or a custom envelope), encoded by hand.
+    async fn send_envelope(&self, tx_type: u8, fees: Fees, nonce: Option<U256>, label: &str) -> TxResult {
+        let mut latency = Latency::default();
+        let (raw, hash) = match self.sign_envelope(tx_type, fees, nonce, &mut latency).await {
+            Ok(signed) => signed,
+            Err(e) => {
+                progress!(self, warn, "could not build type-{} tx: {}", tx_type, e);
+                return TxResult::new(tx_type, "build error").with_error(e);
+            }
+        };
+        let mut retries = 0;
+        let mut private = None;
+        let mut result = match self.submit(raw, hash, &mut latency, &mut retries, &mut private).await {
+            Ok(pending) => self.wait(tx_type, label, pending, latency).await,
+            Err(e) => {
+                progress!(self, warn, "submission failed: {}", e);
+                let mut result = TxResult::new(tx_type, "submit error").with_error(e);
+                result.latency = Some(latency);
+                result
+            }
+        };
+        result.retries = retries;
+        result.private = private;
+        result
+    }
+
+    async fn dry_run_typed(&self, mut tx: TypedTransaction) -> TxResult {
+        let tx_type = tx_type_of(&tx);
+        let mut latency = Latency::default();
+        match self.fill_and_sign(&mut tx, &mut latency).await {
+            Ok(raw) => {
+                let hash = H256::from(keccak256(&raw));
+                let mut result = self.signed(tx_type, raw, hash);
+                result.latency = Some(latency);
+                result
+            }
+            Err(e) => {
+                progress!(self, warn, "could not sign: {}", e);
+                TxResult::new(tx_type, "build error").with_error(e)
+            }
+        }
+    }
+
+    async fn dry_run_envelope(&self, tx_type: u8, fees: Fees, nonce: Option<U256>) -> TxResult {
+        let mut latency = Latency::default();
+        match self.sign_envelope(tx_type, fees, nonce, &mut latency).await {
+            Ok((raw, hash)) => {
+                let mut result = self.signed(tx_type, raw, hash);
+                result.latency = Some(latency);
+                result
+            }
+            Err(e) => {
+                progress!(self, warn, "could not build type-{} tx: {}", tx_type, e);
+                TxResult::new(tx_type, "build error").with_error(e)
+            }
+        }
+    }
+
+    fn signed(&self, tx_type: u8, raw: Bytes, hash: H256) -> TxResult {
+        progress!(self, "hash: 0x{:x}", hash);
+        progress!(self, "raw:  {}", raw);
+        let mut result = TxResult::new(tx_type, "signed");
+        result.tx_hash = Some(hash);
+        result.raw = Some(raw);
+        result
+    }
+
+    async fn sign_envelope(&self, tx_type: u8, fees: Fees, nonce: Option<U256>, latency: &mut Latency) -> Result<(Bytes, H256)> {
+        match self.builders.get(tx_type) {
+            Some(builder) if builder.custom_envelope() => self.sign_custom(builder.as_ref(), fees, nonce, latency).await,
+            _ if tx_type == ZKSYNC_EIP712_TX_TYPE => self.sign_eip712(fees, nonce, latency).await,
+            _ => self.sign_blob(fees, nonce, latency).await,
+        }
+    }
+
+    /// Build with a custom-envelope builder, then have it encode the transaction for the gas
+    /// estimate (its own fields may change what it costs), the signature and the network.
+    async fn sign_custom(&self, builder: &dyn TxTypeBuilder, fees: Fees, nonce: Option<U256>, latency: &mut Latency) -> Result<(Bytes, H256)> {
+        let started = Instant::now();
+        let (to, value, data) = self.sent_call().await?;
+        let mut tx = builder.build(self.from, to.unwrap_or_default(), value, fees)?;
+        if to.is_none() {
+            deploy::into_create(&mut tx);
+        }
+        if !data.is_empty() {
+            tx.set_data(data);
+        }
+        if let Some(n) = nonce {
+            tx.set_nonce(n);
+        }
+        match self.offline_gas {
+            Some(gas) => {
+                if tx.nonce().is_none() {
+                    return Err(eyre!("offline signing needs a nonce manager"));
+                }
+                tx.set_from(self.from).set_chain_id(self.client.signer().chain_id()).set_gas(gas);
+            }
+            None => {
+                let gas: U256 = self.client.provider().request("eth_estimateGas", [builder.request(&tx)?]).await?;
+                tx.set_gas(gas);
+                self.client.fill_transaction(&mut tx, None).await?;
+            }
+        }
+        latency.build_ms = Some(started.elapsed().as_millis());
+        let started = Instant::now();
+        let sig = self.client.signer().sign_hash(builder.sighash(&tx)?)?;
+        latency.sign_ms = Some(started.elapsed().as_millis());
+        let raw = builder.rlp_signed(&tx, &sig)?;
+        let hash = H256::from(keccak256(&raw));
+        Ok((raw, hash))
+    }
+
+    async fn sign_blob(&self, fees: Fees, nonce: Option<U256>, latency: &mut Latency) -> Result<(Bytes, H256)> {
+        let started = Instant::now();
+        let sidecar = Sidecar::build(&self.blob_source)?;
+        let (to, value, data) = self.sent_call().await?;
+        let to = to.ok_or_else(|| eyre!("blob transactions cannot create contracts"))?;
+        let gas = if let Some(gas) = self.offline_gas {
+            gas
+        } else if data.is_empty() {
+            BLOB_TX_GAS.into()
+        } else {
+            // Estimate the execution part as the equivalent type-2 call.
+            let mut probe = build_tx(2, self.from, to, value, fees)?;
+            probe.set_data(data.clone());
+            self.client.estimate_gas(&probe, None).await?
+        };
+        let nonce = match nonce {
+            Some(n) => n,
+            None => self.client.get_transaction_count(self.from, Some(BlockNumber::Pending.into())).await?,
+        };
+        let tx = BlobTransaction {
+            chain_id: self.client.signer().chain_id(),
+            nonce,
+            max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+            max_fee_per_gas: fees.max_fee_per_gas,
+            gas,
+            to,
+            value,
+            data,
+            max_fee_per_blob_gas: fees.max_fee_per_blob_gas,
+            blob_versioned_hashes: sidecar.versioned_hashes(),
+            ..Default::default()
+        };
+        latency.build_ms = Some(started.elapsed().as_millis());
+        let started = Instant::now();
+        let sig = self.client.signer().sign_hash(tx.sighash())?;
+        latency.sign_ms = Some(started.elapsed().as_millis());
+        let (raw, hash) = (tx.rlp_network(&sig, &sidecar), tx.hash(&sig));
+        self.record_signed(|| Signed::blob(&tx, self.from, hash), &raw);
+        Ok((raw, hash))
+    }
+
+    async fn sign_eip712(&self, fees: Fees, nonce: Option<U256>, latency: &mut Latency) -> Result<(Bytes, H256)> {
+        let started = Instant::now();
+        let (to, value, data) = self.sent_call().await?;
+        let to = to.ok_or_else(|| eyre!("type-0x71 deployments need factory deps, which are not supported"))?;
+        let nonce = match nonce {
+            Some(n) => n,
+            None => self.client.get_transaction_count(self.from, Some(BlockNumber::Pending.into())).await?,
+        };
+        let mut tx = Eip712Transaction {
+            chain_id: self.client.signer().chain_id(),
+            nonce,
+            from: self.from,
+            to,
+            max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+            max_fee_per_gas: fees.max_fee_per_gas,
+            value,
+            data,
+            meta: self.eip712_meta.clone(),
+            ..Default::default()
+        };
+        tx.gas = match self.offline_gas {
+            Some(gas) => gas,
+            None => zksync::estimate_gas(self.client.as_ref(), &tx).await?,
+        };
+        latency.build_ms = Some(started.elapsed().as_millis());
+        let started = Instant::now();
+        let sig = self.client.signer().sign_hash(tx.sighash())?;
+        latency.sign_ms = Some(started.elapsed().as_millis());
+        let (raw, hash) = (tx.rlp_signed(&sig), tx.hash(&sig));
+        self.record_signed(|| Signed::eip712(&tx, hash), &raw);
+        Ok((raw, hash))
+    }
+
+    fn log_submitted(&self, label: &str, tx_type: u8, hash: H256) {
+        if let Some(log) = &self.run_log {
+            log.submitted(label, tx_type, hash);
+        }
+        if let Some(dashboard) = &self.dashboard {
+            dashboard.submitted(label, tx_type, hash);
+        }
+    }
+
+    fn show_started(&self, label: &str, tx_type: u8) {
+        if let Some(dashboard) = &self.dashboard {
+            dashboard.started(label, tx_type);
+        }
+    }
+
+    /// ` <explorer url>` of `hash` for progress lines, or nothing.
+    fn link(&self, hash: H256) -> String {
+        self.explorer.as_deref().map(|e| format!(" {}", chains::tx_url(e, hash))).unwrap_or_default()
+    }
+
+    async fn wait(&self, tx_type: u8, label: &str, pending: PendingTransaction<'_, M::Provider>, latency: Latency) -> TxResult {
+        let tx_hash = pending.tx_hash();
+        progress!(self, "submitted: 0x{:x}{}", tx_hash, self.link(tx_hash));
+        self.log_submitted(label, tx_type, tx_hash);
+        let since = self.current_block().await;
+        let started = Instant::now();
+        let receipt = async {
+            match (&self.heads, &self.receipts) {
+                (Some(heads), _) => self.receipt_on_new_heads(tx_hash, heads.clone()).await,
+                (None, Some(receipts)) => receipts.receipt(tx_hash).await.map(Some),
+                (None, None) => pending.await.map_err(Into::into),
+            }
+        };
+        let limited = async {
+            match self.wait_limit() {
+                Some(limit) => timeout(limit, receipt).await.map_err(|_| {
+                    progress!(self, warn, "timed out after {:?}", limit);
+                    format!("no receipt after {:?}", limit)
+                }),
+                None => Ok(receipt.await),
+            }
+        };
+        let receipt = tokio::select! {
+            receipt = limited => receipt,
+            _ = self.interrupted() => {
+                progress!(self, warn, "gave up waiting: interrupted");
+                Err("interrupted before a receipt arrived".to_string())
+            }
+        };
+        let receipt = match receipt {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                let mut result = TxResult::new(tx_type, "timeout").with_error(e);
+                result.tx_hash = Some(tx_hash);
+                result.latency = Some(latency);
+                return result;
+            }
+        };
+        let mut result = self.receipt_result(tx_type, receipt);
+        result.tx_hash = Some(tx_hash);
+        result.latency = Some(self.included(latency, &result, started, since));
+        result
+    }
+
+    /// Resolves when the grace period after Ctrl-C is over; never without a shutdown handler.
+    async fn interrupted(&self) {
+        match &self.shutdown {
+            Some(shutdown) => shutdown.expired().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Whether in-flight waits should stop because the run was interrupted.
+    fn grace_over(&self) -> bool {
+        self.shutdown.as_ref().is_some_and(Shutdown::grace_over)
+    }
+
+    /// Latest block number, from the head subscription when there is one.
+    async fn current_block(&self) -> Option<u64> {
+        match &self.heads {
+            Some(heads) if *heads.borrow() > 0 => Some(*heads.borrow()),
+            _ => self.client.get_block_number().await.ok().map(|n| n.as_u64()),
+        }
+    }
+
+    /// Complete `latency` with the inclusion phase of a transaction submitted at `started`
+    /// while the chain was at block `since`.
+    fn included(&self, mut latency: Latency, result: &TxResult, started: Instant, since: Option<u64>) -> Latency {
+        if result.block_number.is_some() {
+            latency.inclusion_ms = Some(started.elapsed().as_millis());
+        }
+        if let (Some(mined), Some(since)) = (result.block_number, since) {
+            latency.blocks_waited = Some(mined.saturating_sub(since));
+        }
+        if latency.inclusion_ms.is_some() {
+            progress!(self, "latency: {}", latency);
+        }
+        latency
+    }
+
+    fn receipt_result(&self, tx_type: u8, receipt: Result<Option<TransactionReceipt>>) -> TxResult {
+        match receipt {
+            Ok(Some(r)) => {
+                let mut result = TxResult::mined(tx_type, &r);
+                progress!(
+                    self,
+                    "mined in block {} (status: {})",
+                    r.block_number.map(|n| n.to_string()).unwrap_or_else(|| "?".into()),
+                    result.status
+                );
+                match self.workload {
+                    Workload::Erc20 { token, amount } => {
+                        result.token_transfer =
+                            Some(erc20::transfer_event_matches(&r, token, self.safe.unwrap_or(self.from), self.to, amount));
+                    }
+                    Workload::Deploy => result.contract_address = r.contract_address,
+                    Workload::Native => {}
+                }
+                result
+            }
+            Ok(None) => {
+                progress!(self, "pending (no receipt yet)");
+                TxResult::new(tx_type, "pending")
+            }
+            Err(e) => {
+                progress!(self, warn, "error awaiting receipt: {}", e);
+                TxResult::new(tx_type, "await error").with_error(e)
+            }
+        }
+    }
+
+    /// Send `tx` and, while it stays unmined for `policy.after_blocks` blocks, rebroadcast it
+    /// with the same nonce and bumped fees.
+    async fn send_escalating(&self, mut tx: TypedTransaction, policy: EscalationPolicy, label: &str) -> TxResult {
+        let tx_type = tx_type_of(&tx);
+        // Pin nonce and gas so every rebroadcast replaces the same transaction.
+        let mut latency = Latency::default();
+        let mut retries = 0;
+        let mut private = None;
+        let first = match self.fill_and_sign(&mut tx, &mut latency).await {
+            Ok(raw) => {
+                let hash = H256::from(keccak256(&raw));
+                self.submit(raw, hash, &mut latency, &mut retries, &mut private).await.map(|p| p.tx_hash())
+            }
+            Err(e) => Err(e),
+        };
+        let mut hashes = match first {
+            Ok(hash) => vec![hash],
+            Err(e) => {
+                progress!(self, warn, "submission failed: {}", e);
+                let mut result = TxResult::new(tx_type, "submit error").with_error(e);
+                result.latency = Some(latency);
+                result.retries = retries;
+                result.private = private;
+                return result;
+            }
+        };
+        progress!(self, "submitted: 0x{:x}{}", hashes[0], self.link(hashes[0]));
+        self.log_submitted(label, tx_type, hashes[0]);
+        let started = Instant::now();
+        let submitted_at_block = self.current_block().await;
+
+        let give_up_at = self.wait_limit().map(|l| Instant::now() + l);
+        let mut heads = self.heads.clone();
+        let mut bumps = Vec::new();
+        let mut since = self.client.get_block_number().await.map(|n| n.as_u64()).unwrap_or_default();
+        let receipt = loop {
+            match self.find_receipt(&hashes).await {
+                Ok(None) => {}
+                found => break found,
+            }
+            let interrupted = self.grace_over();
+            if interrupted || give_up_at.is_some_and(|t| Instant::now() >= t) {
+                progress!(self, warn, "timed out after {} fee bump(s)", bumps.len());
+                let error = if interrupted { "interrupted before a receipt arrived" } else { "no receipt before the wait limit" };
+                let mut result = TxResult::new(tx_type, "timeout").with_error(error);
+                result.tx_hash = hashes.last().copied();
+                result.bumps = bumps;
+                result.latency = Some(latency);
+                result.retries = retries;
+                result.private = private;
+                return result;
+            }
+            let block = self.client.get_block_number().await.map(|n| n.as_u64()).unwrap_or(since);
+            if block >= since + policy.after_blocks && bumps.len() < policy.max_bumps as usize {
+                bump_fees(&mut tx, policy.bump_percent);
+                let mut bump = FeeBump::of(&tx, block);
+                // Timings stay those of the first attempt; the replacement goes out the same way.
+                let mut bump_latency = Latency::default();
+                let sent = match self.fill_and_sign(&mut tx, &mut bump_latency).await {
+                    Ok(raw) => {
+                        let hash = H256::from(keccak256(&raw));
+                        self.submit(raw, hash, &mut bump_latency, &mut retries, &mut private).await.map(|p| p.tx_hash())
+                    }
+                    Err(e) => Err(e),
+                };
+                match sent {
+                    Ok(hash) => {
+                        progress!(self, "bumped fees, resubmitted: 0x{:x}{}", hash, self.link(hash));
+                        self.log_submitted(label, tx_type, hash);
+                        bump.tx_hash = Some(hash);
+                        hashes.push(hash);
+                    }
+                    Err(e) => {
+                        progress!(self, warn, "fee bump rejected: {}", e);
+                        bump.error = Some(e.to_string());
+                    }
+                }
+                bumps.push(bump);
+                since = block;
+            }
+            self.tick(&mut heads).await;
+        };
+        let mut result = self.receipt_result(tx_type, receipt);
+        if result.tx_hash.is_none() {
+            result.tx_hash = hashes.last().copied();
+        }
+        result.bumps = bumps;
+        result.latency = Some(self.included(latency, &result, started, submitted_at_block));
+        result.retries = retries;
+        result.private = private;
+        result
+    }
+
+    /// Receipt of whichever of `hashes` (replacements of one another) got mined.
+    async fn find_receipt(&self, hashes: &[H256]) -> Result<Option<TransactionReceipt>> {
+        for hash in hashes {
+            if let Some(r) = self.client.get_transaction_receipt(*hash).await? {
+                return Ok(Some(r));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Wait for the next pushed block head, or one polling interval without a subscription.
+    async fn tick(&self, heads: &mut Option<watch::Receiver<u64>>) {
+        let interval = self.client.provider().get_interval();
+        match heads {
+            Some(rx) => {
+                if let Ok(Err(_)) = timeout(interval, rx.changed()).await {
+                    *heads = None;
+                }
+            }
+            None => tokio::time::sleep(interval).await,
+        }
+    }
+
+    async fn receipt_on_new_heads(
+        &self,
+        tx_hash: H256,
+        mut heads: watch::Receiver<u64>,
+    ) -> Result<Option<TransactionReceipt>> {
+        loop {
+            if let Some(r) = self.client.get_transaction_receipt(tx_hash).await? {
+                return Ok(Some(r));
+            }
+            // Subscription closed: nothing more will arrive.
+            if heads.changed().await.is_err() {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+pub(crate) fn tx_type_of(tx: &TypedTransaction) -> u8 {
+    match tx {
+        TypedTransaction::Legacy(_) => 0,
+        TypedTransaction::Eip2930(_) => 1,
+        TypedTransaction::Eip1559(_) => 2,
+    }
+}
+
+pub fn format_address(addr: Address) -> String {
+    let s = format!("0x{:x}", addr);
+    if s.len() > 12 {
+        format!("{}…{}", &s[..8], &s[s.len() - 4..])
+    } else {
+        s
+    }
+}
+
+pub fn format_gwei(v: U256) -> String {
+    // best-effort pretty formatting for logs only
+    let gwei = v / U256::exp10(9);
+    gwei.to_string()
+}
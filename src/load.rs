@@ -0,0 +1,299 @@
+//! Constant-rate load generation: submit transactions of one type at a fixed rate for a
+//! while, then summarize how many made it in and how long inclusion took.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::{Address, U256};
+use eyre::{eyre, Result};
+use futures::future::join_all;
+use serde::Serialize;
+use tokio::time::{interval, sleep, Instant, MissedTickBehavior};
+
+use crate::metrics::Metrics;
+use crate::nonce::NonceManager;
+use crate::{build_tx, Fees, HashSigner};
+
+/// Gas limit for a plain transfer, fixed so the generator does not estimate per send.
+const TRANSFER_GAS: u64 = 21_000;
+
+#[derive(Clone, Copy, Debug)]
+pub struct LoadConfig {
+    /// 0, 1 or 2.
+    pub tx_type: u8,
+    pub to: Address,
+    pub value: U256,
+    pub fees: Fees,
+    /// Transactions per second; must be positive and finite.
+    pub rate: f64,
+    /// How long to keep submitting.
+    pub duration: Duration,
+    /// How long to keep waiting for receipts after the last submission.
+    pub wait: Duration,
+}
+
+/// Outcome of one generated transaction.
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    submitted: bool,
+    included: bool,
+    succeeded: bool,
+    /// Submission → receipt observed.
+    latency: Option<Duration>,
+}
+
+const NOT_SUBMITTED: Sample = Sample { submitted: false, included: false, succeeded: false, latency: None };
+
+/// Statistics over a whole load run.
+#[derive(Clone, Debug, Serialize)]
+pub struct LoadReport {
+    pub tx_type: u8,
+    pub target_rate: f64,
+    pub duration_secs: f64,
+    /// Transactions the generator attempted.
+    pub attempted: usize,
+    /// Accepted by `eth_sendRawTransaction`.
+    pub submitted: usize,
+    /// Mined within the wait window.
+    pub included: usize,
+    /// Mined with status 1.
+    pub succeeded: usize,
+    /// `submitted / attempted`.
+    pub submit_success_rate: f64,
+    /// Included transactions per second of the submission window.
+    pub throughput: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_p50_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_p95_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_p99_ms: Option<u128>,
+    /// First few distinct submission errors.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |v: Option<u128>| v.map(|v| format!("{}ms", v)).unwrap_or_else(|| "-".into());
+        writeln!(f, "type-{} at {} tx/s for {:.0}s", self.tx_type, self.target_rate, self.duration_secs)?;
+        writeln!(
+            f,
+            "  attempted {}, submitted {} ({:.1}%), included {}, succeeded {}",
+            self.attempted,
+            self.submitted,
+            self.submit_success_rate * 100.0,
+            self.included,
+            self.succeeded
+        )?;
+        writeln!(f, "  throughput: {:.2} tx/s", self.throughput)?;
+        write!(
+            f,
+            "  inclusion latency: p50 {} p95 {} p99 {}",
+            ms(self.latency_p50_ms),
+            ms(self.latency_p95_ms),
+            ms(self.latency_p99_ms)
+        )?;
+        for e in &self.errors {
+            write!(f, "\n  error: {}", e)?;
+        }
+        Ok(())
+    }
+}
+
+/// Maximum distinct submission errors kept in the report.
+const MAX_ERRORS: usize = 5;
+
+/// Run the generator until `config.duration` passes, then wait for outstanding receipts.
+/// Fails before sending anything when `config.rate` gives no usable period between sends.
+pub async fn run<M, S>(
+    client: Arc<SignerMiddleware<M, S>>,
+    nonces: Arc<NonceManager>,
+    config: LoadConfig,
+    metrics: Option<Arc<Metrics>>,
+) -> Result<LoadReport>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let period = match Duration::try_from_secs_f64(1.0 / config.rate) {
+        Ok(period) if config.rate.is_finite() && !period.is_zero() => period,
+        _ => return Err(eyre!("rate {:e} tx/s gives no usable period between sends", config.rate)),
+    };
+    let started = Instant::now();
+    let stop_at = started + config.duration;
+    let give_up_at = stop_at + config.wait;
+    let mut ticker = interval(period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Burst);
+
+    let mut tasks = Vec::new();
+    loop {
+        ticker.tick().await;
+        if Instant::now() >= stop_at {
+            break;
+        }
+        let (client, nonces, metrics) = (client.clone(), nonces.clone(), metrics.clone());
+        tasks.push(tokio::spawn(async move {
+            let sample = generate(client, nonces, config, give_up_at).await;
+            if let Some(metrics) = &metrics {
+                let s = sample.as_ref().copied().unwrap_or(NOT_SUBMITTED);
+                metrics.observe(config.tx_type, s.submitted, s.included, s.succeeded, s.latency);
+            }
+            sample
+        }));
+    }
+    let window = started.elapsed();
+
+    let mut samples = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+    for outcome in join_all(tasks).await {
+        match outcome {
+            Ok(Ok(sample)) => samples.push(sample),
+            Ok(Err(e)) => {
+                if errors.len() < MAX_ERRORS && !errors.contains(&e) {
+                    errors.push(e);
+                }
+                samples.push(NOT_SUBMITTED);
+            }
+            Err(join) => {
+                if errors.len() < MAX_ERRORS {
+                    errors.push(join.to_string());
+                }
+                samples.push(NOT_SUBMITTED);
+            }
+        }
+    }
+    Ok(summarize(&config, window, &samples, errors))
+}
+
+/// Submit one transaction and poll for its receipt until `give_up_at`.
+async fn generate<M, S>(
+    client: Arc<SignerMiddleware<M, S>>,
+    nonces: Arc<NonceManager>,
+    config: LoadConfig,
+    give_up_at: Instant,
+) -> Result<Sample, String>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let mut tx =
+        build_tx(config.tx_type, client.address(), config.to, config.value, config.fees).map_err(|e| e.to_string())?;
+    let nonce = nonces.reserve();
+    tx.set_nonce(nonce).set_gas(TRANSFER_GAS);
+    let submitted_at = Instant::now();
+    let hash = match client.send_transaction(tx, None).await {
+        Ok(pending) => pending.tx_hash(),
+        Err(e) => {
+            nonces.release(nonce);
+            return Err(e.to_string());
+        }
+    };
+    let interval = client.provider().get_interval();
+    while Instant::now() < give_up_at {
+        if let Ok(Some(r)) = client.get_transaction_receipt(hash).await {
+            return Ok(Sample {
+                submitted: true,
+                included: true,
+                succeeded: r.status.is_some_and(|s| s.as_u64() == 1),
+                latency: Some(submitted_at.elapsed()),
+            });
+        }
+        sleep(interval).await;
+    }
+    Ok(Sample { submitted: true, included: false, succeeded: false, latency: None })
+}
+
+fn summarize(config: &LoadConfig, window: Duration, samples: &[Sample], errors: Vec<String>) -> LoadReport {
+    let attempted = samples.len();
+    let submitted = samples.iter().filter(|s| s.submitted).count();
+    let included = samples.iter().filter(|s| s.included).count();
+    let succeeded = samples.iter().filter(|s| s.succeeded).count();
+    let mut latencies: Vec<Duration> = samples.iter().filter_map(|s| s.latency).collect();
+    latencies.sort();
+    let percentile = |p: f64| {
+        let rank = ((p / 100.0) * latencies.len() as f64).ceil() as usize;
+        latencies.get(rank.saturating_sub(1)).map(|d| d.as_millis())
+    };
+    LoadReport {
+        tx_type: config.tx_type,
+        target_rate: config.rate,
+        duration_secs: window.as_secs_f64(),
+        attempted,
+        submitted,
+        included,
+        succeeded,
+        submit_success_rate: if attempted == 0 { 0.0 } else { submitted as f64 / attempted as f64 },
+        throughput: included as f64 / window.as_secs_f64().max(f64::MIN_POSITIVE),
+        latency_p50_ms: percentile(50.0),
+        latency_p95_ms: percentile(95.0),
+        latency_p99_ms: percentile(99.0),
+        errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> LoadConfig {
+        LoadConfig {
+            tx_type: 2,
+            to: Address::zero(),
+            value: U256::zero(),
+            fees: Fees::default(),
+            rate: 10.0,
+            duration: Duration::from_secs(10),
+            wait: Duration::from_secs(5),
+        }
+    }
+
+    fn mined(ms: u64) -> Sample {
+        Sample { submitted: true, included: true, succeeded: true, latency: Some(Duration::from_millis(ms)) }
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        // Shuffled 1..=100 ms.
+        let samples: Vec<Sample> = (0..100).map(|i| mined((i * 37) % 100 + 1)).collect();
+        let report = summarize(&config(), Duration::from_secs(10), &samples, Vec::new());
+        assert_eq!(report.latency_p50_ms, Some(50));
+        assert_eq!(report.latency_p95_ms, Some(95));
+        assert_eq!(report.latency_p99_ms, Some(99));
+        assert_eq!(report.throughput, 10.0);
+    }
+
+    #[test]
+    fn few_samples() {
+        let samples = [mined(30), mined(10), mined(20)];
+        let report = summarize(&config(), Duration::from_secs(1), &samples, Vec::new());
+        assert_eq!(report.latency_p50_ms, Some(20));
+        assert_eq!(report.latency_p95_ms, Some(30));
+        assert_eq!(report.latency_p99_ms, Some(30));
+    }
+
+    #[test]
+    fn counts_outcomes() {
+        let pending = Sample { submitted: true, included: false, succeeded: false, latency: None };
+        let reverted = Sample { succeeded: false, ..mined(40) };
+        let samples = [mined(10), reverted, pending, NOT_SUBMITTED];
+        let report = summarize(&config(), Duration::from_secs(2), &samples, vec!["nonce too low".into()]);
+        assert_eq!((report.attempted, report.submitted, report.included, report.succeeded), (4, 3, 2, 1));
+        assert_eq!(report.submit_success_rate, 0.75);
+        assert_eq!(report.throughput, 1.0);
+        assert_eq!(report.latency_p50_ms, Some(10));
+        assert_eq!(report.errors, ["nonce too low"]);
+    }
+
+    #[test]
+    fn no_samples() {
+        let report = summarize(&config(), Duration::ZERO, &[], Vec::new());
+        assert_eq!(report.attempted, 0);
+        assert_eq!(report.submit_success_rate, 0.0);
+        assert_eq!(report.latency_p50_ms, None);
+        assert!(report.throughput.is_finite());
+    }
+}
@@ -0,0 +1,120 @@
+//! Prometheus metrics for long-running matrix and load runs, served as plain text on
+//! `/metrics` by a minimal HTTP listener.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use eyre::Result;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::report::TxResult;
+
+/// Upper bounds (seconds) of the inclusion latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 10] = [0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0];
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    by_type: Mutex<BTreeMap<u8, TypeMetrics>>,
+}
+
+#[derive(Debug, Default)]
+struct TypeMetrics {
+    submitted: u64,
+    mined: u64,
+    failed: u64,
+    /// Cumulative counts per entry of `LATENCY_BUCKETS`.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: f64,
+    latency_count: u64,
+}
+
+impl Metrics {
+    /// Count one transaction. `failed` covers everything that did not end in a successful
+    /// receipt: rejections, reverts and timeouts.
+    pub fn observe(&self, tx_type: u8, submitted: bool, mined: bool, succeeded: bool, latency: Option<Duration>) {
+        let mut by_type = self.by_type.lock().unwrap();
+        let m = by_type.entry(tx_type).or_default();
+        m.submitted += submitted as u64;
+        m.mined += mined as u64;
+        m.failed += !succeeded as u64;
+        if let Some(latency) = latency {
+            let secs = latency.as_secs_f64();
+            for (bucket, le) in m.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+                *bucket += (secs <= le) as u64;
+            }
+            m.latency_sum += secs;
+            m.latency_count += 1;
+        }
+    }
+
+    /// Count a matrix result; dry-run results are ignored.
+    pub fn record(&self, r: &TxResult) {
+        if r.status == "signed" {
+            return;
+        }
+        let latency = r.latency.and_then(|l| l.inclusion_ms).map(|ms| Duration::from_millis(ms as u64));
+        self.observe(r.tx_type, r.tx_hash.is_some(), r.block_number.is_some(), r.status == "success", latency);
+    }
+
+    /// Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let by_type = self.by_type.lock().unwrap();
+        let mut out = String::new();
+        let counters = [
+            ("submitted", "Transactions accepted by the node."),
+            ("mined", "Transactions with a receipt."),
+            ("failed", "Transactions that did not end in a successful receipt."),
+        ];
+        for (i, (name, help)) in counters.into_iter().enumerate() {
+            let _ = writeln!(out, "# HELP test_transaction_{}_total {}", name, help);
+            let _ = writeln!(out, "# TYPE test_transaction_{}_total counter", name);
+            for (tx_type, m) in by_type.iter() {
+                let value = [m.submitted, m.mined, m.failed][i];
+                let _ = writeln!(out, "test_transaction_{}_total{{tx_type=\"{}\"}} {}", name, tx_type, value);
+            }
+        }
+        let name = "test_transaction_inclusion_latency_seconds";
+        let _ = writeln!(out, "# HELP {} Submission to receipt.", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (tx_type, m) in by_type.iter() {
+            for (count, le) in m.buckets.iter().zip(LATENCY_BUCKETS) {
+                let _ = writeln!(out, "{}_bucket{{tx_type=\"{}\",le=\"{}\"}} {}", name, tx_type, le, count);
+            }
+            let _ = writeln!(out, "{}_bucket{{tx_type=\"{}\",le=\"+Inf\"}} {}", name, tx_type, m.latency_count);
+            let _ = writeln!(out, "{}_sum{{tx_type=\"{}\"}} {}", name, tx_type, m.latency_sum);
+            let _ = writeln!(out, "{}_count{{tx_type=\"{}\"}} {}", name, tx_type, m.latency_count);
+        }
+        out
+    }
+}
+
+/// Serve `GET /metrics` on `addr` in a background task for the rest of the process.
+pub async fn serve(addr: SocketAddr, metrics: Arc<Metrics>) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let metrics = metrics.clone();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                let Ok(n) = stream.read(&mut buf).await else { return };
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let response = if request.starts_with("GET /metrics ") {
+                    let body = metrics.render();
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Reads batched into one `eth_call` through Multicall3's `aggregate3` when the chain has it
+//! deployed at its usual address, and made one by one otherwise, so runs with many senders
+//! spend fewer round trips on slow public RPCs.
+//!
+//! Only what the EVM can read goes through it: native balances (Multicall3's own
+//! `getEthBalance`) and view calls. Nonces, code and fee history have no contract getter and
+//! stay plain RPC calls.
+
+use eyre::{eyre, Result};
+use ethers::abi::{self, ParamType, Token};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionRequest, U256};
+use ethers::utils::id;
+use futures::future::{join_all, try_join_all};
+use tokio::sync::OnceCell;
+
+/// Multicall3's address, the same on nearly every chain.
+pub const MULTICALL3: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// Batches reads through Multicall3 once it is found on the chain. The check for the
+/// contract runs at the first batch of two or more reads and is remembered once it answers.
+#[derive(Debug)]
+pub struct Reader {
+    /// `None` when batching is turned off.
+    address: Option<Address>,
+    deployed: OnceCell<bool>,
+}
+
+impl Default for Reader {
+    fn default() -> Self {
+        Self::new(Some(MULTICALL3.parse().expect("valid address")))
+    }
+}
+
+impl Reader {
+    pub fn new(address: Option<Address>) -> Self {
+        Self { address, deployed: OnceCell::new() }
+    }
+
+    /// A reader that makes every read on its own.
+    pub fn disabled() -> Self {
+        Self::new(None)
+    }
+
+    /// The Multicall3 contract to batch through, if batching is on and it is deployed. A
+    /// failed lookup counts as not deployed this time and is tried again at the next batch.
+    pub async fn multicall<M: Middleware>(&self, client: &M) -> Option<Address> {
+        let address = self.address?;
+        let deployed = self
+            .deployed
+            .get_or_try_init(|| async { client.get_code(address, None).await.map(|code| !code.is_empty()) })
+            .await;
+        deployed.is_ok_and(|deployed| *deployed).then_some(address)
+    }
+
+    /// `eth_call` each `(to, data)` pair; a call that reverts fails on its own without
+    /// failing the others.
+    pub async fn calls<M: Middleware>(&self, client: &M, calls: &[(Address, Bytes)]) -> Result<Vec<Result<Bytes>>> {
+        if calls.len() < 2 {
+            return Ok(join_all(calls.iter().map(|(to, data)| call(client, *to, data.clone()))).await);
+        }
+        let Some(multicall) = self.multicall(client).await else {
+            return Ok(join_all(calls.iter().map(|(to, data)| call(client, *to, data.clone()))).await);
+        };
+        let batch: Vec<Token> = calls
+            .iter()
+            .map(|(to, data)| Token::Tuple(vec![Token::Address(*to), Token::Bool(true), Token::Bytes(data.to_vec())]))
+            .collect();
+        let mut input = id("aggregate3((address,bool,bytes)[])").to_vec();
+        input.extend(abi::encode(&[Token::Array(batch)]));
+        let out = call(client, multicall, input.into()).await?;
+        let decoded = abi::decode(&[ParamType::Array(Box::new(ParamType::Tuple(vec![ParamType::Bool, ParamType::Bytes])))], &out)
+            .map_err(|e| eyre!("decoding aggregate3: {}", e))?;
+        let Some(Token::Array(results)) = decoded.into_iter().next() else {
+            return Err(eyre!("aggregate3 returned no results"));
+        };
+        if results.len() != calls.len() {
+            return Err(eyre!("aggregate3 returned {} results for {} calls", results.len(), calls.len()));
+        }
+        Ok(results
+            .into_iter()
+            .zip(calls)
+            .map(|(result, (to, _))| match result {
+                Token::Tuple(fields) => match fields.as_slice() {
+                    [Token::Bool(true), Token::Bytes(data)] => Ok(Bytes::from(data.clone())),
+                    [Token::Bool(false), Token::Bytes(data)] => Err(eyre!("eth_call to {:?} reverted: {}", to, Bytes::from(data.clone()))),
+                    _ => Err(eyre!("unexpected aggregate3 result for {:?}", to)),
+                },
+                _ => Err(eyre!("unexpected aggregate3 result for {:?}", to)),
+            })
+            .collect())
+    }
+
+    /// Latest native balance of each of `addresses`, in order.
+    pub async fn balances<M: Middleware>(&self, client: &M, addresses: &[Address]) -> Result<Vec<U256>> {
+        let multicall = match addresses.len() {
+            0 | 1 => None,
+            _ => self.multicall(client).await,
+        };
+        let Some(multicall) = multicall else {
+            return try_join_all(addresses.iter().map(|address| async move {
+                client.get_balance(*address, None).await.map_err(|e| eyre!("eth_getBalance: {}", e))
+            }))
+            .await;
+        };
+        let calls: Vec<_> = addresses
+            .iter()
+            .map(|address| {
+                let mut data = id("getEthBalance(address)").to_vec();
+                data.extend(abi::encode(&[Token::Address(*address)]));
+                (multicall, Bytes::from(data))
+            })
+            .collect();
+        self.calls(client, &calls)
+            .await?
+            .into_iter()
+            .map(|out| {
+                let out = out?;
+                out.get(..32).map(U256::from_big_endian).ok_or_else(|| eyre!("getEthBalance returned {} bytes", out.len()))
+            })
+            .collect()
+    }
+}
+
+async fn call<M: Middleware>(client: &M, to: Address, data: Bytes) -> Result<Bytes> {
+    let tx: TypedTransaction = TransactionRequest::new().to(to).data(data).into();
+    client.call(&tx, None).await.map_err(|e| eyre!("eth_call to {:?}: {}", to, e))
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Mutex;
+
+use eyre::Result;
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockNumber, U256};
+use serde::Serialize;
+
+/// Hands out sequential nonces for a whole matrix run from a single starting fetch.
+///
+/// A nonce whose submission failed is released; the next reservation reuses it so a
+/// sequential run never leaves a hole. Released nonces below the highest reserved one are
+/// gaps: later transactions cannot be mined until they are filled.
+#[derive(Debug)]
+pub struct NonceManager {
+    address: Address,
+    start: U256,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    next: U256,
+    released: BTreeSet<U256>,
+}
+
+impl NonceManager {
+    /// Start from the account's pending transaction count.
+    pub async fn init<M: Middleware>(client: &M, address: Address) -> Result<Self>
+    where
+        M::Error: 'static,
+    {
+        let start = client.get_transaction_count(address, Some(BlockNumber::Pending.into())).await?;
+        Ok(Self::starting_at(address, start))
+    }
+
+    pub fn starting_at(address: Address, start: U256) -> Self {
+        Self { address, start, state: Mutex::new(State { next: start, released: BTreeSet::new() }) }
+    }
+
+    /// Reserve the next nonce, filling released ones first.
+    pub fn reserve(&self) -> U256 {
+        let mut state = self.state.lock().unwrap();
+        if let Some(n) = state.released.pop_first() {
+            return n;
+        }
+        let n = state.next;
+        state.next += U256::one();
+        n
+    }
+
+    /// Give back a nonce whose transaction never reached the mempool.
+    pub fn release(&self, nonce: U256) {
+        let state = &mut *self.state.lock().unwrap();
+        state.released.insert(nonce);
+        // Trailing releases are not gaps, just unused numbers.
+        while !state.next.is_zero() && state.released.remove(&(state.next - 1)) {
+            state.next -= U256::one();
+        }
+    }
+
+    /// Nonces that were released while a higher one is still in use.
+    pub fn gaps(&self) -> Vec<U256> {
+        self.state.lock().unwrap().released.iter().copied().collect()
+    }
+
+    /// Compare our bookkeeping with the node's view of the account.
+    pub async fn check<M: Middleware>(&self, client: &M) -> Result<NonceReport>
+    where
+        M::Error: 'static,
+    {
+        let latest = client.get_transaction_count(self.address, Some(BlockNumber::Latest.into())).await?;
+        let pending = client.get_transaction_count(self.address, Some(BlockNumber::Pending.into())).await?;
+        let next = self.state.lock().unwrap().next;
+        Ok(NonceReport { address: self.address, start: self.start, next, chain_latest: latest, chain_pending: pending, gaps: self.gaps() })
+    }
+}
+
+/// Nonce bookkeeping at the end of a run.
+#[derive(Clone, Debug, Serialize)]
+pub struct NonceReport {
+    pub address: Address,
+    /// First nonce handed out.
+    pub start: U256,
+    /// Nonce the next transaction would get.
+    pub next: U256,
+    /// Account nonce in the latest block.
+    pub chain_latest: U256,
+    /// Account nonce including the node's mempool.
+    pub chain_pending: U256,
+    /// Released nonces below `next` that leave later transactions unmineable.
+    pub gaps: Vec<U256>,
+}
+
+impl NonceReport {
+    /// Transactions we consider submitted that the node does not have in its pool.
+    pub fn missing(&self) -> bool {
+        self.chain_pending < self.next
+    }
+
+    /// Transactions still waiting to be mined.
+    pub fn stuck(&self) -> bool {
+        self.chain_latest < self.chain_pending
+    }
+}
+
+impl fmt::Display for NonceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "start={} next={} chain latest={} pending={}",
+            self.start, self.next, self.chain_latest, self.chain_pending
+        )?;
+        if !self.gaps.is_empty() {
+            let gaps: Vec<String> = self.gaps.iter().map(|n| n.to_string()).collect();
+            write!(f, " gaps=[{}]", gaps.join(","))?;
+        }
+        if self.missing() {
+            write!(f, " (node is missing {} of our txs)", self.next - self.chain_pending)?;
+        }
+        if self.stuck() {
+            write!(f, " ({} tx(s) pending)", self.chain_pending - self.chain_latest)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(start: u64) -> NonceManager {
+        NonceManager::starting_at(Address::zero(), start.into())
+    }
+
+    #[test]
+    fn reserves_sequentially() {
+        let nonces = manager(5);
+        assert_eq!([nonces.reserve(), nonces.reserve(), nonces.reserve()], [5.into(), 6.into(), 7.into()]);
+        assert!(nonces.gaps().is_empty());
+    }
+
+    #[test]
+    fn released_below_the_highest_is_a_gap() {
+        let nonces = manager(0);
+        let (a, b, _c) = (nonces.reserve(), nonces.reserve(), nonces.reserve());
+        nonces.release(b);
+        nonces.release(a);
+        assert_eq!(nonces.gaps(), [U256::from(0), U256::from(1)]);
+        // Gaps are filled first, lowest first.
+        assert_eq!(nonces.reserve(), a);
+        assert_eq!(nonces.gaps(), [b]);
+        assert_eq!(nonces.reserve(), b);
+        assert_eq!(nonces.reserve(), 3.into());
+    }
+
+    #[test]
+    fn trailing_releases_are_not_gaps() {
+        let nonces = manager(10);
+        let (a, b, c) = (nonces.reserve(), nonces.reserve(), nonces.reserve());
+        nonces.release(c);
+        assert!(nonces.gaps().is_empty());
+        assert_eq!(nonces.reserve(), c);
+        // Releasing the top collapses gaps that became trailing.
+        nonces.release(b);
+        assert_eq!(nonces.gaps(), [b]);
+        nonces.release(c);
+        assert!(nonces.gaps().is_empty());
+        nonces.release(a);
+        assert!(nonces.gaps().is_empty());
+        assert_eq!(nonces.reserve(), a);
+    }
+
+    #[test]
+    fn release_at_zero() {
+        let nonces = manager(0);
+        let first = nonces.reserve();
+        nonces.release(first);
+        assert!(nonces.gaps().is_empty());
+        assert_eq!(nonces.reserve(), first);
+    }
+}
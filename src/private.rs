@@ -0,0 +1,161 @@
+//! Private submission: signed transactions go to a relay instead of the public mempool, to
+//! compare how they are included. A Flashbots-style relay is simulated against first with
+//! `eth_callBundle`, then sent `eth_sendPrivateTransaction`, every request signed with an
+//! auth key (`X-Flashbots-Signature`); a Protect-style RPC just takes
+//! `eth_sendRawTransaction`.
+
+use std::fmt;
+
+use eyre::{eyre, Result};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Bytes, H256, U256};
+use ethers::utils::keccak256;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::transport::redact;
+
+/// Blocks a private transaction stays eligible for, Flashbots' own default.
+pub const DEFAULT_MAX_BLOCKS: u64 = 25;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelayApi {
+    /// `eth_callBundle` + `eth_sendPrivateTransaction`, authenticated.
+    Relay,
+    /// Plain `eth_sendRawTransaction` to a private RPC such as Flashbots Protect.
+    Protect,
+}
+
+#[derive(Clone, Debug)]
+pub struct PrivateRelay {
+    url: String,
+    api: RelayApi,
+    auth: LocalWallet,
+    max_blocks: u64,
+    http: reqwest::Client,
+}
+
+/// How a transaction went out privately.
+#[derive(Clone, Debug, Serialize)]
+pub struct PrivateReport {
+    pub relay: String,
+    /// Last block the relay may include the transaction in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_block_number: Option<u64>,
+    /// The relay's simulation of the transaction as a one-transaction bundle.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulation: Option<BundleSimulation>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BundleSimulation {
+    pub gas_used: U256,
+    /// Payment to the block builder, priority fee and direct transfers.
+    pub coinbase_diff: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl fmt::Display for PrivateReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "via {}", self.relay)?;
+        if let Some(block) = self.max_block_number {
+            write!(f, " until block {}", block)?;
+        }
+        if let Some(sim) = &self.simulation {
+            write!(f, ", simulated {} gas, {} wei to the builder", sim.gas_used, sim.coinbase_diff)?;
+            if let Some(e) = &sim.error {
+                write!(f, " ({})", e)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `eth_callBundle` result.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CallBundle {
+    total_gas_used: U256,
+    coinbase_diff: U256,
+    #[serde(default)]
+    results: Vec<CallResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallResult {
+    error: Option<String>,
+    revert: Option<String>,
+}
+
+impl PrivateRelay {
+    /// Relay at `url`; requests are signed with `auth` (a throwaway key if `None`, which
+    /// relays accept but give no reputation).
+    pub fn new(url: impl Into<String>, api: RelayApi, auth: Option<LocalWallet>) -> Self {
+        Self {
+            url: url.into(),
+            api,
+            auth: auth.unwrap_or_else(|| LocalWallet::new(&mut rand::thread_rng())),
+            max_blocks: DEFAULT_MAX_BLOCKS,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Blocks after the current one the transaction stays eligible for.
+    pub fn with_max_blocks(mut self, max_blocks: u64) -> Self {
+        self.max_blocks = max_blocks;
+        self
+    }
+
+    /// Submit `raw` while the chain is at `block`: simulated and sent privately through a
+    /// relay, or passed on as is to a Protect-style RPC. The report is returned even when
+    /// submission fails, so a failed simulation is not lost.
+    pub async fn submit(&self, raw: &Bytes, block: u64) -> (Result<H256>, PrivateReport) {
+        let mut report = PrivateReport { relay: redact(&self.url), max_block_number: None, simulation: None };
+        if self.api == RelayApi::Protect {
+            return (self.call("eth_sendRawTransaction", json!([raw])).await, report);
+        }
+        match self.simulate(raw, block + 1).await {
+            Ok(sim) => report.simulation = Some(sim),
+            Err(e) => return (Err(e), report),
+        }
+        let max_block = block + self.max_blocks;
+        report.max_block_number = Some(max_block);
+        let params = json!([{ "tx": raw, "maxBlockNumber": U256::from(max_block), "preferences": { "fast": true } }]);
+        (self.call("eth_sendPrivateTransaction", params).await, report)
+    }
+
+    /// `eth_callBundle` of `raw` alone, for inclusion in `block`.
+    async fn simulate(&self, raw: &Bytes, block: u64) -> Result<BundleSimulation> {
+        let params = json!([{ "txs": [raw], "blockNumber": U256::from(block), "stateBlockNumber": "latest" }]);
+        let bundle: CallBundle = self.call("eth_callBundle", params).await?;
+        let error = bundle.results.into_iter().find_map(|r| match (r.error, r.revert) {
+            (Some(e), Some(revert)) => Some(format!("{}: {}", e, revert)),
+            (e, revert) => e.or(revert),
+        });
+        Ok(BundleSimulation { gas_used: bundle.total_gas_used, coinbase_diff: bundle.coinbase_diff, error })
+    }
+
+    async fn call<R: DeserializeOwned>(&self, method: &str, params: Value) -> Result<R> {
+        let body = serde_json::to_string(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))?;
+        // Flashbots authenticates the hex of the body's hash, signed as a personal message.
+        let digest = format!("0x{}", ethers::utils::hex::encode(keccak256(&body)));
+        let sig = self.auth.sign_message(digest).await.map_err(|e| eyre!("signing the relay request: {}", e))?;
+        let response = self
+            .http
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .header("X-Flashbots-Signature", format!("{:?}:0x{}", self.auth.address(), sig))
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| eyre!("{} to {}: {}", method, redact(&self.url), e))?;
+        let reply: Value = response.json().await.map_err(|e| eyre!("{}: unreadable reply: {}", method, e))?;
+        if let Some(error) = reply.get("error") {
+            let message = error.get("message").and_then(Value::as_str).map(str::to_string).unwrap_or_else(|| error.to_string());
+            return Err(eyre!("{}: {}", method, message));
+        }
+        serde_json::from_value(reply.get("result").cloned().unwrap_or(Value::Null)).map_err(|e| eyre!("{}: {}", method, e))
+    }
+}
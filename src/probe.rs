@@ -0,0 +1,244 @@
+//! Read-only capability probe: query a node's fee and block data and predict which tx types
+//! and fee levels it should accept, without sending anything.
+
+use std::fmt;
+
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockNumber, Bytes, H256, U256};
+use eyre::{eyre, Result};
+use serde::Serialize;
+
+/// Tx types the matrix attempts, with the fork that introduced each.
+const TYPES: [(u8, &str); 6] =
+    [(0, "frontier"), (1, "berlin"), (2, "london"), (3, "cancun"), (4, "prague"), (5, "-")];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Support {
+    Yes,
+    No,
+    Unknown,
+}
+
+impl fmt::Display for Support {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Support::Yes => "yes",
+            Support::No => "no",
+            Support::Unknown => "?",
+        })
+    }
+}
+
+/// Forks inferred from the header fields of the latest block.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Forks {
+    /// `baseFeePerGas` present.
+    pub london: bool,
+    /// `withdrawalsRoot` present.
+    pub shanghai: bool,
+    /// `blobGasUsed` / `excessBlobGas` present.
+    pub cancun: bool,
+    /// `requestsHash` present.
+    pub prague: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TypeSupport {
+    pub tx_type: u8,
+    pub fork: &'static str,
+    /// Whether the chain should accept the envelope at all.
+    pub accepted: Support,
+    /// Whether it should accept it with every fee field set to 0.
+    pub zero_fee: Support,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<&'static str>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Probe {
+    pub chain_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_version: Option<String>,
+    pub block_number: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// Median priority fee of recent blocks from `eth_feeHistory`; `None` if unsupported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_history_reward: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob_base_fee: Option<U256>,
+    pub forks: Forks,
+    pub types: Vec<TypeSupport>,
+}
+
+/// Query the node. Only `eth_chainId` and the latest block are required; every other call
+/// is optional and simply left out when the node does not support it.
+pub async fn probe<M: Middleware>(client: &M) -> Result<Probe>
+where
+    M::Error: 'static,
+{
+    let chain_id = client.get_chainid().await.map_err(|e| eyre!("eth_chainId: {}", e))?.as_u64();
+    let block = client
+        .get_block(BlockNumber::Latest)
+        .await
+        .map_err(|e| eyre!("eth_getBlockByNumber: {}", e))?
+        .ok_or_else(|| eyre!("node returned no latest block"))?;
+    let client_version = client.client_version().await.ok();
+    let gas_price = client.get_gas_price().await.ok();
+    let max_priority_fee_per_gas = client.provider().request::<_, U256>("eth_maxPriorityFeePerGas", ()).await.ok();
+    let fee_history_reward = client.fee_history(5u64, BlockNumber::Latest, &[50.0]).await.ok().map(|h| {
+        let mut rewards: Vec<U256> = h.reward.iter().filter_map(|r| r.first().copied()).collect();
+        rewards.sort();
+        rewards.get(rewards.len() / 2).copied().unwrap_or_default()
+    });
+    let blob_base_fee = client.provider().request::<_, U256>("eth_blobBaseFee", ()).await.ok();
+
+    let forks = Forks {
+        london: block.base_fee_per_gas.is_some(),
+        shanghai: block.withdrawals_root.is_some(),
+        cancun: block.excess_blob_gas.is_some() || block.blob_gas_used.is_some(),
+        prague: block.other.contains_key("requestsHash"),
+    };
+    let base_fee = block.base_fee_per_gas.unwrap_or_default();
+    let types = TYPES
+        .iter()
+        .map(|&(tx_type, fork)| predict(tx_type, fork, &forks, base_fee, gas_price))
+        .collect();
+
+    Ok(Probe {
+        chain_id,
+        client_version,
+        block_number: block.number.map(|n| n.as_u64()).unwrap_or_default(),
+        base_fee_per_gas: block.base_fee_per_gas,
+        gas_price,
+        max_priority_fee_per_gas,
+        fee_history_reward,
+        blob_base_fee,
+        forks,
+        types,
+    })
+}
+
+/// Which node answered a run, recorded at its start so results can be attributed to a
+/// specific client build. Every field is best effort.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Fingerprint {
+    /// `web3_clientVersion`, e.g. `Geth/v1.14.11-stable/linux-amd64/go1.23.2`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_version: Option<String>,
+    /// `net_version`, the network id (not always the chain id).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub net_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_timestamp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<U256>,
+    /// Fee recipient of the latest block; a sequencer's own address on most L2s.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub miner: Option<Address>,
+    /// Often names the builder or sequencer software.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_data: Option<Bytes>,
+}
+
+/// Fingerprint the node; calls it does not answer are left out rather than failing.
+pub async fn fingerprint<M: Middleware>(client: &M) -> Fingerprint {
+    let client_version = client.client_version().await.ok();
+    let net_version = client.get_net_version().await.ok();
+    let mut fp = Fingerprint { client_version, net_version, ..Fingerprint::default() };
+    if let Ok(Some(block)) = client.get_block(BlockNumber::Latest).await {
+        fp.block_number = block.number.map(|n| n.as_u64());
+        fp.block_hash = block.hash;
+        fp.block_timestamp = Some(block.timestamp.as_u64());
+        fp.base_fee_per_gas = block.base_fee_per_gas;
+        fp.gas_limit = Some(block.gas_limit);
+        fp.miner = block.author;
+        fp.extra_data = Some(block.extra_data);
+    }
+    fp
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.client_version.as_deref().unwrap_or("unknown client"))?;
+        if let Some(net) = &self.net_version {
+            write!(f, " (net {})", net)?;
+        }
+        if let (Some(number), Some(hash)) = (self.block_number, self.block_hash) {
+            write!(f, " at block {} (0x{:x})", number, hash)?;
+        }
+        if let Some(gas_limit) = self.gas_limit {
+            write!(f, ", gas limit {}", gas_limit)?;
+        }
+        if let Some(base_fee) = self.base_fee_per_gas {
+            write!(f, ", base fee {}", base_fee)?;
+        }
+        Ok(())
+    }
+}
+
+fn predict(tx_type: u8, fork: &'static str, forks: &Forks, base_fee: U256, gas_price: Option<U256>) -> TypeSupport {
+    let yes_no = |b: bool| if b { Support::Yes } else { Support::No };
+    let (accepted, note) = match tx_type {
+        0 => (Support::Yes, None),
+        // Berlin has no header marker; any London chain has it.
+        1 => (if forks.london { Support::Yes } else { Support::Unknown }, None),
+        2 => (yes_no(forks.london), None),
+        3 => (yes_no(forks.cancun), None),
+        4 => (yes_no(forks.prague), forks.prague.then_some("this tool cannot build type 4 yet")),
+        _ => (Support::Unknown, Some("no known envelope")),
+    };
+    // A zero-fee tx needs a zero base fee (and EIP-4844 floors the blob base fee at 1 wei);
+    // a non-zero eth_gasPrice hints at a node minimum.
+    let zero_fee = if accepted == Support::No || !base_fee.is_zero() || tx_type == 3 {
+        Support::No
+    } else if gas_price.is_some_and(|p| p.is_zero()) {
+        accepted
+    } else {
+        Support::Unknown
+    };
+    TypeSupport { tx_type, fork, accepted, zero_fee, note }
+}
+
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let wei = |v: Option<U256>| v.map(|v| v.to_string()).unwrap_or_else(|| "-".into());
+        let flag = |b: bool| if b { "yes" } else { "no" };
+        writeln!(f, "Chain id:        {}", self.chain_id)?;
+        writeln!(f, "Client:          {}", self.client_version.as_deref().unwrap_or("-"))?;
+        writeln!(f, "Latest block:    {}", self.block_number)?;
+        writeln!(f, "Base fee:        {}", wei(self.base_fee_per_gas))?;
+        writeln!(f, "Gas price:       {}", wei(self.gas_price))?;
+        writeln!(f, "Priority fee:    {}", wei(self.max_priority_fee_per_gas))?;
+        writeln!(f, "Fee history:     {}", wei(self.fee_history_reward))?;
+        writeln!(f, "Blob base fee:   {}", wei(self.blob_base_fee))?;
+        writeln!(
+            f,
+            "Forks:           london={} shanghai={} cancun={} prague={}",
+            flag(self.forks.london),
+            flag(self.forks.shanghai),
+            flag(self.forks.cancun),
+            flag(self.forks.prague)
+        )?;
+        writeln!(f, "\ntype  fork       accepted  zero-fee")?;
+        for t in &self.types {
+            write!(f, "{:<5} {:<10} {:<9} {}", t.tx_type, t.fork, t.accepted.to_string(), t.zero_fee)?;
+            if let Some(note) = t.note {
+                write!(f, "  ({})", note)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+//! Receipts of many pending transactions polled together: one JSON-RPC batch of
+//! `eth_getTransactionReceipt` per tick for everything still waiting, instead of a request
+//! per transaction, to stay under public endpoints' rate limits when the matrix runs in
+//! parallel.
+//!
+//! Endpoints that do not take batches are polled one request per transaction, as before.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::providers::{JsonRpcClient, RpcError};
+use ethers::types::{TransactionReceipt, H256};
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+use tracing::warn;
+
+use crate::transport::{Transport, TransportError};
+
+/// Most receipts asked for in one batch; providers cap batch sizes.
+const MAX_BATCH: usize = 100;
+
+type Waiter = oneshot::Sender<Result<TransactionReceipt, String>>;
+
+/// Shared by every matrix of a run; polling stops once it is dropped.
+#[derive(Debug)]
+pub struct ReceiptBatcher {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    transport: Transport,
+    waiting: Mutex<HashMap<H256, Vec<Waiter>>>,
+    /// Set once the endpoint turned a batch down.
+    unbatched: AtomicBool,
+}
+
+impl ReceiptBatcher {
+    /// Start polling through `transport` every `interval`.
+    pub fn new(transport: Transport, interval: Duration) -> Self {
+        let inner = Arc::new(Inner { transport, waiting: Mutex::default(), unbatched: AtomicBool::new(false) });
+        let weak = Arc::downgrade(&inner);
+        tokio::spawn(poll_loop(weak, interval));
+        Self { inner }
+    }
+
+    /// Wait for the receipt of `hash`, mined in a block. Dropping the future stops asking.
+    pub async fn receipt(&self, hash: H256) -> Result<TransactionReceipt> {
+        let (tx, rx) = oneshot::channel();
+        self.inner.waiting.lock().unwrap().entry(hash).or_default().push(tx);
+        match rx.await {
+            Ok(receipt) => receipt.map_err(|e| eyre!("eth_getTransactionReceipt: {}", e)),
+            Err(_) => Err(eyre!("receipt polling stopped")),
+        }
+    }
+}
+
+async fn poll_loop(inner: Weak<Inner>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let Some(inner) = inner.upgrade() else { return };
+        inner.poll().await;
+    }
+}
+
+impl Inner {
+    async fn poll(&self) {
+        let hashes: Vec<H256> = {
+            let mut waiting = self.waiting.lock().unwrap();
+            // Waits that timed out or were interrupted have dropped their receiver.
+            waiting.retain(|_, waiters| {
+                waiters.retain(|w| !w.is_closed());
+                !waiters.is_empty()
+            });
+            waiting.keys().copied().collect()
+        };
+        for chunk in hashes.chunks(MAX_BATCH) {
+            if !self.unbatched.load(Ordering::Relaxed) {
+                let params: Vec<Value> = chunk.iter().map(|hash| json!([hash])).collect();
+                match self.transport.batch("eth_getTransactionReceipt", &params).await {
+                    Ok(Some(results)) => {
+                        for (hash, result) in chunk.iter().zip(results) {
+                            self.settle(*hash, result.map_err(|e| e.to_string()).and_then(receipt));
+                        }
+                        continue;
+                    }
+                    Ok(None) => {
+                        warn!("the RPC endpoint does not accept JSON-RPC batches; polling receipts one by one");
+                        self.unbatched.store(true, Ordering::Relaxed);
+                    }
+                    // Try again next tick, like a failed single poll.
+                    Err(e) => {
+                        warn!("{}", e);
+                        continue;
+                    }
+                }
+            }
+            for hash in chunk {
+                match self.transport.request::<_, Value>("eth_getTransactionReceipt", [hash]).await {
+                    Ok(value) => self.settle(*hash, receipt(value)),
+                    Err(e) if e.as_error_response().is_some() || matches!(e, TransportError::Budget(_)) => {
+                        self.settle(*hash, Err(e.to_string()))
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+
+    /// Hand a mined receipt, or an error the node returned for `hash`, to everyone waiting
+    /// for it; nothing happens while it is still pending.
+    fn settle(&self, hash: H256, outcome: Result<Option<TransactionReceipt>, String>) {
+        let outcome = match outcome {
+            Ok(None) => return,
+            Ok(Some(receipt)) => Ok(receipt),
+            Err(e) => Err(e),
+        };
+        for waiter in self.waiting.lock().unwrap().remove(&hash).unwrap_or_default() {
+            let _ = waiter.send(outcome.clone());
+        }
+    }
+}
+
+/// A receipt from a JSON-RPC result: `None` while there is none or it has no block yet.
+fn receipt(value: Value) -> Result<Option<TransactionReceipt>, String> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    let receipt: TransactionReceipt = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    Ok(receipt.block_number.is_some().then_some(receipt))
+}
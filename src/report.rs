@@ -0,0 +1,459 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ethers::types::{Address, Bytes, TransactionReceipt, H256, U256};
+use serde::Serialize;
+
+use crate::access_list::AccessListReport;
+use crate::basefee::BaseFeeReport;
+use crate::error_kind::ErrorKind;
+use crate::escalator::FeeBump;
+use crate::nonce::NonceReport;
+use crate::private::PrivateReport;
+use crate::probe::Fingerprint;
+use crate::trace::TraceReport;
+use crate::txpool::PoolReport;
+use crate::verify::FeeAudit;
+
+/// Outcome of a single tx type within a series.
+#[derive(Clone, Debug, Serialize)]
+pub struct TxResult {
+    pub tx_type: u8,
+    /// Short outcome: `success`, `failed`, `unknown`, `pending`, `timeout`, `signed` (dry run),
+    /// `reorged` (mined, then gone), `skipped` (interrupted before sending), `unsupported`,
+    /// `fee error`, `build error`, `submit error` or `await error`.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Category of `error`, stable across clients' wording.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ErrorKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    /// Nonce assigned by the run's nonce manager.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<H256>,
+    /// Blocks the chain had built on the transaction's block when a reorg replaced it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reorg_depth: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_gas_price: Option<U256>,
+    /// The charged `effectiveGasPrice` against the fees signed and the block's base fee.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_audit: Option<FeeAudit>,
+    /// Intrinsic gas of the mined transaction: base cost, calldata and access list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intrinsic_gas: Option<u64>,
+    /// `gas_used` minus `intrinsic_gas`: execution, or a chain's own surcharges.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_delta: Option<i64>,
+    /// Blob gas consumed, for type-3 transactions that were mined.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U256>,
+    /// Decoded revert reason of a `failed` transaction, replayed with `eth_call`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    /// Whether the receipt carries the expected ERC-20 `Transfer` event (ERC-20 workload only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_transfer: Option<bool>,
+    /// Address of the contract created by a deployment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<Address>,
+    /// Size of the code found at `contract_address` via `eth_getCode`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_size: Option<usize>,
+    /// Populated access list a type-1 transaction was sent with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_list: Option<AccessListReport>,
+    /// Pre-flight `eth_call` / `eth_estimateGas` verdict, when simulation is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulation: Option<Simulation>,
+    /// Gas breakdown and internal calls from `debug_traceTransaction`, when tracing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<TraceReport>,
+    /// Where a transaction left pending sits in the node's pool, from `txpool_*`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txpool: Option<PoolReport>,
+    /// Relay a privately submitted transaction went through, and its bundle simulation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private: Option<PrivateReport>,
+    /// Differences between what we sent and what the chain recorded or charged.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub anomalies: Vec<String>,
+    /// Time spent in each phase, from building the transaction to seeing its receipt.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<Latency>,
+    /// Endpoint that accepted the transaction, when several RPC URLs are configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc: Option<String>,
+    /// Block explorer page of the transaction, when the chain has a known explorer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explorer_url: Option<String>,
+    /// Resubmissions after transient RPC errors.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub retries: u32,
+    /// Fee bumps sent while the transaction was stuck.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bumps: Vec<FeeBump>,
+    /// Signed envelope, only kept for dry runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Bytes>,
+}
+
+impl TxResult {
+    pub fn new(tx_type: u8, status: impl Into<String>) -> Self {
+        Self {
+            tx_type,
+            status: status.into(),
+            error: None,
+            error_kind: None,
+            tx_hash: None,
+            nonce: None,
+            block_number: None,
+            block_hash: None,
+            reorg_depth: None,
+            gas_used: None,
+            effective_gas_price: None,
+            fee_audit: None,
+            intrinsic_gas: None,
+            gas_delta: None,
+            blob_gas_used: None,
+            revert_reason: None,
+            token_transfer: None,
+            contract_address: None,
+            code_size: None,
+            access_list: None,
+            simulation: None,
+            trace: None,
+            txpool: None,
+            private: None,
+            anomalies: Vec::new(),
+            latency: None,
+            rpc: None,
+            explorer_url: None,
+            retries: 0,
+            bumps: Vec::new(),
+            raw: None,
+        }
+    }
+
+    /// Status (`success`, `failed` or `unknown`) and receipt fields of a mined transaction.
+    pub fn mined(tx_type: u8, r: &TransactionReceipt) -> Self {
+        let status = r.status.map(|s| if s.as_u64() == 1 { "success" } else { "failed" }).unwrap_or("unknown");
+        let mut result = TxResult::new(tx_type, status);
+        result.tx_hash = Some(r.transaction_hash);
+        result.block_number = r.block_number.map(|n| n.as_u64());
+        result.block_hash = r.block_hash;
+        result.gas_used = r.gas_used;
+        result.effective_gas_price = r.effective_gas_price;
+        result.blob_gas_used = r.other.get_deserialized::<U256>("blobGasUsed").and_then(|v| v.ok());
+        result
+    }
+
+    /// Attach `error`, classified.
+    pub fn with_error(mut self, error: impl ToString) -> Self {
+        let error = error.to_string();
+        self.error_kind = Some(ErrorKind::classify(&error));
+        self.error = Some(error);
+        self
+    }
+}
+
+/// What the node said about a transaction before it was broadcast.
+#[derive(Clone, Debug, Serialize)]
+pub struct Simulation {
+    /// Both `eth_call` and `eth_estimateGas` accepted the transaction.
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl fmt::Display for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.error, self.estimated_gas) {
+            (Some(e), _) => write!(f, "rejected: {}", e),
+            (None, Some(gas)) => write!(f, "ok, gas {}", gas),
+            (None, None) => write!(f, "ok"),
+        }
+    }
+}
+
+/// Per-phase timings of one transaction, so RPC latency can be told apart from inclusion.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct Latency {
+    /// Filling nonce, gas and fees (or building the blob sidecar).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sign_ms: Option<u128>,
+    /// `eth_sendRawTransaction` round trip.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submit_ms: Option<u128>,
+    /// Accepted by the node → receipt seen.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inclusion_ms: Option<u128>,
+    /// Blocks between submission and inclusion.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_waited: Option<u64>,
+}
+
+impl fmt::Display for Latency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let phases = [
+            ("build", self.build_ms),
+            ("sign", self.sign_ms),
+            ("submit", self.submit_ms),
+            ("inclusion", self.inclusion_ms),
+        ];
+        let parts: Vec<String> =
+            phases.iter().filter_map(|(name, ms)| ms.map(|ms| format!("{} {}ms", name, ms))).collect();
+        write!(f, "{}", parts.join(", "))?;
+        if let Some(blocks) = self.blocks_waited {
+            write!(f, " ({} block(s))", blocks)?;
+        }
+        Ok(())
+    }
+}
+
+/// Results of running the matrix once at a given fee level.
+#[derive(Clone, Debug, Serialize)]
+pub struct SeriesReport {
+    pub label: String,
+    /// Fee point the series ran at, as given in `FEE_SERIES`.
+    pub fees: String,
+    /// Sender of this series, when the run uses several.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<Address>,
+    /// Recipient of this series, when the run fans out to several.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    pub results: Vec<TxResult>,
+}
+
+/// Everything a run produced, in the shape emitted by `--output json`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub chain_id: u64,
+    /// The node the run talked to, as it was when the run started; `None` offline.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<Fingerprint>,
+    pub from: Vec<Address>,
+    pub to: Vec<Address>,
+    pub value: U256,
+    pub series: Vec<SeriesReport>,
+    /// Nonce bookkeeping per sender.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub nonces: Vec<NonceReport>,
+    /// Base fee over the run, with `--track-base-fee`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee: Option<BaseFeeReport>,
+    /// JSON-RPC requests the run made to `RPC_URL`; `None` offline.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc_requests: Option<u64>,
+}
+
+impl Report {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// JUnit XML: one test suite per series and one test case per tx type. `success` and
+    /// `signed` pass, `unsupported` and `skipped` are skipped, everything else fails with the error as message.
+    pub fn to_junit(&self) -> String {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let skipped = |r: &TxResult| matches!(r.status.as_str(), "unsupported" | "skipped");
+        let failed = |r: &TxResult| !skipped(r) && !matches!(r.status.as_str(), "success" | "signed");
+        let total_failures = self.series.iter().flat_map(|s| &s.results).filter(|r| failed(r)).count();
+        let total: usize = self.series.iter().map(|s| s.results.len()).sum();
+        out.push_str(&format!(
+            "<testsuites name=\"test-transaction chain {}\" tests=\"{}\" failures=\"{}\">\n",
+            self.chain_id, total, total_failures
+        ));
+        for series in &self.series {
+            let failures = series.results.iter().filter(|r| failed(r)).count();
+            let skips = series.results.iter().filter(|r| skipped(r)).count();
+            out.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">\n",
+                xml_escape(&series.label),
+                series.results.len(),
+                failures,
+                skips
+            ));
+            if let Some(version) = self.node.as_ref().and_then(|n| n.client_version.as_deref()) {
+                out.push_str(&format!(
+                    "    <properties>\n      <property name=\"client_version\" value=\"{}\"/>\n    </properties>\n",
+                    xml_escape(version)
+                ));
+            }
+            for r in &series.results {
+                let time = r.latency.and_then(|l| l.inclusion_ms).map(|ms| ms as f64 / 1000.0).unwrap_or_default();
+                out.push_str(&format!(
+                    "    <testcase name=\"type-{}\" classname=\"{}\" time=\"{:.3}\"",
+                    r.tx_type,
+                    xml_escape(&series.label),
+                    time
+                ));
+                let message = xml_escape(r.error.as_deref().unwrap_or(&r.status));
+                if skipped(r) {
+                    out.push_str(&format!(">\n      <skipped message=\"{}\"/>\n    </testcase>\n", message));
+                } else if failed(r) {
+                    out.push_str(&format!(
+                        ">\n      <failure message=\"{}\" type=\"{}\"/>\n    </testcase>\n",
+                        message,
+                        xml_escape(&r.status)
+                    ));
+                } else {
+                    out.push_str("/>\n");
+                }
+            }
+            out.push_str("  </testsuite>\n");
+        }
+        out.push_str("</testsuites>");
+        out
+    }
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+/// Print the per-type outcome of a series.
+pub fn print_summary(label: &str, results: &[TxResult]) {
+    println!("\nSummary ({}):", label);
+    for r in results {
+        let mut line = format!("  type-{}: {}", r.tx_type, r.status);
+        if let Some(kind) = r.error_kind {
+            line.push_str(&format!(" [{}]", kind));
+        }
+        if let Some(e) = &r.error {
+            line.push_str(&format!(": {}", e));
+        }
+        if let Some(depth) = r.reorg_depth {
+            line.push_str(&format!(" (reorg depth {})", depth));
+        }
+        if let Some(reason) = &r.revert_reason {
+            line.push_str(&format!(" (reverted: {})", reason));
+        }
+        match r.token_transfer {
+            Some(true) => line.push_str(" [Transfer event: ok]"),
+            Some(false) => line.push_str(" [Transfer event: missing]"),
+            None => {}
+        }
+        if let Some(address) = r.contract_address {
+            let code = r.code_size.map(|n| format!("{} bytes of code", n)).unwrap_or_else(|| "code unknown".into());
+            line.push_str(&format!(" [deployed at {}, {}]", crate::format_address(address), code));
+        }
+        if let Some(list) = &r.access_list {
+            line.push_str(&format!(" [access list: {}]", list));
+        }
+        if let Some(sim) = &r.simulation {
+            line.push_str(&format!(" [sim: {}]", sim));
+        }
+        if let Some(rpc) = &r.rpc {
+            line.push_str(&format!(" (via {})", rpc));
+        }
+        if r.retries > 0 {
+            line.push_str(&format!(" (retried {}x)", r.retries));
+        }
+        if !r.bumps.is_empty() {
+            line.push_str(&format!(" (fees bumped {}x)", r.bumps.len()));
+        }
+        if let Some(delta) = r.gas_delta {
+            line.push_str(&format!(" (gas delta {:+})", delta));
+        }
+        if let Some(blob_gas) = r.blob_gas_used {
+            line.push_str(&format!(" (blob gas used: {})", blob_gas));
+        }
+        if let Some(latency) = &r.latency {
+            line.push_str(&format!(" [{}]", latency));
+        }
+        if !r.anomalies.is_empty() {
+            line.push_str(&format!(" [anomalies: {}]", r.anomalies.join("; ")));
+        }
+        println!("{}", line);
+        if let Some(url) = &r.explorer_url {
+            println!("    {}", url);
+        }
+        if let Some(trace) = &r.trace {
+            println!("    trace: {}", trace);
+        }
+        if let Some(pool) = &r.txpool {
+            println!("    txpool: {}", pool);
+        }
+        if let Some(private) = &r.private {
+            println!("    private: {}", private);
+        }
+    }
+}
+
+/// Print per-recipient outcome counts across all series of a fan-out run.
+pub fn print_recipient_totals(series: &[SeriesReport]) {
+    let mut totals: BTreeMap<Address, BTreeMap<&str, usize>> = BTreeMap::new();
+    for s in series {
+        let Some(to) = s.to else { continue };
+        let counts = totals.entry(to).or_default();
+        for r in &s.results {
+            *counts.entry(r.status.as_str()).or_default() += 1;
+        }
+    }
+    println!("\nRecipients:");
+    for (to, counts) in totals {
+        let counts: Vec<String> = counts.iter().map(|(status, n)| format!("{} {}", n, status)).collect();
+        println!("  {}: {}", crate::format_address(to), counts.join(", "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_xml_special_characters() {
+        assert_eq!(xml_escape(r#"a<b>&"c"'d'"#), "a&lt;b&gt;&amp;&quot;c&quot;&apos;d&apos;");
+        assert_eq!(xml_escape("&lt;"), "&amp;lt;");
+        assert_eq!(xml_escape("plain"), "plain");
+    }
+
+    #[test]
+    fn junit_escapes_labels_and_errors() {
+        let report = Report {
+            chain_id: 1,
+            node: None,
+            from: Vec::new(),
+            to: Vec::new(),
+            value: U256::zero(),
+            series: vec![SeriesReport {
+                label: "fees=<1 & 2>".into(),
+                fees: "1".into(),
+                from: None,
+                to: None,
+                results: vec![
+                    TxResult::new(0, "success"),
+                    TxResult::new(1, "unsupported"),
+                    TxResult::new(2, "rejected").with_error(r#"tx "0x1" < base fee"#),
+                ],
+            }],
+            nonces: Vec::new(),
+            base_fee: None,
+            rpc_requests: None,
+        };
+        let xml = report.to_junit();
+        assert!(xml.contains(r#"<testsuites name="test-transaction chain 1" tests="3" failures="1">"#), "{}", xml);
+        assert!(xml.contains(r#"<testsuite name="fees=&lt;1 &amp; 2&gt;" tests="3" failures="1" skipped="1">"#), "{}", xml);
+        assert!(xml.contains(r#"<skipped message="unsupported"/>"#), "{}", xml);
+        assert!(xml.contains(r#"<failure message="tx &quot;0x1&quot; &lt; base fee" type="rejected"/>"#), "{}", xml);
+        assert!(!xml.contains("<1 &"));
+    }
+}
@@ -0,0 +1,67 @@
+//! Retrying submissions that failed for reasons unrelated to the transaction itself.
+
+use std::time::Duration;
+
+use rand::Rng;
+
+/// How often and how patiently to retry a transient submission failure.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled on each further one.
+    pub base_delay: Duration,
+    /// Upper bound on a single delay.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_retries: 3, base_delay: Duration::from_millis(500), max_delay: Duration::from_secs(10) }
+    }
+}
+
+impl RetryPolicy {
+    /// Jittered exponential delay before retry number `retry` (0-based): uniformly between
+    /// half and all of `base_delay * 2^retry`, capped at `max_delay`.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let full = self.base_delay.saturating_mul(1 << retry.min(16)).min(self.max_delay);
+        let half = full / 2;
+        half + full.saturating_sub(half).mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+/// Error fragments of rate limiting, timeouts and connection trouble: the node may never have
+/// seen the transaction, so sending the same bytes again is safe.
+const TRANSIENT: [&str; 16] = [
+    "rate limit",
+    "too many requests",
+    "429",
+    "limit exceeded",
+    "timeout",
+    "timed out",
+    "connection reset",
+    "connection refused",
+    "connection closed",
+    "broken pipe",
+    "error sending request",
+    "502",
+    "503",
+    "504",
+    "service unavailable",
+    "temporarily unavailable",
+];
+
+/// Whether a submission error is worth retrying. Everything else (nonce too low, underpriced,
+/// invalid signature, ...) is a verdict on the transaction and final.
+pub fn is_transient(error: &str) -> bool {
+    let error = error.to_ascii_lowercase();
+    TRANSIENT.iter().any(|t| error.contains(t))
+}
+
+/// The node already has the transaction, e.g. because an earlier attempt reached it even
+/// though its response was lost.
+pub fn is_already_known(error: &str) -> bool {
+    let error = error.to_ascii_lowercase();
+    error.contains("already known") || error.contains("known transaction") || error.contains("already imported")
+}
@@ -0,0 +1,46 @@
+//! Decoding of revert data returned by `eth_call`.
+
+use ethers::abi::{self, ParamType, Token};
+
+/// Selector of `Error(string)`, used by `require` and `revert("…")`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`, used by failed asserts and checked arithmetic.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Human-readable revert reason: the `Error(string)` message, the panic code, or the selector
+/// of a custom error.
+pub fn decode(data: &[u8]) -> String {
+    let Some((selector, args)) = data.split_first_chunk::<4>() else {
+        return if data.is_empty() { "reverted without reason".into() } else { format!("0x{}", hex(data)) };
+    };
+    match *selector {
+        ERROR_SELECTOR => match abi::decode(&[ParamType::String], args).as_deref() {
+            Ok([Token::String(msg)]) => msg.clone(),
+            _ => format!("malformed Error(string): 0x{}", hex(data)),
+        },
+        PANIC_SELECTOR => match abi::decode(&[ParamType::Uint(256)], args).as_deref() {
+            Ok([Token::Uint(code)]) => format!("panic 0x{:x} ({})", code, panic_meaning(code.low_u64())),
+            _ => format!("malformed Panic(uint256): 0x{}", hex(data)),
+        },
+        _ => format!("custom error 0x{} ({} bytes of arguments)", hex(selector), args.len()),
+    }
+}
+
+fn panic_meaning(code: u64) -> &'static str {
+    match code {
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow",
+        0x12 => "division by zero",
+        0x21 => "invalid enum value",
+        0x22 => "bad storage encoding",
+        0x31 => "pop on empty array",
+        0x32 => "index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to zero function",
+        _ => "unknown",
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,251 @@
+//! Round-trip checks of our own encoding: every transaction as built is compared field by
+//! field with its raw bytes decoded back, and once mined with the node's
+//! `eth_getTransactionByHash` view, so a field lost or mangled on the way (by our encoder,
+//! the node's decoder or its RPC output) shows up as an anomaly.
+
+use std::fmt;
+
+use eyre::{eyre, Result};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip2930::AccessList;
+use ethers::types::{Address, Bytes, H256, U256, U64};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::blob::{BlobTransaction, BLOB_TX_TYPE};
+use crate::decode::{self, DecodedTx};
+use crate::tx_type_of;
+use crate::zksync::{Eip712Transaction, ZKSYNC_EIP712_TX_TYPE};
+
+/// Fields of a transaction as it was signed. Unset fees and access lists are ones its type
+/// does not have, and are not compared.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Signed {
+    pub tx_type: u8,
+    pub hash: H256,
+    pub from: Address,
+    pub chain_id: Option<u64>,
+    pub nonce: U256,
+    pub gas_limit: U256,
+    pub gas_price: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub max_fee_per_blob_gas: Option<U256>,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub input: Bytes,
+    pub access_list: Option<AccessList>,
+    pub blob_versioned_hashes: Vec<H256>,
+}
+
+impl Signed {
+    /// A filled and signed type 0, 1 or 2 transaction.
+    pub fn typed(tx: &TypedTransaction, from: Address, hash: H256) -> Self {
+        let (gas_price, max_priority_fee_per_gas, max_fee_per_gas) = match tx {
+            TypedTransaction::Eip1559(inner) => (None, inner.max_priority_fee_per_gas, inner.max_fee_per_gas),
+            _ => (tx.gas_price(), None, None),
+        };
+        Self {
+            tx_type: tx_type_of(tx),
+            hash,
+            from,
+            chain_id: tx.chain_id().map(|c| c.as_u64()),
+            nonce: tx.nonce().copied().unwrap_or_default(),
+            gas_limit: tx.gas().copied().unwrap_or_default(),
+            gas_price,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            max_fee_per_blob_gas: None,
+            to: tx.to_addr().copied(),
+            value: tx.value().copied().unwrap_or_default(),
+            input: tx.data().cloned().unwrap_or_default(),
+            access_list: tx.access_list().cloned(),
+            blob_versioned_hashes: Vec::new(),
+        }
+    }
+
+    pub fn blob(tx: &BlobTransaction, from: Address, hash: H256) -> Self {
+        Self {
+            tx_type: BLOB_TX_TYPE,
+            hash,
+            from,
+            chain_id: Some(tx.chain_id),
+            nonce: tx.nonce,
+            gas_limit: tx.gas,
+            gas_price: None,
+            max_priority_fee_per_gas: Some(tx.max_priority_fee_per_gas),
+            max_fee_per_gas: Some(tx.max_fee_per_gas),
+            max_fee_per_blob_gas: Some(tx.max_fee_per_blob_gas),
+            to: Some(tx.to),
+            value: tx.value,
+            input: tx.data.clone(),
+            access_list: Some(tx.access_list.clone()),
+            blob_versioned_hashes: tx.blob_versioned_hashes.clone(),
+        }
+    }
+
+    pub fn eip712(tx: &Eip712Transaction, hash: H256) -> Self {
+        Self {
+            tx_type: ZKSYNC_EIP712_TX_TYPE,
+            hash,
+            from: tx.from,
+            chain_id: Some(tx.chain_id),
+            nonce: tx.nonce,
+            gas_limit: tx.gas,
+            gas_price: None,
+            max_priority_fee_per_gas: Some(tx.max_priority_fee_per_gas),
+            max_fee_per_gas: Some(tx.max_fee_per_gas),
+            max_fee_per_blob_gas: None,
+            to: Some(tx.to),
+            value: tx.value,
+            input: tx.data.clone(),
+            access_list: None,
+            blob_versioned_hashes: Vec::new(),
+        }
+    }
+
+    fn decoded(tx: DecodedTx) -> Self {
+        Self {
+            tx_type: tx.tx_type,
+            hash: tx.hash,
+            from: tx.from.unwrap_or_default(),
+            chain_id: tx.chain_id,
+            nonce: tx.nonce,
+            gas_limit: tx.gas_limit,
+            gas_price: tx.gas_price,
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+            max_fee_per_gas: tx.max_fee_per_gas,
+            max_fee_per_blob_gas: tx.max_fee_per_blob_gas,
+            to: tx.to,
+            value: tx.value,
+            input: tx.input,
+            access_list: tx.access_list,
+            blob_versioned_hashes: tx.blob_versioned_hashes,
+        }
+    }
+
+    /// The transaction as `eth_getTransactionByHash` returned it. Fields it leaves out are
+    /// unset, `type` defaults to legacy.
+    fn from_node(tx: &Value) -> Result<Self> {
+        Ok(Self {
+            tx_type: field::<U64>(tx, "type")?.map(|t| t.as_u64() as u8).unwrap_or_default(),
+            hash: field(tx, "hash")?.unwrap_or_default(),
+            from: field(tx, "from")?.unwrap_or_default(),
+            chain_id: field::<U64>(tx, "chainId")?.map(|c| c.as_u64()),
+            nonce: field(tx, "nonce")?.unwrap_or_default(),
+            gas_limit: field(tx, "gas")?.unwrap_or_default(),
+            gas_price: field(tx, "gasPrice")?,
+            max_priority_fee_per_gas: field(tx, "maxPriorityFeePerGas")?,
+            max_fee_per_gas: field(tx, "maxFeePerGas")?,
+            max_fee_per_blob_gas: field(tx, "maxFeePerBlobGas")?,
+            to: field(tx, "to")?,
+            value: field(tx, "value")?.unwrap_or_default(),
+            input: field(tx, "input")?.unwrap_or_default(),
+            access_list: field(tx, "accessList")?,
+            blob_versioned_hashes: field(tx, "blobVersionedHashes")?.unwrap_or_default(),
+        })
+    }
+
+    /// Decode `raw`, our own encoding of this transaction, and list every field that does
+    /// not come back as signed, along with anything wrong with the signature.
+    pub fn decode_mismatches(&self, raw: &[u8]) -> Vec<String> {
+        let decoded = match decode::decode(raw) {
+            Ok(decoded) => decoded,
+            Err(e) => return vec![format!("our encoding does not decode: {}", e)],
+        };
+        let mut mismatches: Vec<String> = decoded.problems.iter().map(|p| format!("signature: {}", p)).collect();
+        mismatches.extend(self.differences(&Self::decoded(decoded)).into_iter().map(|d| d.to_string()));
+        mismatches
+    }
+
+    /// Fetch the node's copy of the transaction and list every field that differs from what
+    /// was signed. The gas price of a type-2 or later transaction is what it was charged,
+    /// not a signed field, so it is not compared.
+    pub async fn node_mismatches<M: Middleware>(&self, client: &M) -> Result<Vec<String>> {
+        let tx: Value = client
+            .provider()
+            .request("eth_getTransactionByHash", json!([self.hash]))
+            .await
+            .map_err(|e| eyre!("eth_getTransactionByHash: {}", e))?;
+        if tx.is_null() {
+            return Err(eyre!("the node does not know the transaction"));
+        }
+        let node = Self::from_node(&tx)?;
+        Ok(self.differences(&node).into_iter().map(|d| d.to_string()).collect())
+    }
+
+    /// Fields of `other` that differ from this one's, skipping those this one leaves unset.
+    fn differences(&self, other: &Signed) -> Vec<Difference> {
+        let mut out = Vec::new();
+        let mut compare = |field: &'static str, ours: Option<String>, theirs: Option<String>| {
+            if let Some(ours) = ours
+                && Some(&ours) != theirs.as_ref()
+            {
+                out.push(Difference { field, ours, theirs });
+            }
+        };
+        let number = |v: Option<U256>| v.map(|v| v.to_string());
+        let access_list = |l: &Option<AccessList>| l.as_ref().map(|l| serde_json::to_string(l).unwrap_or_default());
+        compare("type", Some(self.tx_type.to_string()), Some(other.tx_type.to_string()));
+        compare("hash", Some(format!("{:?}", self.hash)), Some(format!("{:?}", other.hash)));
+        compare("from", Some(format!("{:?}", self.from)), Some(format!("{:?}", other.from)));
+        compare("chainId", self.chain_id.map(|c| c.to_string()), other.chain_id.map(|c| c.to_string()));
+        compare("nonce", number(Some(self.nonce)), number(Some(other.nonce)));
+        compare("gas", number(Some(self.gas_limit)), number(Some(other.gas_limit)));
+        compare("gasPrice", number(self.gas_price), number(other.gas_price));
+        compare("maxPriorityFeePerGas", number(self.max_priority_fee_per_gas), number(other.max_priority_fee_per_gas));
+        compare("maxFeePerGas", number(self.max_fee_per_gas), number(other.max_fee_per_gas));
+        compare("maxFeePerBlobGas", number(self.max_fee_per_blob_gas), number(other.max_fee_per_blob_gas));
+        compare(
+            "to",
+            Some(self.to.map_or("none".into(), |a| format!("{:?}", a))),
+            Some(other.to.map_or("none".into(), |a| format!("{:?}", a))),
+        );
+        compare("value", number(Some(self.value)), number(Some(other.value)));
+        compare("input", Some(self.input.to_string()), Some(other.input.to_string()));
+        compare("accessList", access_list(&self.access_list), access_list(&other.access_list));
+        if !self.blob_versioned_hashes.is_empty() {
+            compare(
+                "blobVersionedHashes",
+                Some(format!("{:?}", self.blob_versioned_hashes)),
+                Some(format!("{:?}", other.blob_versioned_hashes)),
+            );
+        }
+        out
+    }
+}
+
+/// One field that did not survive the round trip.
+struct Difference {
+    field: &'static str,
+    ours: String,
+    /// `None` when the other side does not have the field at all.
+    theirs: Option<String>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.theirs {
+            Some(theirs) => write!(f, "{}: sent {}, got {}", self.field, truncated(&self.ours), truncated(theirs)),
+            None => write!(f, "{}: sent {}, got nothing", self.field, truncated(&self.ours)),
+        }
+    }
+}
+
+/// Long calldata and access lists cut to their start for the log.
+fn truncated(s: &str) -> String {
+    const MAX: usize = 80;
+    match s.char_indices().nth(MAX) {
+        Some((i, _)) => format!("{}… ({} chars)", &s[..i], s.len()),
+        None => s.to_string(),
+    }
+}
+
+/// `key` of `tx`, `None` when missing or null.
+fn field<T: DeserializeOwned>(tx: &Value, key: &str) -> Result<Option<T>> {
+    match tx.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => serde_json::from_value(v.clone()).map(Some).map_err(|e| eyre!("{}: {}", key, e)),
+    }
+}
@@ -0,0 +1,174 @@
+//! Run files: what a `send` run has put in flight (nonces, hashes, outcomes), rewritten as it
+//! goes so `--resume <run-id>` can wait for the receipts of an interrupted run instead of
+//! sending again and colliding on nonces.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use eyre::{eyre, Result};
+use ethers::types::{H256, U256};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::report::TxResult;
+
+/// One matrix entry, keyed by series label and tx type.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub series: String,
+    pub tx_type: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U256>,
+    /// Latest hash submitted for the entry (the last fee bump, when escalating).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    /// Final status; unset while the transaction may still be mined.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+}
+
+impl Entry {
+    /// Submitted, but with no final status yet.
+    pub fn in_flight(&self) -> bool {
+        self.tx_hash.is_some() && self.status.is_none()
+    }
+
+    /// The recorded outcome of a settled entry.
+    pub fn result(&self) -> TxResult {
+        let mut result = TxResult::new(self.tx_type, self.status.clone().unwrap_or_default());
+        result.error = self.error.clone();
+        result.tx_hash = self.tx_hash;
+        result.nonce = self.nonce;
+        result.block_number = self.block_number;
+        result
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RunFile {
+    chain_id: u64,
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+pub struct RunLog {
+    id: String,
+    path: PathBuf,
+    file: Mutex<RunFile>,
+}
+
+impl RunLog {
+    /// Start a new run file in `dir`, named after the current time.
+    pub fn create(dir: &Path, chain_id: u64) -> Result<Self> {
+        std::fs::create_dir_all(dir).map_err(|e| eyre!("creating {}: {}", dir.display(), e))?;
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let (mut id, mut n) = (secs.to_string(), 1);
+        while run_path(dir, &id).exists() {
+            n += 1;
+            id = format!("{}-{}", secs, n);
+        }
+        let log = Self { path: run_path(dir, &id), id, file: Mutex::new(RunFile { chain_id, entries: Vec::new() }) };
+        log.save(&log.file.lock().unwrap())?;
+        Ok(log)
+    }
+
+    /// Reopen the run file of run `id` in `dir`.
+    pub fn open(dir: &Path, id: &str) -> Result<Self> {
+        let path = run_path(dir, id);
+        let json = std::fs::read_to_string(&path).map_err(|e| eyre!("no run {} ({}): {}", id, path.display(), e))?;
+        let file: RunFile = serde_json::from_str(&json).map_err(|e| eyre!("parsing {}: {}", path.display(), e))?;
+        Ok(Self { id: id.to_string(), path, file: Mutex::new(file) })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.file.lock().unwrap().chain_id
+    }
+
+    pub fn entry(&self, series: &str, tx_type: u8) -> Option<Entry> {
+        let file = self.file.lock().unwrap();
+        file.entries.iter().find(|e| e.series == series && e.tx_type == tx_type).cloned()
+    }
+
+    /// Number of entries submitted without a final status.
+    pub fn in_flight(&self) -> usize {
+        self.file.lock().unwrap().entries.iter().filter(|e| e.in_flight()).count()
+    }
+
+    /// `nonce` was assigned to the entry.
+    pub fn reserved(&self, series: &str, tx_type: u8, nonce: U256) {
+        self.update(series, tx_type, |e| e.nonce = Some(nonce));
+    }
+
+    /// `hash` was accepted by the node for the entry.
+    pub fn submitted(&self, series: &str, tx_type: u8, hash: H256) {
+        self.update(series, tx_type, |e| e.tx_hash = Some(hash));
+    }
+
+    /// The entry ended with `result`. A timed-out transaction stays in flight, and a skipped
+    /// entry stays unstarted, so a resumed run picks them up.
+    pub fn finished(&self, series: &str, result: &TxResult) {
+        if result.status == "skipped" {
+            return;
+        }
+        self.update(series, result.tx_type, |e| {
+            e.nonce = result.nonce.or(e.nonce);
+            e.tx_hash = result.tx_hash.or(e.tx_hash);
+            if result.status == "timeout" && e.tx_hash.is_some() {
+                return;
+            }
+            e.status = Some(result.status.clone());
+            e.error = result.error.clone();
+            e.block_number = result.block_number;
+        });
+    }
+
+    /// Delete the run file, once nothing is left to resume.
+    pub fn remove(&self) -> Result<()> {
+        std::fs::remove_file(&self.path).map_err(|e| eyre!("removing {}: {}", self.path.display(), e))
+    }
+
+    fn update(&self, series: &str, tx_type: u8, change: impl FnOnce(&mut Entry)) {
+        let mut file = self.file.lock().unwrap();
+        let i = match file.entries.iter().position(|e| e.series == series && e.tx_type == tx_type) {
+            Some(i) => i,
+            None => {
+                file.entries.push(Entry {
+                    series: series.to_string(),
+                    tx_type,
+                    nonce: None,
+                    tx_hash: None,
+                    status: None,
+                    error: None,
+                    block_number: None,
+                });
+                file.entries.len() - 1
+            }
+        };
+        change(&mut file.entries[i]);
+        // A stale run file only costs a resume; it must not fail the run.
+        if let Err(e) = self.save(&file) {
+            warn!("could not update run file: {}", e);
+        }
+    }
+
+    /// Write through a temporary file so an interrupted write never leaves half a file.
+    fn save(&self, file: &RunFile) -> Result<()> {
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(file)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+fn run_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
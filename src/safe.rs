@@ -0,0 +1,148 @@
+//! Sending through a Safe (v1.3 or later) with a single owner's signature: the matrix
+//! transaction goes to the Safe's `execTransaction`, which makes the actual call with the
+//! Safe's own funds, so the same tx types and fee levels are covered for a Safe treasury.
+//!
+//! With `safeTxGas` and `gasPrice` both zero, `execTransaction` reverts when the inner call
+//! fails, so the receipt status stands for both.
+
+use eyre::{eyre, Result};
+use ethers::abi::{self, Token};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, Signature, TransactionRequest, H256, U256};
+use ethers::utils::{id, keccak256};
+
+use crate::multicall::Reader;
+
+/// `Enum.Operation.Call`; delegate calls are not used.
+const OPERATION_CALL: u8 = 0;
+
+/// `execTransaction` calldata for `to`, `value` and `data`, carrying `sig`, the owner's
+/// signature of [`tx_hash`].
+pub fn exec_calldata(to: Address, value: U256, data: &Bytes, sig: &Signature) -> Bytes {
+    // r, s and v packed as Safe expects them, v being 27 or 28 for a signed digest.
+    let mut signature = sig.to_vec();
+    if signature[64] < 27 {
+        signature[64] += 27;
+    }
+    let mut calldata =
+        id("execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)").to_vec();
+    calldata.extend(abi::encode(&[
+        Token::Address(to),
+        Token::Uint(value),
+        Token::Bytes(data.to_vec()),
+        Token::Uint(OPERATION_CALL.into()),
+        // safeTxGas, baseGas and gasPrice: no refund, the sender pays the gas.
+        Token::Uint(U256::zero()),
+        Token::Uint(U256::zero()),
+        Token::Uint(U256::zero()),
+        Token::Address(Address::zero()),
+        Token::Address(Address::zero()),
+        Token::Bytes(signature),
+    ]));
+    calldata.into()
+}
+
+/// EIP-712 hash of the Safe transaction an owner signs, with no refund and Safe's v1.3+
+/// domain (chain id and the Safe's address).
+pub fn tx_hash(chain_id: u64, safe: Address, to: Address, value: U256, data: &Bytes, nonce: U256) -> H256 {
+    let domain = keccak256(abi::encode(&[
+        Token::FixedBytes(keccak256("EIP712Domain(uint256 chainId,address verifyingContract)").to_vec()),
+        Token::Uint(chain_id.into()),
+        Token::Address(safe),
+    ]));
+    let safe_tx = keccak256(abi::encode(&[
+        Token::FixedBytes(
+            keccak256(
+                "SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,uint256 baseGas,\
+                 uint256 gasPrice,address gasToken,address refundReceiver,uint256 nonce)",
+            )
+            .to_vec(),
+        ),
+        Token::Address(to),
+        Token::Uint(value),
+        Token::FixedBytes(keccak256(data).to_vec()),
+        Token::Uint(OPERATION_CALL.into()),
+        Token::Uint(U256::zero()),
+        Token::Uint(U256::zero()),
+        Token::Uint(U256::zero()),
+        Token::Address(Address::zero()),
+        Token::Address(Address::zero()),
+        Token::Uint(nonce),
+    ]));
+    let mut message = vec![0x19, 0x01];
+    message.extend_from_slice(&domain);
+    message.extend_from_slice(&safe_tx);
+    keccak256(message).into()
+}
+
+/// The Safe's next transaction nonce.
+pub async fn nonce<M: Middleware>(client: &M, safe: Address) -> Result<U256> {
+    let out = call(client, safe, id("nonce()").to_vec()).await?;
+    word(&out).map_err(|e| eyre!("nonce(): {}", e))
+}
+
+/// Check that each of `owners` can execute alone: it is an owner and the threshold is 1.
+/// The reads go out as one batch when `reader` can batch them.
+pub async fn check_owners<M: Middleware>(reader: &Reader, client: &M, safe: Address, owners: &[Address]) -> Result<()> {
+    let mut calls = vec![(safe, Bytes::from(id("getThreshold()").to_vec()))];
+    for owner in owners {
+        let mut data = id("isOwner(address)").to_vec();
+        data.extend(abi::encode(&[Token::Address(*owner)]));
+        calls.push((safe, data.into()));
+    }
+    let mut results = reader.calls(client, &calls).await?.into_iter();
+    let threshold = results
+        .next()
+        .unwrap_or_else(|| Err(eyre!("no result")))
+        .and_then(|out| word(&out))
+        .map_err(|e| eyre!("{:?} does not look like a Safe: getThreshold(): {}", safe, e))?;
+    if threshold != U256::one() {
+        return Err(eyre!("the Safe {:?} needs {} signatures; only single-owner Safes are supported", safe, threshold));
+    }
+    for (owner, result) in owners.iter().zip(results) {
+        if word(&result?)?.is_zero() {
+            return Err(eyre!("{:?} is not an owner of the Safe {:?}", owner, safe));
+        }
+    }
+    Ok(())
+}
+
+async fn call<M: Middleware>(client: &M, to: Address, data: Vec<u8>) -> Result<Bytes> {
+    let tx: TypedTransaction = TransactionRequest::new().to(to).data(data).into();
+    client.call(&tx, None).await.map_err(|e| eyre!("eth_call to {:?}: {}", to, e))
+}
+
+fn word(out: &[u8]) -> Result<U256> {
+    out.get(..32).map(U256::from_big_endian).ok_or_else(|| eyre!("returned {} bytes", out.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tx_hash_matches_a_known_vector() {
+        let safe: Address = "0x5afe5afe5afe5afe5afe5afe5afe5afe5afe5afe".parse().unwrap();
+        let to: Address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".parse().unwrap();
+        let data = Bytes::from(vec![0xde, 0xad, 0xbe, 0xef]);
+        let hash = tx_hash(11155111, safe, to, U256::exp10(18), &data, 7.into());
+        let expected: H256 = "0x4902be34d0ce6107ddf6212cc16ee465642c6e73949afa0e8f728e1b31cc0267".parse().unwrap();
+        assert_eq!(hash, expected);
+        assert_ne!(tx_hash(1, safe, to, U256::exp10(18), &data, 7.into()), expected);
+        assert_ne!(tx_hash(11155111, safe, to, U256::exp10(18), &data, 8.into()), expected);
+    }
+
+    #[test]
+    fn exec_calldata_carries_a_27_or_28_v() {
+        let sig = Signature { r: U256::from(1), s: U256::from(2), v: 1 };
+        let calldata = exec_calldata(Address::zero(), U256::zero(), &Bytes::new(), &sig);
+        // execTransaction's well-known selector.
+        assert_eq!(calldata[..4], [0x6a, 0x76, 0x12, 0x02]);
+        // The 65-byte signature is the last dynamic argument, padded to 96 bytes.
+        let signature = &calldata[calldata.len() - 96..calldata.len() - 31];
+        assert_eq!(signature[31], 1);
+        assert_eq!(signature[63], 2);
+        assert_eq!(signature[64], 28);
+    }
+}
@@ -0,0 +1,151 @@
+//! Send transfers carrying ever larger calldata at one fee level to find where a chain (or its
+//! mempool) stops accepting them: a transaction size cap, the block gas limit, or fees.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockNumber, Bytes, H256, U256};
+use rand::RngCore;
+use serde::Serialize;
+
+use super::wait_for_receipt;
+use crate::{build_tx, Fees, HashSigner};
+
+/// Gas per calldata byte assumed when the node cannot estimate: the EIP-7623 floor price of a
+/// non-zero byte, which random payloads almost exclusively consist of.
+const GAS_PER_BYTE: u64 = 40;
+
+/// A calldata length: plain bytes, or with a `kb` / `mb` suffix (binary multiples).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DataSize(pub usize);
+
+impl FromStr for DataSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        let (amount, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(i) => s.split_at(i),
+            None => (s.as_str(), "b"),
+        };
+        let scale = match unit {
+            "b" => 1,
+            "k" | "kb" => 1 << 10,
+            "m" | "mb" => 1 << 20,
+            other => return Err(format!("unknown size unit '{}'", other)),
+        };
+        let amount: usize = amount.trim().parse().map_err(|e| format!("invalid size '{}': {}", s, e))?;
+        Ok(DataSize(amount * scale))
+    }
+}
+
+impl fmt::Display for DataSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            n if n > 0 && n % (1 << 20) == 0 => write!(f, "{}MB", n >> 20),
+            n if n > 0 && n % (1 << 10) == 0 => write!(f, "{}KB", n >> 10),
+            n => write!(f, "{}B", n),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SizeOutcome {
+    pub tx_type: u8,
+    /// Calldata length in bytes.
+    pub size: usize,
+    /// Gas limit the transaction was (or would have been) sent with.
+    pub gas: U256,
+    /// `mined`, `reverted`, `rejected`, `not mined`, or `over block gas limit` (not sent).
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Why `eth_estimateGas` failed, in which case `gas` is our own upper bound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+}
+
+impl SizeOutcome {
+    pub fn accepted(&self) -> bool {
+        self.status == "mined"
+    }
+}
+
+/// Send one transfer of `tx_type` (0, 1 or 2) with `size` random calldata bytes and wait up to
+/// `wait` for it to be mined. Transactions whose gas would exceed the latest block's gas limit
+/// are not sent.
+pub async fn run<M, S>(
+    client: &SignerMiddleware<M, S>,
+    tx_type: u8,
+    to: Address,
+    fees: Fees,
+    size: usize,
+    wait: Duration,
+) -> Result<SizeOutcome>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let from = client.address();
+    let mut data = vec![0u8; size];
+    rand::thread_rng().fill_bytes(&mut data);
+    let mut tx = build_tx(tx_type, from, to, U256::zero(), fees)?;
+    tx.set_data(Bytes::from(data));
+
+    let mut outcome = SizeOutcome {
+        tx_type,
+        size,
+        gas: U256::zero(),
+        status: "rejected".into(),
+        error: None,
+        estimate_error: None,
+        tx_hash: None,
+        gas_used: None,
+        block_number: None,
+    };
+    outcome.gas = match client.estimate_gas(&tx, None).await {
+        Ok(gas) => gas,
+        Err(e) => {
+            outcome.estimate_error = Some(e.to_string());
+            U256::from(21_000 + GAS_PER_BYTE * size as u64)
+        }
+    };
+    let block = client
+        .get_block(BlockNumber::Latest)
+        .await
+        .map_err(|e| eyre!("eth_getBlockByNumber: {}", e))?
+        .ok_or_else(|| eyre!("node returned no latest block"))?;
+    if outcome.gas > block.gas_limit {
+        outcome.status = "over block gas limit".into();
+        outcome.error = Some(format!("needs {} gas, blocks hold {}", outcome.gas, block.gas_limit));
+        return Ok(outcome);
+    }
+    tx.set_gas(outcome.gas);
+
+    let hash = match client.send_transaction(tx, None).await {
+        Ok(pending) => pending.tx_hash(),
+        Err(e) => {
+            outcome.error = Some(e.to_string());
+            return Ok(outcome);
+        }
+    };
+    outcome.tx_hash = Some(hash);
+    outcome.status = "not mined".into();
+
+    if let Some(r) = wait_for_receipt(client, hash, wait).await? {
+        outcome.status = if r.status == Some(1.into()) { "mined" } else { "reverted" }.into();
+        outcome.gas_used = r.gas_used;
+        outcome.block_number = r.block_number.map(|n| n.as_u64());
+    }
+    Ok(outcome)
+}
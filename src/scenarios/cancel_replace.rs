@@ -0,0 +1,115 @@
+//! Submit an underpriced transfer, then try to cancel it with a 0-value self-transfer that
+//! reuses its nonce at higher fees, and see which of the two the chain mines.
+
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockNumber, TransactionReceipt, H256, U256};
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::{build_tx, Fees, HashSigner};
+
+/// Gas limit for a plain transfer, fixed so the replacement only differs in fees.
+const TRANSFER_GAS: u64 = 21_000;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CancelReplaceOutcome {
+    pub tx_type: u8,
+    pub nonce: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replacement_error: Option<String>,
+    /// `replacement`, `original`, or `none` if neither was mined in time.
+    pub mined: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+}
+
+impl CancelReplaceOutcome {
+    /// The chain honored the replacement: the cancel got mined instead of the original.
+    pub fn honored(&self) -> bool {
+        self.mined == "replacement"
+    }
+}
+
+/// Run the scenario for one tx type (0, 1 or 2), waiting up to `wait` for either tx to mine.
+pub async fn run<M, S>(
+    client: &SignerMiddleware<M, S>,
+    tx_type: u8,
+    to: Address,
+    value: U256,
+    underpriced: Fees,
+    replacement_fees: Fees,
+    wait: Duration,
+) -> Result<CancelReplaceOutcome>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let from = client.address();
+    let nonce = client.get_transaction_count(from, Some(BlockNumber::Pending.into())).await?;
+    let mut outcome = CancelReplaceOutcome {
+        tx_type,
+        nonce,
+        original: None,
+        original_error: None,
+        replacement: None,
+        replacement_error: None,
+        mined: "none".into(),
+        block_number: None,
+    };
+
+    let mut original = build_tx(tx_type, from, to, value, underpriced)?;
+    original.set_nonce(nonce).set_gas(TRANSFER_GAS);
+    match client.send_transaction(original, None).await {
+        Ok(pending) => outcome.original = Some(pending.tx_hash()),
+        Err(e) => {
+            // Nothing in the pool to replace.
+            outcome.original_error = Some(e.to_string());
+            return Ok(outcome);
+        }
+    }
+
+    let mut cancel = build_tx(tx_type, from, from, U256::zero(), replacement_fees)?;
+    cancel.set_nonce(nonce).set_gas(TRANSFER_GAS);
+    match client.send_transaction(cancel, None).await {
+        Ok(pending) => outcome.replacement = Some(pending.tx_hash()),
+        Err(e) => outcome.replacement_error = Some(e.to_string()),
+    }
+
+    let give_up_at = Instant::now() + wait;
+    let interval = client.provider().get_interval();
+    while Instant::now() < give_up_at {
+        if let Some(r) = receipt(client, outcome.replacement).await? {
+            outcome.mined = "replacement".into();
+            outcome.block_number = r.block_number.map(|n| n.as_u64());
+            break;
+        }
+        if let Some(r) = receipt(client, outcome.original).await? {
+            outcome.mined = "original".into();
+            outcome.block_number = r.block_number.map(|n| n.as_u64());
+            break;
+        }
+        tokio::time::sleep(interval).await;
+    }
+    Ok(outcome)
+}
+
+async fn receipt<M, S>(client: &SignerMiddleware<M, S>, hash: Option<H256>) -> Result<Option<TransactionReceipt>>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    match hash {
+        Some(h) => client.get_transaction_receipt(h).await.map_err(|e| eyre!("{}", e)),
+        None => Ok(None),
+    }
+}
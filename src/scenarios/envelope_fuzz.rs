@@ -0,0 +1,237 @@
+//! Submit raw EIP-2718 envelopes with arbitrary type bytes and malformed payloads and catalog
+//! how the node rejects each, for differential testing of client implementations. Nothing
+//! sent here is a valid transaction, so a node that accepts one has a decoding bug.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use eyre::{eyre, Result};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::{Middleware, RpcError};
+use ethers::types::{Bytes, H256, U256};
+use rand::RngCore;
+use serde::Serialize;
+
+use crate::error_kind::ErrorKind;
+use crate::{build_tx, Fees, HashSigner};
+
+/// Lowest type byte fuzzed: 0x00-0x02 are the envelopes a re-tagged type-2 body could
+/// actually be valid as.
+pub const FIRST_FUZZ_TYPE: u8 = 0x03;
+/// Highest EIP-2718 type byte; from 0xc0 on the first byte reads as a legacy RLP list.
+pub const LAST_FUZZ_TYPE: u8 = 0x7f;
+
+/// What follows the type byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Payload {
+    /// Nothing at all.
+    Empty,
+    /// A well-formed, signed type-2 body under the wrong type byte.
+    Retagged,
+    /// The first half of that body.
+    Truncated,
+    /// 64 random bytes.
+    Garbage,
+    /// An RLP list header claiming 64 KiB that are not there.
+    OversizedLength,
+}
+
+impl Payload {
+    pub const ALL: [Payload; 5] =
+        [Payload::Empty, Payload::Retagged, Payload::Truncated, Payload::Garbage, Payload::OversizedLength];
+}
+
+impl FromStr for Payload {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Payload::ALL
+            .into_iter()
+            .find(|p| p.to_string() == s.trim())
+            .ok_or_else(|| format!("unknown payload '{}' (empty, retagged, truncated, garbage, oversized-length)", s))
+    }
+}
+
+impl fmt::Display for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Payload::Empty => "empty",
+            Payload::Retagged => "retagged",
+            Payload::Truncated => "truncated",
+            Payload::Garbage => "garbage",
+            Payload::OversizedLength => "oversized-length",
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FuzzOutcome {
+    pub type_byte: u8,
+    pub payload: Payload,
+    /// Envelope length in bytes, type byte included.
+    pub size: usize,
+    /// `rejected`, or `accepted` when the node returned a hash.
+    pub status: String,
+    /// JSON-RPC error code of the rejection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ErrorKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+}
+
+/// Fuzzed envelopes grouped by the node's (normalized) error message.
+#[derive(Clone, Debug, Serialize)]
+pub struct ErrorClass {
+    pub error: String,
+    pub error_kind: ErrorKind,
+    pub count: usize,
+    pub payloads: Vec<Payload>,
+    pub type_bytes: Vec<u8>,
+}
+
+/// Submit every combination of `type_bytes` and `payloads`. The re-tagged body is a 0-value
+/// self-transfer signed at `fees` with the client's next nonce, so it looks real down to the
+/// signature.
+pub async fn run<M, S>(
+    client: &SignerMiddleware<M, S>,
+    type_bytes: impl IntoIterator<Item = u8>,
+    payloads: &[Payload],
+    fees: Fees,
+) -> Result<Vec<FuzzOutcome>>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let from = client.address();
+    let mut tx = build_tx(2, from, from, U256::zero(), fees)?;
+    client.fill_transaction(&mut tx, None).await.map_err(|e| eyre!("filling the template tx: {}", e))?;
+    let sig = client.signer().sign_transaction(&tx).await.map_err(|e| eyre!("signing the template tx: {}", e))?;
+    // Without the leading type byte.
+    let body = tx.rlp_signed(&sig)[1..].to_vec();
+
+    let mut outcomes = Vec::new();
+    for type_byte in type_bytes {
+        for &payload in payloads {
+            let raw = envelope(type_byte, payload, &body);
+            let size = raw.len();
+            let mut outcome = FuzzOutcome {
+                type_byte,
+                payload,
+                size,
+                status: "rejected".into(),
+                code: None,
+                error: None,
+                error_kind: None,
+                tx_hash: None,
+            };
+            match client.provider().request::<_, H256>("eth_sendRawTransaction", [raw]).await {
+                Ok(hash) => {
+                    outcome.status = "accepted".into();
+                    outcome.tx_hash = Some(hash);
+                }
+                Err(e) => match e.as_error_response() {
+                    Some(response) => {
+                        outcome.code = Some(response.code);
+                        outcome.error = Some(response.message.clone());
+                        outcome.error_kind = Some(ErrorKind::from_rpc(response.code, &response.message));
+                    }
+                    None => {
+                        outcome.error = Some(e.to_string());
+                        outcome.error_kind = Some(ErrorKind::classify(&e.to_string()));
+                    }
+                },
+            }
+            outcomes.push(outcome);
+        }
+    }
+    Ok(outcomes)
+}
+
+fn envelope(type_byte: u8, payload: Payload, body: &[u8]) -> Bytes {
+    let mut raw = vec![type_byte];
+    match payload {
+        Payload::Empty => {}
+        Payload::Retagged => raw.extend_from_slice(body),
+        Payload::Truncated => raw.extend_from_slice(&body[..body.len() / 2]),
+        Payload::Garbage => {
+            let mut garbage = [0u8; 64];
+            rand::thread_rng().fill_bytes(&mut garbage);
+            raw.extend_from_slice(&garbage);
+        }
+        Payload::OversizedLength => raw.extend_from_slice(&[0xf9, 0xff, 0xff, 0x01, 0x02, 0x03]),
+    }
+    raw.into()
+}
+
+/// Group rejected outcomes by error, hex values and numbers blanked out so messages that only
+/// differ in a hash or length fall together; the most common error first.
+pub fn catalog(outcomes: &[FuzzOutcome]) -> Vec<ErrorClass> {
+    let mut classes: BTreeMap<String, ErrorClass> = BTreeMap::new();
+    for o in outcomes {
+        let Some(error) = &o.error else { continue };
+        let key = normalize(error);
+        let class = classes.entry(key.clone()).or_insert_with(|| ErrorClass {
+            error: key,
+            error_kind: o.error_kind.unwrap_or(ErrorKind::Other),
+            count: 0,
+            payloads: Vec::new(),
+            type_bytes: Vec::new(),
+        });
+        class.count += 1;
+        if !class.payloads.contains(&o.payload) {
+            class.payloads.push(o.payload);
+        }
+        if !class.type_bytes.contains(&o.type_byte) {
+            class.type_bytes.push(o.type_byte);
+        }
+    }
+    let mut classes: Vec<ErrorClass> = classes.into_values().collect();
+    classes.sort_by_key(|c| std::cmp::Reverse(c.count));
+    classes
+}
+
+fn normalize(error: &str) -> String {
+    let mut out = String::with_capacity(error.len());
+    let mut chars = error.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '0' && chars.peek() == Some(&'x') {
+            chars.next();
+            while chars.peek().is_some_and(char::is_ascii_hexdigit) {
+                chars.next();
+            }
+            out.push_str("0x…");
+        } else if c.is_ascii_digit() {
+            while chars.peek().is_some_and(char::is_ascii_digit) {
+                chars.next();
+            }
+            out.push('N');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// `0x03-0x05, 0x7e` style list of type bytes.
+pub fn format_type_bytes(type_bytes: &[u8]) -> String {
+    let mut sorted = type_bytes.to_vec();
+    sorted.sort_unstable();
+    let mut ranges: Vec<(u8, u8)> = Vec::new();
+    for t in sorted {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == t => *end = t,
+            _ => ranges.push((t, t)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(start, end)| if start == end { format!("0x{:02x}", start) } else { format!("0x{:02x}-0x{:02x}", start, end) })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,156 @@
+//! Type-2 transactions with invalid or borderline EIP-1559 fee combinations, recording whether
+//! the node turns each down at submission or only at inclusion.
+
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockNumber, H256, U256};
+use serde::Serialize;
+
+use super::wait_for_receipt;
+use crate::{build_tx, Fees, HashSigner};
+
+/// Gas limit for a plain transfer, fixed so no estimate (which may itself reject the fees) is needed.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// One fee combination, in the order they are sent: cases a node is most likely to leave
+/// pending, which would hold up every later nonce, go last.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeEdge {
+    /// `maxPriorityFeePerGas` 0 with a `maxFeePerGas` well above the base fee: valid, but
+    /// gives the block producer nothing.
+    ZeroPriority,
+    /// `maxPriorityFeePerGas` > `maxFeePerGas`: invalid by EIP-1559.
+    PriorityAboveMax,
+    /// Both fees `U256::MAX`: the sender cannot cover `gas * maxFeePerGas`.
+    U256Max,
+    /// `maxFeePerGas` below the current base fee: valid, but not includable until the base
+    /// fee drops.
+    MaxBelowBase,
+}
+
+impl FeeEdge {
+    pub const ALL: [FeeEdge; 4] = [FeeEdge::ZeroPriority, FeeEdge::PriorityAboveMax, FeeEdge::U256Max, FeeEdge::MaxBelowBase];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FeeEdge::ZeroPriority => "zero-priority",
+            FeeEdge::PriorityAboveMax => "priority-above-max",
+            FeeEdge::U256Max => "u256-max",
+            FeeEdge::MaxBelowBase => "max-below-base",
+        }
+    }
+
+    /// `(maxFeePerGas, maxPriorityFeePerGas)` for a block with `base_fee`; `None` when the
+    /// case cannot be expressed (a max fee below a base fee of 0).
+    pub fn fees(self, base_fee: U256) -> Option<(U256, U256)> {
+        let gwei = U256::exp10(9);
+        let max_fee = base_fee * 2 + gwei;
+        match self {
+            FeeEdge::ZeroPriority => Some((max_fee, U256::zero())),
+            FeeEdge::PriorityAboveMax => Some((max_fee, max_fee + 1)),
+            FeeEdge::U256Max => Some((U256::MAX, U256::MAX)),
+            FeeEdge::MaxBelowBase if base_fee.is_zero() => None,
+            FeeEdge::MaxBelowBase => Some((base_fee / 2, U256::zero())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EdgeOutcome {
+    pub case: &'static str,
+    pub base_fee: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// `rejected` (at submission), `mined`, `reverted`, `pending` (accepted but not included
+    /// in time), or `skipped`.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_gas_price: Option<U256>,
+}
+
+/// Send a 0-value transfer to `to` for each case, waiting up to `wait` for each to be mined.
+/// Once one stays pending, the remaining cases are skipped since their nonces would queue
+/// behind it.
+pub async fn run<M, S>(
+    client: &SignerMiddleware<M, S>,
+    to: Address,
+    cases: &[FeeEdge],
+    wait: Duration,
+) -> Result<Vec<EdgeOutcome>>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let from = client.address();
+    let block = client
+        .get_block(BlockNumber::Latest)
+        .await
+        .map_err(|e| eyre!("eth_getBlockByNumber: {}", e))?
+        .ok_or_else(|| eyre!("node returned no latest block"))?;
+    let base_fee = block.base_fee_per_gas.ok_or_else(|| eyre!("latest block has no base fee; chain is pre-London"))?;
+
+    let mut outcomes = Vec::with_capacity(cases.len());
+    let mut stuck: Option<H256> = None;
+    for &case in cases {
+        let fees = case.fees(base_fee);
+        let mut outcome = EdgeOutcome {
+            case: case.name(),
+            base_fee,
+            max_fee_per_gas: fees.map(|(max, _)| max),
+            max_priority_fee_per_gas: fees.map(|(_, priority)| priority),
+            status: "skipped".into(),
+            error: None,
+            tx_hash: None,
+            block_number: None,
+            effective_gas_price: None,
+        };
+        let Some((max_fee_per_gas, max_priority_fee_per_gas)) = fees else {
+            outcome.error = Some("base fee is 0".into());
+            outcomes.push(outcome);
+            continue;
+        };
+        if let Some(hash) = stuck {
+            outcome.error = Some(format!("0x{:x} is still pending", hash));
+            outcomes.push(outcome);
+            continue;
+        }
+
+        let fees = Fees { gas_price: None, max_priority_fee_per_gas, max_fee_per_gas, max_fee_per_blob_gas: U256::zero() };
+        let mut tx = build_tx(2, from, to, U256::zero(), fees)?;
+        tx.set_gas(TRANSFER_GAS);
+        outcome.status = "rejected".into();
+        let hash = match client.send_transaction(tx, None).await {
+            Ok(pending) => pending.tx_hash(),
+            Err(e) => {
+                outcome.error = Some(e.to_string());
+                outcomes.push(outcome);
+                continue;
+            }
+        };
+        outcome.tx_hash = Some(hash);
+        match wait_for_receipt(client, hash, wait).await? {
+            Some(r) => {
+                outcome.status = if r.status == Some(1.into()) { "mined" } else { "reverted" }.into();
+                outcome.block_number = r.block_number.map(|n| n.as_u64());
+                outcome.effective_gas_price = r.effective_gas_price;
+            }
+            None => {
+                outcome.status = "pending".into();
+                stuck = Some(hash);
+            }
+        }
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
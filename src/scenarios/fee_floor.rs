@@ -0,0 +1,259 @@
+//! Find the lowest fee a chain still includes: 0-value transfers at halving fees (2 gwei → 1 →
+//! 0.5 → ... → 0) until one is not included within a number of blocks, then a binary search
+//! between that fee and the last included one. Either the priority fee of type-2 transfers is
+//! lowered, or the `gasPrice` of legacy (type 0 or 1) ones.
+//!
+//! A fee that is accepted but not included leaves its nonce pending, so the next probe
+//! replaces it and must outbid it by the usual 10%; the search ends early when the gap is too
+//! small for that. A transaction still pending at the end is replaced at the lowest included
+//! fee.
+
+use eyre::{eyre, Result};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockNumber, H256, U256};
+use serde::Serialize;
+use tokio::time::sleep;
+
+use crate::error_kind::ErrorKind;
+use crate::{build_tx, Fees, HashSigner};
+
+/// Gas limit for a plain transfer, fixed so no estimate is needed.
+const TRANSFER_GAS: u64 = 21_000;
+/// Bump, in percent, a replacement needs over the fees of the transaction it replaces.
+const REPLACEMENT_BUMP: u64 = 10;
+
+/// The fee a sweep lowers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SweptFee {
+    /// `maxPriorityFeePerGas` of type-2 transfers, with `maxFeePerGas` at twice the base fee
+    /// plus the tip.
+    PriorityFee,
+    /// `gasPrice` of type-0 or type-1 transfers.
+    GasPrice { tx_type: u8 },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProbeOutcome {
+    pub tx_type: u8,
+    /// The swept fee: the tip of a type-2 probe, the gas price of a legacy one.
+    pub fee: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    /// Base fee of the latest block when the probe was sent; `None` before London.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee: Option<U256>,
+    /// `included`, `not included` (accepted, still pending after the window) or `rejected`.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ErrorKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Blocks between sending and inclusion.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_waited: Option<u64>,
+    /// Replaced a probe that was still pending.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub replacement: bool,
+}
+
+impl ProbeOutcome {
+    pub fn included(&self) -> bool {
+        self.status == "included"
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SweepReport {
+    pub tx_type: u8,
+    pub probes: Vec<ProbeOutcome>,
+    /// Lowest fee that was included in time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lowest_included: Option<U256>,
+    /// Highest fee that was not, when one was found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highest_excluded: Option<U256>,
+    /// The pending probe left over by the search, replaced at `lowest_included`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cleanup: Option<ProbeOutcome>,
+}
+
+/// A probe that is still pending and must be replaced.
+#[derive(Clone, Copy)]
+struct Pending {
+    nonce: U256,
+    fee: U256,
+    max_fee_per_gas: Option<U256>,
+}
+
+/// Sweep `swept` from `max` down, halving until a probe is not included within `blocks`
+/// blocks (fees below `resolution` are skipped for 0), then narrow the gap down to
+/// `resolution`.
+pub async fn run<M, S>(
+    client: &SignerMiddleware<M, S>,
+    to: Address,
+    swept: SweptFee,
+    max: U256,
+    resolution: U256,
+    blocks: u64,
+) -> Result<SweepReport>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let tx_type = match swept {
+        SweptFee::PriorityFee => 2,
+        SweptFee::GasPrice { tx_type } => tx_type,
+    };
+    let mut report =
+        SweepReport { tx_type, probes: Vec::new(), lowest_included: None, highest_excluded: None, cleanup: None };
+    let mut pending: Option<Pending> = None;
+
+    // Halving phase.
+    let mut fee = max;
+    loop {
+        let outcome = probe(client, to, swept, fee, blocks, &mut pending).await?;
+        let included = outcome.included();
+        report.probes.push(outcome);
+        if !included {
+            report.highest_excluded = Some(fee);
+            break;
+        }
+        report.lowest_included = Some(fee);
+        if fee.is_zero() {
+            break;
+        }
+        fee = if fee / 2 < resolution { U256::zero() } else { fee / 2 };
+    }
+
+    // Binary search between the highest excluded and the lowest included fee.
+    while let (Some(low), Some(high)) = (report.highest_excluded, report.lowest_included) {
+        if high - low <= resolution {
+            break;
+        }
+        let mid = low + (high - low) / 2;
+        if pending.is_some_and(|p| mid < bumped(p.fee)) {
+            break;
+        }
+        let outcome = probe(client, to, swept, mid, blocks, &mut pending).await?;
+        // The probe it was to replace got included after all; try again on the next nonce.
+        if outcome.error_kind == Some(ErrorKind::NonceTooLow) {
+            report.probes.push(outcome);
+            continue;
+        }
+        if outcome.included() {
+            report.lowest_included = Some(mid);
+        } else {
+            report.highest_excluded = Some(mid);
+        }
+        report.probes.push(outcome);
+    }
+
+    if pending.is_some() {
+        let fee = report.lowest_included.unwrap_or(max);
+        report.cleanup = Some(probe(client, to, swept, fee, blocks, &mut pending).await?);
+    }
+    Ok(report)
+}
+
+/// Send one 0-value transfer at `fee`, replacing `pending` if set, and wait `blocks` blocks
+/// for it. Leaves `pending` set when the transfer was accepted but not included.
+async fn probe<M, S>(
+    client: &SignerMiddleware<M, S>,
+    to: Address,
+    swept: SweptFee,
+    fee: U256,
+    blocks: u64,
+    pending: &mut Option<Pending>,
+) -> Result<ProbeOutcome>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let block = client
+        .get_block(BlockNumber::Latest)
+        .await
+        .map_err(|e| eyre!("eth_getBlockByNumber: {}", e))?
+        .ok_or_else(|| eyre!("node returned no latest block"))?;
+    let base_fee = block.base_fee_per_gas;
+    let sent_at = block.number.map(|n| n.as_u64()).unwrap_or_default();
+
+    let fee = match *pending {
+        Some(p) => fee.max(bumped(p.fee)),
+        None => fee,
+    };
+    let (tx_type, fees, max_fee_per_gas) = match swept {
+        SweptFee::PriorityFee => {
+            let base_fee = base_fee.ok_or_else(|| eyre!("latest block has no base fee; chain is pre-London"))?;
+            let mut max_fee_per_gas: U256 = base_fee * 2 + fee;
+            if let Some(previous) = pending.and_then(|p| p.max_fee_per_gas) {
+                max_fee_per_gas = max_fee_per_gas.max(bumped(previous));
+            }
+            let fees =
+                Fees { gas_price: None, max_priority_fee_per_gas: fee, max_fee_per_gas, max_fee_per_blob_gas: U256::zero() };
+            (2, fees, Some(max_fee_per_gas))
+        }
+        SweptFee::GasPrice { tx_type } => (tx_type, Fees::flat(fee), None),
+    };
+    let mut outcome = ProbeOutcome {
+        tx_type,
+        fee,
+        max_fee_per_gas,
+        base_fee,
+        status: "rejected".into(),
+        error: None,
+        error_kind: None,
+        tx_hash: None,
+        block_number: None,
+        blocks_waited: None,
+        replacement: pending.is_some(),
+    };
+    let mut tx = build_tx(tx_type, client.address(), to, U256::zero(), fees)?;
+    tx.set_gas(TRANSFER_GAS);
+    if let Some(p) = *pending {
+        tx.set_nonce(p.nonce);
+    }
+    client.fill_transaction(&mut tx, None).await.map_err(|e| eyre!("filling the probe: {}", e))?;
+    let nonce = *tx.nonce().ok_or_else(|| eyre!("probe has no nonce"))?;
+    let hash = match client.send_transaction(tx, None).await {
+        Ok(sent) => sent.tx_hash(),
+        Err(e) => {
+            let kind = ErrorKind::classify(&e.to_string());
+            if kind == ErrorKind::NonceTooLow {
+                *pending = None;
+            }
+            outcome.error_kind = Some(kind);
+            outcome.error = Some(e.to_string());
+            return Ok(outcome);
+        }
+    };
+    outcome.tx_hash = Some(hash);
+    outcome.status = "not included".into();
+    *pending = Some(Pending { nonce, fee, max_fee_per_gas });
+
+    let interval = client.provider().get_interval();
+    loop {
+        if let Some(r) = client.get_transaction_receipt(hash).await.map_err(|e| eyre!("{}", e))? {
+            let mined = r.block_number.map(|n| n.as_u64());
+            outcome.status = "included".into();
+            outcome.block_number = mined;
+            outcome.blocks_waited = mined.map(|n| n.saturating_sub(sent_at));
+            *pending = None;
+            return Ok(outcome);
+        }
+        let head = client.get_block_number().await.map_err(|e| eyre!("eth_blockNumber: {}", e))?.as_u64();
+        if head >= sent_at + blocks {
+            return Ok(outcome);
+        }
+        sleep(interval).await;
+    }
+}
+
+/// Least a replacement must offer over `fee`.
+fn bumped(fee: U256) -> U256 {
+    fee + fee * REPLACEMENT_BUMP / 100 + 1
+}
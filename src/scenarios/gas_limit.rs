@@ -0,0 +1,131 @@
+//! Send the same call with the gas limit left to the client, set to the node's
+//! `eth_estimateGas` result, and set a margin below it, to catch chains whose estimation
+//! under-reports for some tx types.
+
+use std::time::Duration;
+
+use eyre::Result;
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{H256, U256};
+use serde::Serialize;
+
+use super::wait_for_receipt;
+use crate::{tx_type_of, HashSigner};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GasOutcome {
+    pub tx_type: u8,
+    /// `auto` (filled in by the client), `estimate`, or `estimate-N%`.
+    pub limit: String,
+    /// What `eth_estimateGas` returned for the fully populated transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<U256>,
+    /// Gas limit the transaction was sent with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas: Option<U256>,
+    /// `mined`, `out of gas`, `reverted`, `rejected`, or `not mined`.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<U256>,
+}
+
+/// Send `tx` (e.g. from [`build_tx`](crate::build_tx)) with each of the three gas limits, one
+/// after the other, waiting up to `wait` for each to be mined. `margin_pct` is how far below
+/// the estimate the last one goes.
+pub async fn run<M, S>(
+    client: &SignerMiddleware<M, S>,
+    tx: TypedTransaction,
+    margin_pct: u64,
+    wait: Duration,
+) -> Result<Vec<GasOutcome>>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let estimate = client.estimate_gas(&tx, None).await;
+
+    let mut outcomes = Vec::with_capacity(3);
+    outcomes.push(send(client, tx.clone(), "auto".into(), None, wait).await?);
+    match estimate {
+        Ok(estimate) => {
+            let short = estimate * (100 - margin_pct.min(100)) / 100;
+            outcomes.push(send(client, tx.clone(), "estimate".into(), Some((estimate, estimate)), wait).await?);
+            outcomes.push(send(client, tx, format!("estimate-{}%", margin_pct), Some((estimate, short)), wait).await?);
+        }
+        Err(e) => outcomes.push(GasOutcome {
+            tx_type: tx_type_of(&tx),
+            limit: "estimate".into(),
+            estimate: None,
+            gas: None,
+            status: "rejected".into(),
+            error: Some(format!("eth_estimateGas: {}", e)),
+            tx_hash: None,
+            gas_used: None,
+        }),
+    }
+    Ok(outcomes)
+}
+
+/// Send `tx` with `gas` = `(estimate, limit)`, or with whatever the client fills in if `None`.
+async fn send<M, S>(
+    client: &SignerMiddleware<M, S>,
+    mut tx: TypedTransaction,
+    limit: String,
+    gas: Option<(U256, U256)>,
+    wait: Duration,
+) -> Result<GasOutcome>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let mut outcome = GasOutcome {
+        tx_type: tx_type_of(&tx),
+        limit,
+        estimate: gas.map(|(estimate, _)| estimate),
+        gas: None,
+        status: "rejected".into(),
+        error: None,
+        tx_hash: None,
+        gas_used: None,
+    };
+    match gas {
+        Some((_, limit)) => {
+            tx.set_gas(limit);
+        }
+        None => {
+            if let Err(e) = client.fill_transaction(&mut tx, None).await {
+                outcome.error = Some(e.to_string());
+                return Ok(outcome);
+            }
+        }
+    }
+    outcome.gas = tx.gas().copied();
+
+    let hash = match client.send_transaction(tx, None).await {
+        Ok(pending) => pending.tx_hash(),
+        Err(e) => {
+            outcome.error = Some(e.to_string());
+            return Ok(outcome);
+        }
+    };
+    outcome.tx_hash = Some(hash);
+    outcome.status = "not mined".into();
+
+    if let Some(r) = wait_for_receipt(client, hash, wait).await? {
+        outcome.gas_used = r.gas_used;
+        outcome.status = match (r.status == Some(1.into()), r.gas_used, outcome.gas) {
+            (true, ..) => "mined",
+            // A failed call that burnt its whole allowance ran out of gas.
+            (false, Some(used), Some(limit)) if used == limit => "out of gas",
+            (false, ..) => "reverted",
+        }
+        .into();
+    }
+    Ok(outcome)
+}
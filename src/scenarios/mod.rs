@@ -0,0 +1,31 @@
+//! Targeted multi-step tests that go beyond one transfer per tx type.
+
+pub mod calldata_sweep;
+pub mod cancel_replace;
+pub mod envelope_fuzz;
+pub mod fee_edges;
+pub mod fee_floor;
+pub mod gas_limit;
+pub mod op_deposit;
+pub mod plan;
+pub mod retryable;
+
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::providers::Middleware;
+use ethers::types::{TransactionReceipt, H256};
+use tokio::time::Instant;
+
+/// Poll for the receipt of `hash` until it shows up or `wait` has passed.
+pub(crate) async fn wait_for_receipt<M: Middleware>(client: &M, hash: H256, wait: Duration) -> Result<Option<TransactionReceipt>> {
+    let give_up_at = Instant::now() + wait;
+    let interval = client.provider().get_interval();
+    while Instant::now() < give_up_at {
+        if let Some(r) = client.get_transaction_receipt(hash).await.map_err(|e| eyre!("{}", e))? {
+            return Ok(Some(r));
+        }
+        tokio::time::sleep(interval).await;
+    }
+    Ok(None)
+}
@@ -0,0 +1,181 @@
+//! Deposit through an OP Stack chain's L1 `OptimismPortal` (`depositTransaction`) and follow
+//! the resulting type-0x7E deposited transaction onto L2, covering the L1→L2 path that plain
+//! L2 transfers never take.
+
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::abi::{self, ParamType, Token};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::{Address, Bytes, Log, TransactionReceipt, H256, U256};
+use ethers::utils::rlp::RlpStream;
+use ethers::utils::{id, keccak256};
+use serde::Serialize;
+
+use super::wait_for_receipt;
+use crate::{build_tx, Fees, HashSigner};
+
+/// EIP-2718 type of deposited transactions.
+pub const DEPOSIT_TX_TYPE: u8 = 0x7e;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DepositOutcome {
+    /// Amount minted on L2 and sent to the recipient.
+    pub mint: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l1_tx_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l1_block_number: Option<u64>,
+    /// Hash of the deposited transaction on L2, derived from the L1 `TransactionDeposited` log.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l2_tx_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l2_block_number: Option<u64>,
+    /// `success` or `failed` once on L2; `rejected`, `reverted` or `not deposited` (no
+    /// deposit event) on L1; `pending` when either side was not mined in time.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Deposit `mint` to `to` with an L2 gas limit of `gas_limit` through `portal` on `l1`, at
+/// `fees`, then wait up to `wait` for each side to be mined, L2 (`l2`) included.
+#[allow(clippy::too_many_arguments)]
+pub async fn run<M, S, L>(
+    l1: &SignerMiddleware<M, S>,
+    l2: &L,
+    portal: Address,
+    to: Address,
+    mint: U256,
+    gas_limit: u64,
+    fees: Fees,
+    wait: Duration,
+) -> Result<DepositOutcome>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+    L: Middleware,
+{
+    let mut outcome = DepositOutcome {
+        mint,
+        l1_tx_hash: None,
+        l1_block_number: None,
+        l2_tx_hash: None,
+        l2_block_number: None,
+        status: "pending".into(),
+        error: None,
+    };
+    let mut tx = build_tx(2, l1.address(), portal, mint, fees)?;
+    tx.set_data(deposit_calldata(to, mint, gas_limit));
+    let hash = match l1.send_transaction(tx, None).await {
+        Ok(pending) => pending.tx_hash(),
+        Err(e) => {
+            outcome.status = "rejected".into();
+            outcome.error = Some(e.to_string());
+            return Ok(outcome);
+        }
+    };
+    outcome.l1_tx_hash = Some(hash);
+    let Some(receipt) = wait_for_receipt(l1, hash, wait).await? else { return Ok(outcome) };
+    outcome.l1_block_number = receipt.block_number.map(|b| b.as_u64());
+    if receipt.status.is_some_and(|s| s.is_zero()) {
+        outcome.status = "reverted".into();
+        return Ok(outcome);
+    }
+
+    let l2_hash = match deposit_hash(&receipt) {
+        Ok(hash) => hash,
+        Err(e) => {
+            outcome.status = "not deposited".into();
+            outcome.error = Some(e.to_string());
+            return Ok(outcome);
+        }
+    };
+    outcome.l2_tx_hash = Some(l2_hash);
+    if let Some(r) = wait_for_receipt(l2, l2_hash, wait).await? {
+        outcome.l2_block_number = r.block_number.map(|b| b.as_u64());
+        outcome.status = if r.status.is_some_and(|s| s.is_zero()) { "failed" } else { "success" }.into();
+    }
+    Ok(outcome)
+}
+
+/// `depositTransaction(to, value, gasLimit, isCreation = false, data = "")`.
+fn deposit_calldata(to: Address, value: U256, gas_limit: u64) -> Bytes {
+    let mut data = id("depositTransaction(address,uint256,uint64,bool,bytes)").to_vec();
+    data.extend(abi::encode(&[
+        Token::Address(to),
+        Token::Uint(value),
+        Token::Uint(gas_limit.into()),
+        Token::Bool(false),
+        Token::Bytes(Vec::new()),
+    ]));
+    data.into()
+}
+
+/// L2 hash of the deposit announced in `receipt`: `keccak256(0x7E || rlp([sourceHash, from,
+/// to, mint, value, gas, isSystemTx, data]))`, with the source hash derived from the L1 block
+/// hash and log index.
+fn deposit_hash(receipt: &TransactionReceipt) -> Result<H256> {
+    let topic = H256::from(keccak256("TransactionDeposited(address,address,uint256,bytes)"));
+    let log = receipt
+        .logs
+        .iter()
+        .find(|log| log.topics.len() == 4 && log.topics[0] == topic)
+        .ok_or_else(|| eyre!("no TransactionDeposited log in the L1 receipt; is the address an OptimismPortal?"))?;
+    let deposit = Deposit::decode(log)?;
+
+    let (block_hash, log_index) = log.block_hash.zip(log.log_index).ok_or_else(|| eyre!("log without block hash or index"))?;
+    let mut index = [0u8; 32];
+    log_index.to_big_endian(&mut index);
+    let deposit_id = keccak256([block_hash.as_bytes(), &index[..]].concat());
+    // User deposits use domain 0.
+    let source_hash = keccak256([&[0u8; 32][..], &deposit_id[..]].concat());
+
+    let mut s = RlpStream::new_list(8);
+    s.append(&&source_hash[..]);
+    s.append(&deposit.from);
+    match deposit.to {
+        Some(to) => s.append(&to),
+        None => s.append_empty_data(),
+    };
+    s.append(&deposit.mint);
+    s.append(&deposit.value);
+    s.append(&deposit.gas_limit);
+    s.append(&false);
+    s.append(&deposit.data.as_ref());
+    let mut envelope = vec![DEPOSIT_TX_TYPE];
+    envelope.extend_from_slice(&s.out());
+    Ok(keccak256(envelope).into())
+}
+
+/// A `TransactionDeposited(from, to, version, opaqueData)` event, with `opaqueData` being
+/// `abi.encodePacked(mint, value, gasLimit, isCreation, data)`.
+struct Deposit {
+    from: Address,
+    to: Option<Address>,
+    mint: U256,
+    value: U256,
+    gas_limit: u64,
+    data: Bytes,
+}
+
+impl Deposit {
+    fn decode(log: &Log) -> Result<Self> {
+        let Some(Token::Bytes(opaque)) = abi::decode(&[ParamType::Bytes], &log.data)?.pop() else {
+            return Err(eyre!("malformed TransactionDeposited data"));
+        };
+        if opaque.len() < 73 {
+            return Err(eyre!("TransactionDeposited data is {} bytes, expected at least 73", opaque.len()));
+        }
+        let is_creation = opaque[72] != 0;
+        Ok(Self {
+            from: Address::from(log.topics[1]),
+            to: (!is_creation).then(|| Address::from(log.topics[2])),
+            mint: U256::from_big_endian(&opaque[0..32]),
+            value: U256::from_big_endian(&opaque[32..64]),
+            gas_limit: u64::from_be_bytes(opaque[64..72].try_into()?),
+            data: opaque[73..].to_vec().into(),
+        })
+    }
+}
@@ -0,0 +1,365 @@
+//! Test plans kept in a TOML file: a sequence of transactions, each with the outcome it is
+//! expected to have, run one after the other from one sender.
+//!
+//! ```toml
+//! [defaults]           # applied to every step that leaves a field out
+//! to = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+//! fees = "market"
+//! wait_secs = 60
+//!
+//! [[step]]
+//! name = "legacy transfer"
+//! type = 0
+//! value = "0.001"      # whole native units
+//! expect.status = "success"
+//! expect.gas_used = "<= 21000"
+//!
+//! [[step]]
+//! name = "call with data"
+//! type = 2
+//! data = "0xdeadbeef"
+//! fees = "0"
+//! expect.status = "rejected"
+//! expect.error_contains = "underpriced"
+//! ```
+//!
+//! Statuses are `success`, `failed` (mined but reverted), `rejected` (at submission),
+//! `pending` (not mined in time), `unsupported` (the type cannot be built) and `skipped`
+//! (queued behind a pending step). Numeric assertions take a bare number (equality) or a
+//! comparison: `<`, `<=`, `>`, `>=`, `==` or `!=` followed by the number.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::{Address, Bytes, H256, U256};
+use serde::{Deserialize, Serialize};
+
+use super::wait_for_receipt;
+use crate::amount::Native;
+use crate::fees::FeeSeries;
+use crate::{build_tx, ens, HashSigner};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Plan {
+    #[serde(default)]
+    defaults: Defaults,
+    #[serde(default, rename = "step")]
+    steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct Defaults {
+    to: Option<String>,
+    fees: Option<String>,
+    wait_secs: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Step {
+    name: Option<String>,
+    #[serde(rename = "type")]
+    tx_type: u8,
+    /// Address or ENS name; the defaults', or else the sender itself, when omitted.
+    to: Option<String>,
+    /// ETH, e.g. `0.001`.
+    value: Option<String>,
+    data: Option<String>,
+    fees: Option<String>,
+    wait_secs: Option<u64>,
+    #[serde(default)]
+    expect: Expect,
+}
+
+/// Assertions on a step's outcome; unset ones are not checked.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Expect {
+    status: Option<String>,
+    error_contains: Option<String>,
+    gas_used: Option<Bound>,
+    effective_gas_price: Option<Bound>,
+    block_number: Option<Bound>,
+}
+
+/// A number, or a comparison such as `"<= 21000"`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum Bound {
+    Exact(u64),
+    Expr(String),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn holds(self, actual: U256, limit: U256) -> bool {
+        match self {
+            Op::Lt => actual < limit,
+            Op::Le => actual <= limit,
+            Op::Gt => actual > limit,
+            Op::Ge => actual >= limit,
+            Op::Eq => actual == limit,
+            Op::Ne => actual != limit,
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Eq => "==",
+            Op::Ne => "!=",
+        })
+    }
+}
+
+impl Bound {
+    fn parse(&self) -> Result<(Op, U256)> {
+        let expr = match self {
+            Bound::Exact(n) => return Ok((Op::Eq, (*n).into())),
+            Bound::Expr(expr) => expr.trim(),
+        };
+        // Two-character operators first so `<=` is not read as `<` followed by `=`.
+        let ops = [("<=", Op::Le), (">=", Op::Ge), ("==", Op::Eq), ("!=", Op::Ne), ("<", Op::Lt), (">", Op::Gt)];
+        let (op, number) = ops
+            .iter()
+            .find_map(|(prefix, op)| expr.strip_prefix(prefix).map(|rest| (*op, rest)))
+            .unwrap_or((Op::Eq, expr));
+        let number = U256::from_dec_str(number.trim()).map_err(|e| eyre!("invalid comparison {:?}: {}", expr, e))?;
+        Ok((op, number))
+    }
+}
+
+impl Expect {
+    fn validate(&self) -> Result<()> {
+        for (name, bound) in self.bounds() {
+            if let Some(bound) = bound {
+                bound.parse().map_err(|e| eyre!("expect.{}: {}", name, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn bounds(&self) -> [(&'static str, Option<&Bound>); 3] {
+        [
+            ("gas_used", self.gas_used.as_ref()),
+            ("effective_gas_price", self.effective_gas_price.as_ref()),
+            ("block_number", self.block_number.as_ref()),
+        ]
+    }
+
+    /// A message for every assertion `outcome` violates.
+    fn violations(&self, outcome: &StepOutcome) -> Vec<String> {
+        let mut violations = Vec::new();
+        if let Some(status) = &self.status
+            && *status != outcome.status
+        {
+            violations.push(format!("expect.status: expected {}, got {}", status, outcome.status));
+        }
+        if let Some(needle) = &self.error_contains {
+            match &outcome.error {
+                Some(error) if error.contains(needle.as_str()) => {}
+                Some(error) => violations.push(format!("expect.error_contains: {:?} not in {:?}", needle, error)),
+                None => violations.push(format!("expect.error_contains: no error, expected one containing {:?}", needle)),
+            }
+        }
+        let actuals = [outcome.gas_used, outcome.effective_gas_price, outcome.block_number.map(U256::from)];
+        for ((name, bound), actual) in self.bounds().into_iter().zip(actuals) {
+            // Validated on load.
+            let Some(Ok((op, limit))) = bound.map(Bound::parse) else { continue };
+            match actual {
+                Some(actual) if op.holds(actual, limit) => {}
+                Some(actual) => violations.push(format!("expect.{}: {} is not {} {}", name, actual, op, limit)),
+                None => violations.push(format!("expect.{}: no {} ({})", name, name.replace('_', " "), outcome.status)),
+            }
+        }
+        violations
+    }
+}
+
+/// Fallback for `wait_secs` when neither the step nor the defaults set it.
+const DEFAULT_WAIT_SECS: u64 = 60;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StepOutcome {
+    pub name: String,
+    pub tx_type: u8,
+    pub status: String,
+    /// One message per violated `expect` assertion.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_gas_price: Option<U256>,
+}
+
+impl StepOutcome {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl Plan {
+    /// Read and check a plan file: fee points, values, data and assertions must all parse.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let plan: Self = toml::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| eyre!("invalid plan file {}: {}", path.display(), e))?;
+        if plan.steps.is_empty() {
+            return Err(eyre!("plan file {} has no [[step]]", path.display()));
+        }
+        for (i, step) in plan.steps.iter().enumerate() {
+            let name = step.name(i);
+            plan.fees(step).map_err(|e| eyre!("{}: {}", name, e))?;
+            step.value(&Native::default()).map_err(|e| eyre!("{}: {}", name, e))?;
+            step.data().map_err(|e| eyre!("{}: {}", name, e))?;
+            step.expect.validate().map_err(|e| eyre!("{}: {}", name, e))?;
+        }
+        Ok(plan)
+    }
+
+    fn fees(&self, step: &Step) -> Result<FeeSeries> {
+        let fees = step.fees.as_deref().or(self.defaults.fees.as_deref()).unwrap_or("market");
+        fees.parse().map_err(|e| eyre!("{}", e))
+    }
+}
+
+impl Step {
+    fn name(&self, i: usize) -> String {
+        self.name.clone().unwrap_or_else(|| format!("step {}", i + 1))
+    }
+
+    fn value(&self, native: &Native) -> Result<U256> {
+        match &self.value {
+            Some(v) => native.parse(v).map_err(|e| eyre!("invalid value: {}", e)),
+            None => Ok(U256::zero()),
+        }
+    }
+
+    fn data(&self) -> Result<Bytes> {
+        match &self.data {
+            Some(hex) => Bytes::from_str(hex).map_err(|e| eyre!("invalid data: {}", e)),
+            None => Ok(Bytes::new()),
+        }
+    }
+}
+
+/// Run every step of `plan` in order. Once a step stays pending, the rest are skipped since
+/// their nonces would queue behind it. Values are in `native` units.
+pub async fn run<M, S>(client: &SignerMiddleware<M, S>, plan: &Plan, native: &Native) -> Result<Vec<StepOutcome>>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let from = client.address();
+    let mut outcomes = Vec::with_capacity(plan.steps.len());
+    let mut stuck: Option<H256> = None;
+    for (i, step) in plan.steps.iter().enumerate() {
+        let mut outcome = StepOutcome {
+            name: step.name(i),
+            tx_type: step.tx_type,
+            status: "skipped".into(),
+            violations: Vec::new(),
+            error: None,
+            tx_hash: None,
+            block_number: None,
+            gas_used: None,
+            effective_gas_price: None,
+        };
+        match stuck {
+            Some(hash) => outcome.error = Some(format!("0x{:x} is still pending", hash)),
+            None => {
+                execute(client, plan, step, from, native, &mut outcome).await?;
+                if outcome.status == "pending" {
+                    stuck = outcome.tx_hash;
+                }
+            }
+        }
+        outcome.violations = step.expect.violations(&outcome);
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+async fn execute<M, S>(
+    client: &SignerMiddleware<M, S>,
+    plan: &Plan,
+    step: &Step,
+    from: Address,
+    native: &Native,
+    outcome: &mut StepOutcome,
+) -> Result<()>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let to = match step.to.as_deref().or(plan.defaults.to.as_deref()) {
+        Some(to) => ens::resolve(client, to).await?,
+        None => from,
+    };
+    let fees = match plan.fees(step)?.resolve(client).await {
+        Ok(fees) => fees,
+        Err(e) => {
+            outcome.status = "rejected".into();
+            outcome.error = Some(format!("resolving fees: {}", e));
+            return Ok(());
+        }
+    };
+    let mut tx = match build_tx(step.tx_type, from, to, step.value(native)?, fees) {
+        Ok(tx) => tx,
+        Err(e) => {
+            outcome.status = "unsupported".into();
+            outcome.error = Some(e.to_string());
+            return Ok(());
+        }
+    };
+    let data = step.data()?;
+    if !data.is_empty() {
+        tx.set_data(data);
+    }
+
+    outcome.status = "rejected".into();
+    let hash = match client.send_transaction(tx, None).await {
+        Ok(pending) => pending.tx_hash(),
+        Err(e) => {
+            outcome.error = Some(e.to_string());
+            return Ok(());
+        }
+    };
+    outcome.tx_hash = Some(hash);
+    let wait = Duration::from_secs(step.wait_secs.or(plan.defaults.wait_secs).unwrap_or(DEFAULT_WAIT_SECS));
+    match wait_for_receipt(client, hash, wait).await? {
+        Some(r) => {
+            outcome.status = if r.status == Some(1.into()) { "success" } else { "failed" }.into();
+            outcome.block_number = r.block_number.map(|n| n.as_u64());
+            outcome.gas_used = r.gas_used;
+            outcome.effective_gas_price = r.effective_gas_price;
+        }
+        None => outcome.status = "pending".into(),
+    }
+    Ok(())
+}
@@ -0,0 +1,251 @@
+//! Submit an Arbitrum retryable ticket through the L1 `Inbox` (`createRetryableTicket`) and
+//! follow it onto L2: the ticket's creation, then its automatic redeem.
+
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::abi::{self, ParamType, Token};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockNumber, Bytes, TransactionReceipt, TransactionRequest, H256, U256};
+use ethers::utils::rlp::RlpStream;
+use ethers::utils::{id, keccak256};
+use serde::Serialize;
+
+use super::wait_for_receipt;
+use crate::{build_tx, Fees, HashSigner};
+
+/// EIP-2718 type of the L2 transaction that creates a retryable ticket.
+pub const SUBMIT_RETRYABLE_TX_TYPE: u8 = 0x69;
+/// `ArbRetryableTx` precompile, which logs the scheduled auto-redeem.
+const ARB_RETRYABLE_TX: Address = ethers::types::H160([
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x6e,
+]);
+/// Head room on the L1 base fee the submission cost is priced at, and on the L2 gas price;
+/// the excess is refunded on L2.
+const FEE_MARGIN: u64 = 2;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RetryableOutcome {
+    /// Call value delivered to the recipient on L2.
+    pub l2_call_value: U256,
+    /// Everything sent with the L1 transaction: call value, submission cost and L2 gas.
+    pub deposit: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l1_tx_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l1_block_number: Option<u64>,
+    /// Hash of the L2 transaction that creates the ticket (type 0x69).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ticket_id: Option<H256>,
+    /// Hash of the scheduled auto-redeem (type 0x68).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redeem_tx_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l2_block_number: Option<u64>,
+    /// `redeemed`; `redeem failed` (the ticket waits for a manual redeem) or `ticket failed`
+    /// on L2; `rejected`, `reverted` or `not submitted` (no message event) on L1; `pending`
+    /// when a step was not mined in time.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Create a ticket calling `to` with `l2_call_value` and `gas_limit` L2 gas through `inbox`
+/// on `l1`, at `fees`, then wait up to `wait` for each step, the L2 ones on `l2`.
+#[allow(clippy::too_many_arguments)]
+pub async fn run<M, S, L>(
+    l1: &SignerMiddleware<M, S>,
+    l2: &L,
+    inbox: Address,
+    to: Address,
+    l2_call_value: U256,
+    gas_limit: u64,
+    fees: Fees,
+    wait: Duration,
+) -> Result<RetryableOutcome>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+    L: Middleware,
+{
+    let from = l1.address();
+    let l1_base_fee = l1
+        .get_block(BlockNumber::Latest)
+        .await
+        .map_err(|e| eyre!("L1 eth_getBlockByNumber: {}", e))?
+        .and_then(|b| b.base_fee_per_gas)
+        .ok_or_else(|| eyre!("L1 reports no base fee"))?;
+    let max_submission_cost = submission_fee(l1, inbox, l1_base_fee * FEE_MARGIN).await?;
+    let max_fee_per_gas = l2.get_gas_price().await.map_err(|e| eyre!("L2 eth_gasPrice: {}", e))? * FEE_MARGIN;
+    let ticket = Ticket {
+        to,
+        l2_call_value,
+        max_submission_cost,
+        // Refunds go back to the sender on L2.
+        excess_fee_refund: from,
+        call_value_refund: from,
+        gas_limit,
+        max_fee_per_gas,
+        data: Bytes::new(),
+    };
+    let deposit = max_submission_cost + l2_call_value + max_fee_per_gas * gas_limit;
+    let mut outcome = RetryableOutcome {
+        l2_call_value,
+        deposit,
+        l1_tx_hash: None,
+        l1_block_number: None,
+        ticket_id: None,
+        redeem_tx_hash: None,
+        l2_block_number: None,
+        status: "pending".into(),
+        error: None,
+    };
+
+    let mut tx = build_tx(2, from, inbox, deposit, fees)?;
+    tx.set_data(ticket.calldata());
+    let hash = match l1.send_transaction(tx, None).await {
+        Ok(pending) => pending.tx_hash(),
+        Err(e) => {
+            outcome.status = "rejected".into();
+            outcome.error = Some(e.to_string());
+            return Ok(outcome);
+        }
+    };
+    outcome.l1_tx_hash = Some(hash);
+    let Some(receipt) = wait_for_receipt(l1, hash, wait).await? else { return Ok(outcome) };
+    outcome.l1_block_number = receipt.block_number.map(|b| b.as_u64());
+    if receipt.status.is_some_and(|s| s.is_zero()) {
+        outcome.status = "reverted".into();
+        return Ok(outcome);
+    }
+
+    let l2_chain_id = l2.get_chainid().await.map_err(|e| eyre!("L2 eth_chainId: {}", e))?;
+    let ticket_id = match ticket.id(&receipt, l2_chain_id, deposit) {
+        Ok(id) => id,
+        Err(e) => {
+            outcome.status = "not submitted".into();
+            outcome.error = Some(e.to_string());
+            return Ok(outcome);
+        }
+    };
+    outcome.ticket_id = Some(ticket_id);
+    let Some(creation) = wait_for_receipt(l2, ticket_id, wait).await? else { return Ok(outcome) };
+    outcome.l2_block_number = creation.block_number.map(|b| b.as_u64());
+    if creation.status.is_some_and(|s| s.is_zero()) {
+        outcome.status = "ticket failed".into();
+        return Ok(outcome);
+    }
+
+    // RedeemScheduled(ticketId, retryTxHash, sequenceNum, donatedGas, gasDonor, maxRefund,
+    // submissionFeeRefund)
+    let topic = H256::from(keccak256("RedeemScheduled(bytes32,bytes32,uint64,uint64,address,uint256,uint256)"));
+    let Some(redeem) = creation
+        .logs
+        .iter()
+        .find(|log| log.address == ARB_RETRYABLE_TX && log.topics.len() == 4 && log.topics[0] == topic)
+        .map(|log| log.topics[2])
+    else {
+        // Too little L2 gas or fee cap: no auto-redeem was attempted.
+        outcome.status = "redeem failed".into();
+        outcome.error = Some("no auto-redeem scheduled; redeem the ticket manually".into());
+        return Ok(outcome);
+    };
+    outcome.redeem_tx_hash = Some(redeem);
+    if let Some(r) = wait_for_receipt(l2, redeem, wait).await? {
+        outcome.l2_block_number = r.block_number.map(|b| b.as_u64());
+        outcome.status = if r.status.is_some_and(|s| s.is_zero()) { "redeem failed" } else { "redeemed" }.into();
+    }
+    Ok(outcome)
+}
+
+/// `Inbox.calculateRetryableSubmissionFee(0, baseFee)`: the submission cost of a ticket
+/// without calldata.
+async fn submission_fee<M: Middleware>(l1: &M, inbox: Address, base_fee: U256) -> Result<U256> {
+    let mut data = id("calculateRetryableSubmissionFee(uint256,uint256)").to_vec();
+    data.extend(abi::encode(&[Token::Uint(U256::zero()), Token::Uint(base_fee)]));
+    let call: TypedTransaction = TransactionRequest::new().to(inbox).data(data).into();
+    let out = l1.call(&call, None).await.map_err(|e| eyre!("Inbox.calculateRetryableSubmissionFee: {}", e))?;
+    let word = out.get(..32).ok_or_else(|| eyre!("calculateRetryableSubmissionFee returned {} bytes; is the address an Arbitrum Inbox?", out.len()))?;
+    Ok(U256::from_big_endian(word))
+}
+
+struct Ticket {
+    to: Address,
+    l2_call_value: U256,
+    max_submission_cost: U256,
+    excess_fee_refund: Address,
+    call_value_refund: Address,
+    gas_limit: u64,
+    max_fee_per_gas: U256,
+    data: Bytes,
+}
+
+impl Ticket {
+    /// `createRetryableTicket(to, l2CallValue, maxSubmissionCost, excessFeeRefundAddress,
+    /// callValueRefundAddress, gasLimit, maxFeePerGas, data)`.
+    fn calldata(&self) -> Bytes {
+        let mut data =
+            id("createRetryableTicket(address,uint256,uint256,address,address,uint256,uint256,bytes)").to_vec();
+        data.extend(abi::encode(&[
+            Token::Address(self.to),
+            Token::Uint(self.l2_call_value),
+            Token::Uint(self.max_submission_cost),
+            Token::Address(self.excess_fee_refund),
+            Token::Address(self.call_value_refund),
+            Token::Uint(self.gas_limit.into()),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Bytes(self.data.to_vec()),
+        ]));
+        data.into()
+    }
+
+    /// Hash of the L2 submission: `keccak256(0x69 || rlp([chainId, messageNumber, sender,
+    /// l1BaseFee, deposit, maxFeePerGas, gasLimit, to, l2CallValue, callValueRefund,
+    /// maxSubmissionCost, excessFeeRefund, data]))`, with the message number, (aliased)
+    /// sender and L1 base fee taken from the bridge's `MessageDelivered` log.
+    fn id(&self, receipt: &TransactionReceipt, l2_chain_id: U256, deposit: U256) -> Result<H256> {
+        // MessageDelivered(messageIndex, beforeInboxAcc, inbox, kind, sender, messageDataHash,
+        // baseFeeL1, timestamp)
+        let topic =
+            H256::from(keccak256("MessageDelivered(uint256,bytes32,address,uint8,address,bytes32,uint256,uint64)"));
+        let log = receipt
+            .logs
+            .iter()
+            .find(|log| log.topics.len() == 3 && log.topics[0] == topic)
+            .ok_or_else(|| eyre!("no MessageDelivered log in the L1 receipt; is the address an Arbitrum Inbox?"))?;
+        let fields = abi::decode(
+            &[
+                ParamType::Address,
+                ParamType::Uint(8),
+                ParamType::Address,
+                ParamType::FixedBytes(32),
+                ParamType::Uint(256),
+                ParamType::Uint(64),
+            ],
+            &log.data,
+        )?;
+        let (Some(Token::Address(sender)), Some(Token::Uint(l1_base_fee))) = (fields.get(2), fields.get(4)) else {
+            return Err(eyre!("malformed MessageDelivered data"));
+        };
+
+        let mut s = RlpStream::new_list(13);
+        s.append(&l2_chain_id);
+        s.append(&log.topics[1].as_bytes());
+        s.append(sender);
+        s.append(l1_base_fee);
+        s.append(&deposit);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas_limit);
+        s.append(&self.to);
+        s.append(&self.l2_call_value);
+        s.append(&self.call_value_refund);
+        s.append(&self.max_submission_cost);
+        s.append(&self.excess_fee_refund);
+        s.append(&self.data.as_ref());
+        let mut envelope = vec![SUBMIT_RETRYABLE_TX_TYPE];
+        envelope.extend_from_slice(&s.out());
+        Ok(keccak256(envelope).into())
+    }
+}
@@ -0,0 +1,66 @@
+//! Ctrl-C handling: the first interrupt stops new transactions from being sent and gives
+//! in-flight ones a short grace period to be mined, so the run still ends with a (partial)
+//! summary. A second interrupt exits at once.
+
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::{sleep_until, Instant};
+use tracing::warn;
+
+/// Exit status of an interrupted run, the conventional 128 + SIGINT.
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Shared view of whether the run was interrupted, and when.
+#[derive(Clone, Debug)]
+pub struct Shutdown {
+    requested: watch::Receiver<Option<Instant>>,
+    grace: Duration,
+}
+
+impl Shutdown {
+    /// Listen for Ctrl-C in the background; in-flight waits end `grace` after the first one.
+    pub fn on_ctrl_c(grace: Duration) -> Self {
+        let (tx, requested) = watch::channel(None);
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_err() {
+                return;
+            }
+            warn!("interrupted: sending nothing more, waiting up to {:?} for in-flight receipts (Ctrl-C again to quit)", grace);
+            tx.send_replace(Some(Instant::now()));
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(EXIT_INTERRUPTED);
+            }
+        });
+        Self { requested, grace }
+    }
+
+    /// Whether Ctrl-C was pressed.
+    pub fn requested(&self) -> bool {
+        self.requested.borrow().is_some()
+    }
+
+    /// Resolves once Ctrl-C is pressed; at once if it already was.
+    pub async fn interrupted(&self) {
+        let mut requested = self.requested.clone();
+        if requested.wait_for(Option::is_some).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+
+    /// Whether the grace period after Ctrl-C is over.
+    pub fn grace_over(&self) -> bool {
+        self.requested.borrow().is_some_and(|at| Instant::now() >= at + self.grace)
+    }
+
+    /// Resolves once the grace period after Ctrl-C is over; never without a Ctrl-C.
+    pub async fn expired(&self) {
+        let mut requested = self.requested.clone();
+        // An error means the listener is gone, so no Ctrl-C will ever be seen.
+        let at = requested.wait_for(Option::is_some).await.map(|at| *at).unwrap_or_default();
+        match at {
+            Some(at) => sleep_until(at + self.grace).await,
+            None => std::future::pending().await,
+        }
+    }
+}
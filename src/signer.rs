@@ -0,0 +1,121 @@
+use std::fmt;
+
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+#[cfg(feature = "ledger")]
+use ethers::signers::{Ledger, LedgerError};
+use ethers::signers::{LocalWallet, Signer, WalletError};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::types::{Address, Signature, H256};
+
+/// A signer that can also sign a precomputed 32-byte digest.
+///
+/// Needed for envelopes ethers' `TypedTransaction` cannot represent (e.g. type-3 blob
+/// transactions), where we compute the sighash ourselves. Signers that cannot sign raw
+/// digests keep the default, which makes those types report as unsupported.
+pub trait HashSigner: Signer {
+    fn sign_hash(&self, _hash: H256) -> Result<Signature> {
+        Err(eyre!("signer cannot sign raw digests"))
+    }
+}
+
+impl HashSigner for LocalWallet {
+    fn sign_hash(&self, hash: H256) -> Result<Signature> {
+        Ok(LocalWallet::sign_hash(self, hash)?)
+    }
+}
+
+/// A Ledger only signs what it can show on screen, so no raw digests.
+#[cfg(feature = "ledger")]
+impl HashSigner for Ledger {}
+
+/// Signer backend picked at runtime.
+#[derive(Debug)]
+pub enum AnySigner {
+    Local(LocalWallet),
+    #[cfg(feature = "ledger")]
+    Ledger(Ledger),
+}
+
+#[derive(Debug)]
+pub enum AnySignerError {
+    Local(WalletError),
+    #[cfg(feature = "ledger")]
+    Ledger(LedgerError),
+}
+
+impl fmt::Display for AnySignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnySignerError::Local(e) => e.fmt(f),
+            #[cfg(feature = "ledger")]
+            AnySignerError::Ledger(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AnySignerError {}
+
+#[async_trait]
+impl Signer for AnySigner {
+    type Error = AnySignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(&self, message: S) -> Result<Signature, Self::Error> {
+        match self {
+            AnySigner::Local(s) => s.sign_message(message).await.map_err(AnySignerError::Local),
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(s) => Signer::sign_message(s, message).await.map_err(AnySignerError::Ledger),
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            AnySigner::Local(s) => s.sign_transaction(tx).await.map_err(AnySignerError::Local),
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(s) => s.sign_transaction(tx).await.map_err(AnySignerError::Ledger),
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(&self, payload: &T) -> Result<Signature, Self::Error> {
+        match self {
+            AnySigner::Local(s) => s.sign_typed_data(payload).await.map_err(AnySignerError::Local),
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(s) => Signer::sign_typed_data(s, payload).await.map_err(AnySignerError::Ledger),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            AnySigner::Local(s) => s.address(),
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(s) => Signer::address(s),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            AnySigner::Local(s) => s.chain_id(),
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(s) => Signer::chain_id(s),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            AnySigner::Local(s) => AnySigner::Local(s.with_chain_id(chain_id)),
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(s) => AnySigner::Ledger(s.with_chain_id(chain_id)),
+        }
+    }
+}
+
+impl HashSigner for AnySigner {
+    fn sign_hash(&self, hash: H256) -> Result<Signature> {
+        match self {
+            AnySigner::Local(s) => HashSigner::sign_hash(s, hash),
+            #[cfg(feature = "ledger")]
+            AnySigner::Ledger(s) => HashSigner::sign_hash(s, hash),
+        }
+    }
+}
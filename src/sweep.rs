@@ -0,0 +1,93 @@
+//! Send an account's whole balance, less the fee of doing so, back to a treasury or faucet
+//! address, to clean up test accounts after a run.
+
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockNumber, H256, U256};
+use serde::Serialize;
+
+use crate::scenarios::wait_for_receipt;
+use crate::{build_tx, Fees, HashSigner};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Sweep {
+    pub from: Address,
+    pub to: Address,
+    pub balance: U256,
+    /// Gas limit times the highest price the transfer may pay, kept back from `balance`.
+    pub fee: U256,
+    /// Amount sent: `balance - fee`.
+    pub value: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    /// `mined`, `pending` (not mined in time), `rejected`, or `empty` when the balance does
+    /// not cover the fee.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+}
+
+/// Sweep the client's balance to `to` with a type-`tx_type` transfer at `fees`, waiting up
+/// to `wait` for it to be mined. A legacy transfer leaves exactly zero behind; type 2 leaves
+/// the part of `maxFeePerGas` the base fee did not use.
+pub async fn run<M, S>(
+    client: &SignerMiddleware<M, S>,
+    to: Address,
+    tx_type: u8,
+    fees: Fees,
+    wait: Duration,
+) -> Result<Sweep>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let from = client.address();
+    // Pending, so transfers still in the pool are not counted twice.
+    let balance = client
+        .get_balance(from, Some(BlockNumber::Pending.into()))
+        .await
+        .map_err(|e| eyre!("eth_getBalance: {}", e))?;
+    let mut tx = build_tx(tx_type, from, to, U256::zero(), fees)?;
+    let gas = client.estimate_gas(&tx, None).await.map_err(|e| eyre!("eth_estimateGas: {}", e))?;
+    let price = match tx_type {
+        2 => fees.max_fee_per_gas,
+        _ => fees.gas_price.unwrap_or(fees.max_fee_per_gas),
+    };
+    let fee = gas * price;
+    let mut sweep = Sweep {
+        from,
+        to,
+        balance,
+        fee,
+        value: balance.saturating_sub(fee),
+        tx_hash: None,
+        status: "empty".into(),
+        error: None,
+        block_number: None,
+    };
+    if sweep.value.is_zero() {
+        return Ok(sweep);
+    }
+
+    tx.set_value(sweep.value).set_gas(gas);
+    let hash = match client.send_transaction(tx, None).await {
+        Ok(pending) => pending.tx_hash(),
+        Err(e) => {
+            sweep.status = "rejected".into();
+            sweep.error = Some(e.to_string());
+            return Ok(sweep);
+        }
+    };
+    sweep.tx_hash = Some(hash);
+    sweep.status = "pending".into();
+    if let Some(receipt) = wait_for_receipt(client, hash, wait).await? {
+        sweep.status = "mined".into();
+        sweep.block_number = receipt.block_number.map(|b| b.as_u64());
+    }
+    Ok(sweep)
+}
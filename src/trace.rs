@@ -0,0 +1,112 @@
+//! `debug_traceTransaction` with geth's `callTracer`, condensed into a gas breakdown and the
+//! internal calls, to explain why a "simple transfer" used more gas than expected.
+
+use std::fmt;
+
+use eyre::{eyre, Result};
+use ethers::providers::Middleware;
+use ethers::types::{Address, Bytes, H256, U256};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::intrinsic::{calldata_gas, CREATE_GAS, TX_GAS};
+
+/// One frame as `callTracer` reports it.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CallFrame {
+    #[serde(rename = "type")]
+    call_type: String,
+    to: Option<Address>,
+    value: Option<U256>,
+    gas_used: U256,
+    #[serde(default)]
+    input: Bytes,
+    error: Option<String>,
+    #[serde(default)]
+    calls: Vec<CallFrame>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TraceReport {
+    /// `CALL` or `CREATE` for the top-level frame.
+    pub call_type: String,
+    /// Gas the tracer charges the whole transaction.
+    pub gas_used: U256,
+    /// Base cost plus calldata, before any code runs (access lists not counted).
+    pub intrinsic_gas: u64,
+    /// `gas_used` beyond the intrinsic cost: code execution, storage, refunds.
+    pub execution_gas: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Every nested call, depth-first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<InternalCall>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InternalCall {
+    /// 1 for calls made by the transaction's own target.
+    pub depth: usize,
+    pub call_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+    pub gas_used: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl fmt::Display for TraceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} gas {} (intrinsic {}, execution {}), {} internal call(s)",
+            self.call_type,
+            self.gas_used,
+            self.intrinsic_gas,
+            self.execution_gas,
+            self.calls.len()
+        )?;
+        if let Some(e) = &self.error {
+            write!(f, ", error: {}", e)?;
+        }
+        Ok(())
+    }
+}
+
+/// Trace the mined transaction `hash`. Needs a node with the `debug` namespace enabled.
+pub async fn trace<M: Middleware>(client: &M, hash: H256) -> Result<TraceReport> {
+    let frame: CallFrame = client
+        .provider()
+        .request("debug_traceTransaction", (hash, json!({ "tracer": "callTracer" })))
+        .await
+        .map_err(|e| eyre!("debug_traceTransaction: {}", e))?;
+    let create = frame.call_type.starts_with("CREATE");
+    let intrinsic_gas = TX_GAS + if create { CREATE_GAS } else { 0 } + calldata_gas(&frame.input);
+    let mut calls = Vec::new();
+    flatten(&frame.calls, 1, &mut calls);
+    Ok(TraceReport {
+        execution_gas: frame.gas_used.saturating_sub(intrinsic_gas.into()),
+        call_type: frame.call_type,
+        gas_used: frame.gas_used,
+        intrinsic_gas,
+        error: frame.error,
+        calls,
+    })
+}
+
+fn flatten(frames: &[CallFrame], depth: usize, out: &mut Vec<InternalCall>) {
+    for frame in frames {
+        out.push(InternalCall {
+            depth,
+            call_type: frame.call_type.clone(),
+            to: frame.to,
+            value: frame.value,
+            gas_used: frame.gas_used,
+            error: frame.error.clone(),
+        });
+        flatten(&frame.calls, depth + 1, out);
+    }
+}
@@ -0,0 +1,523 @@
+//! JSON-RPC transport chosen from the RPC URL scheme (none for an IPC socket path), so the
+//! rest of the tool can work with a single `Provider<Transport>` type. A comma-separated URL
+//! list becomes a fallback transport.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+use ethers::providers::{
+    Authorization, ConnectionDetails, Http, HttpClientError, Ipc, IpcError, JsonRpcClient, JsonRpcError, Middleware,
+    Provider, ProviderError, PubsubClient, RpcError, Ws, WsClientError,
+};
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use ethers::types::H256;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::watch;
+use tokio::time::{timeout, Instant};
+use tracing::warn;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Certificate, Proxy, Url};
+
+/// How long a fallback endpoint may take to answer before the next one is tried.
+const FALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug)]
+pub enum Transport {
+    /// The reqwest client is kept for JSON-RPC batches, which `Http` does not send.
+    Http(Http, reqwest::Client),
+    Ws(Ws),
+    /// A node's IPC socket (a Unix socket, or a named pipe on Windows).
+    Ipc(Ipc),
+    Fallback(Arc<Fallback>),
+    /// Counts the requests made through another transport and holds them to a rate and a
+    /// total (see [`Transport::limited`]).
+    Limited(Arc<Limited>),
+    /// Refuses every request, for signing without ever contacting a node.
+    Offline,
+}
+
+impl Transport {
+    /// Connect using `ws://`/`wss://` for WebSocket, IPC for a path without a scheme (e.g.
+    /// `/var/lib/geth/geth.ipc`) and plain HTTP(S) otherwise; several comma-separated URLs
+    /// are tried in order (see [`Fallback`]).
+    pub async fn connect(url: &str) -> Result<Self> {
+        Self::connect_with(url, &ClientOptions::default()).await
+    }
+
+    /// [`Transport::connect`] with `options` applied to every endpoint.
+    pub async fn connect_with(url: &str, options: &ClientOptions) -> Result<Self> {
+        let urls: Vec<&str> = url.split(',').map(str::trim).filter(|u| !u.is_empty()).collect();
+        let [url] = urls.as_slice() else {
+            let mut endpoints = Vec::with_capacity(urls.len());
+            for url in urls {
+                match Self::connect_one(url, options).await {
+                    Ok(transport) => endpoints.push((redact(url), transport)),
+                    Err(e) => warn!("skipping RPC endpoint {}: {}", redact(url), e),
+                }
+            }
+            if endpoints.is_empty() {
+                return Err(eyre!("no RPC endpoint could be reached"));
+            }
+            return Ok(Transport::Fallback(Arc::new(Fallback {
+                endpoints,
+                active: AtomicUsize::new(0),
+                served: Mutex::default(),
+            })));
+        };
+        Self::connect_one(url, options).await
+    }
+
+    async fn connect_one(url: &str, options: &ClientOptions) -> Result<Self> {
+        let headers = &options.headers;
+        if is_ipc_path(url) {
+            if !headers.is_empty() || options.proxy.is_some() || !options.ca_certs.is_empty() || options.insecure_tls {
+                return Err(eyre!("RPC_HEADERS, RPC_PROXY, RPC_CA_CERT and RPC_INSECURE_TLS do not apply to an IPC socket"));
+            }
+            return Ok(Transport::Ipc(Ipc::connect(url).await.map_err(|e| eyre!("connecting to {}: {}", url, e))?));
+        }
+        if !url.contains("://") {
+            return Err(eyre!("RPC_URL needs a scheme (http://, https://, ws:// or wss://) or an IPC socket path: {}", redact(url)));
+        }
+        let mut url: Url = url.parse()?;
+        // reqwest lets URL credentials override the header and the WebSocket handshake would do
+        // the opposite, so neither is picked silently.
+        if headers.contains_key(AUTHORIZATION) && !url.username().is_empty() {
+            return Err(eyre!("RPC_URL carries credentials and RPC_HEADERS sets Authorization; use one or the other"));
+        }
+        if url.scheme() == "ws" || url.scheme() == "wss" {
+            if options.proxy.is_some() || !options.ca_certs.is_empty() || options.insecure_tls {
+                return Err(eyre!("RPC_PROXY, RPC_CA_CERT and RPC_INSECURE_TLS only apply to HTTP(S) endpoints"));
+            }
+            if let Some(name) = headers.keys().find(|name| **name != AUTHORIZATION) {
+                return Err(eyre!("RPC_HEADERS: {} cannot be sent over WebSocket, only Authorization", name));
+            }
+            let mut auth = match headers.get(AUTHORIZATION) {
+                Some(value) => Some(Authorization::raw(value.to_str()?)),
+                None => None,
+            };
+            // The WebSocket handshake ignores credentials in the URL; send them as a header.
+            if !url.username().is_empty() {
+                auth = Some(Authorization::basic(url.username(), url.password().unwrap_or_default()));
+                let _ = url.set_username("");
+                let _ = url.set_password(None);
+            }
+            Ok(Transport::Ws(Ws::connect(ConnectionDetails::new(url, auth)).await?))
+        } else {
+            let client = options.http_client()?;
+            Ok(Transport::Http(Http::new_with_client(url, client.clone()), client))
+        }
+    }
+
+    /// Endpoint (scheme and host) that accepted the transaction `hash`, when several are
+    /// configured.
+    pub fn served_by(&self, hash: H256) -> Option<String> {
+        if let Transport::Limited(l) = self {
+            return l.inner.served_by(hash);
+        }
+        let Transport::Fallback(f) = self else { return None };
+        let index = *f.served.lock().unwrap().get(&hash)?;
+        Some(f.endpoints[index].0.clone())
+    }
+
+    /// Wrap in a transport that counts requests, spaces them to at most `max_rps` per second
+    /// (bursts of up to one second's worth) and fails every request after the first
+    /// `max_requests`.
+    pub fn limited(self, max_rps: Option<f64>, max_requests: Option<u64>) -> Self {
+        let bucket = max_rps.map(|rate| Mutex::new(Bucket { tokens: rate.max(1.0), refilled: Instant::now() }));
+        Transport::Limited(Arc::new(Limited { inner: self, max_rps, bucket, max_requests, used: AtomicU64::new(0) }))
+    }
+
+    /// Requests made so far, when counted (see [`Transport::limited`]).
+    pub fn requests(&self) -> Option<u64> {
+        let Transport::Limited(l) = self else { return None };
+        Some(l.used.load(Ordering::Relaxed))
+    }
+
+    /// Call `method` once per entry of `params` in a single JSON-RPC batch, returning each
+    /// call's result or error in order. `None` when the transport is not plain HTTP or the
+    /// endpoint answers a batch with anything but a JSON array, i.e. does not take batches.
+    pub async fn batch(&self, method: &str, params: &[Value]) -> Result<Option<Vec<Result<Value, JsonRpcError>>>> {
+        if let Transport::Limited(l) = self {
+            // Each call of a batch counts, as providers meter them. A batch that would overrun
+            // the budget is refused whole, so its calls go out one by one up to the limit.
+            if l.max_requests.is_some_and(|max| l.used.load(Ordering::Relaxed) + params.len() as u64 > max) {
+                return Ok(None);
+            }
+            for _ in params {
+                l.acquire().await.map_err(|e| eyre!("{} batch: {}", method, e))?;
+            }
+            return Box::pin(l.inner.batch(method, params)).await;
+        }
+        let Transport::Http(http, client) = self else { return Ok(None) };
+        let requests: Vec<Value> = params
+            .iter()
+            .enumerate()
+            .map(|(id, params)| json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+            .collect();
+        let response = client.post(http.url().clone()).json(&requests).send().await.map_err(|e| eyre!("{} batch: {}", method, e))?;
+        // An HTTP error status or a body that is not JSON counts as a refusal too.
+        let Ok(Value::Array(responses)) = response.json::<Value>().await else { return Ok(None) };
+        let mut results: Vec<Option<Result<Value, JsonRpcError>>> = vec![None; params.len()];
+        for mut response in responses {
+            // Responses may come in any order; the id says which request each answers.
+            let Some(slot) = response["id"].as_u64().and_then(|id| results.get_mut(id as usize)) else { continue };
+            *slot = Some(match response.get_mut("error") {
+                Some(error) => Err(serde_json::from_value(error.take())
+                    .map_err(|e| eyre!("{} batch: malformed error: {}", method, e))?),
+                None => Ok(response["result"].take()),
+            });
+        }
+        results
+            .into_iter()
+            .map(|result| result.ok_or_else(|| eyre!("{} batch: a request got no response", method)))
+            .collect::<Result<_>>()
+            .map(Some)
+    }
+
+    /// Latest block number pushed by a `newHeads` subscription, if the transport supports
+    /// subscriptions. The sender side lives in a background task for as long as the
+    /// subscription stays open.
+    /// A fallback transport polls instead, since its first endpoint may be the one that dies.
+    pub fn head_notifications(&self) -> Option<watch::Receiver<u64>> {
+        if let Transport::Limited(l) = self {
+            return l.inner.head_notifications();
+        }
+        match self {
+            Transport::Ws(ws) => Some(subscribe_heads(ws.clone())),
+            Transport::Ipc(ipc) => Some(subscribe_heads(ipc.clone())),
+            _ => None,
+        }
+    }
+}
+
+fn subscribe_heads<P: PubsubClient + 'static>(client: P) -> watch::Receiver<u64> {
+    let (tx, rx) = watch::channel(0u64);
+    let provider = Provider::new(client);
+    tokio::spawn(async move {
+        let Ok(mut heads) = provider.subscribe_blocks().await else { return };
+        while let Some(block) = heads.next().await {
+            if let Some(n) = block.number
+                && tx.send(n.as_u64()).is_err()
+            {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Whether `url` is a path to an IPC socket rather than a URL: it has no scheme and names a
+/// file that exists, or is absolute, starts with `./` or ends in `.ipc`, so a URL that only
+/// lacks its scheme (`localhost:8545`) is not mistaken for one.
+fn is_ipc_path(url: &str) -> bool {
+    !url.contains("://")
+        && (Path::new(url).exists() || Path::new(url).is_absolute() || url.starts_with("./") || url.ends_with(".ipc"))
+}
+
+/// Several endpoints tried in order. Requests stick to the last endpoint that answered and
+/// move on to the next one when it fails at the transport level or does not answer within
+/// `FALLBACK_TIMEOUT`; JSON-RPC error responses are returned as they are, since another node
+/// would reject the same request.
+#[derive(Debug)]
+pub struct Fallback {
+    /// Redacted URL and transport of each endpoint.
+    endpoints: Vec<(String, Transport)>,
+    active: AtomicUsize,
+    /// Which endpoint accepted each `eth_sendRawTransaction`, by tx hash.
+    served: Mutex<HashMap<H256, usize>>,
+}
+
+impl Fallback {
+    async fn request<R: DeserializeOwned>(&self, method: &str, params: Value) -> Result<R, TransportError> {
+        let start = self.active.load(Ordering::Relaxed);
+        let mut last = None;
+        for i in 0..self.endpoints.len() {
+            let index = (start + i) % self.endpoints.len();
+            let (url, transport) = &self.endpoints[index];
+            let outcome = match timeout(FALLBACK_TIMEOUT, transport.request::<_, Value>(method, params.clone())).await {
+                Ok(outcome) => outcome,
+                Err(_) => Err(TransportError::Timeout(url.clone())),
+            };
+            match outcome {
+                Ok(value) => {
+                    if index != start {
+                        warn!("RPC endpoint {} failed; switched to {}", self.endpoints[start].0, url);
+                        self.active.store(index, Ordering::Relaxed);
+                    }
+                    if method == "eth_sendRawTransaction"
+                        && let Ok(hash) = serde_json::from_value::<H256>(value.clone())
+                    {
+                        self.served.lock().unwrap().insert(hash, index);
+                    }
+                    return serde_json::from_value(value).map_err(TransportError::Serde);
+                }
+                Err(e) if e.as_error_response().is_some() => return Err(e),
+                Err(e) => last = Some(e),
+            }
+        }
+        Err(last.expect("fallback has at least one endpoint"))
+    }
+}
+
+/// A transport held to a request rate and budget.
+#[derive(Debug)]
+pub struct Limited {
+    inner: Transport,
+    max_rps: Option<f64>,
+    /// Token bucket refilled at `max_rps` per second.
+    bucket: Option<Mutex<Bucket>>,
+    max_requests: Option<u64>,
+    used: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Limited {
+    /// Take one request from the budget, then wait for a token.
+    async fn acquire(&self) -> Result<(), TransportError> {
+        let used = self.used.fetch_add(1, Ordering::Relaxed);
+        if let Some(max) = self.max_requests
+            && used >= max
+        {
+            self.used.fetch_sub(1, Ordering::Relaxed);
+            return Err(TransportError::Budget(max));
+        }
+        let (Some(rate), Some(bucket)) = (self.max_rps, &self.bucket) else { return Ok(()) };
+        loop {
+            let wait = {
+                let mut bucket = bucket.lock().unwrap();
+                let now = Instant::now();
+                let refill = now.duration_since(bucket.refilled).as_secs_f64() * rate;
+                bucket.tokens = (bucket.tokens + refill).min(rate.max(1.0));
+                bucket.refilled = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return Ok(());
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / rate)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TransportError {
+    Http(HttpClientError),
+    Ws(WsClientError),
+    Ipc(IpcError),
+    /// A fallback endpoint did not answer in time.
+    Timeout(String),
+    /// Method requested through the offline transport.
+    Offline(String),
+    /// `MAX_REQUESTS` were already made.
+    Budget(u64),
+    Serde(serde_json::Error),
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::Http(e) => e.fmt(f),
+            TransportError::Ws(e) => e.fmt(f),
+            TransportError::Ipc(e) => e.fmt(f),
+            TransportError::Timeout(url) => write!(f, "{} timed out after {:?}", url, FALLBACK_TIMEOUT),
+            TransportError::Serde(e) => e.fmt(f),
+            TransportError::Offline(method) => write!(f, "offline mode: refusing to call {}", method),
+            TransportError::Budget(max) => write!(f, "request budget used up: MAX_REQUESTS={}", max),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+impl RpcError for TransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            TransportError::Http(e) => e.as_error_response(),
+            TransportError::Ws(e) => e.as_error_response(),
+            TransportError::Ipc(e) => e.as_error_response(),
+            TransportError::Timeout(_)
+            | TransportError::Serde(_)
+            | TransportError::Offline(_)
+            | TransportError::Budget(_) => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            TransportError::Http(e) => e.as_serde_error(),
+            TransportError::Ws(e) => e.as_serde_error(),
+            TransportError::Ipc(e) => e.as_serde_error(),
+            TransportError::Timeout(_) | TransportError::Offline(_) | TransportError::Budget(_) => None,
+            TransportError::Serde(e) => Some(e),
+        }
+    }
+}
+
+impl From<TransportError> for ProviderError {
+    fn from(e: TransportError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(e))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for Transport {
+    type Error = TransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            Transport::Http(c, _) => c.request(method, params).await.map_err(TransportError::Http),
+            Transport::Ws(c) => c.request(method, params).await.map_err(TransportError::Ws),
+            Transport::Ipc(c) => c.request(method, params).await.map_err(TransportError::Ipc),
+            Transport::Fallback(f) => {
+                f.request(method, serde_json::to_value(params).map_err(TransportError::Serde)?).await
+            }
+            Transport::Limited(l) => {
+                l.acquire().await?;
+                l.inner.request(method, params).await
+            }
+            Transport::Offline => Err(TransportError::Offline(method.to_string())),
+        }
+    }
+}
+
+/// How endpoints are reached beyond their URL.
+#[derive(Clone, Debug, Default)]
+pub struct ClientOptions {
+    /// Sent with every request (see [`parse_headers`]). Over WebSocket only the handshake
+    /// carries them, and `Authorization` is the only header it can take.
+    pub headers: HeaderMap,
+    /// Proxy for every HTTP(S) request. Without one, `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY`
+    /// and `NO_PROXY` are followed as usual.
+    pub proxy: Option<String>,
+    /// CA certificates trusted on top of the usual roots, e.g. a devnet's own.
+    pub ca_certs: Vec<Certificate>,
+    /// Accept any TLS certificate, self-signed or for another host.
+    pub insecure_tls: bool,
+}
+
+impl ClientOptions {
+    /// The HTTP client for an endpoint. reqwest sends `user:password@` from a URL as basic
+    /// auth by itself.
+    pub fn http_client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder().default_headers(self.headers.clone());
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(Proxy::all(proxy.as_str()).map_err(|e| eyre!("RPC_PROXY: {}", e))?);
+        }
+        for cert in &self.ca_certs {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        Ok(builder.danger_accept_invalid_certs(self.insecure_tls).build()?)
+    }
+}
+
+/// The certificates of a PEM file, which may hold several.
+pub fn load_certificates(path: &Path) -> Result<Vec<Certificate>> {
+    let pem = std::fs::read_to_string(path).map_err(|e| eyre!("reading {}: {}", path.display(), e))?;
+    const END: &str = "-----END CERTIFICATE-----";
+    let certs: Vec<Certificate> = pem
+        .split_inclusive(END)
+        .filter(|block| block.contains(END))
+        .map(|block| Certificate::from_pem(block.trim().as_bytes()))
+        .collect::<Result<_, _>>()
+        .map_err(|e| eyre!("{}: {}", path.display(), e))?;
+    if certs.is_empty() {
+        return Err(eyre!("{}: no PEM certificate found", path.display()));
+    }
+    Ok(certs)
+}
+
+/// `Name: value` headers, as given in `RPC_HEADERS`. Values are marked sensitive so they
+/// stay out of debug output.
+pub fn parse_headers(headers: &[String]) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for header in headers.iter().map(|h| h.trim()).filter(|h| !h.is_empty()) {
+        let (name, value) = header.split_once(':').ok_or_else(|| eyre!("RPC_HEADERS: expected `Name: value`, got {:?}", header))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|e| eyre!("RPC_HEADERS: {:?}: {}", name, e))?;
+        let mut value = HeaderValue::from_str(value.trim()).map_err(|e| eyre!("RPC_HEADERS: {}: {}", name, e))?;
+        value.set_sensitive(true);
+        map.append(name, value);
+    }
+    Ok(map)
+}
+
+/// Keep only scheme and host of each comma-separated URL: RPC paths and query strings often
+/// carry API keys.
+pub fn redact(url: &str) -> String {
+    let redact_one = |url: &str| {
+        // A socket path carries no credentials.
+        if is_ipc_path(url) {
+            return url.to_string();
+        }
+        let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+        let host = rest.split(['/', '?']).next().unwrap_or_default();
+        let host = host.rsplit('@').next().unwrap_or(host);
+        if scheme.is_empty() { host.to_string() } else { format!("{}://{}", scheme, host) }
+    };
+    url.split(',').map(|u| redact_one(u.trim())).collect::<Vec<_>>().join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(list: &[&str]) -> Result<HeaderMap> {
+        parse_headers(&list.iter().map(|h| h.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn redacts_urls() {
+        assert_eq!(redact("https://user:pw@rpc.example/v3/key?x=1"), "https://rpc.example");
+        assert_eq!(redact("wss://a.example/key, http://b.example:8545"), "wss://a.example,http://b.example:8545");
+    }
+
+    #[test]
+    fn parses_headers() {
+        let map = headers(&["Authorization: Bearer abc123", " x-api-key:def:456 ", ""]).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[AUTHORIZATION], "Bearer abc123");
+        assert_eq!(map["X-Api-Key"], "def:456");
+        assert!(map.values().all(HeaderValue::is_sensitive));
+    }
+
+    #[test]
+    fn keeps_repeated_headers() {
+        let map = headers(&["x-tag: a", "X-Tag: b"]).unwrap();
+        assert_eq!(map.get_all("x-tag").iter().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        assert!(headers(&["no colon"]).is_err());
+        assert!(headers(&["bad name: x"]).is_err());
+        assert!(headers(&["x-ok: line\nbreak"]).is_err());
+    }
+
+    #[test]
+    fn ipc_paths_need_to_look_like_paths() {
+        assert!(is_ipc_path("/var/lib/geth/geth.ipc"));
+        assert!(is_ipc_path("./geth.ipc"));
+        assert!(is_ipc_path("reth.ipc"));
+        assert!(!is_ipc_path("localhost:8545"));
+        assert!(!is_ipc_path("rpc.example"));
+        assert!(!is_ipc_path("http://127.0.0.1:8545"));
+        assert_eq!(redact("/var/lib/geth/geth.ipc"), "/var/lib/geth/geth.ipc");
+    }
+}
@@ -0,0 +1,128 @@
+//! Where a transaction that never got mined sits in the node's pool (`txpool_*`, geth and
+//! compatible clients), and what is ahead of it.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use eyre::{eyre, Result};
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockNumber, Transaction, H256, U256};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PoolReport {
+    /// `pending` (executable), `queued` (waiting on a lower nonce) or `absent` (evicted,
+    /// replaced, or never propagated).
+    pub state: String,
+    /// Size of the node's whole pool, from `txpool_status`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_pending: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_queued: Option<u64>,
+    /// What keeps the transaction from being mined, as far as the pool tells.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+}
+
+impl fmt::Display for PoolReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.state)?;
+        if let (Some(pending), Some(queued)) = (self.pool_pending, self.pool_queued) {
+            write!(f, " (pool: {} pending, {} queued)", pending, queued)?;
+        }
+        if !self.reasons.is_empty() {
+            write!(f, ": {}", self.reasons.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+/// `txpool_contentFrom`: one account's slice of `txpool_content`, keyed by nonce.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AccountPool {
+    #[serde(default)]
+    pending: BTreeMap<String, Transaction>,
+    #[serde(default)]
+    queued: BTreeMap<String, Transaction>,
+}
+
+/// Look `hash`, sent by `from` with `nonce`, up in the node's pool. Fails when the node
+/// exposes no `txpool` namespace.
+pub async fn inspect<M: Middleware>(client: &M, from: Address, hash: H256, nonce: Option<U256>) -> Result<PoolReport> {
+    let ours = account_pool(client, from).await?;
+    let status = client.txpool_status().await.ok();
+    let in_pool = |txs: &BTreeMap<String, Transaction>| txs.values().any(|tx| tx.hash == hash);
+    let state = if in_pool(&ours.pending) {
+        "pending"
+    } else if in_pool(&ours.queued) {
+        "queued"
+    } else {
+        "absent"
+    };
+
+    let pooled: Vec<&Transaction> = ours.pending.values().chain(ours.queued.values()).collect();
+    let mut reasons = Vec::new();
+    let sent = match nonce {
+        Some(n) => Some(n),
+        None => pooled.iter().find(|tx| tx.hash == hash).map(|tx| tx.nonce),
+    };
+    if let Some(nonce) = sent {
+        let mined = client
+            .get_transaction_count(from, Some(BlockNumber::Latest.into()))
+            .await
+            .map_err(|e| eyre!("eth_getTransactionCount: {}", e))?;
+        if nonce < mined {
+            reasons.push(format!("nonce {} is already used on chain, so another transaction replaced it", nonce));
+        }
+        // Every lower nonce has to be mined first.
+        let mut n = mined;
+        while n < nonce {
+            match pooled.iter().find(|tx| tx.nonce == n) {
+                Some(tx) => reasons.push(format!("nonce {} (0x{:x}) is ahead of it", n, tx.hash)),
+                None => reasons.push(format!("nonce {} is missing from the pool, leaving a gap", n)),
+            }
+            n += U256::one();
+        }
+        if let Some(other) = pooled.iter().find(|tx| tx.nonce == nonce && tx.hash != hash) {
+            reasons.push(format!("0x{:x} holds the same nonce", other.hash));
+        }
+    }
+    if let Some(tx) = pooled.iter().find(|tx| tx.hash == hash)
+        && let Ok(Some(block)) = client.get_block(BlockNumber::Latest).await
+        && let Some(base_fee) = block.base_fee_per_gas
+    {
+        let cap = tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default();
+        if cap < base_fee {
+            reasons.push(format!("fee cap {} is below the base fee {}", cap, base_fee));
+        }
+    }
+    if state == "absent" && reasons.is_empty() {
+        reasons.push("not in the pool: evicted, or never accepted by this node".into());
+    }
+
+    Ok(PoolReport {
+        state: state.into(),
+        pool_pending: status.as_ref().map(|s| s.pending.as_u64()),
+        pool_queued: status.as_ref().map(|s| s.queued.as_u64()),
+        reasons,
+    })
+}
+
+/// Every transaction of `from` in the node's pool, pending or queued.
+pub async fn pooled<M: Middleware>(client: &M, from: Address) -> Result<Vec<Transaction>> {
+    let pool = account_pool(client, from).await?;
+    Ok(pool.pending.into_values().chain(pool.queued.into_values()).collect())
+}
+
+/// `from`'s pooled transactions: `txpool_contentFrom` where supported, else filtered out of
+/// the full `txpool_content`.
+async fn account_pool<M: Middleware>(client: &M, from: Address) -> Result<AccountPool> {
+    if let Ok(pool) = client.provider().request::<_, AccountPool>("txpool_contentFrom", [from]).await {
+        return Ok(pool);
+    }
+    let mut content = client.txpool_content().await.map_err(|e| eyre!("txpool_content: {}", e))?;
+    Ok(AccountPool {
+        pending: content.pending.remove(&from).unwrap_or_default(),
+        queued: content.queued.remove(&from).unwrap_or_default(),
+    })
+}
@@ -0,0 +1,157 @@
+//! EIP-712 typed-data signatures checked the way dapps and contracts check them: recovered
+//! locally, through the chain's `ecrecover` precompile, and optionally by an EIP-1271
+//! contract's `isValidSignature`, all via `eth_call`.
+
+use std::fmt;
+
+use eyre::{eyre, Result};
+use ethers::abi::{self, Token};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::{Eip712, TypedData};
+use ethers::types::{Address, Bytes, TransactionRequest, H160, H256};
+use ethers::utils::id;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::HashSigner;
+
+/// The `ecrecover` precompile.
+const ECRECOVER: Address = H160([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+/// `bytes4(keccak256("isValidSignature(bytes32,bytes)"))`, returned by a contract that
+/// accepts the signature.
+const EIP1271_MAGIC: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+/// Verifying contract of the sample payload when no verifier is given, as in EIP-712's example.
+const SAMPLE_VERIFYING_CONTRACT: &str = "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC";
+
+/// EIP-712's own example, a `Mail` from Cow to Bob, on `chain_id`.
+pub fn sample(chain_id: u64, verifying_contract: Option<Address>) -> Result<TypedData> {
+    let verifying_contract = match verifying_contract {
+        Some(address) => format!("{:?}", address),
+        None => SAMPLE_VERIFYING_CONTRACT.to_string(),
+    };
+    let payload = json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": chain_id,
+            "verifyingContract": verifying_contract
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    });
+    Ok(serde_json::from_value(payload)?)
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Verification {
+    pub signer: Address,
+    /// The EIP-712 digest that was signed.
+    pub digest: H256,
+    pub signature: Bytes,
+    pub checks: Vec<Check>,
+}
+
+impl Verification {
+    pub fn valid(&self) -> bool {
+        self.checks.iter().all(|c| c.ok)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Check {
+    /// `local`, `ecrecover` or `eip1271`.
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.name, if self.ok { "ok" } else { "FAILED" }, self.detail)
+    }
+}
+
+/// Sign `payload` with the client's signer and verify the signature locally, through the
+/// `ecrecover` precompile, and with `verifier`'s EIP-1271 `isValidSignature` when given.
+pub async fn sign_and_verify<M, S>(client: &SignerMiddleware<M, S>, payload: &TypedData, verifier: Option<Address>) -> Result<Verification>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let signer = client.address();
+    let digest = H256::from(payload.encode_eip712().map_err(|e| eyre!("encoding the typed data: {}", e))?);
+    let sig = client.signer().sign_typed_data(payload).await.map_err(|e| eyre!("signing the typed data: {}", e))?;
+    let signature = Bytes::from(sig.to_vec());
+
+    let mut checks = Vec::new();
+    checks.push(match sig.recover(digest) {
+        Ok(recovered) => Check {
+            name: "local",
+            ok: recovered == signer,
+            detail: format!("recovers {:?}", recovered),
+        },
+        Err(e) => Check { name: "local", ok: false, detail: e.to_string() },
+    });
+
+    // ecrecover(hash, v, r, s) takes v as 27 or 28.
+    let v = if sig.v < 27 { sig.v + 27 } else { sig.v };
+    let input = abi::encode(&[
+        Token::FixedBytes(digest.as_bytes().to_vec()),
+        Token::Uint(v.into()),
+        Token::Uint(sig.r),
+        Token::Uint(sig.s),
+    ]);
+    checks.push(match call(client, ECRECOVER, input).await {
+        // An empty result means the precompile rejected the input.
+        Ok(out) if out.len() == 32 => {
+            let recovered = Address::from_slice(&out[12..]);
+            Check { name: "ecrecover", ok: recovered == signer, detail: format!("recovers {:?}", recovered) }
+        }
+        Ok(out) => Check { name: "ecrecover", ok: false, detail: format!("returned {} bytes: {}", out.len(), out) },
+        Err(e) => Check { name: "ecrecover", ok: false, detail: e.to_string() },
+    });
+
+    if let Some(verifier) = verifier {
+        let mut input = id("isValidSignature(bytes32,bytes)").to_vec();
+        input.extend(abi::encode(&[Token::FixedBytes(digest.as_bytes().to_vec()), Token::Bytes(signature.to_vec())]));
+        checks.push(match call(client, verifier, input).await {
+            Ok(out) if out.len() >= 4 && out[..4] == EIP1271_MAGIC => {
+                Check { name: "eip1271", ok: true, detail: format!("{:?} returned the magic value", verifier) }
+            }
+            Ok(out) => Check { name: "eip1271", ok: false, detail: format!("{:?} returned {}", verifier, out) },
+            Err(e) => Check { name: "eip1271", ok: false, detail: format!("{:?} reverted: {}", verifier, e) },
+        });
+    }
+
+    Ok(Verification { signer, digest, signature, checks })
+}
+
+async fn call<M: Middleware>(client: &M, to: Address, data: Vec<u8>) -> Result<Bytes> {
+    let tx: TypedTransaction = TransactionRequest::new().to(to).data(data).into();
+    client.call(&tx, None).await.map_err(|e| eyre!("eth_call: {}", e))
+}
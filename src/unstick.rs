@@ -0,0 +1,141 @@
+//! Clear a nonce gap left behind by an earlier run: every nonce between the account's latest
+//! (mined) and pending count is held by a transaction the chain will not mine, e.g. a zero-fee
+//! experiment, and blocks everything sent after it. Each is replaced with a priced 0-value
+//! self-transfer.
+
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockNumber, Transaction, H256, U256};
+use serde::Serialize;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::{build_tx, txpool, Fees, HashSigner};
+
+/// Gas limit of the self-transfers.
+const TRANSFER_GAS: u64 = 21_000;
+/// Fee increase over a pooled transaction that nodes require to replace it (geth's default
+/// price bump).
+const REPLACEMENT_BUMP_PERCENT: u64 = 10;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Fill {
+    pub nonce: U256,
+    /// Transaction that held the nonce, when the node's pool shows it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaced: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    /// `mined`, `pending` (not mined in time), `rejected`, or `filled` when another
+    /// transaction took the nonce meanwhile.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+}
+
+/// Fill every stuck nonce of the client's account with a type-`tx_type` self-transfer at
+/// `fees` (raised above the stuck transaction's when the pool shows it), then wait up to
+/// `wait` for them to be mined. Empty when there is no gap.
+pub async fn run<M, S>(client: &SignerMiddleware<M, S>, tx_type: u8, fees: Fees, wait: Duration) -> Result<Vec<Fill>>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+{
+    let from = client.address();
+    let latest = nonce(client, from, BlockNumber::Latest).await?;
+    let pending = nonce(client, from, BlockNumber::Pending).await?;
+    if pending <= latest {
+        return Ok(Vec::new());
+    }
+    info!("nonces {} to {} are pending but not mined", latest, pending - 1);
+    let pooled = match txpool::pooled(client, from).await {
+        Ok(pooled) => pooled,
+        Err(e) => {
+            warn!("txpool unavailable, sending at the given fees: {}", e);
+            Vec::new()
+        }
+    };
+
+    let mut fills = Vec::new();
+    let mut n = latest;
+    while n < pending {
+        let stuck = pooled.iter().find(|tx| tx.nonce == n);
+        let mut fill = Fill {
+            nonce: n,
+            replaced: stuck.map(|tx| tx.hash),
+            tx_hash: None,
+            status: "pending".into(),
+            error: None,
+            block_number: None,
+        };
+        let mut tx = build_tx(tx_type, from, from, U256::zero(), fees)?;
+        tx.set_nonce(n).set_gas(TRANSFER_GAS);
+        if let Some(stuck) = stuck {
+            outbid(&mut tx, stuck);
+        }
+        match client.send_transaction(tx, None).await {
+            Ok(sent) => {
+                info!("nonce {}: sent 0x{:x}", n, sent.tx_hash());
+                fill.tx_hash = Some(sent.tx_hash());
+            }
+            Err(e) => {
+                warn!("nonce {}: rejected: {}", n, e);
+                fill.status = "rejected".into();
+                fill.error = Some(e.to_string());
+            }
+        }
+        fills.push(fill);
+        n += U256::one();
+    }
+
+    let give_up_at = Instant::now() + wait;
+    let interval = client.provider().get_interval();
+    loop {
+        for fill in fills.iter_mut().filter(|f| f.status == "pending") {
+            let Some(hash) = fill.tx_hash else { continue };
+            if let Some(r) = client.get_transaction_receipt(hash).await.map_err(|e| eyre!("{}", e))? {
+                fill.status = "mined".into();
+                fill.block_number = r.block_number.map(|b| b.as_u64());
+            }
+        }
+        if fills.iter().all(|f| f.status != "pending") || Instant::now() >= give_up_at {
+            break;
+        }
+        tokio::time::sleep(interval).await;
+    }
+    // A rejected fill may have lost to a transaction that got mined meanwhile.
+    let mined = nonce(client, from, BlockNumber::Latest).await?;
+    for fill in fills.iter_mut().filter(|f| f.status != "mined" && f.nonce < mined) {
+        fill.status = "filled".into();
+    }
+    Ok(fills)
+}
+
+/// Raise `tx`'s fees to at least the replacement bump over `stuck`'s.
+fn outbid(tx: &mut TypedTransaction, stuck: &Transaction) {
+    let bump = |v: U256| (v * (100 + REPLACEMENT_BUMP_PERCENT) / 100).max(v + 1);
+    let price = stuck.max_fee_per_gas.or(stuck.gas_price).unwrap_or_default();
+    let priority = stuck.max_priority_fee_per_gas.or(stuck.gas_price).unwrap_or_default();
+    let raise = |field: &mut Option<U256>, floor: U256| *field = Some(field.unwrap_or_default().max(bump(floor)));
+    match tx {
+        TypedTransaction::Legacy(t) => raise(&mut t.gas_price, price),
+        TypedTransaction::Eip2930(t) => raise(&mut t.tx.gas_price, price),
+        TypedTransaction::Eip1559(t) => {
+            raise(&mut t.max_fee_per_gas, price);
+            raise(&mut t.max_priority_fee_per_gas, priority);
+        }
+    }
+}
+
+async fn nonce<M: Middleware>(client: &M, from: Address, block: BlockNumber) -> Result<U256> {
+    client
+        .get_transaction_count(from, Some(block.into()))
+        .await
+        .map_err(|e| eyre!("eth_getTransactionCount: {}", e))
+}
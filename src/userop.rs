@@ -0,0 +1,365 @@
+//! ERC-4337 account abstraction: the transfer wrapped as a UserOperation of a smart account,
+//! handed to a bundler (`eth_sendUserOperation`) instead of the node, and followed through
+//! `eth_getUserOperationReceipt`. Targets the v0.6 EntryPoint and `SimpleAccount`-style
+//! accounts (`execute(dest, value, func)`, owner signs the op hash as a personal message).
+
+use std::fmt;
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ethers::abi::{self, Token};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionReceipt, TransactionRequest, H160, H256, U256};
+use ethers::utils::{id, keccak256};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::{Fees, HashSigner};
+
+/// EntryPoint v0.6, deployed at the same address on every chain.
+pub const ENTRY_POINT_V06: Address = H160([
+    0x5f, 0xf1, 0x37, 0xd4, 0xb0, 0xfd, 0xcd, 0x49, 0xdc, 0xa3, 0x0c, 0x7c, 0xf5, 0x7e, 0x57, 0x8a, 0x02, 0x6d, 0x27, 0x89,
+]);
+
+/// Well-formed signature bundlers simulate validation with before the real one exists.
+const DUMMY_SIGNATURE: &str = "0xfffffffffffffffffffffffffffffff0000000000000000000000000000000007aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa1c";
+
+/// A v0.6 UserOperation, serialized the way bundlers take it.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    pub sender: Address,
+    pub nonce: U256,
+    pub init_code: Bytes,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub paymaster_and_data: Bytes,
+    pub signature: Bytes,
+}
+
+impl UserOperation {
+    /// `EntryPoint.getUserOpHash`: the op without its signature, bound to the EntryPoint and
+    /// chain.
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> H256 {
+        let packed = abi::encode(&[
+            Token::Address(self.sender),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.init_code).to_vec()),
+            Token::FixedBytes(keccak256(&self.call_data).to_vec()),
+            Token::Uint(self.call_gas_limit),
+            Token::Uint(self.verification_gas_limit),
+            Token::Uint(self.pre_verification_gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::FixedBytes(keccak256(&self.paymaster_and_data).to_vec()),
+        ]);
+        keccak256(abi::encode(&[
+            Token::FixedBytes(keccak256(packed).to_vec()),
+            Token::Address(entry_point),
+            Token::Uint(chain_id.into()),
+        ]))
+        .into()
+    }
+}
+
+/// Who pays for the operation instead of the account's own EntryPoint deposit.
+#[derive(Clone, Debug)]
+pub enum Paymaster<P> {
+    /// A fixed `paymasterAndData`, for paymasters that sponsor anyone or a pre-signed blob.
+    Static(Bytes),
+    /// `pm_sponsorUserOperation(op, entryPoint, context)`, the Pimlico/Stackup-style API: one
+    /// call after estimation returning `paymasterAndData`, possibly with new gas limits.
+    Sponsor { rpc: P, context: Option<Value> },
+    /// ERC-7677: `pm_getPaymasterStubData` for estimation, then `pm_getPaymasterData` for the
+    /// final gas limits.
+    Erc7677 { rpc: P, context: Option<Value> },
+}
+
+/// `pm_sponsorUserOperation` / `pm_getPaymaster(Stub)Data`; gas limits only from services
+/// that re-estimate.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Sponsorship {
+    paymaster_and_data: Bytes,
+    pre_verification_gas: Option<U256>,
+    verification_gas_limit: Option<U256>,
+    call_gas_limit: Option<U256>,
+}
+
+/// `eth_estimateUserOperationGas`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GasEstimate {
+    pre_verification_gas: U256,
+    // Some bundlers still use the pre-release name.
+    #[serde(alias = "verificationGas")]
+    verification_gas_limit: U256,
+    call_gas_limit: U256,
+}
+
+/// `eth_getUserOperationReceipt`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserOpReceipt {
+    success: bool,
+    /// Zero when the account paid for itself.
+    #[serde(default)]
+    paymaster: Address,
+    actual_gas_cost: U256,
+    actual_gas_used: U256,
+    #[serde(default)]
+    reason: Option<String>,
+    receipt: TransactionReceipt,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UserOpOutcome {
+    /// The smart account the operation runs from.
+    pub account: Address,
+    pub nonce: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_op_hash: Option<H256>,
+    /// Hash of the bundle transaction that included the operation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Paymaster named in `paymasterAndData`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paymaster: Option<Address>,
+    /// What the account (or its paymaster) paid the bundler.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual_gas_cost: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual_gas_used: Option<U256>,
+    /// `success`; `reverted` (included, but the call failed); `unsponsored` (included, but
+    /// the receipt names no paymaster although one was asked for); `rejected` by the
+    /// paymaster or bundler before inclusion; `pending` when no receipt arrived in time.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Send `value` from `account` to `to` as a UserOperation signed by the client's key (the
+/// account's owner), priced at `fees` and submitted to `bundler` for `entry_point`, then wait
+/// up to `wait` for it to be bundled. `init_code` deploys the account on first use; with a
+/// `paymaster`, the operation is sponsored instead of paid from the account's deposit.
+#[allow(clippy::too_many_arguments)]
+pub async fn run<M, S, B, P>(
+    client: &SignerMiddleware<M, S>,
+    bundler: &B,
+    entry_point: Address,
+    account: Address,
+    init_code: Bytes,
+    to: Address,
+    value: U256,
+    fees: Fees,
+    paymaster: Option<&Paymaster<P>>,
+    wait: Duration,
+) -> Result<UserOpOutcome>
+where
+    M: Middleware + 'static,
+    S: HashSigner + 'static,
+    B: Middleware,
+    P: Middleware,
+{
+    let chain_id = client.signer().chain_id();
+    let nonce = entry_point_nonce(client, entry_point, account).await?;
+    let mut outcome = UserOpOutcome {
+        account,
+        nonce,
+        user_op_hash: None,
+        tx_hash: None,
+        block_number: None,
+        paymaster: None,
+        actual_gas_cost: None,
+        actual_gas_used: None,
+        status: "rejected".into(),
+        error: None,
+    };
+    let mut op = UserOperation {
+        sender: account,
+        nonce,
+        init_code,
+        call_data: execute_calldata(to, value),
+        max_fee_per_gas: fees.max_fee_per_gas,
+        max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+        signature: DUMMY_SIGNATURE.parse().expect("valid hex"),
+        ..Default::default()
+    };
+
+    // The paymaster's part has to be in place for the bundler to simulate validation with.
+    match paymaster {
+        Some(Paymaster::Static(data)) => op.paymaster_and_data = data.clone(),
+        Some(Paymaster::Erc7677 { rpc, context }) => {
+            let params = (&op, entry_point, U256::from(chain_id), context);
+            match request::<_, Sponsorship>(rpc, "pm_getPaymasterStubData", params).await {
+                Ok(stub) => op.paymaster_and_data = stub.paymaster_and_data,
+                Err(e) => return Ok(outcome.with_error(e)),
+            }
+        }
+        Some(Paymaster::Sponsor { .. }) | None => {}
+    }
+    let estimate = match request::<_, GasEstimate>(bundler, "eth_estimateUserOperationGas", (&op, entry_point)).await {
+        Ok(estimate) => estimate,
+        Err(e) => return Ok(outcome.with_error(e)),
+    };
+    op.pre_verification_gas = estimate.pre_verification_gas;
+    op.verification_gas_limit = estimate.verification_gas_limit;
+    op.call_gas_limit = estimate.call_gas_limit;
+
+    // The sponsor signs over the final gas limits, so this comes after estimation.
+    let sponsorship = match paymaster {
+        Some(Paymaster::Sponsor { rpc, context }) => {
+            let params = (&op, entry_point, context);
+            Some(request::<_, Sponsorship>(rpc, "pm_sponsorUserOperation", params).await)
+        }
+        Some(Paymaster::Erc7677 { rpc, context }) => {
+            let params = (&op, entry_point, U256::from(chain_id), context);
+            Some(request::<_, Sponsorship>(rpc, "pm_getPaymasterData", params).await)
+        }
+        Some(Paymaster::Static(_)) | None => None,
+    };
+    match sponsorship {
+        Some(Ok(s)) => {
+            op.paymaster_and_data = s.paymaster_and_data;
+            op.pre_verification_gas = s.pre_verification_gas.unwrap_or(op.pre_verification_gas);
+            op.verification_gas_limit = s.verification_gas_limit.unwrap_or(op.verification_gas_limit);
+            op.call_gas_limit = s.call_gas_limit.unwrap_or(op.call_gas_limit);
+        }
+        Some(Err(e)) => return Ok(outcome.with_error(e)),
+        None => {}
+    }
+    if let Some(address) = op.paymaster_and_data.get(..20) {
+        outcome.paymaster = Some(Address::from_slice(address));
+    }
+
+    let hash = op.hash(entry_point, client.signer().chain_id());
+    let sig = client.signer().sign_message(hash.as_bytes()).await.map_err(|e| eyre!("signing the UserOperation: {}", e))?;
+    op.signature = sig.to_vec().into();
+
+    let user_op_hash: H256 = match request(bundler, "eth_sendUserOperation", (&op, entry_point)).await {
+        Ok(hash) => hash,
+        Err(e) => return Ok(outcome.with_error(e)),
+    };
+    outcome.user_op_hash = Some(user_op_hash);
+    outcome.status = "pending".into();
+
+    let give_up_at = Instant::now() + wait;
+    let interval = bundler.provider().get_interval();
+    while Instant::now() < give_up_at {
+        let receipt: Option<UserOpReceipt> = bundler
+            .provider()
+            .request("eth_getUserOperationReceipt", [user_op_hash])
+            .await
+            .map_err(|e| eyre!("eth_getUserOperationReceipt: {}", e))?;
+        if let Some(r) = receipt {
+            outcome.status = match (r.success, outcome.paymaster) {
+                (false, _) => "reverted",
+                (true, Some(_)) if r.paymaster.is_zero() => "unsponsored",
+                (true, _) => "success",
+            }
+            .into();
+            outcome.error = r.reason.filter(|reason| !reason.is_empty() && reason != "0x");
+            outcome.tx_hash = Some(r.receipt.transaction_hash);
+            outcome.block_number = r.receipt.block_number.map(|b| b.as_u64());
+            outcome.actual_gas_cost = Some(r.actual_gas_cost);
+            outcome.actual_gas_used = Some(r.actual_gas_used);
+            break;
+        }
+        tokio::time::sleep(interval).await;
+    }
+    Ok(outcome)
+}
+
+impl UserOpOutcome {
+    fn with_error(mut self, e: String) -> Self {
+        self.error = Some(e);
+        self
+    }
+}
+
+/// JSON-RPC call to a bundler or paymaster service, its error prefixed with the method.
+async fn request<M, R>(rpc: &M, method: &str, params: impl fmt::Debug + Serialize + Send + Sync) -> Result<R, String>
+where
+    M: Middleware,
+    R: fmt::Debug + Serialize + DeserializeOwned + Send,
+{
+    rpc.provider().request(method, params).await.map_err(|e| format!("{}: {}", method, e))
+}
+
+/// `EntryPoint.getNonce(account, 0)`.
+async fn entry_point_nonce<M: Middleware>(client: &M, entry_point: Address, account: Address) -> Result<U256> {
+    let mut data = id("getNonce(address,uint192)").to_vec();
+    data.extend(abi::encode(&[Token::Address(account), Token::Uint(U256::zero())]));
+    let call: TypedTransaction = TransactionRequest::new().to(entry_point).data(data).into();
+    let out = client.call(&call, None).await.map_err(|e| eyre!("EntryPoint.getNonce: {}", e))?;
+    let word = out.get(..32).ok_or_else(|| eyre!("getNonce returned {} bytes; is the address an EntryPoint?", out.len()))?;
+    Ok(U256::from_big_endian(word))
+}
+
+/// `execute(dest, value, func)` with no inner call: a plain transfer out of the account.
+fn execute_calldata(to: Address, value: U256) -> Bytes {
+    let mut data = id("execute(address,uint256,bytes)").to_vec();
+    data.extend(abi::encode(&[Token::Address(to), Token::Uint(value), Token::Bytes(Vec::new())]));
+    data.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op() -> UserOperation {
+        let to: Address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".parse().unwrap();
+        UserOperation {
+            sender: "0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC".parse().unwrap(),
+            nonce: 1.into(),
+            call_data: execute_calldata(to, U256::exp10(15)),
+            call_gas_limit: 100_000.into(),
+            verification_gas_limit: 150_000.into(),
+            pre_verification_gas: 50_000.into(),
+            max_fee_per_gas: U256::exp10(9) * 30,
+            max_priority_fee_per_gas: U256::exp10(9) * 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn entry_point_v06_address() {
+        assert_eq!(ENTRY_POINT_V06, "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789".parse().unwrap());
+    }
+
+    #[test]
+    fn execute_calldata_is_a_transfer() {
+        let data = execute_calldata("0x70997970C51812dc3A010C7d01b50e0d17dc79C8".parse().unwrap(), U256::exp10(15));
+        assert_eq!(
+            format!("{:x}", data),
+            "0xb61d27f6\
+             00000000000000000000000070997970c51812dc3a010c7d01b50e0d17dc79c8\
+             00000000000000000000000000000000000000000000000000038d7ea4c68000\
+             0000000000000000000000000000000000000000000000000000000000000060\
+             0000000000000000000000000000000000000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn hash_matches_get_user_op_hash() {
+        let expected: H256 = "0x5a686fd7d8ef2385e3e524b4e50fe57757198bf423ea0a90451e70067fe9bcf8".parse().unwrap();
+        let mut op = op();
+        assert_eq!(op.hash(ENTRY_POINT_V06, 11155111), expected);
+        // The signature is not part of what is signed.
+        op.signature = DUMMY_SIGNATURE.parse().unwrap();
+        assert_eq!(op.hash(ENTRY_POINT_V06, 11155111), expected);
+        assert_ne!(op.hash(ENTRY_POINT_V06, 1), expected);
+        op.paymaster_and_data = Bytes::from(vec![1]);
+        assert_ne!(op.hash(ENTRY_POINT_V06, 11155111), expected);
+    }
+}
@@ -0,0 +1,327 @@
+//! Cross-check a mined transaction against what we asked for: the node's copy of the
+//! transaction and the signer its signature recovers to, the effective gas price it charged,
+//! and the sender's balance change.
+
+use std::fmt;
+
+use eyre::{eyre, Result};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{
+    Address, Block, BlockId, BlockNumber, Signature, Transaction, TransactionReceipt, TransactionRequest, H256, U256, U64,
+};
+use serde::Serialize;
+
+use crate::blob::{BlobTransaction, BLOB_TX_TYPE};
+
+/// What the transaction was supposed to do.
+#[derive(Clone, Copy, Debug)]
+pub struct Expected {
+    pub from: Address,
+    /// `None` for a contract creation.
+    pub to: Option<Address>,
+    pub value: U256,
+}
+
+/// What the cross-check found.
+#[derive(Clone, Debug, Default)]
+pub struct Verification {
+    /// Mismatches between what was asked for and what the chain recorded; empty when all agree.
+    pub anomalies: Vec<String>,
+    pub fee_audit: Option<FeeAudit>,
+}
+
+/// How the price a transaction was charged relates to its fee fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceVerdict {
+    /// Exactly the gas price, or `min(maxFeePerGas, baseFee + maxPriorityFeePerGas)`.
+    Consistent,
+    /// More than the sender's `maxFeePerGas`: the chain took more than it was allowed to.
+    AboveMaxFee,
+    /// More than a legacy transaction's `gasPrice`.
+    AboveGasPrice,
+    /// Less than the block's base fee, which every transaction must pay.
+    BelowBaseFee,
+    /// Just the base fee, though the fee cap left room for the priority fee.
+    PriorityFeeIgnored,
+    /// More than expected, but within the sender's limits.
+    Overcharged,
+    /// Less than expected, but at least the base fee.
+    Undercharged,
+}
+
+impl fmt::Display for PriceVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PriceVerdict::Consistent => "consistent",
+            PriceVerdict::AboveMaxFee => "above maxFeePerGas",
+            PriceVerdict::AboveGasPrice => "above gasPrice",
+            PriceVerdict::BelowBaseFee => "below the base fee",
+            PriceVerdict::PriorityFeeIgnored => "priority fee ignored",
+            PriceVerdict::Overcharged => "overcharged",
+            PriceVerdict::Undercharged => "undercharged",
+        })
+    }
+}
+
+/// The receipt's `effectiveGasPrice` against the fees signed and the block's base fee.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct FeeAudit {
+    pub effective_gas_price: U256,
+    /// What the fee rules say the transaction should have paid.
+    pub expected_gas_price: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
+    pub verdict: PriceVerdict,
+}
+
+impl fmt::Display for FeeAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "effectiveGasPrice {} is {} (expected {}", self.effective_gas_price, self.verdict, self.expected_gas_price)?;
+        if let Some(base_fee) = self.base_fee_per_gas {
+            write!(f, ", base fee {}", base_fee)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// Audit the price `tx` was charged in a block with `base_fee`. Legacy pricing pays the gas
+/// price; dynamic-fee types pay `min(max fee, base fee + tip)`. `None` for a dynamic-fee
+/// transaction without a base fee to price it against.
+pub fn audit_price(tx: &Transaction, base_fee: Option<U256>, effective: U256) -> Option<FeeAudit> {
+    let dynamic = tx.transaction_type.is_some_and(|t| t.as_u64() >= 2);
+    let verdict = |expected: U256, cap: U256, over_cap: PriceVerdict| match base_fee {
+        _ if effective > cap => over_cap,
+        Some(base_fee) if effective < base_fee => PriceVerdict::BelowBaseFee,
+        _ if effective > expected => PriceVerdict::Overcharged,
+        _ if effective < expected => PriceVerdict::Undercharged,
+        _ => PriceVerdict::Consistent,
+    };
+    let (expected, verdict) = if dynamic {
+        let (base_fee, max_fee, tip) = (base_fee?, tx.max_fee_per_gas?, tx.max_priority_fee_per_gas?);
+        let expected = max_fee.min(base_fee + tip);
+        match verdict(expected, max_fee, PriceVerdict::AboveMaxFee) {
+            PriceVerdict::Undercharged if effective == base_fee => (expected, PriceVerdict::PriorityFeeIgnored),
+            v => (expected, v),
+        }
+    } else {
+        let gas_price = tx.gas_price?;
+        (gas_price, verdict(gas_price, gas_price, PriceVerdict::AboveGasPrice))
+    };
+    Some(FeeAudit { effective_gas_price: effective, expected_gas_price: expected, base_fee_per_gas: base_fee, verdict })
+}
+
+/// Recover the signer of the node's copy of `tx` from its `v`, `r` and `s` over the fields it
+/// reports, which catches chains that mangle `v` or `yParity` on the way out. Nothing to check
+/// for zkSync's 0x71, whose signature is not carried there, nor for chain-specific types,
+/// whose extra fields are unknown here.
+pub fn check_signer(tx: &Transaction, from: Address) -> Vec<String> {
+    let mut anomalies = Vec::new();
+    let tx_type = tx.transaction_type.map(|t| t.as_u64()).unwrap_or_default();
+    if tx_type > BLOB_TX_TYPE as u64 {
+        return anomalies;
+    }
+    let v = tx.v.as_u64();
+    if tx_type > 0 && v > 1 {
+        anomalies.push(format!("v is {} for a type-{} transaction, expected its y parity (0 or 1)", v, tx_type));
+    }
+    if let Some(Ok(y_parity)) = tx.other.get_deserialized::<U64>("yParity")
+        && y_parity.as_u64() != v
+    {
+        anomalies.push(format!("yParity is {} but v is {}", y_parity, v));
+    }
+    match signing_hash(tx, tx_type).and_then(|hash| Ok((Signature { r: tx.r, s: tx.s, v }).recover(hash)?)) {
+        Ok(signer) if signer != from => {
+            anomalies.push(format!("signature recovers to {:?}, not the sender {:?}", signer, from));
+        }
+        Ok(_) => {}
+        Err(e) => anomalies.push(format!("signature does not recover a sender: {}", e)),
+    }
+    anomalies
+}
+
+/// Digest the sender of `tx` signed, rebuilt from the node's copy.
+fn signing_hash(tx: &Transaction, tx_type: u64) -> Result<H256> {
+    match tx_type {
+        // The chain id of a legacy transaction is in its `v`, if anywhere.
+        0 => {
+            let v = tx.v.as_u64();
+            let mut request: TransactionRequest = tx.into();
+            request.chain_id = (v >= 35).then(|| ((v - 35) / 2).into());
+            Ok(TypedTransaction::Legacy(request).sighash())
+        }
+        1 | 2 if tx.chain_id.is_none() => Err(eyre!("the node's copy has no chainId")),
+        1 | 2 => {
+            let typed: TypedTransaction = tx.into();
+            Ok(typed.sighash())
+        }
+        t if t == BLOB_TX_TYPE as u64 => {
+            let other = |key: &str| tx.other.get(key).cloned().ok_or_else(|| eyre!("the node's copy has no {}", key));
+            let blob = BlobTransaction {
+                chain_id: tx.chain_id.ok_or_else(|| eyre!("the node's copy has no chainId"))?.as_u64(),
+                nonce: tx.nonce,
+                max_priority_fee_per_gas: tx.max_priority_fee_per_gas.unwrap_or_default(),
+                max_fee_per_gas: tx.max_fee_per_gas.unwrap_or_default(),
+                gas: tx.gas,
+                to: tx.to.ok_or_else(|| eyre!("the node's copy has no to"))?,
+                value: tx.value,
+                data: tx.input.clone(),
+                access_list: tx.access_list.clone().unwrap_or_default(),
+                max_fee_per_blob_gas: serde_json::from_value(other("maxFeePerBlobGas")?)?,
+                blob_versioned_hashes: serde_json::from_value(other("blobVersionedHashes")?)?,
+            };
+            Ok(blob.sighash())
+        }
+        t => Err(eyre!("cannot rebuild the signed fields of type {}", t)),
+    }
+}
+
+/// Cross-check the mined transaction `hash` against `expected`.
+pub async fn check<M: Middleware>(client: &M, hash: H256, expected: &Expected) -> Result<Verification>
+where
+    M::Error: 'static,
+{
+    let mut v = Verification::default();
+    let Some(tx) = client.get_transaction(hash).await? else {
+        v.anomalies.push("eth_getTransactionByHash returned nothing".into());
+        return Ok(v);
+    };
+    if tx.from != expected.from {
+        v.anomalies.push(format!("from is {:?}, expected {:?}", tx.from, expected.from));
+    }
+    if tx.to != expected.to {
+        v.anomalies.push(format!("to is {:?}, expected {:?}", tx.to, expected.to));
+    }
+    if tx.value != expected.value {
+        v.anomalies.push(format!("value is {}, expected {}", tx.value, expected.value));
+    }
+    v.anomalies.extend(check_signer(&tx, expected.from));
+
+    let Some(receipt) = client.get_transaction_receipt(hash).await? else { return Ok(v) };
+    let Some(block_number) = receipt.block_number else { return Ok(v) };
+    let Some(block) = client.get_block(block_number).await? else { return Ok(v) };
+
+    let dynamic = tx.transaction_type.is_some_and(|t| t.as_u64() >= 2);
+    if dynamic && block.base_fee_per_gas.is_none() {
+        v.anomalies.push("block has no base fee for a dynamic-fee transaction".into());
+    }
+    v.fee_audit = match receipt.effective_gas_price {
+        Some(price) => audit_price(&tx, block.base_fee_per_gas, price),
+        None => {
+            v.anomalies.push("receipt has no effectiveGasPrice".into());
+            None
+        }
+    };
+    if let Some(audit) = v.fee_audit.filter(|a| a.verdict != PriceVerdict::Consistent) {
+        v.anomalies.push(audit.to_string());
+    }
+
+    // Historical state is often pruned; a failed lookup costs this check only, not the
+    // anomalies found above.
+    match balance_delta(client, &tx, &receipt, &block).await {
+        Ok(anomaly) => v.anomalies.extend(anomaly),
+        Err(e) => v.anomalies.push(format!("could not check the sender's balance change: {}", e)),
+    }
+    Ok(v)
+}
+
+/// The sender's balance change across the block against value plus fees, when it can be
+/// attributed to `tx` alone.
+async fn balance_delta<M: Middleware>(
+    client: &M,
+    tx: &Transaction,
+    receipt: &TransactionReceipt,
+    block: &Block<H256>,
+) -> Result<Option<String>>
+where
+    M::Error: 'static,
+{
+    let block_number = block.number.ok_or_else(|| eyre!("block has no number"))?;
+    // Only attributable when this is the sender's sole transaction in the block and the
+    // sender did not also collect the block's fees.
+    let before = BlockId::Number(BlockNumber::Number(block_number - 1));
+    let after = BlockId::Number(BlockNumber::Number(block_number));
+    let sent_in_block = client.get_transaction_count(tx.from, Some(after)).await?
+        .saturating_sub(client.get_transaction_count(tx.from, Some(before)).await?);
+    if sent_in_block != U256::one() || block.author == Some(tx.from) {
+        return Ok(None);
+    }
+    let gas_used = receipt.gas_used.unwrap_or_default();
+    let price = receipt.effective_gas_price.unwrap_or_default();
+    let blob_gas = receipt.other.get_deserialized::<U256>("blobGasUsed").and_then(|v| v.ok()).unwrap_or_default();
+    let blob_price = receipt.other.get_deserialized::<U256>("blobGasPrice").and_then(|v| v.ok()).unwrap_or_default();
+    let mut cost = gas_used * price + blob_gas * blob_price;
+    if tx.to != Some(tx.from) && receipt.status.is_some_and(|s| s.as_u64() == 1) {
+        cost += tx.value;
+    }
+    let balance_before = client.get_balance(tx.from, Some(before)).await?;
+    let balance_after = client.get_balance(tx.from, Some(after)).await?;
+    Ok(if balance_before < balance_after {
+        Some(format!("sender balance grew by {}, expected to drop by {}", balance_after - balance_before, cost))
+    } else if balance_before - balance_after != cost {
+        Some(format!("sender balance dropped by {}, expected {} (value + gas)", balance_before - balance_after, cost))
+    } else {
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gwei(n: u64) -> U256 {
+        U256::from(n) * U256::exp10(9)
+    }
+
+    fn dynamic(max_fee: u64, tip: u64) -> Transaction {
+        Transaction {
+            transaction_type: Some(2.into()),
+            max_fee_per_gas: Some(gwei(max_fee)),
+            max_priority_fee_per_gas: Some(gwei(tip)),
+            ..Default::default()
+        }
+    }
+
+    fn legacy(gas_price: u64) -> Transaction {
+        Transaction { gas_price: Some(gwei(gas_price)), ..Default::default() }
+    }
+
+    fn verdict(tx: &Transaction, base_fee: Option<u64>, effective: u64) -> Option<(U256, PriceVerdict)> {
+        audit_price(tx, base_fee.map(gwei), gwei(effective)).map(|a| (a.expected_gas_price, a.verdict))
+    }
+
+    #[test]
+    fn dynamic_fees_pay_the_base_fee_and_the_tip_up_to_the_cap() {
+        let tx = dynamic(30, 2);
+        assert_eq!(verdict(&tx, Some(10), 12), Some((gwei(12), PriceVerdict::Consistent)));
+        assert_eq!(verdict(&tx, Some(29), 30), Some((gwei(30), PriceVerdict::Consistent)));
+        assert_eq!(verdict(&tx, Some(10), 31), Some((gwei(12), PriceVerdict::AboveMaxFee)));
+        assert_eq!(verdict(&tx, Some(10), 13), Some((gwei(12), PriceVerdict::Overcharged)));
+        assert_eq!(verdict(&tx, Some(10), 11), Some((gwei(12), PriceVerdict::Undercharged)));
+        assert_eq!(verdict(&tx, Some(10), 10), Some((gwei(12), PriceVerdict::PriorityFeeIgnored)));
+        assert_eq!(verdict(&tx, Some(10), 9), Some((gwei(12), PriceVerdict::BelowBaseFee)));
+        assert_eq!(verdict(&tx, None, 12), None);
+    }
+
+    #[test]
+    fn legacy_pays_its_gas_price() {
+        let tx = legacy(20);
+        assert_eq!(verdict(&tx, Some(10), 20), Some((gwei(20), PriceVerdict::Consistent)));
+        assert_eq!(verdict(&tx, None, 20), Some((gwei(20), PriceVerdict::Consistent)));
+        assert_eq!(verdict(&tx, Some(10), 21), Some((gwei(20), PriceVerdict::AboveGasPrice)));
+        assert_eq!(verdict(&tx, Some(10), 19), Some((gwei(20), PriceVerdict::Undercharged)));
+        assert_eq!(verdict(&tx, Some(10), 5), Some((gwei(20), PriceVerdict::BelowBaseFee)));
+    }
+
+    #[test]
+    fn describes_the_audit() {
+        let audit = audit_price(&dynamic(30, 2), Some(gwei(10)), gwei(10)).unwrap();
+        assert_eq!(
+            audit.to_string(),
+            "effectiveGasPrice 10000000000 is priority fee ignored (expected 12000000000, base fee 10000000000)"
+        );
+        let audit = audit_price(&legacy(20), None, gwei(21)).unwrap();
+        assert_eq!(audit.to_string(), "effectiveGasPrice 21000000000 is above gasPrice (expected 20000000000)");
+    }
+}
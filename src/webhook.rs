@@ -0,0 +1,207 @@
+//! Run notifications: a JSON summary POSTed to a webhook when a run ends, fails, or has
+//! results that deviate from the expectations file (or, when monitoring, from the previous
+//! run), either as is or as a Slack or Discord message.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::diff::Change;
+use crate::expect::Deviation;
+use crate::report::Report;
+
+/// Discord rejects messages longer than this.
+const DISCORD_MAX_CHARS: usize = 2000;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Body the webhook is sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// The [`Notification`] itself.
+    #[default]
+    Json,
+    /// `{"text": ...}` for a Slack incoming webhook.
+    Slack,
+    /// `{"content": ...}` for a Discord webhook.
+    Discord,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Notification {
+    /// `completed`, `regressed` (results deviate from the expectations), `interrupted`,
+    /// `failed` (the run ended in an error) or, when monitoring, `changed` (statuses differ
+    /// from the previous run's) or `recovered` (a run finished again after failing ones).
+    pub event: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_version: Option<String>,
+    /// Results per status.
+    pub totals: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub series: Vec<SeriesSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deviations: Vec<String>,
+    /// Statuses that changed since the previous run.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<Change>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Status of every tx type of a series.
+#[derive(Clone, Debug, Serialize)]
+pub struct SeriesSummary {
+    pub label: String,
+    pub statuses: BTreeMap<u8, String>,
+}
+
+impl Notification {
+    /// A run that went to the end (or was interrupted), with its `--expect` deviations.
+    pub fn finished(report: &Report, interrupted: bool, deviations: &[Deviation]) -> Self {
+        let mut totals = BTreeMap::new();
+        let series = report
+            .series
+            .iter()
+            .map(|s| {
+                let statuses = s
+                    .results
+                    .iter()
+                    .map(|r| {
+                        *totals.entry(r.status.clone()).or_default() += 1;
+                        let status = match r.error_kind {
+                            Some(kind) => format!("{} [{}]", r.status, kind),
+                            None => r.status.clone(),
+                        };
+                        (r.tx_type, status)
+                    })
+                    .collect();
+                SeriesSummary { label: s.label.clone(), statuses }
+            })
+            .collect();
+        let event = match (interrupted, deviations.is_empty()) {
+            (true, _) => "interrupted",
+            (false, false) => "regressed",
+            (false, true) => "completed",
+        };
+        Self {
+            event,
+            chain_id: Some(report.chain_id),
+            client_version: report.node.as_ref().and_then(|n| n.client_version.clone()),
+            totals,
+            series,
+            deviations: deviations.iter().map(ToString::to_string).collect(),
+            changes: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// A monitoring run whose statuses differ from the previous run's.
+    pub fn changed(report: &Report, changes: Vec<Change>) -> Self {
+        Self { event: "changed", changes, ..Self::finished(report, false, &[]) }
+    }
+
+    /// A monitoring run that finished after the previous one failed, with any status changes
+    /// since the last run that finished.
+    pub fn recovered(report: &Report, changes: Vec<Change>) -> Self {
+        Self { event: "recovered", changes, ..Self::finished(report, false, &[]) }
+    }
+
+    /// A run that ended in `error` before it had a report.
+    pub fn failed(error: impl fmt::Display) -> Self {
+        Self {
+            event: "failed",
+            chain_id: None,
+            client_version: None,
+            totals: BTreeMap::new(),
+            series: Vec::new(),
+            deviations: Vec::new(),
+            changes: Vec::new(),
+            error: Some(error.to_string()),
+        }
+    }
+
+    pub fn regressed(&self) -> bool {
+        !self.deviations.is_empty()
+    }
+}
+
+/// Plain-text rendering, as chat messages carry it.
+impl fmt::Display for Notification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "test-transaction run {}", self.event)?;
+        if let Some(chain_id) = self.chain_id {
+            write!(f, " on chain {}", chain_id)?;
+        }
+        if let Some(version) = &self.client_version {
+            write!(f, " ({})", version)?;
+        }
+        if let Some(e) = &self.error {
+            write!(f, ": {}", e)?;
+        }
+        if !self.totals.is_empty() {
+            let totals: Vec<String> = self.totals.iter().map(|(status, n)| format!("{} {}", n, status)).collect();
+            write!(f, "\n{}", totals.join(", "))?;
+        }
+        for s in &self.series {
+            let statuses: Vec<String> = s.statuses.iter().map(|(t, status)| format!("type-{} {}", t, status)).collect();
+            write!(f, "\n{}: {}", s.label, statuses.join(", "))?;
+        }
+        if !self.changes.is_empty() {
+            write!(f, "\nChanged since the previous run:")?;
+            for c in &self.changes {
+                write!(f, "\n- {}", c)?;
+            }
+        }
+        if !self.deviations.is_empty() {
+            write!(f, "\nDeviations from the expectations:")?;
+            for d in &self.deviations {
+                write!(f, "\n- {}", d)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Webhook {
+    url: String,
+    format: Format,
+    http: reqwest::Client,
+}
+
+impl Webhook {
+    pub fn new(url: impl Into<String>, format: Format) -> Self {
+        Self { url: url.into(), format, http: reqwest::Client::new() }
+    }
+
+    /// POST `notification` to the webhook.
+    pub async fn send(&self, notification: &Notification) -> Result<()> {
+        let body = match self.format {
+            Format::Json => serde_json::to_value(notification)?,
+            Format::Slack => json!({ "text": notification.to_string() }),
+            Format::Discord => json!({ "content": truncate(&notification.to_string(), DISCORD_MAX_CHARS) }),
+        };
+        let response = self.http.post(&self.url).timeout(TIMEOUT).json(&body).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(eyre!("webhook answered {}: {}", status, text.trim()));
+        }
+        Ok(())
+    }
+}
+
+/// `text` cut to at most `max` characters, marked as cut.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max - 1).collect();
+    cut.push('…');
+    cut
+}